@@ -0,0 +1,91 @@
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use dnn_core::time::{TimeInterval, Timestamp};
+use crate::ProviderType;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Above,
+    Below,
+}
+
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Above => "above",
+            Self::Below => "below",
+        })
+    }
+}
+
+/// What an alert waits for, checked on every candle of its symbol. Alerts
+/// trigger when their condition starts to hold, not while it keeps holding.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertCondition {
+    /// The close crosses `price`
+    Price { direction: Direction, price: f64 },
+    /// The close moved `pct` percent or more since the previous close;
+    /// negative for drops
+    ChangePct { pct: f64 },
+    /// A formula of the strategy rules, such as `RSI(14)` or
+    /// `close / SMA(50)`, crosses `threshold`
+    Indicator { formula: String, direction: Direction, threshold: f64 },
+    /// Volume reaches `ratio` times the mean of the `period` candles before
+    VolumeSpike { ratio: f64, period: usize },
+}
+
+impl fmt::Display for AlertCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Price { direction, price } => write!(f, "price {direction} {price}"),
+            Self::ChangePct { pct } if *pct < 0.0 => write!(f, "drop of {}%", -pct),
+            Self::ChangePct { pct } => write!(f, "rise of {pct}%"),
+            Self::Indicator { formula, direction, threshold } => write!(f, "{formula} {direction} {threshold}"),
+            Self::VolumeSpike { ratio, period } => write!(f, "volume {ratio}x its {period} candle average"),
+        }
+    }
+}
+
+/// Body of `POST /alerts`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CreateAlertReq {
+    pub provider: ProviderType,
+    pub symbol: String,
+    pub interval: TimeInterval,
+    pub condition: AlertCondition,
+    /// Keep the alert after it triggers, to trigger again the next time its
+    /// condition starts to hold
+    #[serde(default)]
+    pub repeat: bool,
+    /// URL the triggered [`AlertEvent`] is posted to as JSON
+    #[serde(default)]
+    pub webhook: Option<String>,
+    /// Address to mail when the alert triggers
+    #[serde(default)]
+    pub email: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub id: String,
+    pub config: CreateAlertReq,
+    /// `false` once an alert that doesn't repeat has triggered
+    pub active: bool,
+    pub created_at: Timestamp,
+    pub last_triggered: Option<Timestamp>,
+}
+
+/// An alert that triggered, pushed to `/alerts/stream` and the alert's
+/// notifiers
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct AlertEvent {
+    pub alert_id: String,
+    pub symbol: String,
+    /// Describes the condition that started to hold
+    pub message: String,
+    /// Close of the candle that triggered the alert
+    pub price: f64,
+    pub triggered_at: Timestamp,
+}
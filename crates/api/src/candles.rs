@@ -0,0 +1,157 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use crate::ProviderType;
+
+/// Most candles `GET /candles` returns at once
+pub const MAX_PAGE: usize = 5000;
+/// Page size when the query doesn't set `limit`
+pub const DEFAULT_PAGE: usize = 1000;
+/// Most `interval`s the range of one `GET /candles` query may span
+pub const MAX_RANGE: i64 = 100_000;
+/// Carries the cursor of the next page in CSV and binary responses
+pub const NEXT_CURSOR_HEADER: &str = "x-next-cursor";
+/// Bytes per candle in [`CandleFormat::Binary`]
+pub const BINARY_RECORD: usize = 48;
+
+/// How `GET /candles` encodes its response. Picked by the `format` query
+/// parameter, else the `Accept` header, else JSON.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum CandleFormat {
+    /// A [`CandlePage`]
+    Json,
+    /// A `timestamp,open,high,low,close,volume` header, then a row per
+    /// candle with RFC 3339 timestamps
+    Csv,
+    /// [`BINARY_RECORD`] bytes per candle: milliseconds since the epoch as
+    /// an `i64`, then open, high, low, close and volume as `f64`s, all
+    /// little endian
+    #[serde(alias = "bin")]
+    Binary,
+}
+
+impl CandleFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv",
+            Self::Binary => "application/octet-stream",
+        }
+    }
+
+    /// The format an `Accept` header asks for, if any
+    pub fn from_accept(accept: &str) -> Option<Self> {
+        accept.split(',').find_map(|media| match media.split(';').next()?.trim() {
+            "application/json" => Some(Self::Json),
+            "text/csv" => Some(Self::Csv),
+            "application/octet-stream" => Some(Self::Binary),
+            _ => None,
+        })
+    }
+}
+
+/// Query of `GET /candles`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct CandlesQuery {
+    pub provider: ProviderType,
+    pub symbol: String,
+    /// Interval asked of the provider
+    pub interval: TimeInterval,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// Coarser interval to aggregate the provider's candles into
+    pub resample: Option<TimeInterval>,
+    /// Prices adjusted for splits and dividends
+    #[serde(default)]
+    pub adjusted: bool,
+    /// `next_cursor` of the previous page
+    pub cursor: Option<String>,
+    /// At most [`MAX_PAGE`], [`DEFAULT_PAGE`] if unset
+    pub limit: Option<usize>,
+    pub format: Option<CandleFormat>,
+}
+
+/// One page of `GET /candles` as JSON
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CandlePage {
+    pub candles: Vec<Candle>,
+    /// Pass as `cursor` for the candles after these, `None` on the last page
+    pub next_cursor: Option<String>,
+}
+
+impl CandlePage {
+    /// Time of the last candle sent, held by `cursor`
+    pub fn cursor_time(cursor: &str) -> Result<Timestamp, String> {
+        cursor
+            .parse::<i64>()
+            .ok()
+            .and_then(DateTime::from_timestamp_millis)
+            .ok_or_else(|| format!("bad cursor `{cursor}`"))
+    }
+
+    /// The `limit` candles of `candles` (sorted by time) after `cursor`.
+    /// Cursors are opaque to clients; they hold the time of the last
+    /// candle sent, so they stay valid however the range is paged.
+    pub fn after(candles: Vec<Candle>, cursor: Option<&str>, limit: usize) -> Result<Self, String> {
+        let skip = match cursor {
+            Some(cursor) => {
+                let last = Self::cursor_time(cursor)?;
+                candles.partition_point(|c| c.timestamp <= last)
+            }
+            None => 0,
+        };
+        let limit = limit.max(1);
+        let mut candles: Vec<Candle> = candles.into_iter().skip(skip).collect();
+        let next_cursor = (candles.len() > limit).then(|| candles[limit - 1].timestamp.timestamp_millis().to_string());
+        candles.truncate(limit);
+        Ok(Self { candles, next_cursor })
+    }
+}
+
+pub fn to_csv(candles: &[Candle]) -> String {
+    let mut csv = String::from("timestamp,open,high,low,close,volume\n");
+    for c in candles {
+        csv.push_str(&format!(
+            "{},{},{},{},{},{}\n",
+            c.timestamp.to_rfc3339(),
+            c.open,
+            c.high,
+            c.low,
+            c.close,
+            c.volume
+        ));
+    }
+    csv
+}
+
+/// See [`CandleFormat::Binary`]
+pub fn to_binary(candles: &[Candle]) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(candles.len() * BINARY_RECORD);
+    for c in candles {
+        bytes.extend_from_slice(&c.timestamp.timestamp_millis().to_le_bytes());
+        for value in [c.open, c.high, c.low, c.close, c.volume] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+    bytes
+}
+
+/// Reads what [`to_binary`] wrote
+pub fn from_binary(bytes: &[u8]) -> Result<Vec<Candle>, String> {
+    if !bytes.len().is_multiple_of(BINARY_RECORD) {
+        return Err(format!("{} bytes is not a whole number of candles", bytes.len()));
+    }
+    bytes
+        .chunks_exact(BINARY_RECORD)
+        .map(|record| {
+            // Always in bounds, records are exactly BINARY_RECORD long
+            let field = |i: usize| -> [u8; 8] { record[i * 8..(i + 1) * 8].try_into().unwrap_or_default() };
+            let millis = i64::from_le_bytes(field(0));
+            let timestamp = DateTime::from_timestamp_millis(millis).ok_or_else(|| format!("bad timestamp {millis}"))?;
+            let value = |i| f64::from_le_bytes(field(i));
+            Ok(Candle { timestamp, open: value(1), high: value(2), low: value(3), close: value(4), volume: value(5) })
+        })
+        .collect()
+}
@@ -1,19 +1,30 @@
+pub mod alert;
+pub mod auth;
+pub mod candles;
+pub mod paper;
+pub mod scanner;
 pub mod stock;
+pub mod strategy;
+pub mod watchlist;
 
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum ProviderType {
+    #[serde(alias = "yahoo")]
     Yahoo,
+    #[serde(alias = "binance")]
     Binance,
 }
 
-impl From<&str> for ProviderType {
-    fn from(s: &str) -> Self {
+impl std::str::FromStr for ProviderType {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
-            "yahoo" => ProviderType::Yahoo,
-            "binance" => ProviderType::Binance,
-            _ => panic!("Unknown provider type: {}", s),
+            "yahoo" => Ok(Self::Yahoo),
+            "binance" => Ok(Self::Binance),
+            _ => Err(format!("unknown provider {s}")),
         }
     }
 }
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use dnn_core::time::{TimeInterval, Timestamp};
+use dnn_core::{ExecutionReport, Order, Position};
+use crate::ProviderType;
+
+/// Strategy a paper session runs
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum PaperStrategy {
+    SmaCross { short: usize, long: usize },
+    /// A strategy saved through `/strategies`. Sessions run the version that
+    /// is current when they start, also after being resumed.
+    Saved {
+        strategy_id: String,
+        /// Filled in when the session starts
+        #[serde(default)]
+        version: Option<u32>,
+    },
+}
+
+/// Body of `POST /paper`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StartPaperReq {
+    pub provider: ProviderType,
+    pub symbol: String,
+    pub interval: TimeInterval,
+    pub strategy: PaperStrategy,
+    pub starting_cash: f64,
+}
+
+/// Current state of a paper trading session
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PaperSessionInfo {
+    pub id: String,
+    pub config: StartPaperReq,
+    pub started: Timestamp,
+    pub running: bool,
+    pub equity: f64,
+    pub cash: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub positions: Vec<Position>,
+}
+
+/// Pushed to clients subscribed to `/paper/{id}/stream`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum PaperEvent {
+    /// Sent once on subscribe
+    Session { info: PaperSessionInfo },
+    Order { order: Order },
+    Fill { report: ExecutionReport },
+    Rejected { report: ExecutionReport },
+    /// Portfolio valuation after each bar, in the base currency
+    Pnl {
+        ts: Timestamp,
+        equity: f64,
+        cash: f64,
+        realized: f64,
+        unrealized: f64,
+        positions: Vec<Position>,
+    },
+    /// Line logged by the strategy
+    Log { ts: Timestamp, message: String },
+    Stopped { reason: Option<String> },
+    Error { message: String },
+}
@@ -0,0 +1,71 @@
+use serde::{Deserialize, Serialize};
+use dnn_core::time::Timestamp;
+
+/// Most rows `POST /scanner/query` returns at once
+pub const MAX_ROWS: usize = 500;
+/// Page size when the query doesn't set `limit`
+pub const DEFAULT_ROWS: usize = 50;
+
+/// Names usable in scanner expressions besides the fields and indicators
+/// of daily bars, in the order the scanner passes their values
+pub const VARIABLES: &[&str] = &["price", "change", "change_pct", "avg_volume", "volume_ratio", "market_cap"];
+
+/// Body of `POST /scanner/query`
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScannerQuery {
+    /// Condition every row has to meet, e.g.
+    /// `change_pct > 5 AND volume_ratio > 2 AND RSI(14) < 70`; all of the
+    /// universe if unset
+    #[serde(default)]
+    pub filter: Option<String>,
+    /// Only rows of this sector, ignoring case
+    #[serde(default)]
+    pub sector: Option<String>,
+    /// Formulas to compute for every row, e.g. `RSI(14)` or `close / SMA(50)`
+    #[serde(default)]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub sort: Option<ScannerSort>,
+    #[serde(default)]
+    pub offset: usize,
+    /// At most [`MAX_ROWS`], [`DEFAULT_ROWS`] if unset
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScannerSort {
+    /// `symbol`, `name`, `sector` or a formula. Rows without a value come last.
+    pub by: String,
+    #[serde(default)]
+    pub descending: bool,
+}
+
+/// One symbol of the universe, as of its latest daily bar
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScannerRow {
+    pub symbol: String,
+    pub name: Option<String>,
+    pub sector: Option<String>,
+    pub price: f64,
+    /// Since the previous close
+    pub change: Option<f64>,
+    pub change_pct: Option<f64>,
+    pub volume: f64,
+    /// Mean volume of the days before
+    pub avg_volume: Option<f64>,
+    /// `volume` over `avg_volume`
+    pub volume_ratio: Option<f64>,
+    pub market_cap: Option<f64>,
+    /// Values of the query's `columns`, in order
+    pub columns: Vec<Option<f64>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ScannerPage {
+    pub rows: Vec<ScannerRow>,
+    /// Rows matching the filter, over all pages
+    pub total: usize,
+    /// When the universe was last refreshed
+    pub updated: Option<Timestamp>,
+}
@@ -3,10 +3,74 @@ use dnn_core::market::Candle;
 use dnn_core::time::{TimeInterval, Timestamp};
 use crate::ProviderType;
 
-#[derive(Serialize, Deserialize, Debug)]
+/// Version of the `/live/stock` protocol. Requests must carry it as `v`.
+pub const PROTOCOL_VERSION: u32 = 1;
+/// How often the server pings a connection
+pub const HEARTBEAT_SECS: u64 = 15;
+/// Connections the server hears nothing from for this long are closed.
+/// Browsers answer pings on their own; other clients should too, or send
+/// [`StockWatchReqMsg::Ping`].
+pub const IDLE_TIMEOUT_SECS: u64 = 45;
+/// Subscriptions one connection may hold at once
+pub const MAX_SUBSCRIPTIONS: usize = 50;
+
+/// A request of the client, e.g.
+/// `{"v": 1, "action": "day", "id": "a", "provider": "Yahoo", "symbol": "AAPL", "interval": "1m"}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StockWatchReq {
+    /// [`PROTOCOL_VERSION`] the client speaks
+    pub v: u32,
+    #[serde(flatten)]
+    pub msg: StockWatchReqMsg,
+}
+
+impl StockWatchReq {
+    pub fn new(msg: StockWatchReqMsg) -> Self {
+        Self { v: PROTOCOL_VERSION, msg }
+    }
+
+    /// Reads a request, checking its version. Errors carry the request's
+    /// `id` whenever it has a readable one.
+    pub fn parse(text: &str) -> Result<StockWatchReqMsg, StockWatchError> {
+        /// What's needed to answer even requests that don't parse
+        #[derive(Deserialize)]
+        struct Envelope {
+            v: Option<u32>,
+            id: Option<String>,
+        }
+
+        let envelope: Envelope = serde_json::from_str(text)
+            .map_err(|e| StockWatchError::new(None, ErrorCode::InvalidRequest, format!("invalid request: {e}")))?;
+        match envelope.v {
+            Some(PROTOCOL_VERSION) => {}
+            Some(v) => {
+                return Err(StockWatchError::new(
+                    envelope.id,
+                    ErrorCode::UnsupportedVersion,
+                    format!("protocol version {v} is not supported, the server speaks {PROTOCOL_VERSION}"),
+                ));
+            }
+            None => {
+                return Err(StockWatchError::new(
+                    envelope.id,
+                    ErrorCode::UnsupportedVersion,
+                    format!("requests need a protocol version `v`, the server speaks {PROTOCOL_VERSION}"),
+                ));
+            }
+        }
+        serde_json::from_str::<Self>(text)
+            .map(|req| req.msg)
+            .map_err(|e| StockWatchError::new(envelope.id, ErrorCode::InvalidRequest, format!("invalid request: {e}")))
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(tag = "action", rename_all = "lowercase")]
 pub enum StockWatchReqMsg {
+    /// Live candles. Reusing the `id` of a subscription replaces it.
     Day { id: String, provider: ProviderType, symbol: String, interval: TimeInterval },
+    /// Replay of historical candles, one per second times `playback_speed`,
+    /// followed by [`StockWatchResMsg::End`]
     Night {
         id: String,
         provider: ProviderType,
@@ -17,11 +81,33 @@ pub enum StockWatchReqMsg {
         playback_speed: Option<u32>
     },
     Unsubscribe { id: String },
+    /// Answered with [`StockWatchResMsg::Subscriptions`]
+    List { id: String },
+    /// Answered with [`StockWatchResMsg::Pong`], for clients that can't
+    /// answer WebSocket pings
+    Ping,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+/// An open subscription of the connection
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Subscription {
+    pub id: String,
+    pub provider: ProviderType,
+    pub symbol: String,
+    pub interval: TimeInterval,
+    /// A [`StockWatchReqMsg::Night`] replay rather than live candles
+    pub replay: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 #[serde(tag = "type", rename_all = "lowercase")]
 pub enum StockWatchResMsg {
+    /// Sent once on connect
+    Hello { v: u32, heartbeat_secs: u64, idle_timeout_secs: u64 },
+    /// The subscription is open; its candles follow
+    Subscribed { subscription: Subscription },
+    Unsubscribed { id: String },
+    Subscriptions { id: String, subscriptions: Vec<Subscription> },
     Candle {
         id: String,
         provider: ProviderType,
@@ -29,7 +115,40 @@ pub enum StockWatchResMsg {
         interval: TimeInterval,
         candle: Candle,
     },
-    Error { message: String },
+    /// No more candles will come for the subscription: its replay is over,
+    /// or the provider closed its live stream
+    End { id: String },
+    Pong,
+    Error(StockWatchError),
+}
+
+/// What went wrong with a request, or with a subscription later on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct StockWatchError {
+    /// Of the request or subscription, when known
+    pub id: Option<String>,
+    pub code: ErrorCode,
+    pub message: String,
 }
 
+impl StockWatchError {
+    pub fn new(id: Option<String>, code: ErrorCode, message: impl Into<String>) -> Self {
+        Self { id, code, message: message.into() }
+    }
+}
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// Not JSON, or not a request of this protocol
+    InvalidRequest,
+    /// `v` is missing or not [`PROTOCOL_VERSION`]
+    UnsupportedVersion,
+    UnknownProvider,
+    /// Unsubscribing an `id` that isn't subscribed
+    UnknownSubscription,
+    /// The provider failed to open or keep up a subscription
+    ProviderError,
+    /// The connection already holds [`MAX_SUBSCRIPTIONS`]
+    TooManySubscriptions,
+}
@@ -0,0 +1,144 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use dnn_core::time::{TimeInterval, Timestamp};
+use dnn_core::{ExecutionReport, OrderSide};
+use crate::ProviderType;
+
+/// A saved strategy, as served by `/strategies`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Strategy {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub language: StrategyLanguage,
+    pub code: String,
+    pub status: StrategyStatus,
+    /// Bumped every time the code is edited, starting at 1
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Summary of the latest backtest
+    pub backtest_results: Option<BacktestResult>,
+}
+
+/// How a strategy's `code` is interpreted
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrategyLanguage {
+    /// A Rhai script with `on_bar(ctx, bar)` and friends
+    Rhai,
+    /// Declarative entry/exit rules in YAML
+    Rules,
+    /// A built-in strategy as JSON: `{ "name": "ma_cross", "params": { ... } }`
+    Builtin,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StrategyStatus {
+    Draft,
+    Testing,
+    Active,
+    Paused,
+}
+
+/// An earlier revision of a strategy's code, from `/strategies/{id}/versions`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrategyVersion {
+    pub version: u32,
+    pub language: StrategyLanguage,
+    pub code: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /strategies` and `PUT /strategies/{id}`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateStrategyReq {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub language: StrategyLanguage,
+    pub code: String,
+}
+
+/// Body of `POST /strategies/{id}/backtest`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunBacktestReq {
+    pub provider: ProviderType,
+    pub symbol: String,
+    pub interval: TimeInterval,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub starting_cash: f64,
+}
+
+/// Headline numbers of a backtest. Returns and drawdowns are in percent.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BacktestResult {
+    pub total_return: f64,
+    pub sharpe_ratio: f64,
+    pub max_drawdown: f64,
+    pub win_rate: f64,
+    pub total_trades: i32,
+}
+
+/// Where a backtest job is in its lifecycle
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    /// Done, failed or cancelled: nothing will change any more
+    pub fn is_finished(self) -> bool {
+        matches!(self, Self::Done | Self::Failed | Self::Cancelled)
+    }
+}
+
+/// A backtest job, kept under `/strategies/{id}/backtest/{backtest_id}`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BacktestRun {
+    pub id: String,
+    pub strategy_id: String,
+    /// Version of the strategy's code the job runs
+    pub strategy_version: u32,
+    pub config: RunBacktestReq,
+    pub status: JobStatus,
+    /// Fraction of bars processed, 0 to 1
+    pub progress: f64,
+    pub submitted_at: Timestamp,
+    pub started_at: Option<Timestamp>,
+    pub finished_at: Option<Timestamp>,
+    /// Set once the job is done
+    pub result: Option<BacktestResult>,
+    /// Why the job failed
+    pub error: Option<String>,
+    /// Equity after each bar so far. Left empty in listings.
+    pub equity_curve: Vec<f64>,
+}
+
+/// A fill of a finished job, served by `/strategies/{id}/backtest/{backtest_id}/trades`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BacktestTrade {
+    pub symbol: String,
+    /// Unknown for fills whose order wasn't recorded
+    pub side: Option<OrderSide>,
+    #[serde(flatten)]
+    pub fill: ExecutionReport,
+}
+
+/// Pushed to clients subscribed to `/strategies/{id}/backtest/{backtest_id}/stream`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum BacktestEvent {
+    /// Sent once on subscribe
+    Snapshot { run: BacktestRun },
+    Started { ts: Timestamp },
+    /// Equity points added since the previous progress event
+    Progress { progress: f64, ts: Timestamp, equity: Vec<f64> },
+    /// Last event of the stream, whatever the outcome
+    Finished { run: BacktestRun },
+    Error { message: String },
+}
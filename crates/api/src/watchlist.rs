@@ -0,0 +1,20 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// A named list of symbols to keep an eye on
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Watchlist {
+    pub id: String,
+    pub name: String,
+    /// In the order they were added
+    pub symbols: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Body of `POST /watchlists` and `PUT /watchlists/{id}`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WatchlistReq {
+    pub name: String,
+    #[serde(default)]
+    pub symbols: Vec<String>,
+}
@@ -0,0 +1,40 @@
+use api::alert::{AlertCondition, CreateAlertReq, Direction};
+use api::ProviderType;
+use dnn_core::time::TimeInterval;
+
+#[test]
+fn test_create_request() {
+    let req: CreateAlertReq = serde_json::from_str(
+        r#"{
+            "provider": "yahoo",
+            "symbol": "AAPL",
+            "interval": "1m",
+            "condition": {"kind": "indicator", "formula": "RSI(14)", "direction": "above", "threshold": 70}
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(req.provider, ProviderType::Yahoo);
+    assert_eq!(req.interval, TimeInterval::Minute1);
+    assert_eq!(req.condition, AlertCondition::Indicator {
+        formula: "RSI(14)".to_owned(),
+        direction: Direction::Above,
+        threshold: 70.0,
+    });
+    assert!(!req.repeat);
+    assert_eq!((req.webhook, req.email), (None, None));
+
+    let spike: AlertCondition = serde_json::from_str(r#"{"kind": "volume_spike", "ratio": 3, "period": 20}"#).unwrap();
+    assert_eq!(spike, AlertCondition::VolumeSpike { ratio: 3.0, period: 20 });
+}
+
+#[test]
+fn test_condition_descriptions() {
+    let price = AlertCondition::Price { direction: Direction::Below, price: 182.5 };
+    assert_eq!(price.to_string(), "price below 182.5");
+    assert_eq!(AlertCondition::ChangePct { pct: -5.0 }.to_string(), "drop of 5%");
+    assert_eq!(AlertCondition::ChangePct { pct: 2.5 }.to_string(), "rise of 2.5%");
+    assert_eq!(
+        AlertCondition::VolumeSpike { ratio: 3.0, period: 20 }.to_string(),
+        "volume 3x its 20 candle average"
+    );
+}
@@ -0,0 +1,83 @@
+use chrono::{Duration, TimeZone, Utc};
+use api::candles::{from_binary, to_binary, to_csv, CandleFormat, CandlePage, CandlesQuery, BINARY_RECORD};
+use api::ProviderType;
+use dnn_core::market::Candle;
+use dnn_core::time::TimeInterval;
+
+fn candles(count: i64) -> Vec<Candle> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    (0..count)
+        .map(|i| {
+            let price = 100.0 + i as f64;
+            Candle::new(start + Duration::days(i), price, price + 1.5, price - 0.5, price + 0.25, 1000.0).unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn test_query_string() {
+    let query: CandlesQuery = serde_urlencoded::from_str(
+        "provider=yahoo&symbol=AAPL&interval=1d&start=2024-01-01T00:00:00Z&end=2024-02-01T00:00:00Z&resample=1w&format=csv",
+    )
+    .unwrap();
+    assert_eq!(query.provider, ProviderType::Yahoo);
+    assert_eq!(query.interval, TimeInterval::Day1);
+    assert_eq!(query.resample, Some(TimeInterval::Week1));
+    assert_eq!(query.format, Some(CandleFormat::Csv));
+    assert!(!query.adjusted);
+    assert_eq!(query.limit, None);
+
+    assert_eq!(CandleFormat::from_accept("text/html, text/csv;q=0.9"), Some(CandleFormat::Csv));
+    assert_eq!(CandleFormat::from_accept("*/*"), None);
+}
+
+#[test]
+fn test_cursor_walks_every_candle_once() {
+    let all = candles(10);
+    let mut seen = Vec::new();
+    let mut cursor = None;
+    loop {
+        let page = CandlePage::after(all.clone(), cursor.as_deref(), 4).unwrap();
+        assert!(page.candles.len() <= 4);
+        seen.extend(page.candles);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(seen, all);
+
+    // Pages come out the same when only the candles from the cursor on are fetched
+    let mut fetched = Vec::new();
+    let mut cursor: Option<String> = None;
+    loop {
+        let from = cursor.as_deref().map(|c| CandlePage::cursor_time(c).unwrap());
+        let tail = all.iter().filter(|c| from.is_none_or(|from| c.timestamp >= from)).cloned().collect();
+        let page = CandlePage::after(tail, cursor.as_deref(), 4).unwrap();
+        fetched.extend(page.candles);
+        match page.next_cursor {
+            Some(next) => cursor = Some(next),
+            None => break,
+        }
+    }
+    assert_eq!(fetched, all);
+
+    assert!(CandlePage::after(candles(3), None, 3).unwrap().next_cursor.is_none());
+    assert!(CandlePage::after(all, Some("yesterday"), 4).is_err());
+    assert!(CandlePage::cursor_time("yesterday").is_err());
+}
+
+#[test]
+fn test_csv_and_binary() {
+    let candles = candles(3);
+    let csv = to_csv(&candles);
+    let mut lines = csv.lines();
+    assert_eq!(lines.next(), Some("timestamp,open,high,low,close,volume"));
+    assert_eq!(lines.next(), Some("2024-01-01T00:00:00+00:00,100,101.5,99.5,100.25,1000"));
+    assert_eq!(lines.count(), 2);
+
+    let bytes = to_binary(&candles);
+    assert_eq!(bytes.len(), 3 * BINARY_RECORD);
+    assert_eq!(from_binary(&bytes).unwrap(), candles);
+    assert!(from_binary(&bytes[1..]).is_err());
+}
@@ -0,0 +1,37 @@
+use api::stock::{ErrorCode, StockWatchReq, StockWatchReqMsg, StockWatchResMsg, PROTOCOL_VERSION};
+use api::ProviderType;
+use dnn_core::time::TimeInterval;
+
+#[test]
+fn test_versioned_requests() {
+    let req = StockWatchReq::new(StockWatchReqMsg::Day {
+        id: "a".to_owned(),
+        provider: ProviderType::Yahoo,
+        symbol: "AAPL".to_owned(),
+        interval: TimeInterval::Minute1,
+    });
+    let text = serde_json::to_string(&req).unwrap();
+    assert!(text.contains(&format!(r#""v":{PROTOCOL_VERSION}"#)));
+    assert_eq!(StockWatchReq::parse(&text).unwrap(), req.msg);
+    assert_eq!(StockWatchReq::parse(r#"{"v": 1, "action": "ping"}"#).unwrap(), StockWatchReqMsg::Ping);
+    assert_eq!(
+        StockWatchReq::parse(r#"{"v": 1, "action": "list", "id": "l"}"#).unwrap(),
+        StockWatchReqMsg::List { id: "l".to_owned() }
+    );
+}
+
+#[test]
+fn test_errors_name_the_request() {
+    let error = StockWatchReq::parse(r#"{"action": "unsubscribe", "id": "a"}"#).unwrap_err();
+    assert_eq!((error.id.as_deref(), error.code), (Some("a"), ErrorCode::UnsupportedVersion));
+    let error = StockWatchReq::parse(r#"{"v": 2, "action": "unsubscribe", "id": "a"}"#).unwrap_err();
+    assert_eq!(error.code, ErrorCode::UnsupportedVersion);
+
+    let error = StockWatchReq::parse(r#"{"v": 1, "action": "day", "id": "b", "symbol": "AAPL"}"#).unwrap_err();
+    assert_eq!((error.id.as_deref(), error.code), (Some("b"), ErrorCode::InvalidRequest));
+    let error = StockWatchReq::parse("not json").unwrap_err();
+    assert_eq!((error.id.as_deref(), error.code), (None, ErrorCode::InvalidRequest));
+
+    let text = serde_json::to_string(&StockWatchResMsg::Error(error)).unwrap();
+    assert!(text.starts_with(r#"{"type":"error","id":null,"code":"invalid_request","#));
+}
@@ -0,0 +1,82 @@
+-- Runs unchanged on SQLite and Postgres: timestamps are RFC 3339 text and
+-- structured values (configs, results, ledgers) are JSON text.
+
+CREATE TABLE strategies (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT NOT NULL,
+    language TEXT NOT NULL,
+    code TEXT NOT NULL,
+    status TEXT NOT NULL,
+    version BIGINT NOT NULL,
+    created_at TEXT NOT NULL,
+    backtest_results TEXT
+);
+
+CREATE TABLE strategy_versions (
+    strategy_id TEXT NOT NULL REFERENCES strategies (id),
+    version BIGINT NOT NULL,
+    language TEXT NOT NULL,
+    code TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    PRIMARY KEY (strategy_id, version)
+);
+
+CREATE TABLE backtests (
+    id TEXT PRIMARY KEY,
+    strategy_id TEXT NOT NULL REFERENCES strategies (id),
+    strategy_version BIGINT NOT NULL,
+    config TEXT NOT NULL,
+    status TEXT NOT NULL,
+    progress DOUBLE PRECISION NOT NULL,
+    submitted_at TEXT NOT NULL,
+    started_at TEXT,
+    finished_at TEXT,
+    result TEXT,
+    error TEXT
+);
+
+CREATE INDEX backtests_by_strategy ON backtests (strategy_id, submitted_at);
+
+CREATE TABLE backtest_trades (
+    backtest_id TEXT NOT NULL REFERENCES backtests (id),
+    seq BIGINT NOT NULL,
+    symbol TEXT NOT NULL,
+    -- Unknown for fills whose order wasn't recorded
+    side TEXT,
+    order_id BIGINT NOT NULL,
+    status TEXT NOT NULL,
+    filled_qty DOUBLE PRECISION NOT NULL,
+    fill_price DOUBLE PRECISION NOT NULL,
+    leaves_qty DOUBLE PRECISION NOT NULL,
+    ts TEXT NOT NULL,
+    PRIMARY KEY (backtest_id, seq)
+);
+
+CREATE TABLE backtest_equity (
+    backtest_id TEXT NOT NULL REFERENCES backtests (id),
+    seq BIGINT NOT NULL,
+    equity DOUBLE PRECISION NOT NULL,
+    PRIMARY KEY (backtest_id, seq)
+);
+
+CREATE TABLE watchlists (
+    id TEXT PRIMARY KEY,
+    name TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE watchlist_symbols (
+    watchlist_id TEXT NOT NULL REFERENCES watchlists (id),
+    position BIGINT NOT NULL,
+    symbol TEXT NOT NULL,
+    PRIMARY KEY (watchlist_id, symbol)
+);
+
+CREATE TABLE paper_portfolios (
+    id TEXT PRIMARY KEY,
+    config TEXT NOT NULL,
+    started TEXT NOT NULL,
+    ledger TEXT NOT NULL,
+    updated_at TEXT NOT NULL
+);
@@ -0,0 +1,24 @@
+CREATE TABLE users (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL UNIQUE,
+    password_hash TEXT NOT NULL,
+    created_at TEXT NOT NULL
+);
+
+CREATE TABLE api_keys (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id),
+    name TEXT NOT NULL,
+    prefix TEXT NOT NULL,
+    -- SHA-256 of the secret, hex encoded
+    key_hash TEXT NOT NULL UNIQUE,
+    created_at TEXT NOT NULL
+);
+
+-- Rows from before accounts existed have no owner and stay hidden
+ALTER TABLE strategies ADD COLUMN user_id TEXT REFERENCES users (id);
+ALTER TABLE watchlists ADD COLUMN user_id TEXT REFERENCES users (id);
+ALTER TABLE paper_portfolios ADD COLUMN user_id TEXT REFERENCES users (id);
+
+CREATE INDEX strategies_by_user ON strategies (user_id);
+CREATE INDEX watchlists_by_user ON watchlists (user_id);
@@ -0,0 +1,11 @@
+CREATE TABLE alerts (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES users (id),
+    config TEXT NOT NULL,
+    -- 1 while the alert is still checked, 0 once a one-off alert triggered
+    active BIGINT NOT NULL,
+    created_at TEXT NOT NULL,
+    last_triggered TEXT
+);
+
+CREATE INDEX alerts_by_user ON alerts (user_id);
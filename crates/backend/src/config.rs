@@ -1,24 +1,249 @@
-use std::env;
-use config::BaseConfig;
+use std::collections::HashSet;
+use api::ProviderType;
+use axum::http::HeaderValue;
+use chrono::Duration;
+use config::{BaseConfig, ConfigError, Key, Layers, LogLevel, Sources};
+use rand::distr::{Alphanumeric, SampleString};
+use risk::RiskLimits;
+use tower_http::cors::AllowOrigin;
+use tracing::{warn, Level};
+
+/// Shortest `jwt.secret` accepted, HS256 wants at least 256 bits
+const MIN_JWT_SECRET: usize = 32;
 
 #[derive(Debug, Clone)]
 pub struct BackendConfig {
     pub base: BaseConfig,
     pub port: u16,
+    /// Browser origins allowed to call the API
+    pub cors_origin: AllowOrigin,
+    /// `sqlite://…` or `postgres://…`
+    pub database_url: String,
+    /// Backtest jobs allowed to run at once
+    pub backtest_workers: usize,
+    /// Signs session tokens
+    pub jwt_secret: String,
+    /// How long a session token stays valid
+    pub token_ttl: Duration,
+    /// Market data providers to serve
+    pub providers: Vec<ProviderType>,
+    /// Pre-trade limits of paper trading sessions
+    pub risk: RiskLimits,
+    /// How long `historical` responses are cached, zero for not at all
+    pub history_ttl: std::time::Duration,
+    /// Symbols the scanner screens
+    pub scanner_universe: Vec<String>,
+    pub scanner_provider: ProviderType,
+    /// How often the scanner refetches its universe
+    pub scanner_refresh: std::time::Duration,
+    /// Instrument master file giving the scanner names and sectors
+    pub scanner_instruments: Option<String>,
+    /// SMTP server alert emails go through; no emails if unset
+    pub smtp_host: Option<String>,
+    pub smtp_port: u16,
+    /// Sender address of alert emails
+    pub smtp_from: String,
+    /// Shared cache of all replicas; in memory if unset
+    #[cfg(feature = "redis")]
+    pub redis_url: Option<String>,
+    #[cfg(feature = "redis")]
+    pub redis_password: Option<String>,
 }
 
 impl BackendConfig {
-    pub fn load() -> anyhow::Result<Self> {
-        dotenvy::from_path("crates/backend/.env.public")?;
+    /// Every setting the backend reads, see `traiter.example.toml`
+    const KEYS: &[Key] = &[
+        Key::new("port").with_default("3000"),
+        Key::new("cors_origin").with_default("http://localhost:8080"),
+        Key::new("database.url").secret(),
+        Key::new("database.host"),
+        Key::new("database.port").with_default("5432"),
+        Key::new("database.name").with_default("traiter"),
+        Key::new("database.user").with_default("postgres"),
+        Key::new("database.password").secret(),
+        Key::new("backtest.workers").with_default("2"),
+        Key::new("jwt.secret").secret(),
+        Key::new("jwt.ttl_hours").with_default("24"),
+        Key::new("providers").with_default("yahoo"),
+        Key::new("cache.history_ttl_secs").with_default("300"),
+        Key::new("scanner.universe").with_default("AAPL,MSFT,NVDA,GOOGL,AMZN,META,TSLA,AMD,JPM,BAC,JNJ,PFE"),
+        Key::new("scanner.provider").with_default("yahoo"),
+        Key::new("scanner.refresh_secs").with_default("300"),
+        Key::new("scanner.instruments"),
+        Key::new("smtp.host"),
+        Key::new("smtp.port").with_default("1025"),
+        Key::new("smtp.from").with_default("alerts@traiter.local"),
+        Key::new("redis.url").secret(),
+        Key::new("redis.password").secret(),
+        Key::new("risk.max_position_qty"),
+        Key::new("risk.max_order_notional"),
+        Key::new("risk.max_gross_exposure"),
+        Key::new("risk.max_net_exposure"),
+        Key::new("risk.max_orders_per_minute"),
+        Key::new("risk.max_daily_loss"),
+        Key::new("risk.restricted_symbols"),
+        Key::new("risk.allowed_symbols"),
+        Key::new("risk.price_band_pct"),
+    ];
+
+    /// Defaults, then `.env.public`, `traiter.toml`, the environment and
+    /// the command line
+    pub fn load() -> Result<Self, ConfigError> {
+        let keys = [BaseConfig::KEYS, Self::KEYS].concat();
+        Self::from_layers(&Layers::load(&keys, &Sources::from_process())?)
+    }
+
+    fn from_layers(layers: &Layers) -> Result<Self, ConfigError> {
+        let backtest_workers = layers.get("backtest.workers")?;
+        if backtest_workers == 0 {
+            return Err(layers.invalid("backtest.workers", "must be at least 1"));
+        }
+        let ttl_hours = layers.get("jwt.ttl_hours")?;
+        if ttl_hours <= 0 {
+            return Err(layers.invalid("jwt.ttl_hours", "must be positive"));
+        }
 
-        let port: u16 = env::var("PORT")
-            .unwrap_or_else(|_| "3000".to_string())
-            .parse()
-            .expect("PORT must be a number");
+        let providers: Vec<ProviderType> = layers.get_list("providers")?;
+        if providers.is_empty() {
+            return Err(layers.invalid("providers", "at least one provider is needed"));
+        }
+        let scanner_provider = layers.get("scanner.provider")?;
+        if !providers.contains(&scanner_provider) {
+            return Err(layers.invalid("scanner.provider", "is not one of `providers`"));
+        }
+        let scanner_refresh: u64 = layers.get("scanner.refresh_secs")?;
+        if scanner_refresh == 0 {
+            return Err(layers.invalid("scanner.refresh_secs", "must be positive"));
+        }
+        #[cfg(not(feature = "redis"))]
+        if layers.get_opt::<String>("redis.url")?.is_some() {
+            return Err(layers.invalid("redis.url", "the backend was built without the redis feature"));
+        }
 
         Ok(Self {
-            base: BaseConfig::load()?,
-            port
+            base: BaseConfig::from_layers(layers)?,
+            port: layers.get("port")?,
+            cors_origin: cors_origin(layers)?,
+            database_url: database_url(layers)?,
+            backtest_workers,
+            jwt_secret: jwt_secret(layers)?,
+            token_ttl: Duration::hours(ttl_hours),
+            providers,
+            risk: risk_limits(layers)?,
+            history_ttl: std::time::Duration::from_secs(layers.get("cache.history_ttl_secs")?),
+            scanner_universe: layers.get_list("scanner.universe")?,
+            scanner_provider,
+            scanner_refresh: std::time::Duration::from_secs(scanner_refresh),
+            scanner_instruments: layers.get_opt("scanner.instruments")?,
+            smtp_host: layers.get_opt("smtp.host")?,
+            smtp_port: layers.get("smtp.port")?,
+            smtp_from: layers.get("smtp.from")?,
+            #[cfg(feature = "redis")]
+            redis_url: layers.get_opt("redis.url")?,
+            #[cfg(feature = "redis")]
+            redis_password: layers.get_opt("redis.password")?,
         })
     }
+
+    pub fn tracing_level(&self) -> Level {
+        match self.base.log_level {
+            LogLevel::Error => Level::ERROR,
+            LogLevel::Warn => Level::WARN,
+            LogLevel::Info => Level::INFO,
+            LogLevel::Debug => Level::DEBUG,
+            LogLevel::Trace => Level::TRACE,
+        }
+    }
+}
+
+/// A comma separated list of origins, or `*` for any
+fn cors_origin(layers: &Layers) -> Result<AllowOrigin, ConfigError> {
+    let origins: Vec<String> = layers.get_list("cors_origin")?;
+    if origins.iter().any(|o| o == "*") {
+        return Ok(AllowOrigin::any());
+    }
+    let origins = origins
+        .iter()
+        .map(|o| HeaderValue::from_str(o).map_err(|e| layers.invalid("cors_origin", format!("bad origin `{o}` ({e})"))))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(AllowOrigin::list(origins))
+}
+
+/// `database.url`, else Postgres from `database.host` and the other
+/// `database.*` settings, else `traiter.db` in the working directory
+fn database_url(layers: &Layers) -> Result<String, ConfigError> {
+    if let Some(url) = layers.get_opt::<String>("database.url")? {
+        if !["sqlite:", "postgres:", "postgresql:"].iter().any(|scheme| url.starts_with(scheme)) {
+            return Err(layers.invalid("database.url", "expected a sqlite: or postgres: url"));
+        }
+        return Ok(url);
+    }
+    let Some(host) = layers.get_opt::<String>("database.host")? else {
+        return Ok("sqlite://traiter.db?mode=rwc".to_owned());
+    };
+    let port: u16 = layers.get("database.port")?;
+    let name: String = layers.get("database.name")?;
+    let user: String = layers.get("database.user")?;
+    let password: String = layers.get("database.password")?;
+    let (user, password, name) = (url_encode(&user), url_encode(&password), url_encode(&name));
+    Ok(format!("postgres://{user}:{password}@{host}:{port}/{name}"))
+}
+
+/// Percent-encode everything but RFC 3986's unreserved characters, so a
+/// password holding `@`, `:` or `/` can't break up the url
+fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => char::from(b).to_string(),
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// Without a secret, a random one so development works without any setup;
+/// sessions then end with the process
+fn jwt_secret(layers: &Layers) -> Result<String, ConfigError> {
+    match layers.get_opt::<String>("jwt.secret")? {
+        Some(secret) if secret.len() < MIN_JWT_SECRET => Err(layers.invalid(
+            "jwt.secret",
+            format!("must be at least {MIN_JWT_SECRET} characters"),
+        )),
+        Some(secret) => Ok(secret),
+        None => {
+            warn!("no jwt.secret set, signing sessions with a random secret");
+            Ok(Alphanumeric.sample_string(&mut rand::rng(), 64))
+        }
+    }
+}
+
+fn risk_limits(layers: &Layers) -> Result<RiskLimits, ConfigError> {
+    let price_band_pct = positive(layers, "risk.price_band_pct")?;
+    if price_band_pct.is_some_and(|band| band > 1.0) {
+        return Err(layers.invalid("risk.price_band_pct", "is a fraction, at most 1"));
+    }
+    let allowed: Vec<String> = layers.get_list("risk.allowed_symbols")?;
+
+    Ok(RiskLimits {
+        max_position_qty: positive(layers, "risk.max_position_qty")?,
+        max_order_notional: positive(layers, "risk.max_order_notional")?,
+        max_gross_exposure: positive(layers, "risk.max_gross_exposure")?,
+        max_net_exposure: positive(layers, "risk.max_net_exposure")?,
+        max_orders_per_minute: positive(layers, "risk.max_orders_per_minute")?,
+        max_daily_loss: positive(layers, "risk.max_daily_loss")?,
+        restricted_symbols: layers.get_list::<String>("risk.restricted_symbols")?.into_iter().collect(),
+        allowed_symbols: (!allowed.is_empty()).then(|| allowed.into_iter().collect::<HashSet<_>>()),
+        price_band_pct,
+    })
+}
+
+/// An optional limit, which has to be above zero when set
+fn positive<T>(layers: &Layers, key: &str) -> Result<Option<T>, ConfigError>
+where
+    T: std::str::FromStr + PartialOrd + Default,
+    T::Err: std::fmt::Display,
+{
+    match layers.get_opt::<T>(key)? {
+        Some(limit) if limit <= T::default() => Err(layers.invalid(key, "must be positive")),
+        limit => Ok(limit),
+    }
 }
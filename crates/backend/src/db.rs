@@ -0,0 +1,119 @@
+//! Storage for everything the backend has to remember across restarts.
+//!
+//! One schema serves sqlite (the default, and tests) and Postgres (compose),
+//! through sqlx's `Any` driver. Timestamps are stored as RFC 3339 text and
+//! nested values as JSON so the same SQL runs on both.
+
+mod alerts;
+mod backtests;
+mod paper;
+mod strategies;
+mod users;
+mod watchlists;
+
+pub use paper::PaperPortfolio;
+
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use sqlx::any::{install_default_drivers, AnyPoolOptions};
+use sqlx::migrate::Migrator;
+use sqlx::AnyPool;
+
+static MIGRATOR: Migrator = sqlx::migrate!();
+
+/// Handle to the database; cheap to clone
+#[derive(Clone, Debug)]
+pub struct Database {
+    pool: AnyPool,
+}
+
+impl Database {
+    /// Connect to `url` (`sqlite://…` or `postgres://…`) and bring the
+    /// schema up to date
+    pub async fn connect(url: &str) -> anyhow::Result<Self> {
+        install_default_drivers();
+        let pool = AnyPoolOptions::new()
+            .connect(url)
+            .await
+            .with_context(|| format!("failed to connect to {}", redact(url)))?;
+        Self::migrate(pool).await
+    }
+
+    /// A private in-memory sqlite database that lives as long as the handle
+    pub async fn in_memory() -> anyhow::Result<Self> {
+        install_default_drivers();
+        // Every sqlite connection to `:memory:` is its own database, so
+        // keep exactly one open
+        let pool = AnyPoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect("sqlite::memory:")
+            .await?;
+        Self::migrate(pool).await
+    }
+
+    async fn migrate(pool: AnyPool) -> anyhow::Result<Self> {
+        MIGRATOR.run(&pool).await.context("failed to run migrations")?;
+        Ok(Self { pool })
+    }
+}
+
+/// Hide the password of a connection url in error messages
+fn redact(url: &str) -> String {
+    match (url.find("://"), url.rfind('@')) {
+        (Some(scheme), Some(at)) if at > scheme => {
+            let credentials = &url[scheme + 3..at];
+            let user = credentials.split(':').next().unwrap_or_default();
+            format!("{}{user}:***{}", &url[..scheme + 3], &url[at..])
+        }
+        _ => url.to_owned(),
+    }
+}
+
+/// Rows written by one multi-row `INSERT`, well inside the bind parameter
+/// limits of sqlite and Postgres
+const BATCH_ROWS: usize = 100;
+
+/// `($1, $2), ($3, $4)` for `rows` rows of `columns` values each
+fn placeholders(rows: usize, columns: usize) -> String {
+    (0..rows)
+        .map(|row| {
+            let values: Vec<String> = (1..=columns).map(|column| format!("${}", row * columns + column)).collect();
+            format!("({})", values.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn ts_text(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn parse_ts(text: &str) -> anyhow::Result<DateTime<Utc>> {
+    Ok(DateTime::parse_from_rfc3339(text)
+        .with_context(|| format!("bad timestamp {text}"))?
+        .with_timezone(&Utc))
+}
+
+fn json<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+fn from_json<T: DeserializeOwned>(text: &str) -> anyhow::Result<T> {
+    Ok(serde_json::from_str(text)?)
+}
+
+/// Unit enum variants are stored bare, as `Draft` rather than `"Draft"`
+fn enum_text<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(s) => Ok(s),
+        other => anyhow::bail!("expected a unit variant, got {other}"),
+    }
+}
+
+fn parse_enum<T: DeserializeOwned>(text: &str) -> anyhow::Result<T> {
+    Ok(serde_json::from_value(serde_json::Value::String(text.to_owned()))?)
+}
@@ -0,0 +1,79 @@
+use api::alert::Alert;
+use dnn_core::time::Timestamp;
+use sqlx::any::AnyRow;
+use sqlx::Row;
+use super::{from_json, json, parse_ts, ts_text, Database};
+
+const COLUMNS: &str = "id, config, active, created_at, last_triggered";
+
+fn alert_from_row(row: &AnyRow) -> anyhow::Result<Alert> {
+    Ok(Alert {
+        id: row.try_get("id")?,
+        config: from_json(&row.try_get::<String, _>("config")?)?,
+        active: row.try_get::<i64, _>("active")? != 0,
+        created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+        last_triggered: row
+            .try_get::<Option<String>, _>("last_triggered")?
+            .as_deref()
+            .map(parse_ts)
+            .transpose()?,
+    })
+}
+
+impl Database {
+    pub async fn insert_alert(&self, owner: &str, alert: &Alert) -> anyhow::Result<()> {
+        sqlx::query(&format!("INSERT INTO alerts ({COLUMNS}, user_id) VALUES ($1, $2, $3, $4, $5, $6)"))
+            .bind(&alert.id)
+            .bind(json(&alert.config)?)
+            .bind(i64::from(alert.active))
+            .bind(ts_text(alert.created_at))
+            .bind(alert.last_triggered.map(ts_text))
+            .bind(owner)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// Every alert of `owner`, oldest first
+    pub async fn alerts(&self, owner: &str) -> anyhow::Result<Vec<Alert>> {
+        sqlx::query(&format!("SELECT {COLUMNS} FROM alerts WHERE user_id = $1 ORDER BY created_at, id"))
+            .bind(owner)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(alert_from_row)
+            .collect()
+    }
+
+    /// Alerts of every user that are still checked, as `(owner, alert)`
+    pub async fn active_alerts(&self) -> anyhow::Result<Vec<(String, Alert)>> {
+        sqlx::query(&format!("SELECT {COLUMNS}, user_id FROM alerts WHERE active = 1 ORDER BY created_at, id"))
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| Ok((row.try_get("user_id")?, alert_from_row(row)?)))
+            .collect()
+    }
+
+    /// Record that alert `id` triggered at `at`, and whether it stays active
+    pub async fn alert_triggered(&self, id: &str, at: Timestamp, active: bool) -> anyhow::Result<()> {
+        sqlx::query("UPDATE alerts SET last_triggered = $1, active = $2 WHERE id = $3")
+            .bind(ts_text(at))
+            .bind(i64::from(active))
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    /// `false` if `owner` has no such alert
+    pub async fn delete_alert(&self, owner: &str, id: &str) -> anyhow::Result<bool> {
+        let deleted = sqlx::query("DELETE FROM alerts WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(owner)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,191 @@
+use api::strategy::{BacktestRun, BacktestTrade, JobStatus};
+use dnn_core::ExecutionReport;
+use sqlx::any::{AnyArguments, AnyRow};
+use sqlx::query::Query;
+use sqlx::{Any, Row};
+use super::{enum_text, from_json, json, parse_enum, parse_ts, placeholders, ts_text, Database, BATCH_ROWS};
+
+const COLUMNS: &str = "id, strategy_id, strategy_version, config, status, progress, submitted_at, started_at, \
+                       finished_at, result, error";
+
+fn run_from_row(row: &AnyRow) -> anyhow::Result<BacktestRun> {
+    let optional_ts = |column: &str| -> anyhow::Result<_> {
+        row.try_get::<Option<String>, _>(column)?.as_deref().map(parse_ts).transpose()
+    };
+    Ok(BacktestRun {
+        id: row.try_get("id")?,
+        strategy_id: row.try_get("strategy_id")?,
+        strategy_version: u32::try_from(row.try_get::<i64, _>("strategy_version")?)?,
+        config: from_json(&row.try_get::<String, _>("config")?)?,
+        status: parse_enum(&row.try_get::<String, _>("status")?)?,
+        progress: row.try_get("progress")?,
+        submitted_at: parse_ts(&row.try_get::<String, _>("submitted_at")?)?,
+        started_at: optional_ts("started_at")?,
+        finished_at: optional_ts("finished_at")?,
+        result: row.try_get::<Option<String>, _>("result")?.as_deref().map(from_json).transpose()?,
+        error: row.try_get("error")?,
+        equity_curve: Vec::new(),
+    })
+}
+
+/// Overwrites everything about a job that can change
+fn update_query(run: &BacktestRun) -> anyhow::Result<Query<'_, Any, AnyArguments<'_>>> {
+    Ok(sqlx::query(
+        "UPDATE backtests SET status = $1, progress = $2, started_at = $3, finished_at = $4, result = $5, error = $6 \
+         WHERE id = $7",
+    )
+    .bind(enum_text(&run.status)?)
+    .bind(run.progress)
+    .bind(run.started_at.map(ts_text))
+    .bind(run.finished_at.map(ts_text))
+    .bind(run.result.as_ref().map(json).transpose()?)
+    .bind(&run.error)
+    .bind(&run.id))
+}
+
+impl Database {
+    pub async fn insert_backtest(&self, run: &BacktestRun) -> anyhow::Result<()> {
+        sqlx::query(&format!(
+            "INSERT INTO backtests ({COLUMNS}) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)"
+        ))
+        .bind(&run.id)
+        .bind(&run.strategy_id)
+        .bind(i64::from(run.strategy_version))
+        .bind(json(&run.config)?)
+        .bind(enum_text(&run.status)?)
+        .bind(run.progress)
+        .bind(ts_text(run.submitted_at))
+        .bind(run.started_at.map(ts_text))
+        .bind(run.finished_at.map(ts_text))
+        .bind(run.result.as_ref().map(json).transpose()?)
+        .bind(&run.error)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Store the state of a job, without its equity curve. `false` if the
+    /// job is gone, e.g. with its strategy.
+    pub async fn update_backtest(&self, run: &BacktestRun) -> anyhow::Result<bool> {
+        let updated = update_query(run)?.execute(&self.pool).await?.rows_affected();
+        Ok(updated > 0)
+    }
+
+    /// Store the final state of a job along with its equity curve and trades
+    pub async fn finish_backtest(&self, run: &BacktestRun, trades: &[BacktestTrade]) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let updated = update_query(run)?.execute(&mut *tx).await?.rows_affected();
+        if updated == 0 {
+            return Ok(());
+        }
+
+        for (batch, curve) in run.equity_curve.chunks(BATCH_ROWS).enumerate() {
+            let sql = format!(
+                "INSERT INTO backtest_equity (backtest_id, seq, equity) VALUES {}",
+                placeholders(curve.len(), 3)
+            );
+            let mut query = sqlx::query(&sql);
+            for (i, equity) in curve.iter().enumerate() {
+                query = query.bind(&run.id).bind(i64::try_from(batch * BATCH_ROWS + i)?).bind(*equity);
+            }
+            query.execute(&mut *tx).await?;
+        }
+        for (batch, trades) in trades.chunks(BATCH_ROWS).enumerate() {
+            let sql = format!(
+                "INSERT INTO backtest_trades \
+                 (backtest_id, seq, symbol, side, order_id, status, filled_qty, fill_price, leaves_qty, ts) VALUES {}",
+                placeholders(trades.len(), 10)
+            );
+            let mut query = sqlx::query(&sql);
+            for (i, trade) in trades.iter().enumerate() {
+                let fill = &trade.fill;
+                query = query
+                    .bind(&run.id)
+                    .bind(i64::try_from(batch * BATCH_ROWS + i)?)
+                    .bind(&trade.symbol)
+                    .bind(trade.side.as_ref().map(enum_text).transpose()?)
+                    .bind(i64::try_from(fill.order_id)?)
+                    .bind(json(&fill.status)?)
+                    .bind(fill.filled_qty)
+                    .bind(fill.fill_price)
+                    .bind(fill.leaves_qty)
+                    .bind(ts_text(fill.ts));
+            }
+            query.execute(&mut *tx).await?;
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// A job with its equity curve
+    pub async fn backtest(&self, id: &str) -> anyhow::Result<Option<BacktestRun>> {
+        let Some(row) = sqlx::query(&format!("SELECT {COLUMNS} FROM backtests WHERE id = $1"))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+        let mut run = run_from_row(&row)?;
+        run.equity_curve = sqlx::query_scalar("SELECT equity FROM backtest_equity WHERE backtest_id = $1 ORDER BY seq")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?;
+        Ok(Some(run))
+    }
+
+    /// Jobs of a strategy, newest first and without equity curves
+    pub async fn backtests(&self, strategy_id: &str) -> anyhow::Result<Vec<BacktestRun>> {
+        sqlx::query(&format!(
+            "SELECT {COLUMNS} FROM backtests WHERE strategy_id = $1 ORDER BY submitted_at DESC, id DESC"
+        ))
+        .bind(strategy_id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(run_from_row)
+        .collect()
+    }
+
+    /// Fills of a finished job, in order
+    pub async fn backtest_trades(&self, id: &str) -> anyhow::Result<Vec<BacktestTrade>> {
+        let rows = sqlx::query(
+            "SELECT symbol, side, order_id, status, filled_qty, fill_price, leaves_qty, ts \
+             FROM backtest_trades WHERE backtest_id = $1 ORDER BY seq",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+        rows.iter()
+            .map(|row| {
+                Ok(BacktestTrade {
+                    symbol: row.try_get("symbol")?,
+                    side: row.try_get::<Option<String>, _>("side")?.as_deref().map(parse_enum).transpose()?,
+                    fill: ExecutionReport {
+                        order_id: u64::try_from(row.try_get::<i64, _>("order_id")?)?,
+                        status: from_json(&row.try_get::<String, _>("status")?)?,
+                        filled_qty: row.try_get("filled_qty")?,
+                        fill_price: row.try_get("fill_price")?,
+                        leaves_qty: row.try_get("leaves_qty")?,
+                        ts: parse_ts(&row.try_get::<String, _>("ts")?)?,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    /// Mark jobs left queued or running by a previous process as failed.
+    /// Returns how many there were.
+    pub async fn fail_unfinished_backtests(&self, reason: &str) -> anyhow::Result<u64> {
+        let finished_at = ts_text(chrono::Utc::now());
+        Ok(sqlx::query("UPDATE backtests SET status = $1, error = $2, finished_at = $3 WHERE status IN ($4, $5)")
+            .bind(enum_text(&JobStatus::Failed)?)
+            .bind(reason)
+            .bind(finished_at)
+            .bind(enum_text(&JobStatus::Queued)?)
+            .bind(enum_text(&JobStatus::Running)?)
+            .execute(&self.pool)
+            .await?
+            .rows_affected())
+    }
+}
@@ -0,0 +1,78 @@
+use api::paper::StartPaperReq;
+use chrono::Utc;
+use dnn_core::ledger::Ledger;
+use dnn_core::time::Timestamp;
+use sqlx::any::AnyRow;
+use sqlx::Row;
+use super::{from_json, json, parse_ts, ts_text, Database};
+
+/// What is kept of a paper session so it can resume after a restart
+#[derive(Clone, Debug)]
+pub struct PaperPortfolio {
+    /// User the session belongs to
+    pub owner: String,
+    pub config: StartPaperReq,
+    pub started: Timestamp,
+    pub ledger: Ledger,
+}
+
+fn portfolio_from_row(row: &AnyRow) -> anyhow::Result<PaperPortfolio> {
+    Ok(PaperPortfolio {
+        owner: row.try_get("user_id")?,
+        config: from_json(&row.try_get::<String, _>("config")?)?,
+        started: parse_ts(&row.try_get::<String, _>("started")?)?,
+        ledger: from_json(&row.try_get::<String, _>("ledger")?)?,
+    })
+}
+
+impl Database {
+    /// Insert or overwrite the portfolio of session `id`
+    pub async fn save_paper_portfolio(&self, id: &str, portfolio: &PaperPortfolio) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO paper_portfolios (id, user_id, config, started, ledger, updated_at) \
+             VALUES ($1, $2, $3, $4, $5, $6) \
+             ON CONFLICT (id) DO UPDATE SET ledger = excluded.ledger, updated_at = excluded.updated_at",
+        )
+        .bind(id)
+        .bind(&portfolio.owner)
+        .bind(json(&portfolio.config)?)
+        .bind(ts_text(portfolio.started))
+        .bind(json(&portfolio.ledger)?)
+        .bind(ts_text(Utc::now()))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Every saved portfolio with an owner as `(session id, portfolio)`,
+    /// oldest first
+    pub async fn paper_portfolios(&self) -> anyhow::Result<Vec<(String, PaperPortfolio)>> {
+        sqlx::query(
+            "SELECT id, user_id, config, started, ledger FROM paper_portfolios \
+             WHERE user_id IS NOT NULL ORDER BY started, id",
+        )
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| Ok((row.try_get("id")?, portfolio_from_row(row)?)))
+        .collect()
+    }
+
+    pub async fn paper_portfolio(&self, id: &str) -> anyhow::Result<Option<PaperPortfolio>> {
+        sqlx::query("SELECT user_id, config, started, ledger FROM paper_portfolios WHERE id = $1 AND user_id IS NOT NULL")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(portfolio_from_row)
+            .transpose()
+    }
+
+    pub async fn delete_paper_portfolio(&self, id: &str) -> anyhow::Result<()> {
+        sqlx::query("DELETE FROM paper_portfolios WHERE id = $1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,191 @@
+use api::strategy::{BacktestResult, Strategy, StrategyVersion};
+use sqlx::any::AnyRow;
+use sqlx::Row;
+use super::{enum_text, from_json, json, parse_enum, parse_ts, ts_text, Database};
+
+const COLUMNS: &str = "id, name, description, language, code, status, version, created_at, backtest_results";
+
+fn strategy_from_row(row: &AnyRow) -> anyhow::Result<Strategy> {
+    Ok(Strategy {
+        id: row.try_get("id")?,
+        name: row.try_get("name")?,
+        description: row.try_get("description")?,
+        language: parse_enum(&row.try_get::<String, _>("language")?)?,
+        code: row.try_get("code")?,
+        status: parse_enum(&row.try_get::<String, _>("status")?)?,
+        version: u32::try_from(row.try_get::<i64, _>("version")?)?,
+        created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+        backtest_results: row
+            .try_get::<Option<String>, _>("backtest_results")?
+            .as_deref()
+            .map(from_json)
+            .transpose()?,
+    })
+}
+
+impl Database {
+    /// Save a new strategy of `owner` along with its first version
+    pub async fn insert_strategy(&self, owner: &str, strategy: &Strategy) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(&format!(
+            "INSERT INTO strategies ({COLUMNS}, user_id) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)"
+        ))
+        .bind(&strategy.id)
+        .bind(&strategy.name)
+        .bind(&strategy.description)
+        .bind(enum_text(&strategy.language)?)
+        .bind(&strategy.code)
+        .bind(enum_text(&strategy.status)?)
+        .bind(i64::from(strategy.version))
+        .bind(ts_text(strategy.created_at))
+        .bind(strategy.backtest_results.as_ref().map(json).transpose()?)
+        .bind(owner)
+        .execute(&mut *tx)
+        .await?;
+        insert_version(&mut tx, &strategy.id, &StrategyVersion {
+            version: strategy.version,
+            language: strategy.language,
+            code: strategy.code.clone(),
+            created_at: strategy.created_at,
+        })
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Overwrite a strategy's details and record its code as `version`.
+    /// `false` if `owner` has no such strategy.
+    pub async fn update_strategy(
+        &self,
+        owner: &str,
+        strategy: &Strategy,
+        version: &StrategyVersion,
+    ) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let updated = sqlx::query(
+            "UPDATE strategies SET name = $1, description = $2, language = $3, code = $4, status = $5, version = $6 \
+             WHERE id = $7 AND user_id = $8",
+        )
+        .bind(&strategy.name)
+        .bind(&strategy.description)
+        .bind(enum_text(&strategy.language)?)
+        .bind(&strategy.code)
+        .bind(enum_text(&strategy.status)?)
+        .bind(i64::from(strategy.version))
+        .bind(&strategy.id)
+        .bind(owner)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+        if updated == 0 {
+            return Ok(false);
+        }
+        insert_version(&mut tx, &strategy.id, version).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Every strategy of `owner`, oldest first
+    pub async fn strategies(&self, owner: &str) -> anyhow::Result<Vec<Strategy>> {
+        sqlx::query(&format!("SELECT {COLUMNS} FROM strategies WHERE user_id = $1 ORDER BY created_at, id"))
+            .bind(owner)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(strategy_from_row)
+            .collect()
+    }
+
+    pub async fn strategy(&self, owner: &str, id: &str) -> anyhow::Result<Option<Strategy>> {
+        sqlx::query(&format!("SELECT {COLUMNS} FROM strategies WHERE id = $1 AND user_id = $2"))
+            .bind(id)
+            .bind(owner)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(strategy_from_row)
+            .transpose()
+    }
+
+    /// Every version of a strategy's code, oldest first
+    pub async fn strategy_versions(&self, id: &str) -> anyhow::Result<Vec<StrategyVersion>> {
+        sqlx::query(
+            "SELECT version, language, code, created_at FROM strategy_versions WHERE strategy_id = $1 ORDER BY version",
+        )
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?
+        .iter()
+        .map(|row| {
+            Ok(StrategyVersion {
+                version: u32::try_from(row.try_get::<i64, _>("version")?)?,
+                language: parse_enum(&row.try_get::<String, _>("language")?)?,
+                code: row.try_get("code")?,
+                created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+            })
+        })
+        .collect()
+    }
+
+    /// Keep the summary of a finished backtest; a draft moves on to testing
+    pub async fn set_strategy_results(&self, id: &str, result: &BacktestResult) -> anyhow::Result<()> {
+        sqlx::query(
+            "UPDATE strategies SET backtest_results = $1, \
+             status = CASE WHEN status = 'Draft' THEN 'Testing' ELSE status END \
+             WHERE id = $2",
+        )
+        .bind(json(result)?)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a strategy with its versions and backtests. `false` if
+    /// `owner` has no such strategy.
+    pub async fn delete_strategy(&self, owner: &str, id: &str) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let owned = sqlx::query("SELECT id FROM strategies WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(owner)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if !owned {
+            return Ok(false);
+        }
+        for statement in [
+            "DELETE FROM backtest_trades WHERE backtest_id IN (SELECT id FROM backtests WHERE strategy_id = $1)",
+            "DELETE FROM backtest_equity WHERE backtest_id IN (SELECT id FROM backtests WHERE strategy_id = $1)",
+            "DELETE FROM backtests WHERE strategy_id = $1",
+            "DELETE FROM strategy_versions WHERE strategy_id = $1",
+        ] {
+            sqlx::query(statement).bind(id).execute(&mut *tx).await?;
+        }
+        let deleted = sqlx::query("DELETE FROM strategies WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+}
+
+async fn insert_version(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    strategy_id: &str,
+    version: &StrategyVersion,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO strategy_versions (strategy_id, version, language, code, created_at) VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(strategy_id)
+    .bind(i64::from(version.version))
+    .bind(enum_text(&version.language)?)
+    .bind(&version.code)
+    .bind(ts_text(version.created_at))
+    .execute(&mut **tx)
+    .await?;
+    Ok(())
+}
@@ -0,0 +1,114 @@
+use api::auth::{ApiKey, User};
+use sqlx::any::AnyRow;
+use sqlx::Row;
+use super::{parse_ts, ts_text, Database};
+
+fn user_from_row(row: &AnyRow) -> anyhow::Result<User> {
+    Ok(User {
+        id: row.try_get("id")?,
+        username: row.try_get("username")?,
+        created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+    })
+}
+
+impl Database {
+    /// `false` if the username is taken
+    pub async fn insert_user(&self, user: &User, password_hash: &str) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let taken = sqlx::query("SELECT id FROM users WHERE username = $1")
+            .bind(&user.username)
+            .fetch_optional(&mut *tx)
+            .await?
+            .is_some();
+        if taken {
+            return Ok(false);
+        }
+        sqlx::query("INSERT INTO users (id, username, password_hash, created_at) VALUES ($1, $2, $3, $4)")
+            .bind(&user.id)
+            .bind(&user.username)
+            .bind(password_hash)
+            .bind(ts_text(user.created_at))
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// A user with their password hash, to check a login against
+    pub async fn user_credentials(&self, username: &str) -> anyhow::Result<Option<(User, String)>> {
+        sqlx::query("SELECT id, username, created_at, password_hash FROM users WHERE username = $1")
+            .bind(username)
+            .fetch_optional(&self.pool)
+            .await?
+            .map(|row| Ok((user_from_row(&row)?, row.try_get("password_hash")?)))
+            .transpose()
+    }
+
+    pub async fn user(&self, id: &str) -> anyhow::Result<Option<User>> {
+        sqlx::query("SELECT id, username, created_at FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?
+            .as_ref()
+            .map(user_from_row)
+            .transpose()
+    }
+
+    pub async fn insert_api_key(&self, user_id: &str, key: &ApiKey, key_hash: &str) -> anyhow::Result<()> {
+        sqlx::query(
+            "INSERT INTO api_keys (id, user_id, name, prefix, key_hash, created_at) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(&key.id)
+        .bind(user_id)
+        .bind(&key.name)
+        .bind(&key.prefix)
+        .bind(key_hash)
+        .bind(ts_text(key.created_at))
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Owner of the key with this hash
+    pub async fn api_key_user(&self, key_hash: &str) -> anyhow::Result<Option<User>> {
+        sqlx::query(
+            "SELECT users.id, users.username, users.created_at FROM api_keys \
+             JOIN users ON users.id = api_keys.user_id WHERE api_keys.key_hash = $1",
+        )
+        .bind(key_hash)
+        .fetch_optional(&self.pool)
+        .await?
+        .as_ref()
+        .map(user_from_row)
+        .transpose()
+    }
+
+    /// Keys of a user, oldest first
+    pub async fn api_keys(&self, user_id: &str) -> anyhow::Result<Vec<ApiKey>> {
+        sqlx::query("SELECT id, name, prefix, created_at FROM api_keys WHERE user_id = $1 ORDER BY created_at, id")
+            .bind(user_id)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| {
+                Ok(ApiKey {
+                    id: row.try_get("id")?,
+                    name: row.try_get("name")?,
+                    prefix: row.try_get("prefix")?,
+                    created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+                })
+            })
+            .collect()
+    }
+
+    /// `false` if the user has no such key
+    pub async fn delete_api_key(&self, user_id: &str, id: &str) -> anyhow::Result<bool> {
+        let deleted = sqlx::query("DELETE FROM api_keys WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(user_id)
+            .execute(&self.pool)
+            .await?
+            .rows_affected();
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,122 @@
+use api::watchlist::Watchlist;
+use sqlx::Row;
+use super::{parse_ts, ts_text, Database};
+
+impl Database {
+    pub async fn insert_watchlist(&self, owner: &str, watchlist: &Watchlist) -> anyhow::Result<()> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("INSERT INTO watchlists (id, name, created_at, user_id) VALUES ($1, $2, $3, $4)")
+            .bind(&watchlist.id)
+            .bind(&watchlist.name)
+            .bind(ts_text(watchlist.created_at))
+            .bind(owner)
+            .execute(&mut *tx)
+            .await?;
+        insert_symbols(&mut tx, &watchlist.id, &watchlist.symbols).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Rename a watchlist and replace its symbols. `false` if `owner` has
+    /// no such watchlist.
+    pub async fn update_watchlist(&self, owner: &str, watchlist: &Watchlist) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let updated = sqlx::query("UPDATE watchlists SET name = $1 WHERE id = $2 AND user_id = $3")
+            .bind(&watchlist.name)
+            .bind(&watchlist.id)
+            .bind(owner)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        if updated == 0 {
+            return Ok(false);
+        }
+        sqlx::query("DELETE FROM watchlist_symbols WHERE watchlist_id = $1")
+            .bind(&watchlist.id)
+            .execute(&mut *tx)
+            .await?;
+        insert_symbols(&mut tx, &watchlist.id, &watchlist.symbols).await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    /// Every watchlist of `owner`, oldest first
+    pub async fn watchlists(&self, owner: &str) -> anyhow::Result<Vec<Watchlist>> {
+        let rows = sqlx::query("SELECT id, name, created_at FROM watchlists WHERE user_id = $1 ORDER BY created_at, id")
+            .bind(owner)
+            .fetch_all(&self.pool)
+            .await?;
+        let mut watchlists = Vec::with_capacity(rows.len());
+        for row in rows {
+            let id: String = row.try_get("id")?;
+            watchlists.push(Watchlist {
+                symbols: self.watchlist_symbols(&id).await?,
+                id,
+                name: row.try_get("name")?,
+                created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+            });
+        }
+        Ok(watchlists)
+    }
+
+    pub async fn watchlist(&self, owner: &str, id: &str) -> anyhow::Result<Option<Watchlist>> {
+        let Some(row) = sqlx::query("SELECT name, created_at FROM watchlists WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(owner)
+            .fetch_optional(&self.pool)
+            .await?
+        else {
+            return Ok(None);
+        };
+        Ok(Some(Watchlist {
+            id: id.to_owned(),
+            name: row.try_get("name")?,
+            symbols: self.watchlist_symbols(id).await?,
+            created_at: parse_ts(&row.try_get::<String, _>("created_at")?)?,
+        }))
+    }
+
+    /// `false` if `owner` has no such watchlist
+    pub async fn delete_watchlist(&self, owner: &str, id: &str) -> anyhow::Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query(
+            "DELETE FROM watchlist_symbols WHERE watchlist_id IN \
+             (SELECT id FROM watchlists WHERE id = $1 AND user_id = $2)",
+        )
+        .bind(id)
+        .bind(owner)
+        .execute(&mut *tx)
+        .await?;
+        let deleted = sqlx::query("DELETE FROM watchlists WHERE id = $1 AND user_id = $2")
+            .bind(id)
+            .bind(owner)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        tx.commit().await?;
+        Ok(deleted > 0)
+    }
+
+    async fn watchlist_symbols(&self, id: &str) -> anyhow::Result<Vec<String>> {
+        Ok(sqlx::query_scalar("SELECT symbol FROM watchlist_symbols WHERE watchlist_id = $1 ORDER BY position")
+            .bind(id)
+            .fetch_all(&self.pool)
+            .await?)
+    }
+}
+
+async fn insert_symbols(
+    tx: &mut sqlx::Transaction<'_, sqlx::Any>,
+    watchlist_id: &str,
+    symbols: &[String],
+) -> anyhow::Result<()> {
+    for (position, symbol) in symbols.iter().enumerate() {
+        sqlx::query("INSERT INTO watchlist_symbols (watchlist_id, position, symbol) VALUES ($1, $2, $3)")
+            .bind(watchlist_id)
+            .bind(i64::try_from(position)?)
+            .bind(symbol)
+            .execute(&mut **tx)
+            .await?;
+    }
+    Ok(())
+}
@@ -0,0 +1,5 @@
+//! Pieces of the backend that stand on their own, for the server binary and
+//! integration tests.
+
+pub mod db;
+pub mod services;
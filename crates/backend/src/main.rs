@@ -1,30 +1,49 @@
 mod routes;
 mod config;
-mod services;
 mod state;
 
 use std::net::SocketAddr;
 use std::sync::Arc;
+use api::candles::NEXT_CURSOR_HEADER;
+use axum::http::HeaderName;
 use axum::{routing::get, Router};
 use tokio::net::TcpListener;
-use tracing::info;
+use tower_http::cors::{Any, CorsLayer};
+use tracing::{error, info, Level};
 use crate::config::BackendConfig;
 use crate::routes::api_routes;
 use crate::state::BackendState;
 
 #[tokio::main]
-async fn main() {
-    tracing_subscriber::fmt::init();
+async fn main() -> anyhow::Result<()> {
+    // The log level comes from the config, so warnings while loading it go to a
+    // temporary subscriber
+    let loading = tracing_subscriber::fmt().with_max_level(Level::WARN).finish();
+    let config = tracing::subscriber::with_default(loading, BackendConfig::load)?;
+    tracing_subscriber::fmt().with_max_level(config.tracing_level()).init();
 
-    let state = Arc::new(BackendState::new().unwrap());
+    let cors = CorsLayer::new()
+        .allow_origin(config.cors_origin.clone())
+        .allow_methods(Any)
+        .allow_headers(Any)
+        .expose_headers([HeaderName::from_static(NEXT_CURSOR_HEADER)]);
+    let state = Arc::new(BackendState::new(config).await?);
+    if let Err(e) = state.resume_paper_sessions().await {
+        error!("failed to resume paper sessions: {:?}", e);
+    }
+    if let Err(e) = state.resume_alerts().await {
+        error!("failed to resume alerts: {:?}", e);
+    }
     let app = Router::new()
-        .merge(api_routes())
+        .merge(api_routes(state.clone()))
+        .layer(cors)
         .with_state(state.clone());
 
     let addr = SocketAddr::from(([0, 0, 0, 0], state.config.port));
     info!("🚀 Listening on http://{}", addr);
 
     // Start the server
-    let listener = TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    let listener = TcpListener::bind(addr).await?;
+    axum::serve(listener, app).await?;
+    Ok(())
 }
@@ -1,16 +1,58 @@
+mod alerts;
+mod auth;
+mod candles;
 mod live;
+mod paper;
+mod scanner;
+mod strategies;
+mod watchlists;
 
 use std::sync::Arc;
-use axum::Router;
-use axum::routing::get;
+use axum::routing::{delete, get, post, put};
+use axum::{middleware, Router};
+use crate::routes::alerts::{create_alert, delete_alert, list_alerts, stream_alerts};
+use crate::routes::auth::{create_api_key, delete_api_key, list_api_keys, login, me, register, require_user};
+use crate::routes::candles::get_candles;
 use crate::routes::live::stream_stock;
+use crate::routes::paper::{get_paper, list_paper, start_paper, stop_paper, stream_paper};
+use crate::routes::scanner::query_scanner;
+use crate::routes::strategies::{
+    cancel_backtest, create_strategy, delete_strategy, get_backtest_results, get_backtest_trades, get_strategies,
+    get_strategy_versions, list_backtests, run_backtest, stream_backtest, update_strategy,
+};
+use crate::routes::watchlists::{
+    create_watchlist, delete_watchlist, get_watchlist, list_watchlists, update_watchlist,
+};
 use crate::state::BackendState;
 
-pub fn api_routes() -> Router<Arc<BackendState>> {
-    Router::new()
+/// Accounts are open to everyone; everything else needs a signed-in user
+pub fn api_routes(state: Arc<BackendState>) -> Router<Arc<BackendState>> {
+    let protected = Router::new()
+        .route("/alerts", get(list_alerts).post(create_alert))
+        .route("/alerts/stream", get(stream_alerts))
+        .route("/alerts/{id}", delete(delete_alert))
+        .route("/auth/me", get(me))
+        .route("/auth/keys", get(list_api_keys).post(create_api_key))
+        .route("/auth/keys/{id}", delete(delete_api_key))
+        .route("/candles", get(get_candles))
         .route("/live/stock", get(stream_stock))
+        .route("/paper", get(list_paper).post(start_paper))
+        .route("/paper/{id}", get(get_paper).delete(stop_paper))
+        .route("/paper/{id}/stream", get(stream_paper))
+        .route("/scanner/query", post(query_scanner))
         .route("/strategies", get(get_strategies).post(create_strategy))
-        .route("/strategies/:id", delete(delete_strategy))
-        .route("/strategies/:id/backtest", post(run_backtest))
-        .route("/strategies/:id/backtest/:backtest_id", get(get_backtest_results))
-}
\ No newline at end of file
+        .route("/strategies/{id}", put(update_strategy).delete(delete_strategy))
+        .route("/strategies/{id}/versions", get(get_strategy_versions))
+        .route("/strategies/{id}/backtest", get(list_backtests).post(run_backtest))
+        .route("/strategies/{id}/backtest/{backtest_id}", get(get_backtest_results).delete(cancel_backtest))
+        .route("/strategies/{id}/backtest/{backtest_id}/trades", get(get_backtest_trades))
+        .route("/strategies/{id}/backtest/{backtest_id}/stream", get(stream_backtest))
+        .route("/watchlists", get(list_watchlists).post(create_watchlist))
+        .route("/watchlists/{id}", get(get_watchlist).put(update_watchlist).delete(delete_watchlist))
+        .route_layer(middleware::from_fn_with_state(state, require_user));
+
+    Router::new()
+        .route("/auth/register", post(register))
+        .route("/auth/login", post(login))
+        .merge(protected)
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+use axum::extract::{Path, State, WebSocketUpgrade};
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use api::alert::{Alert, AlertEvent, CreateAlertReq};
+use futures::{SinkExt, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use backend::services::alerts::AlertError;
+use backend::services::auth::AuthUser;
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+fn api_error(e: &AlertError) -> ApiError {
+    let status = match *e {
+        AlertError::NotFound(_) => StatusCode::NOT_FOUND,
+        AlertError::Invalid(_) => StatusCode::BAD_REQUEST,
+        AlertError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+pub async fn list_alerts(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<Alert>>, ApiError> {
+    state.alerts.list(&user.id).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn create_alert(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<CreateAlertReq>,
+) -> Result<(StatusCode, Json<Alert>), ApiError> {
+    let provider = state
+        .get_provider(req.provider)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("unknown provider {}", req.provider.as_ref())))?
+        .clone();
+    state.alerts
+        .create(&user.id, req, provider)
+        .await
+        .map(|alert| (StatusCode::CREATED, Json(alert)))
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn delete_alert(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.alerts
+        .delete(&user.id, &id)
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|e| api_error(&e))
+}
+
+/// Every alert of the user that triggers while connected
+pub async fn stream_alerts(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| on_stream_alerts(socket, state, user))
+}
+
+async fn on_stream_alerts(ws: WebSocket, state: Arc<BackendState>, user: AuthUser) {
+    let (mut sender, mut receiver) = ws.split();
+    let mut events = state.alerts.subscribe(&user.id).await;
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if send_event(&mut sender, &event).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client misses some alerts but keeps the stream
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_event(
+    sender: &mut (impl SinkExt<Message, Error = axum::Error> + Unpin),
+    event: &AlertEvent,
+) -> anyhow::Result<()> {
+    let text = serde_json::to_string(event)?;
+    sender.send(Message::Text(Utf8Bytes::from(text))).await?;
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use std::sync::Arc;
+use axum::extract::{Path, Request, State};
+use axum::http::header::WWW_AUTHENTICATE;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::{Extension, Json};
+use api::auth::{ApiKey, AuthSession, CreateApiKeyReq, Credentials, NewApiKey, User};
+use backend::services::auth::{AuthError, AuthUser};
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+fn api_error(e: &AuthError) -> ApiError {
+    let status = match *e {
+        AuthError::InvalidCredentials | AuthError::Unauthorized => StatusCode::UNAUTHORIZED,
+        AuthError::UsernameTaken(_) => StatusCode::CONFLICT,
+        AuthError::KeyNotFound(_) => StatusCode::NOT_FOUND,
+        AuthError::Invalid(_) => StatusCode::BAD_REQUEST,
+        AuthError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+/// Let a request through only with a valid session token or API key, and
+/// hand the handler its [`AuthUser`]
+pub async fn require_user(
+    State(state): State<Arc<BackendState>>,
+    mut req: Request,
+    next: Next,
+) -> Result<Response, Response> {
+    let user = state.auth.authenticate(req.headers(), req.uri()).await.map_err(|e| {
+        let (status, message) = api_error(&e);
+        (status, [(WWW_AUTHENTICATE, "Bearer")], message).into_response()
+    })?;
+    req.extensions_mut().insert(user);
+    Ok(next.run(req).await)
+}
+
+pub async fn register(
+    State(state): State<Arc<BackendState>>,
+    Json(credentials): Json<Credentials>,
+) -> Result<(StatusCode, Json<AuthSession>), ApiError> {
+    state.auth
+        .register(credentials)
+        .await
+        .map(|session| (StatusCode::CREATED, Json(session)))
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn login(
+    State(state): State<Arc<BackendState>>,
+    Json(credentials): Json<Credentials>,
+) -> Result<Json<AuthSession>, ApiError> {
+    state.auth.login(credentials).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn me(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<User>, ApiError> {
+    state.auth.user(&user).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn list_api_keys(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<ApiKey>>, ApiError> {
+    state.auth.api_keys(&user).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn create_api_key(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<CreateApiKeyReq>,
+) -> Result<(StatusCode, Json<NewApiKey>), ApiError> {
+    state.auth
+        .create_api_key(&user, &req.name)
+        .await
+        .map(|key| (StatusCode::CREATED, Json(key)))
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn delete_api_key(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.auth
+        .delete_api_key(&user, &id)
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|e| api_error(&e))
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use axum::extract::{Query, State};
+use axum::http::header::{ACCEPT, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderName, HeaderValue, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use api::candles::{
+    to_binary, to_csv, CandleFormat, CandlePage, CandlesQuery, DEFAULT_PAGE, MAX_PAGE, MAX_RANGE, NEXT_CURSOR_HEADER,
+};
+use dnn_core::resample::resample;
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+/// Historical candles in pages, see [`CandlesQuery`]. Each page is fetched
+/// from its cursor to the end of the range, so later pages ask the provider
+/// for less.
+pub async fn get_candles(
+    State(state): State<Arc<BackendState>>,
+    headers: HeaderMap,
+    Query(query): Query<CandlesQuery>,
+) -> Result<Response, ApiError> {
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE);
+    if !(1..=MAX_PAGE).contains(&limit) {
+        return Err((StatusCode::BAD_REQUEST, format!("limit must be between 1 and {MAX_PAGE}")));
+    }
+    if query.start >= query.end {
+        return Err((StatusCode::BAD_REQUEST, "start must be before end".to_owned()));
+    }
+    if (query.end - query.start).num_seconds() / query.interval.to_seconds() > MAX_RANGE {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("range is longer than {MAX_RANGE} {} candles", query.interval),
+        ));
+    }
+    if let Some(to) = query.resample
+        && to.to_seconds() < query.interval.to_seconds()
+    {
+        return Err((StatusCode::BAD_REQUEST, format!("can't resample {} candles into {to}", query.interval)));
+    }
+    let format = query
+        .format
+        .or_else(|| headers.get(ACCEPT).and_then(|v| v.to_str().ok()).and_then(CandleFormat::from_accept))
+        .unwrap_or(CandleFormat::Json);
+
+    // Resampled cursors are bucket starts, so fetching from the cursor
+    // rebuilds that bucket whole before it's skipped
+    let from = match query.cursor.as_deref() {
+        Some(cursor) => CandlePage::cursor_time(cursor).map_err(|e| (StatusCode::BAD_REQUEST, e))?.max(query.start),
+        None => query.start,
+    };
+    if from >= query.end {
+        return Err((StatusCode::BAD_REQUEST, "cursor is past the end of the range".to_owned()));
+    }
+
+    let provider = state
+        .get_provider(query.provider)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("unknown provider {}", query.provider.as_ref())))?;
+    let candles = if query.adjusted {
+        provider.historical_adjusted(&query.symbol, query.interval, from, query.end).await
+    } else {
+        provider.historical(&query.symbol, query.interval, from, query.end).await
+    }
+    .map_err(|e| (StatusCode::BAD_GATEWAY, format!("{e:#}")))?;
+    let candles = match query.resample {
+        Some(to) if to != query.interval => resample(&candles, to),
+        _ => candles,
+    };
+
+    let page = CandlePage::after(candles, query.cursor.as_deref(), limit).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let mut response = match format {
+        CandleFormat::Json => Json(&page).into_response(),
+        CandleFormat::Csv => to_csv(&page.candles).into_response(),
+        CandleFormat::Binary => to_binary(&page.candles).into_response(),
+    };
+    let headers = response.headers_mut();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+    if let Some(cursor) = &page.next_cursor
+        && let Ok(cursor) = HeaderValue::from_str(cursor)
+    {
+        headers.insert(HeaderName::from_static(NEXT_CURSOR_HEADER), cursor);
+    }
+    Ok(response)
+}
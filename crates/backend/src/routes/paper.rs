@@ -0,0 +1,119 @@
+use std::sync::Arc;
+use axum::extract::{Path, State, WebSocketUpgrade};
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use api::paper::{PaperEvent, PaperSessionInfo, StartPaperReq};
+use futures::{SinkExt, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use backend::services::auth::AuthUser;
+use backend::services::strategies::StrategyError;
+use crate::routes::strategies::api_error;
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+pub async fn start_paper(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<StartPaperReq>,
+) -> Result<Json<PaperSessionInfo>, ApiError> {
+    if req.starting_cash <= 0.0 {
+        return Err((StatusCode::BAD_REQUEST, "starting_cash must be positive".to_owned()));
+    }
+    let provider = state
+        .get_provider(req.provider)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("unknown provider {}", req.provider.as_ref())))?
+        .clone();
+
+    state.paper
+        .start(&user.id, req, provider)
+        .await
+        .map(Json)
+        .map_err(|e| match e.downcast_ref::<StrategyError>() {
+            Some(e) => api_error(e),
+            None => (StatusCode::BAD_GATEWAY, e.to_string()),
+        })
+}
+
+pub async fn list_paper(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Json<Vec<PaperSessionInfo>> {
+    Json(state.paper.list(&user.id).await)
+}
+
+pub async fn get_paper(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<PaperSessionInfo>, ApiError> {
+    state.paper
+        .get(&user.id, &id)
+        .await
+        .map(Json)
+        .ok_or((StatusCode::NOT_FOUND, format!("unknown paper session {id}")))
+}
+
+pub async fn stop_paper(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.paper
+        .stop(&user.id, &id)
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))
+}
+
+pub async fn stream_paper(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| on_stream_paper(socket, state, user, id))
+}
+
+async fn on_stream_paper(ws: WebSocket, state: Arc<BackendState>, user: AuthUser, id: String) {
+    let (mut sender, mut receiver) = ws.split();
+
+    let Some((info, mut events)) = state.paper.subscribe(&user.id, &id).await else {
+        let msg = PaperEvent::Error { message: format!("unknown paper session {id}") };
+        let _ = send_event(&mut sender, &msg).await;
+        return;
+    };
+    if send_event(&mut sender, &PaperEvent::Session { info }).await.is_err() {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    if send_event(&mut sender, &event).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client misses some events but keeps the stream
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_event(
+    sender: &mut (impl SinkExt<Message, Error = axum::Error> + Unpin),
+    event: &PaperEvent,
+) -> anyhow::Result<()> {
+    let text = serde_json::to_string(event)?;
+    sender.send(Message::Text(Utf8Bytes::from(text))).await?;
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use std::sync::Arc;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::Json;
+use api::scanner::{ScannerPage, ScannerQuery};
+use backend::services::scanner::ScannerError;
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+fn api_error(e: &ScannerError) -> ApiError {
+    let status = match e {
+        ScannerError::Invalid(_) => StatusCode::BAD_REQUEST,
+    };
+    (status, e.to_string())
+}
+
+pub async fn query_scanner(
+    State(state): State<Arc<BackendState>>,
+    Json(query): Json<ScannerQuery>,
+) -> Result<Json<ScannerPage>, ApiError> {
+    state.scanner.query(&query).await.map(Json).map_err(|e| api_error(&e))
+}
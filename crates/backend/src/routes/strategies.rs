@@ -0,0 +1,203 @@
+use std::sync::Arc;
+use axum::extract::{Path, State, WebSocketUpgrade};
+use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
+use axum::http::StatusCode;
+use axum::response::IntoResponse;
+use axum::{Extension, Json};
+use api::strategy::{
+    BacktestEvent, BacktestRun, BacktestTrade, CreateStrategyReq, RunBacktestReq, Strategy, StrategyVersion,
+};
+use futures::{SinkExt, StreamExt};
+use tokio::sync::broadcast::error::RecvError;
+use backend::services::auth::AuthUser;
+use backend::services::strategies::StrategyError;
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+pub(crate) fn api_error(e: &StrategyError) -> ApiError {
+    let status = match *e {
+        StrategyError::NotFound(_) | StrategyError::BacktestNotFound(_) => StatusCode::NOT_FOUND,
+        StrategyError::BacktestFinished(_) => StatusCode::CONFLICT,
+        StrategyError::Invalid(_) => StatusCode::BAD_REQUEST,
+        StrategyError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+pub async fn get_strategies(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<Strategy>>, ApiError> {
+    state.strategies.list(&user.id).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn create_strategy(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<CreateStrategyReq>,
+) -> Result<(StatusCode, Json<Strategy>), ApiError> {
+    state.strategies
+        .create(&user.id, req)
+        .await
+        .map(|strategy| (StatusCode::CREATED, Json(strategy)))
+        .map_err(|e| api_error(&e))
+}
+
+/// Edit a strategy; its code is saved as a new version
+pub async fn update_strategy(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(req): Json<CreateStrategyReq>,
+) -> Result<Json<Strategy>, ApiError> {
+    state.strategies
+        .update(&user.id, &id, req)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn get_strategy_versions(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<StrategyVersion>>, ApiError> {
+    state.strategies
+        .versions(&user.id, &id)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn delete_strategy(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.strategies.delete(&user.id, &id).await.map_err(|e| api_error(&e))?;
+    state.backtests.remove_strategy(&id).await;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Queue a backtest; follow it with the stream or by polling its id
+pub async fn run_backtest(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(req): Json<RunBacktestReq>,
+) -> Result<(StatusCode, Json<BacktestRun>), ApiError> {
+    let provider = state
+        .get_provider(req.provider)
+        .ok_or_else(|| (StatusCode::BAD_REQUEST, format!("unknown provider {}", req.provider.as_ref())))?
+        .clone();
+
+    state.backtests
+        .submit(&user.id, &id, req, provider)
+        .await
+        .map(|run| (StatusCode::ACCEPTED, Json(run)))
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn list_backtests(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<BacktestRun>>, ApiError> {
+    state.backtests
+        .list(&user.id, &id)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn get_backtest_results(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path((id, backtest_id)): Path<(String, String)>,
+) -> Result<Json<BacktestRun>, ApiError> {
+    state.backtests
+        .get(&user.id, &id, &backtest_id)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn get_backtest_trades(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path((id, backtest_id)): Path<(String, String)>,
+) -> Result<Json<Vec<BacktestTrade>>, ApiError> {
+    state.backtests
+        .trades(&user.id, &id, &backtest_id)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn cancel_backtest(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path((id, backtest_id)): Path<(String, String)>,
+) -> Result<Json<BacktestRun>, ApiError> {
+    state.backtests
+        .cancel(&user.id, &id, &backtest_id)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn stream_backtest(
+    ws: WebSocketUpgrade,
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path((id, backtest_id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| on_stream_backtest(socket, state, user, id, backtest_id))
+}
+
+async fn on_stream_backtest(ws: WebSocket, state: Arc<BackendState>, user: AuthUser, id: String, backtest_id: String) {
+    let (mut sender, mut receiver) = ws.split();
+
+    let (run, mut events) = match state.backtests.subscribe(&user.id, &id, &backtest_id).await {
+        Ok(subscription) => subscription,
+        Err(e) => {
+            let _ = send_event(&mut sender, &BacktestEvent::Error { message: e.to_string() }).await;
+            return;
+        }
+    };
+    let finished = run.status.is_finished();
+    if send_event(&mut sender, &BacktestEvent::Snapshot { run }).await.is_err() || finished {
+        return;
+    }
+
+    loop {
+        tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) => {
+                    let last = matches!(event, BacktestEvent::Finished { .. });
+                    if send_event(&mut sender, &event).await.is_err() || last {
+                        break;
+                    }
+                }
+                // Missed progress only loses intermediate equity points; the
+                // final event carries the whole curve
+                Err(RecvError::Lagged(_)) => {}
+                Err(RecvError::Closed) => break,
+            },
+            msg = receiver.next() => match msg {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+async fn send_event(
+    sender: &mut (impl SinkExt<Message, Error = axum::Error> + Unpin),
+    event: &BacktestEvent,
+) -> anyhow::Result<()> {
+    let text = serde_json::to_string(event)?;
+    sender.send(Message::Text(Utf8Bytes::from(text))).await?;
+    Ok(())
+}
@@ -0,0 +1,71 @@
+use std::sync::Arc;
+use axum::extract::{Path, State};
+use axum::http::StatusCode;
+use axum::{Extension, Json};
+use api::watchlist::{Watchlist, WatchlistReq};
+use backend::services::auth::AuthUser;
+use backend::services::watchlists::WatchlistError;
+use crate::state::BackendState;
+
+type ApiError = (StatusCode, String);
+
+fn api_error(e: &WatchlistError) -> ApiError {
+    let status = match *e {
+        WatchlistError::NotFound(_) => StatusCode::NOT_FOUND,
+        WatchlistError::Invalid(_) => StatusCode::BAD_REQUEST,
+        WatchlistError::Storage(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    };
+    (status, e.to_string())
+}
+
+pub async fn list_watchlists(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+) -> Result<Json<Vec<Watchlist>>, ApiError> {
+    state.watchlists.list(&user.id).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn create_watchlist(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Json(req): Json<WatchlistReq>,
+) -> Result<(StatusCode, Json<Watchlist>), ApiError> {
+    state.watchlists
+        .create(&user.id, req)
+        .await
+        .map(|watchlist| (StatusCode::CREATED, Json(watchlist)))
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn get_watchlist(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<Json<Watchlist>, ApiError> {
+    state.watchlists.get(&user.id, &id).await.map(Json).map_err(|e| api_error(&e))
+}
+
+pub async fn update_watchlist(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+    Json(req): Json<WatchlistReq>,
+) -> Result<Json<Watchlist>, ApiError> {
+    state.watchlists
+        .update(&user.id, &id, req)
+        .await
+        .map(Json)
+        .map_err(|e| api_error(&e))
+}
+
+pub async fn delete_watchlist(
+    State(state): State<Arc<BackendState>>,
+    Extension(user): Extension<AuthUser>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, ApiError> {
+    state.watchlists
+        .delete(&user.id, &id)
+        .await
+        .map(|()| StatusCode::NO_CONTENT)
+        .map_err(|e| api_error(&e))
+}
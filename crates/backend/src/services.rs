@@ -0,0 +1,24 @@
+pub mod alerts;
+pub mod auth;
+pub mod backtests;
+pub mod live;
+pub mod notifiers;
+pub mod paper;
+pub mod scanner;
+pub mod strategies;
+pub mod watchlists;
+
+use std::sync::Arc;
+use data::providers::Provider;
+use rand::distr::{Alphanumeric, SampleString};
+
+pub type SafeProvider = Arc<Box<dyn Provider + Send + Sync>>;
+
+/// Random characters after the prefix of an id, enough that ids never
+/// repeat across restarts or replicas
+const ID_LEN: usize = 20;
+
+/// A new id such as `paper-Xq3…`
+pub fn random_id(prefix: &str) -> String {
+    format!("{prefix}-{}", Alphanumeric.sample_string(&mut rand::rng(), ID_LEN))
+}
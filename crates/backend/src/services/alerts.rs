@@ -0,0 +1,385 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+use api::alert::{Alert, AlertCondition, AlertEvent, CreateAlertReq, Direction};
+use api::ProviderType;
+use crate::db::Database;
+use chrono::Utc;
+use dnn_core::market::Candle;
+use dnn_core::time::TimeInterval;
+use futures::StreamExt;
+use strats::rules::expr::Formula;
+use thiserror::Error;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+use crate::services::notifiers::{webhook_url, Notifier};
+use crate::services::{random_id, SafeProvider};
+
+/// Candles of history fetched when a feed starts, so indicators have
+/// something to work with from the first live candle
+const SEED_BARS: i64 = 300;
+/// Candles kept per feed
+pub const KEEP_BARS: usize = 500;
+/// Wait before reopening a feed whose stream ended or failed
+const RETRY_AFTER: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Error)]
+pub enum AlertError {
+    #[error("unknown alert {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("storage error: {0:#}")]
+    Storage(#[from] anyhow::Error),
+}
+
+/// An [`AlertCondition`] ready to check
+enum Check {
+    Price { direction: Direction, price: f64 },
+    ChangePct(f64),
+    Indicator { formula: Formula, direction: Direction, threshold: f64 },
+    VolumeSpike { ratio: f64, period: usize },
+}
+
+impl Check {
+    fn compile(condition: &AlertCondition) -> Result<Self, AlertError> {
+        let invalid = |message: &str| Err(AlertError::Invalid(message.to_owned()));
+        Ok(match condition {
+            AlertCondition::Price { direction, price } => {
+                if !price.is_finite() || *price <= 0.0 {
+                    return invalid("price must be positive");
+                }
+                Self::Price { direction: *direction, price: *price }
+            }
+            AlertCondition::ChangePct { pct } => {
+                if !pct.is_finite() || *pct == 0.0 {
+                    return invalid("pct must not be zero");
+                }
+                Self::ChangePct(*pct)
+            }
+            AlertCondition::Indicator { formula, direction, threshold } => {
+                if !threshold.is_finite() {
+                    return invalid("threshold must be a number");
+                }
+                let formula = Formula::parse_with(formula, &[])
+                    .map_err(|e| AlertError::Invalid(format!("formula: {e}")))?;
+                if formula.lookback() > KEEP_BARS {
+                    return Err(AlertError::Invalid(format!(
+                        "formula needs {} bars, alerts keep {KEEP_BARS}",
+                        formula.lookback()
+                    )));
+                }
+                Self::Indicator { formula, direction: *direction, threshold: *threshold }
+            }
+            AlertCondition::VolumeSpike { ratio, period } => {
+                if !ratio.is_finite() || *ratio <= 0.0 {
+                    return invalid("ratio must be positive");
+                }
+                if *period == 0 {
+                    return invalid("period must be at least 1");
+                }
+                Self::VolumeSpike { ratio: *ratio, period: *period }
+            }
+        })
+    }
+
+    /// Whether the condition holds as of the last of `bars`
+    fn holds(&self, bars: &[Candle]) -> bool {
+        let Some(last) = bars.last() else { return false };
+        let before = &bars[..bars.len() - 1];
+        match self {
+            Self::Price { direction, price } => beyond(*direction, last.close, *price),
+            Self::ChangePct(pct) => before.last().is_some_and(|prev| {
+                let change = (last.close / prev.close - 1.0) * 100.0;
+                prev.close > 0.0 && if *pct > 0.0 { change >= *pct } else { change <= *pct }
+            }),
+            Self::Indicator { formula, direction, threshold } => {
+                formula.value_with(bars, &[]).is_some_and(|value| beyond(*direction, value, *threshold))
+            }
+            Self::VolumeSpike { ratio, period } => {
+                if before.len() < *period {
+                    return false;
+                }
+                let average = before[before.len() - period..].iter().map(|c| c.volume).sum::<f64>() / *period as f64;
+                average > 0.0 && last.volume >= ratio * average
+            }
+        }
+    }
+}
+
+fn beyond(direction: Direction, value: f64, level: f64) -> bool {
+    match direction {
+        Direction::Above => value > level,
+        Direction::Below => value < level,
+    }
+}
+
+/// Alerts on the same candles share one provider stream
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct FeedKey {
+    provider: ProviderType,
+    symbol: String,
+    interval: TimeInterval,
+}
+
+impl FeedKey {
+    fn of(config: &CreateAlertReq) -> Self {
+        Self { provider: config.provider, symbol: config.symbol.clone(), interval: config.interval }
+    }
+}
+
+struct Watch {
+    owner: String,
+    alert: Alert,
+    check: Check,
+    /// Whether the condition held on the last check, `None` before the
+    /// first one
+    held: Option<bool>,
+}
+
+#[derive(Default)]
+struct FeedState {
+    bars: Vec<Candle>,
+    watches: Vec<Watch>,
+}
+
+struct Feed {
+    state: Arc<Mutex<FeedState>>,
+    task: JoinHandle<()>,
+}
+
+/// Checks the conditions of every active alert on the live candles of their
+/// symbols. Triggered alerts go out to their owner's `/alerts/stream`
+/// subscribers and through every [`Notifier`].
+pub struct Alerts {
+    db: Database,
+    notifiers: Vec<Arc<dyn Notifier>>,
+    feeds: Mutex<HashMap<FeedKey, Feed>>,
+    /// Per user
+    events: Mutex<HashMap<String, broadcast::Sender<AlertEvent>>>,
+}
+
+impl Alerts {
+    pub fn new(db: Database, notifiers: Vec<Arc<dyn Notifier>>) -> Arc<Self> {
+        Arc::new(Self {
+            db,
+            notifiers,
+            feeds: Mutex::new(HashMap::new()),
+            events: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Every alert of `owner`, oldest first, including triggered ones
+    pub async fn list(&self, owner: &str) -> Result<Vec<Alert>, AlertError> {
+        Ok(self.db.alerts(owner).await?)
+    }
+
+    /// Stored alerts still to be checked, as `(owner, alert)`, to be resumed
+    /// with [`Self::resume`]
+    pub async fn saved_alerts(&self) -> anyhow::Result<Vec<(String, Alert)>> {
+        self.db.active_alerts().await
+    }
+
+    pub async fn create(
+        self: &Arc<Self>,
+        owner: &str,
+        req: CreateAlertReq,
+        provider: SafeProvider,
+    ) -> Result<Alert, AlertError> {
+        let check = validate(&req)?;
+        let alert = Alert {
+            id: random_id("alert"),
+            config: CreateAlertReq { symbol: req.symbol.trim().to_ascii_uppercase(), ..req },
+            active: true,
+            created_at: Utc::now(),
+            last_triggered: None,
+        };
+        self.db.insert_alert(owner, &alert).await?;
+        self.watch(owner.to_owned(), alert.clone(), check, provider).await;
+        Ok(alert)
+    }
+
+    /// Start checking a stored alert again
+    pub async fn resume(self: &Arc<Self>, owner: String, alert: Alert, provider: SafeProvider) -> Result<(), AlertError> {
+        let check = Check::compile(&alert.config.condition)?;
+        self.watch(owner, alert, check, provider).await;
+        Ok(())
+    }
+
+    pub async fn delete(&self, owner: &str, id: &str) -> Result<(), AlertError> {
+        if !self.db.delete_alert(owner, id).await? {
+            return Err(AlertError::NotFound(id.to_owned()));
+        }
+        self.unwatch(|watch| watch.alert.id == id).await;
+        Ok(())
+    }
+
+    /// Alerts of `owner` that trigger from now on
+    pub async fn subscribe(&self, owner: &str) -> broadcast::Receiver<AlertEvent> {
+        self.events
+            .lock()
+            .await
+            .entry(owner.to_owned())
+            .or_insert_with(|| broadcast::channel(64).0)
+            .subscribe()
+    }
+
+    async fn watch(self: &Arc<Self>, owner: String, alert: Alert, check: Check, provider: SafeProvider) {
+        let key = FeedKey::of(&alert.config);
+        let mut feeds = self.feeds.lock().await;
+        let feed = feeds.entry(key.clone()).or_insert_with(|| {
+            info!("following {} {} candles for alerts", key.symbol, key.interval);
+            let state = Arc::new(Mutex::new(FeedState::default()));
+            let task = tokio::spawn(run_feed(Arc::downgrade(self), key, provider, state.clone()));
+            Feed { state, task }
+        });
+        let mut state = feed.state.lock().await;
+        // Conditions that already hold only trigger once they stop and
+        // start again
+        let held = (!state.bars.is_empty()).then(|| check.holds(&state.bars));
+        state.watches.push(Watch { owner, alert, check, held });
+    }
+
+    /// Stop checking the alerts `matches` picks, and close the feeds left
+    /// without alerts
+    async fn unwatch(&self, matches: impl Fn(&Watch) -> bool) {
+        let mut feeds = self.feeds.lock().await;
+        let mut idle = Vec::new();
+        for (key, feed) in feeds.iter() {
+            let mut state = feed.state.lock().await;
+            state.watches.retain(|watch| !matches(watch));
+            if state.watches.is_empty() {
+                idle.push(key.clone());
+            }
+        }
+        for key in idle {
+            if let Some(feed) = feeds.remove(&key) {
+                info!("stopped following {} {} candles", key.symbol, key.interval);
+                feed.task.abort();
+            }
+        }
+    }
+
+    async fn on_candle(&self, state: &Mutex<FeedState>, candle: Candle) {
+        let mut triggered = Vec::new();
+        {
+            let mut state = state.lock().await;
+            let FeedState { bars, watches } = &mut *state;
+            // Streams update the forming candle until the next one starts
+            match bars.last_mut() {
+                Some(last) if last.timestamp == candle.timestamp => *last = candle,
+                Some(last) if last.timestamp > candle.timestamp => return,
+                _ => bars.push(candle),
+            }
+            let excess = bars.len().saturating_sub(KEEP_BARS);
+            bars.drain(..excess);
+
+            let Some(last) = bars.last() else { return };
+            let now = Utc::now();
+            for watch in watches.iter_mut() {
+                let holds = watch.check.holds(bars);
+                if holds && watch.held == Some(false) {
+                    watch.alert.last_triggered = Some(now);
+                    watch.alert.active = watch.alert.config.repeat;
+                    let event = AlertEvent {
+                        alert_id: watch.alert.id.clone(),
+                        symbol: watch.alert.config.symbol.clone(),
+                        message: format!("{} {}", watch.alert.config.symbol, watch.alert.config.condition),
+                        price: last.close,
+                        triggered_at: now,
+                    };
+                    triggered.push((watch.owner.clone(), watch.alert.clone(), event));
+                }
+                watch.held = Some(holds);
+            }
+        }
+
+        for (owner, alert, event) in &triggered {
+            self.trigger(owner, alert, event).await;
+        }
+        if triggered.iter().any(|(_, alert, _)| !alert.active) {
+            self.unwatch(|watch| !watch.alert.active).await;
+        }
+    }
+
+    async fn trigger(&self, owner: &str, alert: &Alert, event: &AlertEvent) {
+        info!("alert {} triggered: {}", alert.id, event.message);
+        if let Err(e) = self.db.alert_triggered(&alert.id, event.triggered_at, alert.active).await {
+            error!("failed to save alert {}: {:#}", alert.id, e);
+        }
+        // Send errors only mean nobody is listening
+        if let Some(events) = self.events.lock().await.get(owner) {
+            let _ = events.send(event.clone());
+        }
+        // Slow webhooks or mail servers mustn't hold up the feed
+        for notifier in &self.notifiers {
+            let (notifier, alert, event) = (notifier.clone(), alert.clone(), event.clone());
+            tokio::spawn(async move {
+                if let Err(e) = notifier.notify(&alert, &event).await {
+                    warn!("failed to deliver alert {}: {:#}", alert.id, e);
+                }
+            });
+        }
+    }
+}
+
+fn validate(req: &CreateAlertReq) -> Result<Check, AlertError> {
+    let symbol = req.symbol.trim();
+    if symbol.is_empty() || symbol.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(AlertError::Invalid("symbol must be a single word".to_owned()));
+    }
+    if let Some(url) = &req.webhook {
+        webhook_url(url).map_err(|e| AlertError::Invalid(e.to_string()))?;
+    }
+    if let Some(email) = &req.email
+        && (!email.contains('@') || email.chars().any(|c| c.is_whitespace() || c.is_control() || c == '<' || c == '>'))
+    {
+        return Err(AlertError::Invalid(format!("bad email address `{email}`")));
+    }
+    Check::compile(&req.condition)
+}
+
+/// Follows the candles of `key` for as long as the alerts service lives,
+/// reopening the stream whenever it ends
+async fn run_feed(alerts: Weak<Alerts>, key: FeedKey, provider: SafeProvider, state: Arc<Mutex<FeedState>>) {
+    loop {
+        if let Err(e) = follow(&alerts, &key, &provider, &state).await {
+            warn!("alert feed of {} {} failed: {:#}", key.symbol, key.interval, e);
+        }
+        if alerts.strong_count() == 0 {
+            break;
+        }
+        tokio::time::sleep(RETRY_AFTER).await;
+    }
+}
+
+async fn follow(
+    alerts: &Weak<Alerts>,
+    key: &FeedKey,
+    provider: &SafeProvider,
+    state: &Mutex<FeedState>,
+) -> anyhow::Result<()> {
+    let end = Utc::now();
+    let start = end - chrono::Duration::seconds(key.interval.to_seconds() * SEED_BARS);
+    match provider.historical(&key.symbol, key.interval, start, end).await {
+        Ok(history) => {
+            let mut state = state.lock().await;
+            let FeedState { bars, watches } = &mut *state;
+            *bars = history;
+            for watch in watches.iter_mut() {
+                watch.held = Some(watch.check.holds(bars));
+            }
+        }
+        // Alerts on indicators wait longer for enough candles, the others
+        // work without history
+        Err(e) => warn!("no history for alerts on {} {}: {:#}", key.symbol, key.interval, e),
+    }
+
+    let mut stream = provider.stream(&key.symbol, key.interval).await?;
+    while let Some(candle) = stream.next().await {
+        let Some(alerts) = alerts.upgrade() else { return Ok(()) };
+        alerts.on_candle(state, candle).await;
+    }
+    anyhow::bail!("provider stream ended")
+}
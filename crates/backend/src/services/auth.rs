@@ -0,0 +1,233 @@
+use std::fmt::Write;
+use api::auth::{ApiKey, AuthSession, Credentials, NewApiKey, User};
+use argon2::password_hash::rand_core::OsRng;
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use axum::extract::Query;
+use axum::http::header::{AUTHORIZATION, UPGRADE};
+use axum::http::{HeaderMap, Uri};
+use crate::db::Database;
+use crate::services::random_id;
+use chrono::{Duration, Utc};
+use jsonwebtoken::{DecodingKey, EncodingKey, Header, Validation};
+use rand::distr::{Alphanumeric, SampleString};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use thiserror::Error;
+
+/// Marks API key secrets so they are easy to spot, e.g. in leaked logs
+const API_KEY_PREFIX: &str = "trk_";
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("wrong username or password")]
+    InvalidCredentials,
+    #[error("username {0} is taken")]
+    UsernameTaken(String),
+    #[error("missing or invalid credentials")]
+    Unauthorized,
+    #[error("unknown api key {0}")]
+    KeyNotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("storage error: {0:#}")]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Who a request acts for, put in the request extensions by the auth
+/// middleware
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuthUser {
+    pub id: String,
+    pub username: String,
+}
+
+/// Browsers can't set headers on a WebSocket upgrade, so it may carry its
+/// credentials in the query instead
+#[derive(Deserialize)]
+struct UpgradeCredentials {
+    token: Option<String>,
+    api_key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Claims {
+    sub: String,
+    name: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Accounts, their sessions and API keys.
+///
+/// Passwords are hashed with Argon2; sessions are HS256 JWTs signed with the
+/// configured secret; API keys are random secrets of which only a SHA-256
+/// hash is stored.
+pub struct Auth {
+    db: Database,
+    encoding: EncodingKey,
+    decoding: DecodingKey,
+    token_ttl: Duration,
+}
+
+impl Auth {
+    pub fn new(db: Database, secret: &[u8], token_ttl: Duration) -> Self {
+        Self {
+            db,
+            encoding: EncodingKey::from_secret(secret),
+            decoding: DecodingKey::from_secret(secret),
+            token_ttl,
+        }
+    }
+
+    /// Create an account and sign it in
+    pub async fn register(&self, credentials: Credentials) -> Result<AuthSession, AuthError> {
+        let username = credentials.username.trim();
+        if username.len() < 3 || username.len() > 32 {
+            return Err(AuthError::Invalid("username must be 3 to 32 characters".to_owned()));
+        }
+        if !username.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+            return Err(AuthError::Invalid("username may only hold letters, digits, '_' and '-'".to_owned()));
+        }
+        if credentials.password.chars().count() < 8 {
+            return Err(AuthError::Invalid("password must be at least 8 characters".to_owned()));
+        }
+
+        let user = User {
+            id: random_id("user"),
+            username: username.to_owned(),
+            created_at: Utc::now(),
+        };
+        let hash = hash_password(credentials.password).await?;
+        if !self.db.insert_user(&user, &hash).await? {
+            return Err(AuthError::UsernameTaken(user.username));
+        }
+        self.session(user)
+    }
+
+    pub async fn login(&self, credentials: Credentials) -> Result<AuthSession, AuthError> {
+        let (user, hash) = self.db
+            .user_credentials(credentials.username.trim())
+            .await?
+            .ok_or(AuthError::InvalidCredentials)?;
+        if !verify_password(credentials.password, hash).await? {
+            return Err(AuthError::InvalidCredentials);
+        }
+        self.session(user)
+    }
+
+    fn session(&self, user: User) -> Result<AuthSession, AuthError> {
+        let now = Utc::now();
+        let expires_at = now + self.token_ttl;
+        let claims = Claims {
+            sub: user.id.clone(),
+            name: user.username.clone(),
+            iat: now.timestamp(),
+            exp: expires_at.timestamp(),
+        };
+        let token = jsonwebtoken::encode(&Header::default(), &claims, &self.encoding)
+            .map_err(|e| anyhow::anyhow!("failed to sign token: {e}"))?;
+        Ok(AuthSession { token, expires_at, user })
+    }
+
+    /// Check the signature and expiry of a session token
+    pub fn verify_token(&self, token: &str) -> Result<AuthUser, AuthError> {
+        let data = jsonwebtoken::decode::<Claims>(token, &self.decoding, &Validation::default())
+            .map_err(|_err| AuthError::Unauthorized)?;
+        Ok(AuthUser { id: data.claims.sub, username: data.claims.name })
+    }
+
+    /// The user a request acts for: a session token in `Authorization`,
+    /// else an API key in `X-Api-Key`, else either one in the query of a
+    /// websocket upgrade
+    pub async fn authenticate(&self, headers: &HeaderMap, uri: &Uri) -> Result<AuthUser, AuthError> {
+        if let Some(token) = bearer_token(headers) {
+            return self.verify_token(token);
+        }
+        if let Some(key) = headers.get("x-api-key").and_then(|v| v.to_str().ok()) {
+            return self.verify_api_key(key).await;
+        }
+
+        if headers.contains_key(UPGRADE)
+            && let Ok(Query(query)) = Query::<UpgradeCredentials>::try_from_uri(uri)
+        {
+            if let Some(token) = query.token {
+                return self.verify_token(&token);
+            }
+            if let Some(key) = query.api_key {
+                return self.verify_api_key(&key).await;
+            }
+        }
+        Err(AuthError::Unauthorized)
+    }
+
+    pub async fn verify_api_key(&self, secret: &str) -> Result<AuthUser, AuthError> {
+        if !secret.starts_with(API_KEY_PREFIX) {
+            return Err(AuthError::Unauthorized);
+        }
+        let user = self.db.api_key_user(&hash_key(secret)).await?.ok_or(AuthError::Unauthorized)?;
+        Ok(AuthUser { id: user.id, username: user.username })
+    }
+
+    pub async fn user(&self, user: &AuthUser) -> Result<User, AuthError> {
+        self.db.user(&user.id).await?.ok_or(AuthError::Unauthorized)
+    }
+
+    pub async fn create_api_key(&self, user: &AuthUser, name: &str) -> Result<NewApiKey, AuthError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(AuthError::Invalid("name must not be empty".to_owned()));
+        }
+        let secret = format!("{API_KEY_PREFIX}{}", Alphanumeric.sample_string(&mut rand::rng(), 40));
+        let key = ApiKey {
+            id: random_id("key"),
+            name: name.to_owned(),
+            prefix: secret[..API_KEY_PREFIX.len() + 6].to_owned(),
+            created_at: Utc::now(),
+        };
+        self.db.insert_api_key(&user.id, &key, &hash_key(&secret)).await?;
+        Ok(NewApiKey { key, secret })
+    }
+
+    pub async fn api_keys(&self, user: &AuthUser) -> Result<Vec<ApiKey>, AuthError> {
+        Ok(self.db.api_keys(&user.id).await?)
+    }
+
+    pub async fn delete_api_key(&self, user: &AuthUser, id: &str) -> Result<(), AuthError> {
+        if !self.db.delete_api_key(&user.id, id).await? {
+            return Err(AuthError::KeyNotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<&str> {
+    headers.get(AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")
+}
+
+/// Argon2 is slow on purpose, so keep it off the async workers
+async fn hash_password(password: String) -> anyhow::Result<String> {
+    tokio::task::spawn_blocking(move || {
+        let salt = SaltString::generate(&mut OsRng);
+        Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map(|hash| hash.to_string())
+            .map_err(|e| anyhow::anyhow!("failed to hash password: {e}"))
+    })
+    .await?
+}
+
+async fn verify_password(password: String, hash: String) -> anyhow::Result<bool> {
+    tokio::task::spawn_blocking(move || {
+        let hash = PasswordHash::new(&hash).map_err(|e| anyhow::anyhow!("bad password hash: {e}"))?;
+        Ok(Argon2::default().verify_password(password.as_bytes(), &hash).is_ok())
+    })
+    .await?
+}
+
+fn hash_key(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes()).iter().fold(String::with_capacity(64), |mut hex, byte| {
+        let _ = write!(hex, "{byte:02x}");
+        hex
+    })
+}
@@ -0,0 +1,391 @@
+use std::collections::HashMap;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, PoisonError};
+use api::strategy::{BacktestEvent, BacktestRun, BacktestTrade, JobStatus, RunBacktestReq};
+use backtest::{Backtester, Progress};
+use crate::db::Database;
+use chrono::Utc;
+use dnn_core::market::CandleRange;
+use tokio::sync::{broadcast, Mutex, Semaphore};
+use tracing::{info, warn};
+use crate::services::strategies::{summarize, Strategies, StrategyError};
+use crate::services::{random_id, SafeProvider};
+
+/// Progress events are sent at most once per this fraction of the run
+const PROGRESS_STEP: f64 = 0.01;
+
+/// Job record shared between the queue and the task running it. Only ever
+/// locked briefly, including from the blocking backtest thread.
+type SharedRun = Arc<std::sync::Mutex<BacktestRun>>;
+
+struct Job {
+    run: SharedRun,
+    events: broadcast::Sender<BacktestEvent>,
+    cancel: Arc<AtomicBool>,
+}
+
+impl Job {
+    fn snapshot(&self) -> BacktestRun {
+        self.run.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+}
+
+/// Jobs that are queued or running, by id
+type JobMap = Arc<Mutex<HashMap<String, Job>>>;
+
+/// Queue of backtest jobs, run on a pool of at most `workers` at a time.
+///
+/// Jobs report progress and partial equity curves to subscribers. Every job
+/// is stored in the database, and once finished is served from there along
+/// with its equity curve and trades.
+pub struct BacktestJobs {
+    strategies: Arc<Strategies>,
+    db: Database,
+    workers: Arc<Semaphore>,
+    jobs: JobMap,
+}
+
+impl BacktestJobs {
+    pub async fn new(strategies: Arc<Strategies>, db: Database, workers: usize) -> anyhow::Result<Self> {
+        let interrupted = db.fail_unfinished_backtests("interrupted by a restart").await?;
+        if interrupted > 0 {
+            warn!("{} backtests were interrupted by a restart", interrupted);
+        }
+        Ok(Self {
+            strategies,
+            workers: Arc::new(Semaphore::new(workers.max(1))),
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            db,
+        })
+    }
+
+    /// Queue a backtest of a saved strategy
+    pub async fn submit(
+        &self,
+        owner: &str,
+        strategy_id: &str,
+        config: RunBacktestReq,
+        provider: SafeProvider,
+    ) -> Result<BacktestRun, StrategyError> {
+        if config.starting_cash <= 0.0 {
+            return Err(StrategyError::Invalid("starting_cash must be positive".to_owned()));
+        }
+        if config.start >= config.end {
+            return Err(StrategyError::Invalid("start must be before end".to_owned()));
+        }
+        // Fail fast on a missing or broken strategy rather than in the worker
+        let (_, strategy_version) = self.strategies.runnable(owner, strategy_id).await?;
+
+        let run = BacktestRun {
+            id: random_id("backtest"),
+            strategy_id: strategy_id.to_owned(),
+            strategy_version,
+            config,
+            status: JobStatus::Queued,
+            progress: 0.0,
+            submitted_at: Utc::now(),
+            started_at: None,
+            finished_at: None,
+            result: None,
+            error: None,
+            equity_curve: Vec::new(),
+        };
+        self.db.insert_backtest(&run).await?;
+        let job = Job {
+            run: Arc::new(std::sync::Mutex::new(run.clone())),
+            events: broadcast::channel(256).0,
+            cancel: Arc::new(AtomicBool::new(false)),
+        };
+
+        let worker = Worker {
+            run: job.run.clone(),
+            events: job.events.clone(),
+            cancel: job.cancel.clone(),
+            owner: owner.to_owned(),
+            strategies: self.strategies.clone(),
+            db: self.db.clone(),
+            jobs: self.jobs.clone(),
+            provider,
+        };
+        self.jobs.lock().await.insert(run.id.clone(), job);
+
+        let workers = self.workers.clone();
+        tokio::spawn(async move {
+            let Ok(_permit) = workers.acquire_owned().await else {
+                return;
+            };
+            worker.run().await;
+        });
+        Ok(run)
+    }
+
+    /// Jobs of a strategy, newest first and without equity curves
+    pub async fn list(&self, owner: &str, strategy_id: &str) -> Result<Vec<BacktestRun>, StrategyError> {
+        self.strategies.get(owner, strategy_id).await?;
+        let mut runs = self.db.backtests(strategy_id).await?;
+        let jobs = self.jobs.lock().await;
+        for run in &mut runs {
+            if let Some(job) = jobs.get(&run.id) {
+                *run = BacktestRun { equity_curve: Vec::new(), ..job.snapshot() };
+            }
+        }
+        Ok(runs)
+    }
+
+    pub async fn get(&self, owner: &str, strategy_id: &str, id: &str) -> Result<BacktestRun, StrategyError> {
+        self.strategies.get(owner, strategy_id).await?;
+        self.find(strategy_id, id).await
+    }
+
+    /// A job of a strategy already known to belong to the caller
+    async fn find(&self, strategy_id: &str, id: &str) -> Result<BacktestRun, StrategyError> {
+        let live = self.jobs.lock().await.get(id).map(Job::snapshot);
+        let run = match live {
+            Some(run) => Some(run),
+            None => self.db.backtest(id).await?,
+        };
+        run.filter(|run| run.strategy_id == strategy_id)
+            .ok_or_else(|| StrategyError::BacktestNotFound(id.to_owned()))
+    }
+
+    /// Fills of a finished job
+    pub async fn trades(&self, owner: &str, strategy_id: &str, id: &str) -> Result<Vec<BacktestTrade>, StrategyError> {
+        self.get(owner, strategy_id, id).await?;
+        Ok(self.db.backtest_trades(id).await?)
+    }
+
+    /// Current state plus a receiver for everything that happens next. The
+    /// receiver of a finished job is already closed.
+    pub async fn subscribe(
+        &self,
+        owner: &str,
+        strategy_id: &str,
+        id: &str,
+    ) -> Result<(BacktestRun, broadcast::Receiver<BacktestEvent>), StrategyError> {
+        self.strategies.get(owner, strategy_id).await?;
+        {
+            let jobs = self.jobs.lock().await;
+            if let Some(job) = jobs.get(id) {
+                // Subscribe before the snapshot so no event falls in between
+                let events = job.events.subscribe();
+                let run = job.snapshot();
+                if run.strategy_id != strategy_id {
+                    return Err(StrategyError::BacktestNotFound(id.to_owned()));
+                }
+                return Ok((run, events));
+            }
+        }
+        let run = self.find(strategy_id, id).await?;
+        Ok((run, broadcast::channel(1).1))
+    }
+
+    /// Stop a queued or running job. A running job ends as cancelled once
+    /// its worker notices, within one bar.
+    pub async fn cancel(&self, owner: &str, strategy_id: &str, id: &str) -> Result<BacktestRun, StrategyError> {
+        self.strategies.get(owner, strategy_id).await?;
+        let run = {
+            let jobs = self.jobs.lock().await;
+            let Some(job) = jobs.get(id).filter(|job| job.snapshot().strategy_id == strategy_id) else {
+                drop(jobs);
+                // Only finished jobs are left to the database
+                self.find(strategy_id, id).await?;
+                return Err(StrategyError::BacktestFinished(id.to_owned()));
+            };
+            job.cancel.store(true, Ordering::Relaxed);
+            let mut run = job.run.lock().unwrap_or_else(PoisonError::into_inner);
+            if run.status.is_finished() {
+                return Err(StrategyError::BacktestFinished(id.to_owned()));
+            }
+            if run.status != JobStatus::Queued {
+                return Ok(run.clone());
+            }
+            // A queued job never reaches a worker, so finish it here
+            run.status = JobStatus::Cancelled;
+            run.finished_at = Some(Utc::now());
+            let _ = job.events.send(BacktestEvent::Finished { run: run.clone() });
+            run.clone()
+        };
+        self.db.update_backtest(&run).await?;
+        Ok(run)
+    }
+
+    /// Cancel every job of a deleted strategy
+    pub async fn remove_strategy(&self, strategy_id: &str) {
+        self.jobs.lock().await.retain(|_, job| {
+            let keep = job.snapshot().strategy_id != strategy_id;
+            if !keep {
+                job.cancel.store(true, Ordering::Relaxed);
+            }
+            keep
+        });
+    }
+}
+
+/// Everything a queued job needs to run
+struct Worker {
+    run: SharedRun,
+    events: broadcast::Sender<BacktestEvent>,
+    cancel: Arc<AtomicBool>,
+    /// User the job runs for
+    owner: String,
+    strategies: Arc<Strategies>,
+    db: Database,
+    jobs: JobMap,
+    provider: SafeProvider,
+}
+
+impl Worker {
+    fn update<T>(&self, f: impl FnOnce(&mut BacktestRun) -> T) -> T {
+        f(&mut self.run.lock().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.load(Ordering::Relaxed)
+    }
+
+    async fn run(self) {
+        let cancelled = self.cancelled();
+        let (started, run) = self.update(|run| {
+            // Cancelled while queued, and already finished by `cancel`
+            if run.status != JobStatus::Queued {
+                return (false, run.clone());
+            }
+            if cancelled {
+                run.status = JobStatus::Cancelled;
+                run.finished_at = Some(Utc::now());
+                return (false, run.clone());
+            }
+            run.status = JobStatus::Running;
+            run.started_at = Some(Utc::now());
+            (true, run.clone())
+        });
+        if !started {
+            self.finish(&run, &[]).await;
+            return;
+        }
+        if let Err(e) = self.db.update_backtest(&run).await {
+            warn!("failed to store backtest {}: {:?}", run.id, e);
+        }
+        let _ = self.events.send(BacktestEvent::Started { ts: Utc::now() });
+        info!("backtest {} of {} started", run.id, run.strategy_id);
+        let outcome = self.execute(&run.strategy_id, run.config.clone()).await;
+
+        let mut trades = Vec::new();
+        let run = self.update(|run| {
+            run.finished_at = Some(Utc::now());
+            match outcome {
+                Ok(result) => {
+                    run.status = JobStatus::Done;
+                    run.progress = 1.0;
+                    run.equity_curve.clone_from(&result.equity_curve);
+                    run.result = Some(summarize(&result));
+                    trades = traded(result);
+                }
+                Err(None) => run.status = JobStatus::Cancelled,
+                Err(Some(message)) => {
+                    run.status = JobStatus::Failed;
+                    run.error = Some(message);
+                }
+            }
+            run.clone()
+        });
+        match (&run.result, &run.error) {
+            (Some(result), _) => {
+                if let Err(e) = self.strategies.record_backtest(&run.strategy_id, result).await {
+                    warn!("failed to record backtest {} on {}: {}", run.id, run.strategy_id, e);
+                }
+            }
+            (_, Some(error)) => warn!("backtest {} failed: {}", run.id, error),
+            _ => info!("backtest {} cancelled", run.id),
+        }
+        self.finish(&run, &trades).await;
+        let _ = self.events.send(BacktestEvent::Finished { run });
+    }
+
+    /// Store the final state and hand the job over to the database
+    async fn finish(&self, run: &BacktestRun, trades: &[BacktestTrade]) {
+        if let Err(e) = self.db.finish_backtest(run, trades).await {
+            warn!("failed to store backtest {}: {:?}", run.id, e);
+        }
+        self.jobs.lock().await.remove(&run.id);
+    }
+
+    /// Load data and run the backtest. `Err(None)` means cancelled.
+    async fn execute(&self, strategy_id: &str, config: RunBacktestReq) -> Result<backtest::BacktestResult, Option<String>> {
+        let (strategy, _) = self.strategies.runnable(&self.owner, strategy_id).await.map_err(|e| Some(e.to_string()))?;
+        let candles = self.provider
+            .historical(&config.symbol, config.interval, config.start, config.end)
+            .await
+            .map_err(|e| Some(format!("failed to load market data: {e}")))?;
+        if candles.is_empty() {
+            return Err(Some(format!("no data for {} in that range", config.symbol)));
+        }
+        if self.cancelled() {
+            return Err(None);
+        }
+
+        let data = CandleRange { symbol: config.symbol, data: candles };
+        let (run, events, cancel) = (self.run.clone(), self.events.clone(), self.cancel.clone());
+        let result = tokio::task::spawn_blocking(move || {
+            let mut reporter = ProgressReporter { run, events, pending: Vec::new(), reported: 0.0 };
+            Backtester::new(strategy, config.starting_cash).run_with_progress(std::slice::from_ref(&data), |p| {
+                if cancel.load(Ordering::Relaxed) {
+                    return ControlFlow::Break(());
+                }
+                reporter.on_progress(p);
+                ControlFlow::Continue(())
+            })
+        })
+        .await
+        .map_err(|e| Some(format!("backtest panicked: {e}")))?;
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(_) if self.cancelled() => Err(None),
+            Err(e) => Err(Some(e.to_string())),
+        }
+    }
+}
+
+/// Fills of a run along with the symbol and side of their orders
+fn traded(result: backtest::BacktestResult) -> Vec<BacktestTrade> {
+    let orders: HashMap<u64, _> = result.orders.into_iter().map(|order| (order.id, order)).collect();
+    result.trades
+        .into_iter()
+        .map(|fill| {
+            let order = orders.get(&fill.order_id);
+            BacktestTrade {
+                symbol: order.map(|order| order.symbol.clone()).unwrap_or_default(),
+                side: order.map(|order| order.side),
+                fill,
+            }
+        })
+        .collect()
+}
+
+/// Batches equity points into progress events, one per [`PROGRESS_STEP`]
+struct ProgressReporter {
+    run: SharedRun,
+    events: broadcast::Sender<BacktestEvent>,
+    pending: Vec<f64>,
+    reported: f64,
+}
+
+impl ProgressReporter {
+    fn on_progress(&mut self, p: &Progress) {
+        self.pending.push(p.equity);
+        let progress = p.fraction();
+        if progress - self.reported < PROGRESS_STEP && progress < 1.0 {
+            return;
+        }
+        self.reported = progress;
+        let equity = std::mem::take(&mut self.pending);
+        {
+            let mut run = self.run.lock().unwrap_or_else(PoisonError::into_inner);
+            run.progress = progress;
+            run.equity_curve.extend_from_slice(&equity);
+        }
+        let _ = self.events.send(BacktestEvent::Progress { progress, ts: p.ts, equity });
+    }
+}
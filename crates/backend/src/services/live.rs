@@ -0,0 +1,259 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use axum::extract::ws::{close_code, CloseFrame, Message, Utf8Bytes, WebSocket};
+use api::stock::{
+    ErrorCode, StockWatchError, StockWatchReq, StockWatchReqMsg, StockWatchResMsg, Subscription, HEARTBEAT_SECS,
+    IDLE_TIMEOUT_SECS, MAX_SUBSCRIPTIONS, PROTOCOL_VERSION,
+};
+use api::ProviderType;
+use futures::stream::SplitSink;
+use futures::{SinkExt, StreamExt};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tracing::{debug, warn};
+use dnn_core::time::Timestamp;
+use crate::services::SafeProvider;
+
+/// Shared by the connection and the tasks of its subscriptions
+type Sender = Arc<Mutex<SplitSink<WebSocket, Message>>>;
+
+/// Timing and size limits of one live connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiveLimits {
+    pub heartbeat: Duration,
+    pub idle_timeout: Duration,
+    pub max_subscriptions: usize,
+}
+
+impl Default for LiveLimits {
+    fn default() -> Self {
+        Self {
+            heartbeat: Duration::from_secs(HEARTBEAT_SECS),
+            idle_timeout: Duration::from_secs(IDLE_TIMEOUT_SECS),
+            max_subscriptions: MAX_SUBSCRIPTIONS,
+        }
+    }
+}
+
+struct Running {
+    subscription: Subscription,
+    task: JoinHandle<()>,
+}
+
+/// Serves the live candle protocol of [`api::stock`], one websocket at a time
+pub struct LiveStreams {
+    providers: HashMap<ProviderType, SafeProvider>,
+    limits: LiveLimits,
+}
+
+impl LiveStreams {
+    pub fn new(providers: HashMap<ProviderType, SafeProvider>, limits: LiveLimits) -> Self {
+        Self { providers, limits }
+    }
+
+    /// Runs one connection until the client leaves or goes idle
+    pub async fn serve(&self, ws: WebSocket) {
+        let (sender, mut receiver) = ws.split();
+        let sender: Sender = Arc::new(Mutex::new(sender));
+        let mut subscriptions: HashMap<String, Running> = HashMap::new();
+
+        let hello = StockWatchResMsg::Hello {
+            v: PROTOCOL_VERSION,
+            heartbeat_secs: self.limits.heartbeat.as_secs(),
+            idle_timeout_secs: self.limits.idle_timeout.as_secs(),
+        };
+        if send_json(&sender, &hello).await.is_err() {
+            return;
+        }
+
+        let mut heartbeat = tokio::time::interval_at(Instant::now() + self.limits.heartbeat, self.limits.heartbeat);
+        let mut last_heard = Instant::now();
+
+        loop {
+            tokio::select! {
+                msg = receiver.next() => {
+                    let Some(Ok(msg)) = msg else { break };
+                    last_heard = Instant::now();
+                    let handled = match msg {
+                        Message::Text(text) => self.on_request(&sender, &mut subscriptions, &text).await,
+                        Message::Binary(_) => {
+                            let error = StockWatchError::new(None, ErrorCode::InvalidRequest, "requests must be text frames");
+                            send_json(&sender, &StockWatchResMsg::Error(error)).await
+                        }
+                        // Pings are answered by axum; both count as activity
+                        Message::Ping(_) | Message::Pong(_) => Ok(()),
+                        Message::Close(_) => break,
+                    };
+                    if handled.is_err() {
+                        break;
+                    }
+                }
+                _ = heartbeat.tick() => {
+                    if last_heard.elapsed() >= self.limits.idle_timeout {
+                        debug!("closing idle live connection");
+                        let close = CloseFrame { code: close_code::AWAY, reason: Utf8Bytes::from_static("idle timeout") };
+                        let _ = sender.lock().await.send(Message::Close(Some(close))).await;
+                        break;
+                    }
+                    if sender.lock().await.send(Message::Ping(Default::default())).await.is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        for running in subscriptions.into_values() {
+            running.task.abort();
+        }
+    }
+
+    /// Answers one request. Fails only once the client can't be written to.
+    async fn on_request(
+        &self,
+        sender: &Sender,
+        subscriptions: &mut HashMap<String, Running>,
+        text: &str,
+    ) -> anyhow::Result<()> {
+        // Replays that ended are no longer subscribed
+        subscriptions.retain(|_, running| !running.task.is_finished());
+
+        let request = match StockWatchReq::parse(text) {
+            Ok(request) => request,
+            Err(error) => return send_json(sender, &StockWatchResMsg::Error(error)).await,
+        };
+        match request {
+            StockWatchReqMsg::Day { id, provider, symbol, interval } => {
+                // Reusing an id replaces its subscription
+                if let Some(old) = subscriptions.remove(&id) {
+                    old.task.abort();
+                }
+                if subscriptions.len() >= self.limits.max_subscriptions {
+                    return self.send_too_many(sender, id).await;
+                }
+                let Some(p) = self.providers.get(&provider) else {
+                    return send_error(sender, id, ErrorCode::UnknownProvider, format!("unknown provider {}", provider.as_ref())).await;
+                };
+                let mut stream = match p.stream(&symbol, interval).await {
+                    Ok(stream) => stream,
+                    Err(e) => return send_error(sender, id, ErrorCode::ProviderError, format!("stream failed: {e:#}")).await,
+                };
+
+                let subscription = Subscription { id: id.clone(), provider, symbol, interval, replay: false };
+                // Acknowledged before the first candle can go out
+                send_json(sender, &StockWatchResMsg::Subscribed { subscription: subscription.clone() }).await?;
+                let task_sender = sender.clone();
+                let sub = subscription.clone();
+                let task = tokio::spawn(async move {
+                    while let Some(candle) = stream.next().await {
+                        let msg = StockWatchResMsg::Candle {
+                            id: sub.id.clone(),
+                            provider: sub.provider,
+                            symbol: sub.symbol.clone(),
+                            interval: sub.interval,
+                            candle,
+                        };
+                        if send_json(&task_sender, &msg).await.is_err() {
+                            return;
+                        }
+                    }
+                    let _ = send_json(&task_sender, &StockWatchResMsg::End { id: sub.id }).await;
+                });
+                subscriptions.insert(id, Running { subscription, task });
+                Ok(())
+            }
+
+            StockWatchReqMsg::Night { id, provider, symbol, interval, start, end, playback_speed } => {
+                if let Some(old) = subscriptions.remove(&id) {
+                    old.task.abort();
+                }
+                if subscriptions.len() >= self.limits.max_subscriptions {
+                    return self.send_too_many(sender, id).await;
+                }
+                let Some(p) = self.providers.get(&provider) else {
+                    return send_error(sender, id, ErrorCode::UnknownProvider, format!("unknown provider {}", provider.as_ref())).await;
+                };
+                if start >= end {
+                    return send_error(sender, id, ErrorCode::InvalidRequest, "start must be before end").await;
+                }
+                if playback_speed == Some(0) {
+                    return send_error(sender, id, ErrorCode::InvalidRequest, "playback_speed must be at least 1").await;
+                }
+
+                let subscription = Subscription { id: id.clone(), provider, symbol, interval, replay: true };
+                send_json(sender, &StockWatchResMsg::Subscribed { subscription: subscription.clone() }).await?;
+                let delay = Duration::from_millis(1000 / u64::from(playback_speed.unwrap_or(1)));
+                let task = tokio::spawn(replay(sender.clone(), p.clone(), subscription.clone(), start, end, delay));
+                subscriptions.insert(id, Running { subscription, task });
+                Ok(())
+            }
+
+            StockWatchReqMsg::Unsubscribe { id } => match subscriptions.remove(&id) {
+                Some(running) => {
+                    running.task.abort();
+                    send_json(sender, &StockWatchResMsg::Unsubscribed { id }).await
+                }
+                None => send_error(sender, id.clone(), ErrorCode::UnknownSubscription, format!("{id} is not subscribed")).await,
+            },
+
+            StockWatchReqMsg::List { id } => {
+                let mut open: Vec<Subscription> = subscriptions.values().map(|r| r.subscription.clone()).collect();
+                open.sort_by(|a, b| a.id.cmp(&b.id));
+                send_json(sender, &StockWatchResMsg::Subscriptions { id, subscriptions: open }).await
+            }
+
+            StockWatchReqMsg::Ping => send_json(sender, &StockWatchResMsg::Pong).await,
+        }
+    }
+
+    async fn send_too_many(&self, sender: &Sender, id: String) -> anyhow::Result<()> {
+        let message = format!("at most {} subscriptions per connection", self.limits.max_subscriptions);
+        send_error(sender, id, ErrorCode::TooManySubscriptions, message).await
+    }
+}
+
+async fn send_json<T: serde::Serialize>(sender: &Sender, msg: &T) -> anyhow::Result<()> {
+    let text = serde_json::to_string(msg)?;
+    sender.lock().await.send(Message::Text(Utf8Bytes::from(text))).await?;
+    Ok(())
+}
+
+async fn send_error(sender: &Sender, id: String, code: ErrorCode, message: impl Into<String>) -> anyhow::Result<()> {
+    send_json(sender, &StockWatchResMsg::Error(StockWatchError::new(Some(id), code, message))).await
+}
+
+/// Plays the candles of `subscription` back, `delay` apart, then ends it
+async fn replay(
+    sender: Sender,
+    provider: SafeProvider,
+    subscription: Subscription,
+    start: Timestamp,
+    end: Timestamp,
+    delay: Duration,
+) {
+    let Subscription { id, symbol, interval, .. } = subscription;
+    let candles = match provider.historical(&symbol, interval, start, end).await {
+        Ok(candles) => candles,
+        Err(e) => {
+            warn!("replay of {} failed: {:#}", symbol, e);
+            let _ = send_error(&sender, id, ErrorCode::ProviderError, format!("historical data failed: {e:#}")).await;
+            return;
+        }
+    };
+
+    for candle in candles {
+        let msg = StockWatchResMsg::Candle {
+            id: id.clone(),
+            provider: provider.get_type(),
+            symbol: symbol.clone(),
+            interval,
+            candle,
+        };
+        if send_json(&sender, &msg).await.is_err() {
+            return;
+        }
+        tokio::time::sleep(delay).await;
+    }
+    let _ = send_json(&sender, &StockWatchResMsg::End { id }).await;
+}
@@ -0,0 +1,225 @@
+//! Ways a triggered alert reaches its owner besides `/alerts/stream`
+
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use anyhow::{anyhow, bail, Context};
+use api::alert::{Alert, AlertEvent};
+use async_trait::async_trait;
+use reqwest::dns::{Name, Resolve, Resolving};
+use reqwest::redirect::Policy;
+use reqwest::Url;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+/// Delivers triggered alerts somewhere outside the backend
+#[async_trait]
+pub trait Notifier: Send + Sync {
+    /// Alerts that don't ask for this kind of delivery are skipped
+    async fn notify(&self, alert: &Alert, event: &AlertEvent) -> anyhow::Result<()>;
+}
+
+/// Posts the [`AlertEvent`] as JSON to the alert's `webhook`. Webhooks are
+/// set by users, so they only ever reach public addresses: hosts resolving
+/// to anything else are refused, and redirects aren't followed.
+pub struct WebhookNotifier {
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new() -> anyhow::Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(10))
+            .redirect(Policy::none())
+            // A proxy would resolve hosts itself
+            .no_proxy()
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()?;
+        Ok(Self { client })
+    }
+}
+
+#[async_trait]
+impl Notifier for WebhookNotifier {
+    async fn notify(&self, alert: &Alert, event: &AlertEvent) -> anyhow::Result<()> {
+        let Some(url) = &alert.config.webhook else { return Ok(()) };
+        let response = self.client.post(webhook_url(url)?).json(event).send().await?;
+        if response.status().is_redirection() {
+            bail!("webhook {url} redirects, which isn't followed");
+        }
+        response.error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Parses a webhook URL, refusing schemes other than http and https and
+/// hosts given as addresses that aren't public. Names are checked when
+/// they're resolved, since what they point to can change.
+pub fn webhook_url(url: &str) -> anyhow::Result<Url> {
+    let parsed = Url::parse(url).with_context(|| format!("bad URL `{url}`"))?;
+    if !matches!(parsed.scheme(), "http" | "https") {
+        bail!("webhook must be an http or https URL");
+    }
+    let host = parsed.host_str().ok_or_else(|| anyhow!("webhook {url} has no host"))?;
+    if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>()
+        && !is_public(ip)
+    {
+        bail!("webhook {url} points to the internal address {ip}");
+    }
+    Ok(parsed)
+}
+
+/// Whether `ip` is reachable on the internet, rather than on this host, a
+/// private network or a range set aside for something else
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        // "This network", carrier-grade NAT, protocol assignments,
+        // benchmarking and reserved
+        || a == 0
+        || (a == 100 && (64..128).contains(&b))
+        || (a, b, c) == (192, 0, 0)
+        || (a == 198 && (b == 18 || b == 19))
+        || a >= 240)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    !(ip.is_unspecified()
+        || ip.is_loopback()
+        || ip.is_multicast()
+        // Unique local, link-local and documentation
+        || (segments[0] & 0xfe00) == 0xfc00
+        || (segments[0] & 0xffc0) == 0xfe80
+        || (segments[0], segments[1]) == (0x2001, 0xdb8)
+        // IPv4 translated by NAT64 could still be internal
+        || (segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0]
+            && !is_public_v4(Ipv4Addr::from_bits((u32::from(segments[6]) << 16) | u32::from(segments[7])))))
+}
+
+/// Resolves webhook hosts like the system does, keeping only public
+/// addresses. Checking here rather than before the request means a name
+/// can't resolve to something else by the time it's connected to.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let host = name.as_str().to_owned();
+            let addrs: Vec<_> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(anyhow!("{host} has no public address").into());
+            }
+            Ok(Box::new(addrs.into_iter()) as _)
+        })
+    }
+}
+
+/// Mails the alert's `email` through a plain SMTP server without
+/// authentication or TLS, such as a local relay or a mail catcher
+pub struct EmailNotifier {
+    host: String,
+    port: u16,
+    from: String,
+}
+
+impl EmailNotifier {
+    pub fn new(host: String, port: u16, from: String) -> Self {
+        Self { host, port, from }
+    }
+}
+
+#[async_trait]
+impl Notifier for EmailNotifier {
+    async fn notify(&self, alert: &Alert, event: &AlertEvent) -> anyhow::Result<()> {
+        let Some(to) = &alert.config.email else { return Ok(()) };
+        let stream = TcpStream::connect((self.host.as_str(), self.port))
+            .await
+            .with_context(|| format!("failed to connect to {}:{}", self.host, self.port))?;
+        let mut smtp = Smtp { stream: BufReader::new(stream) };
+        smtp.reply(220).await?;
+        smtp.command("HELO traiter", 250).await?;
+        smtp.command(&format!("MAIL FROM:<{}>", self.from), 250).await?;
+        smtp.command(&format!("RCPT TO:<{to}>"), 250).await?;
+        smtp.command("DATA", 354).await?;
+
+        let body = format!(
+            "{} at {} ({})\n\nAlert {} on {} {} candles",
+            event.message,
+            event.price,
+            event.triggered_at.to_rfc2822(),
+            alert.id,
+            alert.config.symbol,
+            alert.config.interval,
+        );
+        // Headers end at the first line break
+        let subject = event.message.replace(char::is_control, " ");
+        let mut message = format!(
+            "From: <{}>\r\nTo: <{to}>\r\nSubject: {subject}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n",
+            self.from
+        );
+        for line in body.lines() {
+            // A lone dot would end the message early
+            if line.starts_with('.') {
+                message.push('.');
+            }
+            message.push_str(line);
+            message.push_str("\r\n");
+        }
+        message.push('.');
+        smtp.command(&message, 250).await?;
+        smtp.command("QUIT", 221).await
+    }
+}
+
+struct Smtp {
+    stream: BufReader<TcpStream>,
+}
+
+impl Smtp {
+    async fn command(&mut self, line: &str, code: u16) -> anyhow::Result<()> {
+        self.stream.get_mut().write_all(format!("{line}\r\n").as_bytes()).await?;
+        self.reply(code).await
+    }
+
+    /// Reads a reply, failing unless its code is `code`. Lines of replies
+    /// that go on have a `-` after the code.
+    async fn reply(&mut self, code: u16) -> anyhow::Result<()> {
+        loop {
+            let mut line = String::new();
+            if self.stream.read_line(&mut line).await? == 0 {
+                bail!("the SMTP server closed the connection");
+            }
+            let line = line.trim_end();
+            if line.as_bytes().get(3) == Some(&b'-') {
+                continue;
+            }
+            let got: u16 = line
+                .get(..3)
+                .and_then(|c| c.parse().ok())
+                .ok_or_else(|| anyhow!("bad SMTP reply `{line}`"))?;
+            if got != code {
+                bail!("SMTP server replied `{line}`, expected {code}");
+            }
+            return Ok(());
+        }
+    }
+}
@@ -0,0 +1,324 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use anyhow::{anyhow, Context};
+use api::paper::{PaperEvent, PaperSessionInfo, PaperStrategy, StartPaperReq};
+use crate::db::{Database, PaperPortfolio};
+use backtest::TradingEngine;
+use chrono::Utc;
+use dnn_core::fx::FxRates;
+use dnn_core::market::Candle;
+use dnn_core::time::Timestamp;
+use futures::StreamExt;
+use risk::{RiskEngine, RiskLimits};
+use strats::sma_cross::SmaCross;
+use strats::Strategy;
+use tokio::sync::{broadcast, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use crate::services::strategies::Strategies;
+use crate::services::{random_id, SafeProvider};
+
+type PaperEngine = TradingEngine<Box<dyn Strategy + Send>>;
+
+struct PaperSession {
+    /// User the session belongs to
+    owner: String,
+    info: Arc<Mutex<PaperSessionInfo>>,
+    events: broadcast::Sender<PaperEvent>,
+    task: JoinHandle<()>,
+}
+
+/// Runs strategies against live provider streams with a simulated portfolio.
+/// Every user only sees their own sessions.
+///
+/// Fills use the backtester's execution model and orders are checked against
+/// the configured risk limits; each session's ledger is stored after every
+/// bar so the paper portfolio survives restarts.
+pub struct PaperTrading {
+    db: Database,
+    strategies: Arc<Strategies>,
+    risk: RiskLimits,
+    sessions: Mutex<HashMap<String, PaperSession>>,
+}
+
+impl PaperTrading {
+    pub fn new(db: Database, strategies: Arc<Strategies>, risk: RiskLimits) -> Self {
+        Self {
+            db,
+            strategies,
+            risk,
+            sessions: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Build the strategy of a session of `owner`, pinning saved strategies
+    /// to the version it runs
+    async fn build_strategy(&self, owner: &str, strategy: &mut PaperStrategy) -> anyhow::Result<Box<dyn Strategy + Send>> {
+        match strategy {
+            PaperStrategy::SmaCross { short, long } => Ok(Box::new(SmaCross::new(*short, *long))),
+            PaperStrategy::Saved { strategy_id, version } => {
+                let (built, runs) = self.strategies.runnable_version(owner, strategy_id, *version).await?;
+                *version = Some(runs);
+                Ok(built)
+            }
+        }
+    }
+
+    /// Stored sessions as `(id, config)`, to be resumed with [`Self::resume`]
+    pub async fn saved_sessions(&self) -> anyhow::Result<Vec<(String, StartPaperReq)>> {
+        Ok(self.db
+            .paper_portfolios()
+            .await?
+            .into_iter()
+            .map(|(id, portfolio)| (id, portfolio.config))
+            .collect())
+    }
+
+    /// Start a new session of `owner` with a fresh portfolio
+    pub async fn start(
+        &self,
+        owner: &str,
+        mut config: StartPaperReq,
+        provider: SafeProvider,
+    ) -> anyhow::Result<PaperSessionInfo> {
+        let id = random_id("paper");
+        let strategy = self.build_strategy(owner, &mut config.strategy).await?;
+        let engine = TradingEngine::new(strategy, config.starting_cash)
+            .with_risk(RiskEngine::new(self.risk.clone()));
+        self.spawn(id, owner.to_owned(), config, Utc::now(), engine, provider).await
+    }
+
+    /// Restart a stored session, continuing from its saved ledger
+    pub async fn resume(&self, id: &str, provider: SafeProvider) -> anyhow::Result<PaperSessionInfo> {
+        let mut saved = self.db
+            .paper_portfolio(id)
+            .await?
+            .ok_or_else(|| anyhow!("unknown paper session {id}"))?;
+        let strategy = self.build_strategy(&saved.owner, &mut saved.config.strategy).await?;
+        let engine = TradingEngine::new(strategy, saved.config.starting_cash)
+            .with_risk(RiskEngine::new(self.risk.clone()))
+            .with_ledger(saved.ledger);
+        self.spawn(id.to_owned(), saved.owner, saved.config, saved.started, engine, provider).await
+    }
+
+    async fn spawn(
+        &self,
+        id: String,
+        owner: String,
+        config: StartPaperReq,
+        started: Timestamp,
+        engine: PaperEngine,
+        provider: SafeProvider,
+    ) -> anyhow::Result<PaperSessionInfo> {
+        let stream = provider
+            .stream(&config.symbol, config.interval)
+            .await
+            .context("failed to open provider stream")?;
+
+        // Until the first bar arrives, positions are marked at their cost
+        let fx = FxRates::new();
+        let portfolio = engine.ledger().portfolio();
+        let marks: HashMap<String, f64> = portfolio.positions
+            .iter()
+            .map(|(s, p)| (s.clone(), p.avg_price))
+            .collect();
+        let funded = !engine.ledger().entries().is_empty();
+        let info = PaperSessionInfo {
+            id: id.clone(),
+            config: config.clone(),
+            started,
+            running: true,
+            equity: if funded { portfolio.total_value(&marks, &fx)? } else { config.starting_cash },
+            cash: if funded { portfolio.total_cash(&fx)? } else { config.starting_cash },
+            realized_pnl: portfolio.realized_pnl(&fx)?,
+            unrealized_pnl: 0.0,
+            positions: portfolio.positions.values().cloned().collect(),
+        };
+        let info = Arc::new(Mutex::new(info));
+        let (events, _) = broadcast::channel(256);
+
+        let runner = SessionRunner {
+            id: id.clone(),
+            owner: owner.clone(),
+            db: self.db.clone(),
+            config,
+            started,
+            since: Utc::now(),
+            last: None,
+            forming: None,
+            engine,
+            info: info.clone(),
+            events: events.clone(),
+        };
+        self.db.save_paper_portfolio(&id, &runner.portfolio()).await?;
+        let task = tokio::spawn(runner.run(stream));
+
+        let snapshot = info.lock().await.clone();
+        info!("started paper session {}", id);
+        let mut sessions = self.sessions.lock().await;
+        // Only a session resumed twice can replace another
+        let old = sessions.insert(id, PaperSession { owner, info, events, task });
+        drop(sessions);
+        if let Some(old) = old {
+            old.task.abort();
+            let _ = old.task.await;
+        }
+        Ok(snapshot)
+    }
+
+    /// Sessions of `owner`, oldest first
+    pub async fn list(&self, owner: &str) -> Vec<PaperSessionInfo> {
+        let sessions = self.sessions.lock().await;
+        let mut infos = Vec::new();
+        for session in sessions.values().filter(|session| session.owner == owner) {
+            infos.push(session.info.lock().await.clone());
+        }
+        infos.sort_by_key(|i| i.started);
+        infos
+    }
+
+    pub async fn get(&self, owner: &str, id: &str) -> Option<PaperSessionInfo> {
+        let sessions = self.sessions.lock().await;
+        let session = sessions.get(id).filter(|session| session.owner == owner)?;
+        Some(session.info.lock().await.clone())
+    }
+
+    /// Current state plus a receiver for everything that happens after it
+    pub async fn subscribe(
+        &self,
+        owner: &str,
+        id: &str,
+    ) -> Option<(PaperSessionInfo, broadcast::Receiver<PaperEvent>)> {
+        let sessions = self.sessions.lock().await;
+        let session = sessions.get(id).filter(|session| session.owner == owner)?;
+        let info = session.info.lock().await.clone();
+        Some((info, session.events.subscribe()))
+    }
+
+    /// Stop a session and delete its stored portfolio
+    pub async fn stop(&self, owner: &str, id: &str) -> anyhow::Result<()> {
+        let session = {
+            let mut sessions = self.sessions.lock().await;
+            let owned = sessions.get(id).is_some_and(|session| session.owner == owner);
+            owned.then(|| sessions.remove(id)).flatten()
+        }
+        .ok_or_else(|| anyhow!("unknown paper session {id}"))?;
+        session.task.abort();
+        // A save still in flight would bring the session back on the next
+        // start; the error only says the task was cancelled
+        let _ = session.task.await;
+        let _ = session.events.send(PaperEvent::Stopped { reason: None });
+
+        self.db.delete_paper_portfolio(id).await
+    }
+}
+
+/// Owns the engine of one session inside its task
+struct SessionRunner {
+    id: String,
+    owner: String,
+    db: Database,
+    config: StartPaperReq,
+    started: Timestamp,
+    /// When this run began; bars that closed before are history
+    since: Timestamp,
+    /// Start of the last bar handed to the engine or skipped as history
+    last: Option<Timestamp>,
+    /// The newest bar, which streams keep updating until the next starts
+    forming: Option<Candle>,
+    engine: PaperEngine,
+    info: Arc<Mutex<PaperSessionInfo>>,
+    events: broadcast::Sender<PaperEvent>,
+}
+
+impl SessionRunner {
+    async fn run(mut self, mut stream: data::providers::ProviderStream) {
+        let symbol = self.config.symbol.clone();
+        while let Some(candle) = stream.next().await {
+            let Some(closed) = self.closed_bar(candle) else { continue };
+            if let Err(e) = self.on_bar(&symbol, &closed).await {
+                error!("paper session {} failed: {:?}", self.id, e);
+                let _ = self.events.send(PaperEvent::Error { message: e.to_string() });
+            }
+        }
+
+        self.info.lock().await.running = false;
+        let _ = self.events.send(PaperEvent::Stopped { reason: Some("provider stream ended".to_owned()) });
+    }
+
+    /// Takes the next candle of the stream, returning the bar it closes
+    /// unless that bar closed before this run began. Providers may send
+    /// the same bars again, such as Yahoo re-sending its whole window on
+    /// every poll, so candles older than the last bar are dropped.
+    fn closed_bar(&mut self, candle: Candle) -> Option<Candle> {
+        if self.last.is_some_and(|last| candle.timestamp <= last) {
+            return None;
+        }
+        match &self.forming {
+            Some(forming) if forming.timestamp > candle.timestamp => None,
+            Some(forming) if forming.timestamp == candle.timestamp => {
+                self.forming = Some(candle);
+                None
+            }
+            _ => {
+                let closed = self.forming.replace(candle)?;
+                self.last = Some(closed.timestamp);
+                let closes = closed.timestamp + chrono::Duration::seconds(self.config.interval.to_seconds());
+                (closes > self.since).then_some(closed)
+            }
+        }
+    }
+
+    async fn on_bar(&mut self, symbol: &str, candle: &Candle) -> anyhow::Result<()> {
+        let outcome = self.engine.on_bar(symbol, candle)?;
+
+        // Send errors only mean nobody is listening
+        for order in outcome.orders {
+            let _ = self.events.send(PaperEvent::Order { order });
+        }
+        for report in outcome.fills {
+            let _ = self.events.send(PaperEvent::Fill { report });
+        }
+        for report in outcome.rejections {
+            let _ = self.events.send(PaperEvent::Rejected { report });
+        }
+        for line in outcome.logs {
+            let _ = self.events.send(PaperEvent::Log { ts: line.ts, message: line.message });
+        }
+
+        let fx = FxRates::new();
+        let portfolio = self.engine.ledger().portfolio();
+        let prices = self.engine.last_prices();
+        let cash = portfolio.total_cash(&fx)?;
+        let realized = portfolio.realized_pnl(&fx)?;
+        let unrealized = portfolio.unrealized_pnl(prices, &fx)?;
+        let positions: Vec<_> = portfolio.positions.values().cloned().collect();
+        {
+            let mut info = self.info.lock().await;
+            info.equity = outcome.equity;
+            info.cash = cash;
+            info.realized_pnl = realized;
+            info.unrealized_pnl = unrealized;
+            info.positions = positions.clone();
+        }
+        let _ = self.events.send(PaperEvent::Pnl {
+            ts: candle.timestamp,
+            equity: outcome.equity,
+            cash,
+            realized,
+            unrealized,
+            positions,
+        });
+
+        self.db.save_paper_portfolio(&self.id, &self.portfolio()).await
+    }
+
+    fn portfolio(&self) -> PaperPortfolio {
+        PaperPortfolio {
+            owner: self.owner.clone(),
+            config: self.config.clone(),
+            started: self.started,
+            ledger: self.engine.ledger().clone(),
+        }
+    }
+}
@@ -0,0 +1,266 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use api::scanner::{ScannerPage, ScannerQuery, ScannerRow, DEFAULT_ROWS, MAX_ROWS, VARIABLES};
+use chrono::Utc;
+use data::providers::Profile;
+use dnn_core::instrument::{Instrument, InstrumentMaster};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use futures::StreamExt;
+use strats::rules::expr::{Condition, Formula};
+use thiserror::Error;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+use crate::services::SafeProvider;
+
+/// Calendar days of daily bars kept per symbol, enough for a 200 day average
+const HISTORY_DAYS: i64 = 400;
+/// Trading days in [`HISTORY_DAYS`], leaving room for holidays. Formulas
+/// that need more bars than this would never have a value.
+pub const HISTORY_BARS: usize = 270;
+/// Days before the latest one that make up the average volume
+const VOLUME_DAYS: usize = 20;
+/// Company profiles change slowly, and are costly to fetch
+const PROFILE_EVERY: Duration = Duration::from_secs(6 * 60 * 60);
+/// Symbols fetched at once while refreshing
+const CONCURRENCY: usize = 4;
+
+#[derive(Debug, Error)]
+pub enum ScannerError {
+    #[error("{0}")]
+    Invalid(String),
+}
+
+/// What the scanner knows about one symbol
+#[derive(Clone, Default)]
+struct Entry {
+    bars: Vec<Candle>,
+    profile: Option<Profile>,
+    profile_at: Option<Instant>,
+}
+
+impl Entry {
+    /// Values of [`VARIABLES`], in order
+    fn variables(&self) -> Option<[Option<f64>; 6]> {
+        let last = self.bars.last()?;
+        let prev = self.bars.len().checked_sub(2).map(|i| &self.bars[i]);
+        let change = prev.map(|p| last.close - p.close);
+        let change_pct = prev.and_then(|p| (p.close != 0.0).then(|| (last.close / p.close - 1.0) * 100.0));
+
+        let before = &self.bars[..self.bars.len() - 1];
+        let before = &before[before.len().saturating_sub(VOLUME_DAYS)..];
+        let avg_volume = (!before.is_empty()).then(|| before.iter().map(|c| c.volume).sum::<f64>() / before.len() as f64);
+        let volume_ratio = avg_volume.and_then(|avg| (avg > 0.0).then(|| last.volume / avg));
+        let market_cap = self.profile.as_ref().and_then(|p| p.market_cap);
+
+        Some([Some(last.close), change, change_pct, avg_volume, volume_ratio, market_cap])
+    }
+}
+
+#[derive(Default)]
+struct Universe {
+    entries: HashMap<String, Entry>,
+    updated: Option<Timestamp>,
+}
+
+/// Market screener over a fixed universe of symbols. Daily bars and
+/// company profiles are refreshed in the background; queries filter, sort
+/// and page what was last fetched.
+///
+/// Names and sectors come from the instrument master, and from the
+/// provider's profiles for symbols it doesn't list.
+pub struct Scanner {
+    provider: SafeProvider,
+    symbols: Vec<String>,
+    instruments: InstrumentMaster,
+    universe: RwLock<Universe>,
+}
+
+impl Scanner {
+    /// Starts refreshing `symbols` from `provider` every `every`
+    pub fn new(provider: SafeProvider, symbols: Vec<String>, instruments: InstrumentMaster, every: Duration) -> Arc<Self> {
+        let scanner = Arc::new(Self { provider, symbols, instruments, universe: RwLock::new(Universe::default()) });
+        if !scanner.symbols.is_empty() {
+            let refreshing = Arc::downgrade(&scanner);
+            tokio::spawn(async move {
+                let mut ticks = tokio::time::interval(every);
+                loop {
+                    ticks.tick().await;
+                    // Stops once the backend lets go of the scanner
+                    let Some(scanner) = refreshing.upgrade() else { break };
+                    scanner.refresh().await;
+                }
+            });
+        }
+        scanner
+    }
+
+    /// Fetch every symbol now; also done in the background every refresh period
+    pub async fn refresh(&self) {
+        let current = self.universe.read().await.entries.clone();
+        let jobs: Vec<_> = self
+            .symbols
+            .iter()
+            .map(|symbol| self.refresh_symbol(symbol.clone(), current.get(symbol).cloned().unwrap_or_default()))
+            .collect();
+        let refreshed: Vec<(String, Entry)> = futures::stream::iter(jobs).buffer_unordered(CONCURRENCY).collect().await;
+
+        let mut universe = self.universe.write().await;
+        universe.entries = refreshed.into_iter().collect();
+        universe.updated = Some(Utc::now());
+        info!("scanner refreshed {} symbols", universe.entries.len());
+    }
+
+    /// On failures keeps what it had, so one bad fetch doesn't empty a row
+    async fn refresh_symbol(&self, symbol: String, mut entry: Entry) -> (String, Entry) {
+        let end = Utc::now();
+        let start = end - chrono::Duration::days(HISTORY_DAYS);
+        match self.provider.historical(&symbol, TimeInterval::Day1, start, end).await {
+            Ok(bars) => entry.bars = bars,
+            Err(e) => warn!("scanner failed to fetch bars of {}: {:#}", symbol, e),
+        }
+        if entry.profile_at.is_none_or(|at| at.elapsed() >= PROFILE_EVERY) {
+            // Tried again only after a while, profiles are optional
+            entry.profile_at = Some(Instant::now());
+            match self.provider.profile(&symbol).await {
+                Ok(profile) => entry.profile = Some(profile),
+                Err(e) => warn!("scanner failed to fetch the profile of {}: {:#}", symbol, e),
+            }
+        }
+        (symbol, entry)
+    }
+
+    pub async fn query(&self, query: &ScannerQuery) -> Result<ScannerPage, ScannerError> {
+        let limit = query.limit.unwrap_or(DEFAULT_ROWS);
+        if !(1..=MAX_ROWS).contains(&limit) {
+            return Err(ScannerError::Invalid(format!("limit must be between 1 and {MAX_ROWS}")));
+        }
+        let filter = query
+            .filter
+            .as_deref()
+            .map(|src| Condition::parse_with(src, VARIABLES))
+            .transpose()
+            .map_err(|e| ScannerError::Invalid(format!("filter: {e}")))?;
+        let columns = query
+            .columns
+            .iter()
+            .map(|src| Formula::parse_with(src, VARIABLES).map_err(|e| ScannerError::Invalid(format!("column `{src}`: {e}"))))
+            .collect::<Result<Vec<_>, _>>()?;
+        let sort = query.sort.as_ref().map(|sort| SortKey::parse(&sort.by)).transpose()?;
+
+        let lookbacks = filter
+            .iter()
+            .map(|f| (f.source(), f.lookback()))
+            .chain(columns.iter().map(|c| (c.source(), c.lookback())))
+            .chain(match &sort {
+                Some(SortKey::Formula(f)) => Some((f.source(), f.lookback())),
+                _ => None,
+            });
+        for (source, bars) in lookbacks {
+            if bars > HISTORY_BARS {
+                return Err(ScannerError::Invalid(format!(
+                    "`{source}` needs {bars} daily bars, the scanner keeps {HISTORY_BARS}"
+                )));
+            }
+        }
+
+        let universe = self.universe.read().await;
+        let mut rows: Vec<(ScannerRow, Option<f64>)> = universe
+            .entries
+            .iter()
+            .filter_map(|(symbol, entry)| {
+                let vars = entry.variables()?;
+                let instrument = self.instruments.get(symbol);
+                if let Some(sector) = &query.sector
+                    && !self::sector(instrument, entry).is_some_and(|s| s.eq_ignore_ascii_case(sector))
+                {
+                    return None;
+                }
+                if let Some(filter) = &filter
+                    && !filter.holds_with(&entry.bars, &vars)
+                {
+                    return None;
+                }
+                let key = match &sort {
+                    Some(SortKey::Formula(formula)) => formula.value_with(&entry.bars, &vars),
+                    _ => None,
+                };
+                Some((row(symbol, instrument, entry, &vars, &columns), key))
+            })
+            .collect();
+
+        rows.sort_by(|a, b| a.0.symbol.cmp(&b.0.symbol));
+        if let Some(sort) = &sort {
+            let descending = query.sort.as_ref().is_some_and(|s| s.descending);
+            rows.sort_by(|a, b| sort.compare(a, b, descending));
+        }
+        let total = rows.len();
+        let rows = rows.into_iter().skip(query.offset).take(limit).map(|(row, _)| row).collect();
+        Ok(ScannerPage { rows, total, updated: universe.updated })
+    }
+}
+
+fn sector<'a>(instrument: Option<&'a Instrument>, entry: &'a Entry) -> Option<&'a String> {
+    instrument
+        .and_then(|i| i.sector.as_ref())
+        .or_else(|| entry.profile.as_ref().and_then(|p| p.sector.as_ref()))
+}
+
+fn row(symbol: &str, instrument: Option<&Instrument>, entry: &Entry, vars: &[Option<f64>; 6], columns: &[Formula]) -> ScannerRow {
+    let [price, change, change_pct, avg_volume, volume_ratio, market_cap] = *vars;
+    ScannerRow {
+        symbol: symbol.to_owned(),
+        name: instrument
+            .and_then(|i| i.name.clone())
+            .or_else(|| entry.profile.as_ref().and_then(|p| p.name.clone())),
+        sector: sector(instrument, entry).cloned(),
+        price: price.unwrap_or_default(),
+        change,
+        change_pct,
+        volume: entry.bars.last().map_or(0.0, |c| c.volume),
+        avg_volume,
+        volume_ratio,
+        market_cap,
+        columns: columns.iter().map(|c| c.value_with(&entry.bars, vars)).collect(),
+    }
+}
+
+enum SortKey {
+    Symbol,
+    Name,
+    Sector,
+    Formula(Formula),
+}
+
+impl SortKey {
+    fn parse(by: &str) -> Result<Self, ScannerError> {
+        Ok(match by.to_ascii_lowercase().as_str() {
+            "symbol" => Self::Symbol,
+            "name" => Self::Name,
+            "sector" => Self::Sector,
+            _ => Self::Formula(
+                Formula::parse_with(by, VARIABLES).map_err(|e| ScannerError::Invalid(format!("sort: {e}")))?,
+            ),
+        })
+    }
+
+    /// Missing values last either way
+    fn compare(&self, a: &(ScannerRow, Option<f64>), b: &(ScannerRow, Option<f64>), descending: bool) -> Ordering {
+        let directed = |ordering: Ordering| if descending { ordering.reverse() } else { ordering };
+        let text = |a: Option<&String>, b: Option<&String>| match (a, b) {
+            (Some(a), Some(b)) => directed(a.cmp(b)),
+            (a, b) => a.is_none().cmp(&b.is_none()),
+        };
+        match self {
+            Self::Symbol => directed(a.0.symbol.cmp(&b.0.symbol)),
+            Self::Name => text(a.0.name.as_ref(), b.0.name.as_ref()),
+            Self::Sector => text(a.0.sector.as_ref(), b.0.sector.as_ref()),
+            Self::Formula(_) => match (a.1, b.1) {
+                (Some(x), Some(y)) => directed(x.total_cmp(&y)),
+                (x, y) => x.is_none().cmp(&y.is_none()),
+            },
+        }
+    }
+}
@@ -0,0 +1,176 @@
+use api::strategy::{BacktestResult, CreateStrategyReq, Strategy, StrategyLanguage, StrategyStatus, StrategyVersion};
+use crate::db::Database;
+use crate::services::random_id;
+use chrono::Utc;
+use strats::registry::StrategySpec;
+use strats::rules::RuleStrategy;
+use strats::script::ScriptStrategy;
+use strats::StrategyRegistry;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum StrategyError {
+    #[error("unknown strategy {0}")]
+    NotFound(String),
+    #[error("unknown backtest {0}")]
+    BacktestNotFound(String),
+    #[error("backtest {0} is already finished")]
+    BacktestFinished(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("storage error: {0:#}")]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Build a runnable strategy from its source
+pub fn build_strategy(
+    registry: &StrategyRegistry,
+    language: StrategyLanguage,
+    code: &str,
+) -> Result<Box<dyn strats::Strategy + Send>, String> {
+    match language {
+        StrategyLanguage::Rhai => ScriptStrategy::new(code)
+            .map(|s| Box::new(s) as Box<dyn strats::Strategy + Send>)
+            .map_err(|e| e.to_string()),
+        StrategyLanguage::Rules => RuleStrategy::from_yaml(code)
+            .map(|s| Box::new(s) as Box<dyn strats::Strategy + Send>)
+            .map_err(|errors| errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; ")),
+        StrategyLanguage::Builtin => {
+            let spec: StrategySpec = serde_json::from_str(code).map_err(|e| format!("invalid strategy spec: {e}"))?;
+            registry.create_from_spec(&spec).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Saved strategies, kept in the database. Every user only sees their own.
+pub struct Strategies {
+    registry: StrategyRegistry,
+    db: Database,
+}
+
+impl Strategies {
+    pub fn new(db: Database) -> Self {
+        Self { registry: StrategyRegistry::builtin(), db }
+    }
+
+    /// Every strategy of `owner`, oldest first
+    pub async fn list(&self, owner: &str) -> Result<Vec<Strategy>, StrategyError> {
+        Ok(self.db.strategies(owner).await?)
+    }
+
+    pub async fn get(&self, owner: &str, id: &str) -> Result<Strategy, StrategyError> {
+        self.db.strategy(owner, id).await?.ok_or_else(|| StrategyError::NotFound(id.to_owned()))
+    }
+
+    /// Every version of a strategy's code, oldest first
+    pub async fn versions(&self, owner: &str, id: &str) -> Result<Vec<StrategyVersion>, StrategyError> {
+        self.get(owner, id).await?;
+        Ok(self.db.strategy_versions(id).await?)
+    }
+
+    /// Save a new draft strategy, once its code compiles
+    pub async fn create(&self, owner: &str, req: CreateStrategyReq) -> Result<Strategy, StrategyError> {
+        self.validate(&req)?;
+        let strategy = Strategy {
+            id: random_id("strategy"),
+            name: req.name.trim().to_owned(),
+            description: req.description,
+            language: req.language,
+            code: req.code,
+            status: StrategyStatus::Draft,
+            version: 1,
+            created_at: Utc::now(),
+            backtest_results: None,
+        };
+        self.db.insert_strategy(owner, &strategy).await?;
+        Ok(strategy)
+    }
+
+    /// Edit a strategy, saving its code as a new version
+    pub async fn update(&self, owner: &str, id: &str, req: CreateStrategyReq) -> Result<Strategy, StrategyError> {
+        self.validate(&req)?;
+        let mut strategy = self.get(owner, id).await?;
+        let version = StrategyVersion {
+            version: strategy.version + 1,
+            language: req.language,
+            code: req.code,
+            created_at: Utc::now(),
+        };
+        strategy.name = req.name.trim().to_owned();
+        strategy.description = req.description;
+        strategy.language = version.language;
+        strategy.code.clone_from(&version.code);
+        strategy.version = version.version;
+        if !self.db.update_strategy(owner, &strategy, &version).await? {
+            return Err(StrategyError::NotFound(id.to_owned()));
+        }
+        Ok(strategy)
+    }
+
+    /// Delete a strategy along with its versions and backtests
+    pub async fn delete(&self, owner: &str, id: &str) -> Result<(), StrategyError> {
+        if !self.db.delete_strategy(owner, id).await? {
+            return Err(StrategyError::NotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+
+    fn validate(&self, req: &CreateStrategyReq) -> Result<(), StrategyError> {
+        if req.name.trim().is_empty() {
+            return Err(StrategyError::Invalid("name must not be empty".to_owned()));
+        }
+        build_strategy(&self.registry, req.language, &req.code).map_err(StrategyError::Invalid)?;
+        Ok(())
+    }
+
+    /// Compile the current version of a saved strategy for a backtest run
+    pub async fn runnable(
+        &self,
+        owner: &str,
+        id: &str,
+    ) -> Result<(Box<dyn strats::Strategy + Send>, u32), StrategyError> {
+        self.runnable_version(owner, id, None).await
+    }
+
+    /// Compile `version` of a saved strategy, or its current version if
+    /// `None`
+    pub async fn runnable_version(
+        &self,
+        owner: &str,
+        id: &str,
+        version: Option<u32>,
+    ) -> Result<(Box<dyn strats::Strategy + Send>, u32), StrategyError> {
+        let strategy = self.get(owner, id).await?;
+        let (language, code, version) = match version {
+            Some(version) if version != strategy.version => {
+                let old = self.db
+                    .strategy_versions(id)
+                    .await?
+                    .into_iter()
+                    .find(|v| v.version == version)
+                    .ok_or_else(|| StrategyError::NotFound(format!("{id} version {version}")))?;
+                (old.language, old.code, version)
+            }
+            _ => (strategy.language, strategy.code, strategy.version),
+        };
+        let runnable = build_strategy(&self.registry, language, &code).map_err(StrategyError::Invalid)?;
+        Ok((runnable, version))
+    }
+
+    /// Keep the summary of a finished backtest on its strategy; a deleted
+    /// strategy is left alone
+    pub async fn record_backtest(&self, id: &str, result: &BacktestResult) -> Result<(), StrategyError> {
+        Ok(self.db.set_strategy_results(id, result).await?)
+    }
+}
+
+/// Headline numbers in percent, drawdown negative
+pub fn summarize(result: &backtest::BacktestResult) -> BacktestResult {
+    BacktestResult {
+        total_return: result.return_pct * 100.0,
+        sharpe_ratio: result.sharpe_ratio,
+        max_drawdown: -result.max_drawdown * 100.0,
+        win_rate: result.win_rate() * 100.0,
+        total_trades: i32::try_from(result.trades.len()).unwrap_or(i32::MAX),
+    }
+}
@@ -0,0 +1,85 @@
+use api::watchlist::{Watchlist, WatchlistReq};
+use crate::db::Database;
+use crate::services::random_id;
+use chrono::Utc;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum WatchlistError {
+    #[error("unknown watchlist {0}")]
+    NotFound(String),
+    #[error("{0}")]
+    Invalid(String),
+    #[error("storage error: {0:#}")]
+    Storage(#[from] anyhow::Error),
+}
+
+/// Named lists of symbols, kept in the database per user
+pub struct Watchlists {
+    db: Database,
+}
+
+impl Watchlists {
+    pub fn new(db: Database) -> Self {
+        Self { db }
+    }
+
+    /// Every watchlist of `owner`, oldest first
+    pub async fn list(&self, owner: &str) -> Result<Vec<Watchlist>, WatchlistError> {
+        Ok(self.db.watchlists(owner).await?)
+    }
+
+    pub async fn get(&self, owner: &str, id: &str) -> Result<Watchlist, WatchlistError> {
+        self.db.watchlist(owner, id).await?.ok_or_else(|| WatchlistError::NotFound(id.to_owned()))
+    }
+
+    pub async fn create(&self, owner: &str, req: WatchlistReq) -> Result<Watchlist, WatchlistError> {
+        let (name, symbols) = validate(req)?;
+        let watchlist = Watchlist {
+            id: random_id("watchlist"),
+            name,
+            symbols,
+            created_at: Utc::now(),
+        };
+        self.db.insert_watchlist(owner, &watchlist).await?;
+        Ok(watchlist)
+    }
+
+    /// Rename a watchlist and replace its symbols
+    pub async fn update(&self, owner: &str, id: &str, req: WatchlistReq) -> Result<Watchlist, WatchlistError> {
+        let (name, symbols) = validate(req)?;
+        let mut watchlist = self.get(owner, id).await?;
+        watchlist.name = name;
+        watchlist.symbols = symbols;
+        if !self.db.update_watchlist(owner, &watchlist).await? {
+            return Err(WatchlistError::NotFound(id.to_owned()));
+        }
+        Ok(watchlist)
+    }
+
+    pub async fn delete(&self, owner: &str, id: &str) -> Result<(), WatchlistError> {
+        if !self.db.delete_watchlist(owner, id).await? {
+            return Err(WatchlistError::NotFound(id.to_owned()));
+        }
+        Ok(())
+    }
+}
+
+/// The trimmed name, and the symbols upper cased without repeats
+fn validate(req: WatchlistReq) -> Result<(String, Vec<String>), WatchlistError> {
+    let name = req.name.trim();
+    if name.is_empty() {
+        return Err(WatchlistError::Invalid("name must not be empty".to_owned()));
+    }
+    let mut symbols: Vec<String> = Vec::with_capacity(req.symbols.len());
+    for symbol in req.symbols {
+        let symbol = symbol.trim().to_ascii_uppercase();
+        if symbol.is_empty() {
+            return Err(WatchlistError::Invalid("symbols must not be empty".to_owned()));
+        }
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    Ok((name.to_owned(), symbols))
+}
@@ -1,29 +1,126 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use api::ProviderType;
-use data::providers::{Provider, Yahoo};
+use backend::db::Database;
+use tracing::warn;
+use data::cache::{Cache, MemoryCache};
+use data::providers::{CachedProvider, Yahoo};
+use dnn_core::instrument::InstrumentMaster;
 use crate::config::BackendConfig;
+use backend::services::alerts::Alerts;
+use backend::services::auth::Auth;
+use backend::services::backtests::BacktestJobs;
+use backend::services::live::{LiveLimits, LiveStreams};
+use backend::services::notifiers::{EmailNotifier, Notifier, WebhookNotifier};
+use backend::services::paper::PaperTrading;
+use backend::services::scanner::Scanner;
+use backend::services::strategies::Strategies;
+use backend::services::watchlists::Watchlists;
 
-pub type SafeProvider = Arc<Box<dyn Provider + Send + Sync>>;
+pub use backend::services::SafeProvider;
 
 pub struct BackendState {
     pub config: BackendConfig,
     providers: HashMap<ProviderType, SafeProvider>,
+    pub auth: Auth,
+    pub paper: PaperTrading,
+    pub strategies: Arc<Strategies>,
+    pub backtests: BacktestJobs,
+    pub scanner: Arc<Scanner>,
+    pub live: LiveStreams,
+    pub watchlists: Watchlists,
+    pub alerts: Arc<Alerts>,
 }
 
 impl BackendState {
-    pub fn new() -> anyhow::Result<Self> {
-        let config = BackendConfig::load().expect("Failed to load config");
+    pub async fn new(config: BackendConfig) -> anyhow::Result<Self> {
+        // Redis when configured, so replicas share candles and streams
+        #[cfg(feature = "redis")]
+        let cache: Arc<dyn Cache> = match &config.redis_url {
+            Some(url) => Arc::new(data::cache::RedisCache::new(url, config.redis_password.clone())?),
+            None => Arc::new(MemoryCache::default()),
+        };
+        #[cfg(not(feature = "redis"))]
+        let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
         let mut providers: HashMap<ProviderType, SafeProvider> = HashMap::new();
-        providers.insert(ProviderType::Yahoo, Arc::new(Box::new(Yahoo::new()?)));
+        for provider in &config.providers {
+            let built: SafeProvider = match provider {
+                ProviderType::Yahoo => Arc::new(Box::new(CachedProvider::new(Yahoo::new()?, cache.clone(), config.history_ttl))),
+                ProviderType::Binance => anyhow::bail!("`providers`: there is no binance provider yet"),
+            };
+            providers.insert(*provider, built);
+        }
+
+        let instruments = match &config.scanner_instruments {
+            Some(path) => InstrumentMaster::load(path)?,
+            None => InstrumentMaster::new(),
+        };
+        // Config checks the scanner's provider is among the built ones
+        let scanner = Scanner::new(
+            providers[&config.scanner_provider].clone(),
+            config.scanner_universe.clone(),
+            instruments,
+            config.scanner_refresh,
+        );
+
+        let live = LiveStreams::new(providers.clone(), LiveLimits::default());
+
+        let db = Database::connect(&config.database_url).await?;
+        let auth = Auth::new(db.clone(), config.jwt_secret.as_bytes(), config.token_ttl);
+        let strategies = Arc::new(Strategies::new(db.clone()));
+        let paper = PaperTrading::new(db.clone(), strategies.clone(), config.risk.clone());
+        let watchlists = Watchlists::new(db.clone());
+        let mut notifiers: Vec<Arc<dyn Notifier>> = vec![Arc::new(WebhookNotifier::new()?)];
+        if let Some(host) = &config.smtp_host {
+            notifiers.push(Arc::new(EmailNotifier::new(host.clone(), config.smtp_port, config.smtp_from.clone())));
+        }
+        let alerts = Alerts::new(db.clone(), notifiers);
+        let backtests = BacktestJobs::new(strategies.clone(), db, config.backtest_workers).await?;
 
         Ok(Self {
             config,
             providers,
+            auth,
+            paper,
+            strategies,
+            backtests,
+            scanner,
+            live,
+            watchlists,
+            alerts,
         })
     }
     
     pub fn get_provider(&self, provider: ProviderType) -> Option<&SafeProvider> {
         self.providers.get(&provider)
     }
-}
\ No newline at end of file
+
+    /// Restart paper sessions stored by a previous run
+    pub async fn resume_paper_sessions(&self) -> anyhow::Result<()> {
+        for (id, config) in self.paper.saved_sessions().await? {
+            let Some(provider) = self.get_provider(config.provider) else {
+                warn!("paper session {} uses unavailable provider {}", id, config.provider.as_ref());
+                continue;
+            };
+            if let Err(e) = self.paper.resume(&id, provider.clone()).await {
+                warn!("failed to resume paper session {}: {:?}", id, e);
+            }
+        }
+        Ok(())
+    }
+
+    /// Start checking the alerts left active by a previous run
+    pub async fn resume_alerts(&self) -> anyhow::Result<()> {
+        for (owner, alert) in self.alerts.saved_alerts().await? {
+            let Some(provider) = self.get_provider(alert.config.provider) else {
+                warn!("alert {} uses unavailable provider {}", alert.id, alert.config.provider.as_ref());
+                continue;
+            };
+            let id = alert.id.clone();
+            if let Err(e) = self.alerts.resume(owner, alert, provider.clone()).await {
+                warn!("failed to resume alert {}: {:?}", id, e);
+            }
+        }
+        Ok(())
+    }
+}
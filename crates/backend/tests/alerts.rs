@@ -0,0 +1,250 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use api::alert::{Alert, AlertCondition, AlertEvent, CreateAlertReq, Direction};
+use api::auth::User;
+use api::ProviderType;
+use async_trait::async_trait;
+use backend::db::Database;
+use backend::services::alerts::{AlertError, Alerts, KEEP_BARS};
+use backend::services::notifiers::Notifier;
+use backend::services::SafeProvider;
+use chrono::{TimeZone, Utc};
+use data::providers::{Provider, ProviderStream};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use tokio::sync::{broadcast, mpsc};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// Every symbol has 30 flat candles of history at 100. Live candles are
+/// sent through [`Self::feed`].
+#[derive(Clone, Default)]
+struct FakeProvider {
+    feeds: Arc<Mutex<HashMap<String, mpsc::UnboundedSender<Candle>>>>,
+}
+
+impl FakeProvider {
+    /// The open stream of `symbol`, once the alerts service asked for one
+    async fn feed(&self, symbol: &str) -> mpsc::UnboundedSender<Candle> {
+        tokio::time::timeout(Duration::from_secs(2), async {
+            loop {
+                if let Some(feed) = self.feeds.lock().unwrap().get(symbol) {
+                    return feed.clone();
+                }
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .unwrap()
+    }
+}
+
+#[async_trait]
+impl Provider for FakeProvider {
+    async fn stream(&self, symbol: &str, _interval: TimeInterval) -> anyhow::Result<ProviderStream> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.feeds.lock().unwrap().insert(symbol.to_owned(), tx);
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+
+    async fn historical(
+        &self,
+        _symbol: &str,
+        _interval: TimeInterval,
+        _start: Timestamp,
+        _end: Timestamp,
+    ) -> anyhow::Result<Vec<Candle>> {
+        Ok((0..30).map(|minute| candle(minute, 100.0, 1_000.0)).collect())
+    }
+
+    fn get_type(&self) -> ProviderType {
+        ProviderType::Yahoo
+    }
+}
+
+/// Hands every alert it's given to the test
+struct Recorder(mpsc::UnboundedSender<AlertEvent>);
+
+#[async_trait]
+impl Notifier for Recorder {
+    async fn notify(&self, _alert: &Alert, event: &AlertEvent) -> anyhow::Result<()> {
+        self.0.send(event.clone())?;
+        Ok(())
+    }
+}
+
+fn candle(minute: i64, close: f64, volume: f64) -> Candle {
+    let at = Utc.with_ymd_and_hms(2024, 1, 2, 14, 30, 0).unwrap() + chrono::Duration::minutes(minute);
+    Candle::new(at, close, close, close, close, volume).unwrap()
+}
+
+struct Fixture {
+    alerts: Arc<Alerts>,
+    fake: FakeProvider,
+    notified: mpsc::UnboundedReceiver<AlertEvent>,
+}
+
+impl Fixture {
+    /// Alerts of users "user-1" and "user-2"
+    async fn new() -> Self {
+        let db = Database::in_memory().await.unwrap();
+        for (id, name) in [("user-1", "alice"), ("user-2", "bob")] {
+            let user = User { id: id.to_owned(), username: name.to_owned(), created_at: Utc::now() };
+            assert!(db.insert_user(&user, "hash").await.unwrap());
+        }
+        let (tx, notified) = mpsc::unbounded_channel();
+        let alerts = Alerts::new(db, vec![Arc::new(Recorder(tx))]);
+        Self { alerts, fake: FakeProvider::default(), notified }
+    }
+
+    async fn create(&self, symbol: &str, condition: AlertCondition, repeat: bool) -> Alert {
+        let req = CreateAlertReq {
+            provider: ProviderType::Yahoo,
+            symbol: symbol.to_owned(),
+            interval: TimeInterval::Minute1,
+            condition,
+            repeat,
+            webhook: None,
+            email: None,
+        };
+        let provider: SafeProvider = Arc::new(Box::new(self.fake.clone()));
+        self.alerts.create("user-1", req, provider).await.unwrap()
+    }
+}
+
+fn above(price: f64) -> AlertCondition {
+    AlertCondition::Price { direction: Direction::Above, price }
+}
+
+/// Events up to and including the first at `price`
+async fn events_until(events: &mut broadcast::Receiver<AlertEvent>, price: f64) -> Vec<AlertEvent> {
+    let mut received = Vec::new();
+    while received.last().is_none_or(|event: &AlertEvent| event.price != price) {
+        received.push(tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap().unwrap());
+    }
+    received
+}
+
+#[tokio::test]
+async fn test_alerts_trigger_when_their_condition_starts_to_hold() {
+    let mut fixture = Fixture::new().await;
+    let price = fixture.create("spy", above(105.0), true).await;
+    let once = fixture.create("SPY", above(105.0), false).await;
+    let drop = fixture.create("SPY", AlertCondition::ChangePct { pct: -5.0 }, true).await;
+    let spike = fixture.create("SPY", AlertCondition::VolumeSpike { ratio: 3.0, period: 10 }, true).await;
+    let indicator = fixture
+        .create("SPY", AlertCondition::Indicator { formula: "close / SMA(5)".to_owned(), direction: Direction::Above, threshold: 1.02 }, true)
+        .await;
+    // Already holds, so it waits for the price to drop below first
+    let holding = fixture.create("SPY", AlertCondition::Price { direction: Direction::Below, price: 200.0 }, true).await;
+    assert_eq!(price.config.symbol, "SPY");
+
+    let mut events = fixture.alerts.subscribe("user-1").await;
+    let mut others = fixture.alerts.subscribe("user-2").await;
+    let feed = fixture.fake.feed("SPY").await;
+    let candles = [
+        candle(30, 103.0, 1_000.0),
+        // An older candle changes nothing
+        candle(29, 120.0, 9_000.0),
+        candle(31, 106.0, 5_000.0),
+        candle(32, 99.0, 1_000.0),
+        candle(33, 107.0, 1_000.0),
+        candle(34, 80.0, 1_000.0),
+    ];
+    for candle in candles {
+        feed.send(candle).unwrap();
+    }
+
+    let received = events_until(&mut events, 80.0).await;
+    let names = [
+        (&price.id, "price"),
+        (&once.id, "once"),
+        (&drop.id, "drop"),
+        (&spike.id, "spike"),
+        (&indicator.id, "indicator"),
+        (&holding.id, "holding"),
+    ];
+    let mut triggered: Vec<_> = received
+        .iter()
+        .map(|event| (event.price, names.iter().find(|(id, _)| **id == event.alert_id).unwrap().1))
+        .collect();
+    triggered.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(b.1)));
+    assert_eq!(triggered, [
+        (80.0, "drop"),
+        (99.0, "drop"),
+        (103.0, "indicator"),
+        (106.0, "once"),
+        (106.0, "price"),
+        (106.0, "spike"),
+        (107.0, "indicator"),
+        (107.0, "price"),
+    ]);
+    assert_eq!(received[0].message, "SPY close / SMA(5) above 1.02");
+    assert!(matches!(others.try_recv(), Err(broadcast::error::TryRecvError::Empty)));
+
+    // Notifiers get the same events
+    let mut notified = Vec::new();
+    for _ in 0..received.len() {
+        notified.push(tokio::time::timeout(Duration::from_secs(2), fixture.notified.recv()).await.unwrap().unwrap());
+    }
+    // Their deliveries run side by side, in no particular order
+    let key = |event: &AlertEvent| (event.alert_id.clone(), event.price.to_bits());
+    notified.sort_by_key(key);
+    let mut sorted = received.clone();
+    sorted.sort_by_key(key);
+    assert_eq!(notified, sorted);
+
+    // Alerts that don't repeat are done once they trigger
+    let stored = fixture.alerts.list("user-1").await.unwrap();
+    let once = stored.iter().find(|alert| alert.id == once.id).unwrap();
+    assert!(!once.active && once.last_triggered.is_some());
+    let holding = stored.iter().find(|alert| alert.id == holding.id).unwrap();
+    assert!(holding.active && holding.last_triggered.is_none());
+    let saved = fixture.alerts.saved_alerts().await.unwrap();
+    assert_eq!(saved.len(), 5);
+    assert!(saved.iter().all(|(owner, alert)| owner == "user-1" && alert.id != once.id));
+}
+
+#[tokio::test]
+async fn test_formulas_fit_the_kept_history() {
+    let fixture = Fixture::new().await;
+    let provider: SafeProvider = Arc::new(Box::new(fixture.fake.clone()));
+    let req = |formula: String| CreateAlertReq {
+        provider: ProviderType::Yahoo,
+        symbol: "SPY".to_owned(),
+        interval: TimeInterval::Minute1,
+        condition: AlertCondition::Indicator { formula, direction: Direction::Above, threshold: 1.0 },
+        repeat: true,
+        webhook: None,
+        email: None,
+    };
+
+    let too_long = fixture.alerts.create("user-1", req(format!("SMA({})", KEEP_BARS + 1)), provider.clone()).await;
+    assert!(matches!(too_long, Err(AlertError::Invalid(message)) if message == format!("formula needs {} bars, alerts keep {KEEP_BARS}", KEEP_BARS + 1)));
+    assert!(fixture.alerts.create("user-1", req(format!("SMA({KEEP_BARS})")), provider).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_feeds_close_with_their_last_alert() {
+    let fixture = Fixture::new().await;
+    let first = fixture.create("SPY", above(105.0), true).await;
+    let second = fixture.create("SPY", above(110.0), true).await;
+    let once = fixture.create("QQQ", above(105.0), false).await;
+    let spy = fixture.fake.feed("SPY").await;
+    let qqq = fixture.fake.feed("QQQ").await;
+
+    assert!(matches!(fixture.alerts.delete("user-2", &first.id).await, Err(AlertError::NotFound(_))));
+    fixture.alerts.delete("user-1", &first.id).await.unwrap();
+    assert!(matches!(fixture.alerts.delete("user-1", &first.id).await, Err(AlertError::NotFound(_))));
+    assert!(!spy.is_closed());
+    fixture.alerts.delete("user-1", &second.id).await.unwrap();
+    tokio::time::timeout(Duration::from_secs(2), spy.closed()).await.unwrap();
+
+    // So do feeds whose last alert triggered and doesn't repeat
+    let mut events = fixture.alerts.subscribe("user-1").await;
+    qqq.send(candle(30, 106.0, 1_000.0)).unwrap();
+    let received = events_until(&mut events, 106.0).await;
+    assert_eq!(received.iter().map(|event| &event.alert_id).collect::<Vec<_>>(), [&once.id]);
+    tokio::time::timeout(Duration::from_secs(2), qqq.closed()).await.unwrap();
+    assert!(fixture.alerts.list("user-1").await.unwrap().iter().all(|alert| !alert.active));
+}
@@ -0,0 +1,137 @@
+use api::auth::Credentials;
+use axum::http::{HeaderMap, HeaderValue, Uri};
+use backend::db::Database;
+use backend::services::auth::{Auth, AuthError, AuthUser};
+use chrono::{Duration, Utc};
+
+const SECRET: &[u8] = b"a secret long enough to sign session tokens";
+
+async fn auth() -> Auth {
+    Auth::new(Database::in_memory().await.unwrap(), SECRET, Duration::hours(1))
+}
+
+fn credentials(username: &str, password: &str) -> Credentials {
+    Credentials { username: username.to_owned(), password: password.to_owned() }
+}
+
+fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    for (name, value) in pairs {
+        headers.insert(*name, HeaderValue::from_str(value).unwrap());
+    }
+    headers
+}
+
+fn invalid<T: std::fmt::Debug>(result: Result<T, AuthError>) -> String {
+    match result {
+        Err(AuthError::Invalid(message)) => message,
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_register_and_login() {
+    let auth = auth().await;
+    let session = auth.register(credentials(" alice ", "correct horse")).await.unwrap();
+    assert_eq!(session.user.username, "alice");
+    assert!(session.expires_at > Utc::now() + Duration::minutes(59));
+
+    assert!(matches!(auth.register(credentials("alice", "another one")).await, Err(AuthError::UsernameTaken(_))));
+    assert!(invalid(auth.register(credentials("al", "correct horse")).await).contains("3 to 32"));
+    assert!(invalid(auth.register(credentials("al ice", "correct horse")).await).contains("letters"));
+    assert!(invalid(auth.register(credentials("bob", "short")).await).contains("8 characters"));
+
+    let again = auth.login(credentials("alice", "correct horse")).await.unwrap();
+    assert_eq!(again.user.id, session.user.id);
+    // Wrong passwords and unknown users look the same
+    assert!(matches!(auth.login(credentials("alice", "wrong horse")).await, Err(AuthError::InvalidCredentials)));
+    assert!(matches!(auth.login(credentials("carol", "correct horse")).await, Err(AuthError::InvalidCredentials)));
+}
+
+#[tokio::test]
+async fn test_session_tokens() {
+    let auth = auth().await;
+    let session = auth.register(credentials("alice", "correct horse")).await.unwrap();
+    let user = auth.verify_token(&session.token).unwrap();
+    assert_eq!(user, AuthUser { id: session.user.id.clone(), username: "alice".to_owned() });
+    assert_eq!(auth.user(&user).await.unwrap().id, session.user.id);
+
+    assert!(matches!(auth.verify_token("not a token"), Err(AuthError::Unauthorized)));
+    let mut tampered = session.token.clone();
+    tampered.pop();
+    assert!(matches!(auth.verify_token(&tampered), Err(AuthError::Unauthorized)));
+
+    // Tokens signed with another secret or already expired are refused
+    let db = Database::in_memory().await.unwrap();
+    let other = Auth::new(db.clone(), b"some other secret, also long enough", Duration::hours(1));
+    let foreign = other.register(credentials("alice", "correct horse")).await.unwrap();
+    assert!(matches!(auth.verify_token(&foreign.token), Err(AuthError::Unauthorized)));
+    let expired = Auth::new(db, SECRET, Duration::hours(-1));
+    let old = expired.login(credentials("alice", "correct horse")).await.unwrap();
+    assert!(matches!(auth.verify_token(&old.token), Err(AuthError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_api_keys() {
+    let auth = auth().await;
+    let session = auth.register(credentials("alice", "correct horse")).await.unwrap();
+    let alice = auth.verify_token(&session.token).unwrap();
+    let bob_session = auth.register(credentials("bob", "battery staple")).await.unwrap();
+    let bob = auth.verify_token(&bob_session.token).unwrap();
+
+    assert!(invalid(auth.create_api_key(&alice, "  ").await).contains("name"));
+    let created = auth.create_api_key(&alice, "bot").await.unwrap();
+    assert!(created.secret.starts_with("trk_"));
+    assert!(created.secret.starts_with(&created.key.prefix));
+    assert_eq!(auth.verify_api_key(&created.secret).await.unwrap(), alice);
+    // Listings never show the secret
+    let listed = auth.api_keys(&alice).await.unwrap();
+    assert_eq!(listed.iter().map(|key| (&key.id, &key.prefix)).collect::<Vec<_>>(), [(&created.key.id, &created.key.prefix)]);
+    assert!(auth.api_keys(&bob).await.unwrap().is_empty());
+
+    for wrong in ["trk_nonsense", "nonsense", &created.secret[4..]] {
+        assert!(matches!(auth.verify_api_key(wrong).await, Err(AuthError::Unauthorized)));
+    }
+
+    assert!(matches!(auth.delete_api_key(&bob, &created.key.id).await, Err(AuthError::KeyNotFound(_))));
+    auth.delete_api_key(&alice, &created.key.id).await.unwrap();
+    assert!(matches!(auth.verify_api_key(&created.secret).await, Err(AuthError::Unauthorized)));
+}
+
+#[tokio::test]
+async fn test_authenticate_requests() {
+    let auth = auth().await;
+    let session = auth.register(credentials("alice", "correct horse")).await.unwrap();
+    let key = auth.create_api_key(&auth.verify_token(&session.token).unwrap(), "bot").await.unwrap();
+    let plain: Uri = "/strategies".parse().unwrap();
+    let bearer = format!("Bearer {}", session.token);
+
+    let by_token = auth.authenticate(&headers(&[("authorization", &bearer)]), &plain).await.unwrap();
+    assert_eq!(by_token.username, "alice");
+    let by_key = auth.authenticate(&headers(&[("x-api-key", &key.secret)]), &plain).await.unwrap();
+    assert_eq!(by_key, by_token);
+
+    let rejected = [
+        headers(&[]),
+        headers(&[("authorization", "Bearer nonsense")]),
+        // Only bearer tokens are taken
+        headers(&[("authorization", &format!("Basic {}", session.token))]),
+        headers(&[("x-api-key", "trk_nonsense")]),
+    ];
+    for headers in &rejected {
+        assert!(matches!(auth.authenticate(headers, &plain).await, Err(AuthError::Unauthorized)));
+    }
+    // A bad token isn't rescued by a good key
+    let both = headers(&[("authorization", "Bearer nonsense"), ("x-api-key", &key.secret)]);
+    assert!(matches!(auth.authenticate(&both, &plain).await, Err(AuthError::Unauthorized)));
+
+    // Credentials in the query only count on websocket upgrades
+    let with_token: Uri = format!("/paper/x/stream?token={}", session.token).parse().unwrap();
+    let with_key: Uri = format!("/alerts/stream?api_key={}", key.secret).parse().unwrap();
+    let upgrade = headers(&[("upgrade", "websocket")]);
+    assert!(matches!(auth.authenticate(&headers(&[]), &with_token).await, Err(AuthError::Unauthorized)));
+    assert_eq!(auth.authenticate(&upgrade, &with_token).await.unwrap(), by_token);
+    assert_eq!(auth.authenticate(&upgrade, &with_key).await.unwrap(), by_token);
+    let bad_query: Uri = "/alerts/stream?token=nonsense".parse().unwrap();
+    assert!(matches!(auth.authenticate(&upgrade, &bad_query).await, Err(AuthError::Unauthorized)));
+}
@@ -0,0 +1,206 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use api::auth::User;
+use api::strategy::{BacktestEvent, BacktestRun, CreateStrategyReq, JobStatus, RunBacktestReq, StrategyLanguage};
+use api::ProviderType;
+use async_trait::async_trait;
+use backend::db::Database;
+use backend::services::backtests::BacktestJobs;
+use backend::services::strategies::{StrategyError, Strategies};
+use backend::services::SafeProvider;
+use chrono::{TimeZone, Utc};
+use data::providers::{Provider, ProviderStream};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use tokio::sync::Semaphore;
+
+/// Historical data waits for a permit of `gate`, so tests decide when a
+/// running job gets its data. Tracks how many jobs are loading at once.
+#[derive(Clone)]
+struct FakeProvider {
+    gate: Arc<Semaphore>,
+    loading: Arc<AtomicUsize>,
+    most_loading: Arc<AtomicUsize>,
+}
+
+impl Default for FakeProvider {
+    fn default() -> Self {
+        Self { gate: Arc::new(Semaphore::new(0)), loading: Arc::default(), most_loading: Arc::default() }
+    }
+}
+
+impl FakeProvider {
+    fn release(&self, jobs: usize) {
+        self.gate.add_permits(jobs);
+    }
+}
+
+#[async_trait]
+impl Provider for FakeProvider {
+    async fn stream(&self, _symbol: &str, _interval: TimeInterval) -> anyhow::Result<ProviderStream> {
+        anyhow::bail!("no live data")
+    }
+
+    async fn historical(
+        &self,
+        _symbol: &str,
+        _interval: TimeInterval,
+        start: Timestamp,
+        _end: Timestamp,
+    ) -> anyhow::Result<Vec<Candle>> {
+        let loading = self.loading.fetch_add(1, Ordering::SeqCst) + 1;
+        self.most_loading.fetch_max(loading, Ordering::SeqCst);
+        self.gate.acquire().await?.forget();
+        self.loading.fetch_sub(1, Ordering::SeqCst);
+        Ok((0..60)
+            .map(|day| {
+                let close = 100.0 + (f64::from(day) / 5.0).sin() * 10.0;
+                Candle::new(start + chrono::Duration::days(i64::from(day)), close, close, close, close, 1_000.0).unwrap()
+            })
+            .collect())
+    }
+
+    fn get_type(&self) -> ProviderType {
+        ProviderType::Yahoo
+    }
+}
+
+struct Fixture {
+    jobs: BacktestJobs,
+    fake: FakeProvider,
+    strategy_id: String,
+}
+
+impl Fixture {
+    /// Jobs run on `workers` workers for "user-1", who has one strategy
+    async fn new(workers: usize) -> Self {
+        let db = Database::in_memory().await.unwrap();
+        let user = User { id: "user-1".to_owned(), username: "alice".to_owned(), created_at: Utc::now() };
+        assert!(db.insert_user(&user, "hash").await.unwrap());
+        let strategies = Arc::new(Strategies::new(db.clone()));
+        let req = CreateStrategyReq {
+            name: "Crossover".to_owned(),
+            description: String::new(),
+            language: StrategyLanguage::Builtin,
+            code: r#"{"name": "ma_cross"}"#.to_owned(),
+        };
+        let strategy_id = strategies.create("user-1", req).await.unwrap().id;
+        let jobs = BacktestJobs::new(strategies, db, workers).await.unwrap();
+        Self { jobs, fake: FakeProvider::default(), strategy_id }
+    }
+
+    async fn submit(&self) -> BacktestRun {
+        let config = RunBacktestReq {
+            provider: ProviderType::Yahoo,
+            symbol: "SPY".to_owned(),
+            interval: TimeInterval::Day1,
+            start: Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+            end: Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap(),
+            starting_cash: 10_000.0,
+        };
+        let provider: SafeProvider = Arc::new(Box::new(self.fake.clone()));
+        self.jobs.submit("user-1", &self.strategy_id, config, provider).await.unwrap()
+    }
+
+    async fn status(&self, id: &str) -> JobStatus {
+        self.jobs.get("user-1", &self.strategy_id, id).await.unwrap().status
+    }
+
+    /// Waits until `id` has status `status`
+    async fn wait_for(&self, id: &str, status: JobStatus) {
+        let reached = tokio::time::timeout(Duration::from_secs(2), async {
+            while self.status(id).await != status {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await;
+        assert!(reached.is_ok(), "{id} never became {status:?}");
+    }
+
+    /// Waits for the last event of `id`
+    async fn finished(&self, id: &str) -> BacktestRun {
+        let (run, mut events) = self.jobs.subscribe("user-1", &self.strategy_id, id).await.unwrap();
+        if run.status.is_finished() {
+            return run;
+        }
+        tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                if let Ok(BacktestEvent::Finished { run }) = events.recv().await {
+                    return run;
+                }
+            }
+        })
+        .await
+        .unwrap()
+    }
+}
+
+#[tokio::test]
+async fn test_jobs_queue_behind_busy_workers() {
+    let fixture = Fixture::new(2).await;
+    let runs = [fixture.submit().await, fixture.submit().await, fixture.submit().await];
+    assert!(runs.iter().all(|run| run.status == JobStatus::Queued));
+
+    fixture.wait_for(&runs[0].id, JobStatus::Running).await;
+    fixture.wait_for(&runs[1].id, JobStatus::Running).await;
+    // The third waits for a worker however long the first two take
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(fixture.status(&runs[2].id).await, JobStatus::Queued);
+    assert_eq!(fixture.fake.loading.load(Ordering::SeqCst), 2);
+
+    fixture.fake.release(3);
+    for run in &runs {
+        let done = fixture.finished(&run.id).await;
+        assert_eq!(done.status, JobStatus::Done, "{:?}", done.error);
+        assert!(done.started_at.is_some());
+        assert_eq!(done.progress, 1.0);
+    }
+    // Stored fills say what they traded
+    let trades = fixture.jobs.trades("user-1", &fixture.strategy_id, &runs[0].id).await.unwrap();
+    assert!(!trades.is_empty());
+    assert!(trades.iter().all(|trade| trade.symbol == "SPY" && trade.side.is_some()));
+    assert_eq!(fixture.fake.most_loading.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn test_cancelling_a_queued_job_finishes_it_at_once() {
+    let fixture = Fixture::new(1).await;
+    let running = fixture.submit().await;
+    let queued = fixture.submit().await;
+    fixture.wait_for(&running.id, JobStatus::Running).await;
+
+    let cancelled = fixture.jobs.cancel("user-1", &fixture.strategy_id, &queued.id).await.unwrap();
+    assert_eq!(cancelled.status, JobStatus::Cancelled);
+    assert!(cancelled.finished_at.is_some());
+    let again = fixture.jobs.cancel("user-1", &fixture.strategy_id, &queued.id).await;
+    assert!(matches!(again, Err(StrategyError::BacktestFinished(_))));
+
+    // The worker it was waiting for skips it rather than running it
+    fixture.fake.release(1);
+    assert_eq!(fixture.finished(&running.id).await.status, JobStatus::Done);
+    let skipped = fixture.finished(&queued.id).await;
+    assert_eq!(skipped.status, JobStatus::Cancelled);
+    assert!(skipped.started_at.is_none());
+    assert_eq!(fixture.fake.most_loading.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_cancelling_a_running_job() {
+    let fixture = Fixture::new(1).await;
+    let run = fixture.submit().await;
+    fixture.wait_for(&run.id, JobStatus::Running).await;
+
+    // Still running until its worker notices
+    let cancelling = fixture.jobs.cancel("user-1", &fixture.strategy_id, &run.id).await.unwrap();
+    assert_eq!(cancelling.status, JobStatus::Running);
+    fixture.fake.release(1);
+    let cancelled = fixture.finished(&run.id).await;
+    assert_eq!(cancelled.status, JobStatus::Cancelled);
+    assert!(cancelled.result.is_none());
+
+    // Finished jobs are served from the database
+    assert_eq!(fixture.status(&run.id).await, JobStatus::Cancelled);
+    let again = fixture.jobs.cancel("user-1", &fixture.strategy_id, &run.id).await;
+    assert!(matches!(again, Err(StrategyError::BacktestFinished(_))));
+}
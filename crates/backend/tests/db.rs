@@ -0,0 +1,295 @@
+use api::alert::{Alert, AlertCondition, CreateAlertReq, Direction};
+use api::auth::{ApiKey, User};
+use api::paper::{PaperStrategy, StartPaperReq};
+use api::strategy::{
+    BacktestResult, BacktestRun, BacktestTrade, JobStatus, RunBacktestReq, Strategy, StrategyLanguage, StrategyStatus,
+    StrategyVersion,
+};
+use api::watchlist::Watchlist;
+use api::ProviderType;
+use backend::db::{Database, PaperPortfolio};
+use chrono::{Duration, TimeZone, Utc};
+use dnn_core::ledger::{Ledger, LedgerEvent};
+use dnn_core::time::{TimeInterval, Timestamp};
+use dnn_core::{ExecutionReport, OrderSide};
+
+fn at(day: i64) -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap() + Duration::days(day)
+}
+
+fn strategy(id: &str) -> Strategy {
+    Strategy {
+        id: id.to_owned(),
+        name: "Crossover".to_owned(),
+        description: String::new(),
+        language: StrategyLanguage::Builtin,
+        code: r#"{"name": "ma_cross"}"#.to_owned(),
+        status: StrategyStatus::Draft,
+        version: 1,
+        created_at: at(0),
+        backtest_results: None,
+    }
+}
+
+fn run(id: &str, strategy_id: &str, submitted: i64) -> BacktestRun {
+    BacktestRun {
+        id: id.to_owned(),
+        strategy_id: strategy_id.to_owned(),
+        strategy_version: 1,
+        config: RunBacktestReq {
+            provider: ProviderType::Yahoo,
+            symbol: "SPY".to_owned(),
+            interval: TimeInterval::Day1,
+            start: at(0),
+            end: at(30),
+            starting_cash: 10_000.0,
+        },
+        status: JobStatus::Queued,
+        progress: 0.0,
+        submitted_at: at(submitted),
+        started_at: None,
+        finished_at: None,
+        result: None,
+        error: None,
+        equity_curve: Vec::new(),
+    }
+}
+
+/// A database with users "user-1" and "user-2"
+async fn database() -> Database {
+    let db = Database::in_memory().await.unwrap();
+    for (id, name) in [("user-1", "alice"), ("user-2", "bob")] {
+        let user = User { id: id.to_owned(), username: name.to_owned(), created_at: at(0) };
+        assert!(db.insert_user(&user, "hash").await.unwrap());
+    }
+    db
+}
+
+fn summary() -> BacktestResult {
+    BacktestResult { total_return: 2.5, sharpe_ratio: 1.1, max_drawdown: -0.8, win_rate: 50.0, total_trades: 2 }
+}
+
+#[tokio::test]
+async fn test_strategies_and_versions() {
+    let db = database().await;
+    db.insert_strategy("user-1", &strategy("strategy-1")).await.unwrap();
+    db.insert_strategy("user-1", &strategy("strategy-7")).await.unwrap();
+
+    let mut edited = strategy("strategy-1");
+    edited.name = "Faster crossover".to_owned();
+    edited.code = r#"{"name": "ma_cross", "params": {"fast": 5}}"#.to_owned();
+    edited.version = 2;
+    let version = StrategyVersion {
+        version: 2,
+        language: edited.language,
+        code: edited.code.clone(),
+        created_at: at(1),
+    };
+    assert!(db.update_strategy("user-1", &edited, &version).await.unwrap());
+    assert!(!db.update_strategy("user-1", &strategy("strategy-9"), &version).await.unwrap());
+
+    let versions = db.strategy_versions("strategy-1").await.unwrap();
+    assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!(versions[1], version);
+
+    db.set_strategy_results("strategy-1", &summary()).await.unwrap();
+    let saved = db.strategy("user-1", "strategy-1").await.unwrap().unwrap();
+    assert_eq!(saved.status, StrategyStatus::Testing);
+    assert_eq!(saved.backtest_results, Some(summary()));
+    assert_eq!(saved.name, "Faster crossover");
+    assert_eq!(db.strategies("user-1").await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn test_backtests_with_trades_and_equity() {
+    let db = database().await;
+    db.insert_strategy("user-1", &strategy("strategy-1")).await.unwrap();
+    db.insert_backtest(&run("backtest-1", "strategy-1", 1)).await.unwrap();
+    db.insert_backtest(&run("backtest-2", "strategy-1", 2)).await.unwrap();
+
+    let mut done = run("backtest-1", "strategy-1", 1);
+    done.status = JobStatus::Done;
+    done.progress = 1.0;
+    done.started_at = Some(at(1));
+    done.finished_at = Some(at(1));
+    done.result = Some(summary());
+    // Long enough to take several batches
+    done.equity_curve = (0..250).map(|i| 10_000.0 + f64::from(i)).collect();
+    let trade = |symbol: &str, side, fill| BacktestTrade { symbol: symbol.to_owned(), side: Some(side), fill };
+    let trades = [
+        trade("SPY", OrderSide::Buy, ExecutionReport::filled(1, 10.0, 400.0, at(3))),
+        trade("SPY", OrderSide::Sell, ExecutionReport::partial(2, 4.0, 410.0, 6.0, at(5))),
+    ];
+    db.finish_backtest(&done, &trades).await.unwrap();
+
+    assert_eq!(db.backtest("backtest-1").await.unwrap(), Some(done));
+    assert_eq!(db.backtest_trades("backtest-1").await.unwrap(), trades);
+
+    // Newest first, without curves
+    let listed = db.backtests("strategy-1").await.unwrap();
+    assert_eq!(listed.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["backtest-2", "backtest-1"]);
+    assert!(listed[1].equity_curve.is_empty());
+
+    assert_eq!(db.fail_unfinished_backtests("interrupted").await.unwrap(), 1);
+    let failed = db.backtest("backtest-2").await.unwrap().unwrap();
+    assert_eq!((failed.status, failed.error.as_deref()), (JobStatus::Failed, Some("interrupted")));
+
+    // Deleting the strategy takes its backtests along
+    assert!(db.delete_strategy("user-1", "strategy-1").await.unwrap());
+    assert!(!db.delete_strategy("user-1", "strategy-1").await.unwrap());
+    assert_eq!(db.backtest("backtest-1").await.unwrap(), None);
+    assert!(db.backtest_trades("backtest-1").await.unwrap().is_empty());
+    assert!(db.strategy_versions("strategy-1").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_users_and_api_keys() {
+    let db = database().await;
+    let taken = User { id: "user-3".to_owned(), username: "alice".to_owned(), created_at: at(1) };
+    assert!(!db.insert_user(&taken, "other").await.unwrap());
+
+    let (alice, hash) = db.user_credentials("alice").await.unwrap().unwrap();
+    assert_eq!((alice.id.as_str(), hash.as_str()), ("user-1", "hash"));
+    assert!(db.user_credentials("carol").await.unwrap().is_none());
+
+    let key = ApiKey { id: "key-1".to_owned(), name: "bot".to_owned(), prefix: "trk_abc".to_owned(), created_at: at(1) };
+    db.insert_api_key("user-1", &key, "digest").await.unwrap();
+    assert_eq!(db.api_key_user("digest").await.unwrap(), Some(alice));
+    assert_eq!(db.api_keys("user-1").await.unwrap(), [key]);
+    assert!(db.api_keys("user-2").await.unwrap().is_empty());
+
+    // Only the owner can revoke a key
+    assert!(!db.delete_api_key("user-2", "key-1").await.unwrap());
+    assert!(db.delete_api_key("user-1", "key-1").await.unwrap());
+    assert_eq!(db.api_key_user("digest").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_rows_are_scoped_per_user() {
+    let db = database().await;
+    db.insert_strategy("user-1", &strategy("strategy-1")).await.unwrap();
+    let watchlist = Watchlist { id: "watchlist-1".to_owned(), name: "Mine".to_owned(), symbols: vec![], created_at: at(0) };
+    db.insert_watchlist("user-1", &watchlist).await.unwrap();
+
+    assert!(db.strategies("user-2").await.unwrap().is_empty());
+    assert_eq!(db.strategy("user-2", "strategy-1").await.unwrap(), None);
+    assert!(!db.delete_strategy("user-2", "strategy-1").await.unwrap());
+    assert!(db.watchlists("user-2").await.unwrap().is_empty());
+    assert!(!db.update_watchlist("user-2", &watchlist).await.unwrap());
+    assert!(!db.delete_watchlist("user-2", "watchlist-1").await.unwrap());
+
+    assert_eq!(db.strategies("user-1").await.unwrap().len(), 1);
+    assert_eq!(db.watchlists("user-1").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_watchlists() {
+    let db = database().await;
+    let mut watchlist = Watchlist {
+        id: "watchlist-1".to_owned(),
+        name: "Tech".to_owned(),
+        symbols: vec!["NVDA".to_owned(), "AAPL".to_owned()],
+        created_at: at(0),
+    };
+    db.insert_watchlist("user-1", &watchlist).await.unwrap();
+    assert_eq!(db.watchlist("user-1", "watchlist-1").await.unwrap().as_ref(), Some(&watchlist));
+
+    watchlist.symbols = vec!["MSFT".to_owned(), "NVDA".to_owned()];
+    assert!(db.update_watchlist("user-1", &watchlist).await.unwrap());
+    assert_eq!(db.watchlists("user-1").await.unwrap(), [watchlist]);
+
+    assert!(db.delete_watchlist("user-1", "watchlist-1").await.unwrap());
+    assert!(db.watchlists("user-1").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_alerts() {
+    let db = database().await;
+    let alert = |id: &str, repeat: bool| Alert {
+        id: id.to_owned(),
+        config: CreateAlertReq {
+            provider: ProviderType::Yahoo,
+            symbol: "SPY".to_owned(),
+            interval: TimeInterval::Minute1,
+            condition: AlertCondition::Price { direction: Direction::Above, price: 500.0 },
+            repeat,
+            webhook: Some("http://localhost:9000/hook".to_owned()),
+            email: None,
+        },
+        active: true,
+        created_at: at(0),
+        last_triggered: None,
+    };
+    db.insert_alert("user-1", &alert("alert-1", false)).await.unwrap();
+    db.insert_alert("user-1", &alert("alert-2", true)).await.unwrap();
+    db.insert_alert("user-2", &alert("alert-3", false)).await.unwrap();
+    assert_eq!(db.alerts("user-1").await.unwrap(), [alert("alert-1", false), alert("alert-2", true)]);
+
+    db.alert_triggered("alert-1", at(1), false).await.unwrap();
+    let alerts = db.alerts("user-1").await.unwrap();
+    assert!(!alerts[0].active);
+    assert_eq!(alerts[0].last_triggered, Some(at(1)));
+    let active: Vec<_> = db.active_alerts().await.unwrap().into_iter().map(|(owner, a)| (owner, a.id)).collect();
+    assert_eq!(active, [("user-1".to_owned(), "alert-2".to_owned()), ("user-2".to_owned(), "alert-3".to_owned())]);
+
+    assert!(!db.delete_alert("user-2", "alert-2").await.unwrap());
+    assert!(db.delete_alert("user-1", "alert-2").await.unwrap());
+    assert_eq!(db.alerts("user-1").await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_state_survives_reconnecting() {
+    let path = std::env::temp_dir().join(format!("traiter-db-{}.db", std::process::id()));
+    let url = format!("sqlite://{}?mode=rwc", path.display());
+    let _ = std::fs::remove_file(&path);
+
+    let mut ledger = Ledger::new("USD");
+    ledger.record(at(0), LedgerEvent::CashMovement { currency: "USD".to_owned(), amount: 1_000.0 }).unwrap();
+    ledger
+        .record(at(1), LedgerEvent::Fill {
+            symbol: "SPY".to_owned(),
+            currency: "USD".to_owned(),
+            side: OrderSide::Buy,
+            qty: 2.0,
+            price: 400.0,
+            multiplier: 1.0,
+        })
+        .unwrap();
+    let portfolio = PaperPortfolio {
+        owner: "user-1".to_owned(),
+        config: StartPaperReq {
+            provider: ProviderType::Yahoo,
+            symbol: "SPY".to_owned(),
+            interval: TimeInterval::Minute1,
+            strategy: PaperStrategy::SmaCross { short: 5, long: 20 },
+            starting_cash: 1_000.0,
+        },
+        started: at(0),
+        ledger,
+    };
+
+    let alice = User { id: "user-1".to_owned(), username: "alice".to_owned(), created_at: at(0) };
+    {
+        let db = Database::connect(&url).await.unwrap();
+        db.insert_user(&alice, "hash").await.unwrap();
+        db.insert_strategy("user-1", &strategy("strategy-1")).await.unwrap();
+        db.save_paper_portfolio("paper-1", &portfolio).await.unwrap();
+        // Saving again overwrites rather than duplicates
+        db.save_paper_portfolio("paper-1", &portfolio).await.unwrap();
+    }
+
+    let db = Database::connect(&url).await.unwrap();
+    assert_eq!(db.user("user-1").await.unwrap(), Some(alice));
+    assert_eq!(db.strategy("user-1", "strategy-1").await.unwrap(), Some(strategy("strategy-1")));
+    let saved = db.paper_portfolios().await.unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].0, "paper-1");
+    assert_eq!(saved[0].1.owner, "user-1");
+    assert_eq!(saved[0].1.ledger.entries(), portfolio.ledger.entries());
+    assert_eq!(saved[0].1.ledger.portfolio().positions["SPY"].qty, 2.0);
+
+    db.delete_paper_portfolio("paper-1").await.unwrap();
+    assert!(db.paper_portfolio("paper-1").await.unwrap().is_none());
+    drop(db);
+    let _ = std::fs::remove_file(path);
+}
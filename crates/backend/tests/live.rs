@@ -0,0 +1,217 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use api::stock::{ErrorCode, StockWatchReq, StockWatchReqMsg, StockWatchResMsg, Subscription, PROTOCOL_VERSION};
+use api::ProviderType;
+use async_trait::async_trait;
+use axum::extract::{State, WebSocketUpgrade};
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use backend::services::live::{LiveLimits, LiveStreams};
+use backend::services::SafeProvider;
+use chrono::{TimeZone, Utc};
+use data::providers::{Provider, ProviderStream};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use futures::{SinkExt, StreamExt};
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type Client = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Live candles are pushed through `feeds`; history is three daily candles
+#[derive(Clone, Default)]
+struct FakeProvider {
+    feeds: Arc<Mutex<Vec<mpsc::UnboundedSender<Candle>>>>,
+}
+
+#[async_trait]
+impl Provider for FakeProvider {
+    async fn stream(&self, _symbol: &str, _interval: TimeInterval) -> anyhow::Result<ProviderStream> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.feeds.lock().unwrap().push(tx);
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+
+    async fn historical(
+        &self,
+        _symbol: &str,
+        _interval: TimeInterval,
+        start: Timestamp,
+        _end: Timestamp,
+    ) -> anyhow::Result<Vec<Candle>> {
+        Ok((0..3).map(|day| candle(start + chrono::Duration::days(day), 100.0 + day as f64)).collect())
+    }
+
+    fn get_type(&self) -> ProviderType {
+        ProviderType::Yahoo
+    }
+}
+
+fn candle(ts: Timestamp, close: f64) -> Candle {
+    Candle::new(ts, close, close, close, close, 1_000.0).unwrap()
+}
+
+fn at(day: u32) -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 5, day, 0, 0, 0).unwrap()
+}
+
+async fn stream_stock(ws: WebSocketUpgrade, State(live): State<Arc<LiveStreams>>) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| async move { live.serve(socket).await })
+}
+
+/// Serves `limits` on a local port with only the Yahoo provider, and
+/// connects to it, reading the hello
+async fn connect(fake: &FakeProvider, limits: LiveLimits) -> Client {
+    let provider: SafeProvider = Arc::new(Box::new(fake.clone()));
+    let live = Arc::new(LiveStreams::new(HashMap::from([(ProviderType::Yahoo, provider)]), limits));
+    let app = Router::new().route("/live", get(stream_stock)).with_state(live);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let (mut client, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/live")).await.unwrap();
+    let hello = receive(&mut client).await;
+    assert_eq!(
+        hello,
+        StockWatchResMsg::Hello {
+            v: PROTOCOL_VERSION,
+            heartbeat_secs: limits.heartbeat.as_secs(),
+            idle_timeout_secs: limits.idle_timeout.as_secs(),
+        }
+    );
+    client
+}
+
+async fn send(client: &mut Client, msg: StockWatchReqMsg) {
+    let text = serde_json::to_string(&StockWatchReq::new(msg)).unwrap();
+    client.send(Message::text(text)).await.unwrap();
+}
+
+/// Next protocol message, skipping pings
+async fn receive(client: &mut Client) -> StockWatchResMsg {
+    loop {
+        let msg = tokio::time::timeout(Duration::from_secs(2), client.next()).await.unwrap().unwrap().unwrap();
+        match msg {
+            Message::Text(text) => return serde_json::from_str(&text).unwrap(),
+            Message::Ping(_) | Message::Pong(_) => {}
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+}
+
+fn error_code(msg: &StockWatchResMsg) -> Option<ErrorCode> {
+    match msg {
+        StockWatchResMsg::Error(error) => Some(error.code),
+        _ => None,
+    }
+}
+
+fn day(id: &str, provider: ProviderType) -> StockWatchReqMsg {
+    StockWatchReqMsg::Day { id: id.to_owned(), provider, symbol: "AAPL".to_owned(), interval: TimeInterval::Minute1 }
+}
+
+#[tokio::test]
+async fn test_live_subscription_lifecycle() {
+    let fake = FakeProvider::default();
+    let mut client = connect(&fake, LiveLimits::default()).await;
+
+    send(&mut client, day("a", ProviderType::Yahoo)).await;
+    let StockWatchResMsg::Subscribed { subscription } = receive(&mut client).await else { panic!("not acknowledged") };
+    assert_eq!(subscription.id, "a");
+    assert!(!subscription.replay);
+
+    let feed = fake.feeds.lock().unwrap()[0].clone();
+    feed.send(candle(at(1), 101.0)).unwrap();
+    let StockWatchResMsg::Candle { id, candle, .. } = receive(&mut client).await else { panic!("no candle") };
+    assert_eq!((id.as_str(), candle.close), ("a", 101.0));
+
+    send(&mut client, StockWatchReqMsg::List { id: "l".to_owned() }).await;
+    let listed = receive(&mut client).await;
+    assert_eq!(listed, StockWatchResMsg::Subscriptions { id: "l".to_owned(), subscriptions: vec![subscription] });
+
+    send(&mut client, StockWatchReqMsg::Unsubscribe { id: "a".to_owned() }).await;
+    assert_eq!(receive(&mut client).await, StockWatchResMsg::Unsubscribed { id: "a".to_owned() });
+    send(&mut client, StockWatchReqMsg::Unsubscribe { id: "a".to_owned() }).await;
+    assert_eq!(error_code(&receive(&mut client).await), Some(ErrorCode::UnknownSubscription));
+
+    send(&mut client, StockWatchReqMsg::Ping).await;
+    assert_eq!(receive(&mut client).await, StockWatchResMsg::Pong);
+}
+
+#[tokio::test]
+async fn test_replay_ends_and_leaves_the_list() {
+    let fake = FakeProvider::default();
+    let mut client = connect(&fake, LiveLimits::default()).await;
+
+    let night = StockWatchReqMsg::Night {
+        id: "n".to_owned(),
+        provider: ProviderType::Yahoo,
+        symbol: "AAPL".to_owned(),
+        interval: TimeInterval::Day1,
+        start: at(1),
+        end: at(10),
+        playback_speed: Some(1000),
+    };
+    send(&mut client, night).await;
+    assert!(matches!(receive(&mut client).await, StockWatchResMsg::Subscribed { subscription: Subscription { replay: true, .. } }));
+    for close in [100.0, 101.0, 102.0] {
+        let StockWatchResMsg::Candle { candle, .. } = receive(&mut client).await else { panic!("no candle") };
+        assert_eq!(candle.close, close);
+    }
+    assert_eq!(receive(&mut client).await, StockWatchResMsg::End { id: "n".to_owned() });
+
+    send(&mut client, StockWatchReqMsg::List { id: "l".to_owned() }).await;
+    assert_eq!(receive(&mut client).await, StockWatchResMsg::Subscriptions { id: "l".to_owned(), subscriptions: Vec::new() });
+}
+
+#[tokio::test]
+async fn test_bad_requests_get_error_codes() {
+    let fake = FakeProvider::default();
+    let limits = LiveLimits { max_subscriptions: 2, ..LiveLimits::default() };
+    let mut client = connect(&fake, limits).await;
+
+    client.send(Message::binary(vec![1, 2, 3])).await.unwrap();
+    assert_eq!(error_code(&receive(&mut client).await), Some(ErrorCode::InvalidRequest));
+    client.send(Message::text(r#"{"v": 99, "action": "ping"}"#)).await.unwrap();
+    assert_eq!(error_code(&receive(&mut client).await), Some(ErrorCode::UnsupportedVersion));
+    send(&mut client, day("b", ProviderType::Binance)).await;
+    assert_eq!(error_code(&receive(&mut client).await), Some(ErrorCode::UnknownProvider));
+
+    // Reusing an id replaces rather than adds a subscription
+    for id in ["a", "b", "b"] {
+        send(&mut client, day(id, ProviderType::Yahoo)).await;
+        assert!(matches!(receive(&mut client).await, StockWatchResMsg::Subscribed { .. }));
+    }
+    send(&mut client, day("c", ProviderType::Yahoo)).await;
+    let refused = receive(&mut client).await;
+    assert_eq!(error_code(&refused), Some(ErrorCode::TooManySubscriptions));
+    let StockWatchResMsg::Error(error) = refused else { unreachable!() };
+    assert_eq!(error.id.as_deref(), Some("c"));
+}
+
+#[tokio::test]
+async fn test_idle_connections_are_closed() {
+    let fake = FakeProvider::default();
+    let limits = LiveLimits { heartbeat: Duration::from_secs(1), idle_timeout: Duration::from_secs(1), ..LiveLimits::default() };
+    let mut client = connect(&fake, limits).await;
+
+    // Not reading means not answering pings either
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let closed = tokio::time::timeout(Duration::from_secs(2), async {
+        while let Some(msg) = client.next().await {
+            if let Message::Close(frame) = msg.unwrap() {
+                return frame;
+            }
+        }
+        None
+    })
+    .await
+    .unwrap();
+    assert_eq!(closed.map(|f| f.code), Some(CloseCode::Away));
+}
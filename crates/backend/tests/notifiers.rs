@@ -0,0 +1,90 @@
+use std::net::IpAddr;
+use std::time::Duration;
+use api::alert::{Alert, AlertCondition, AlertEvent, CreateAlertReq, Direction};
+use api::ProviderType;
+use backend::services::notifiers::{is_public, webhook_url, Notifier, WebhookNotifier};
+use chrono::Utc;
+use dnn_core::time::TimeInterval;
+use tokio::net::TcpListener;
+
+#[test]
+fn test_public_addresses() {
+    let public = ["8.8.8.8", "151.101.1.69", "2606:4700::6810:84e5", "::ffff:8.8.8.8", "64:ff9b::808:808"];
+    for ip in public {
+        assert!(is_public(ip.parse().unwrap()), "{ip}");
+    }
+    let internal = [
+        "127.0.0.1",
+        "10.1.2.3",
+        "172.16.0.1",
+        "192.168.1.1",
+        "169.254.169.254",
+        "100.64.0.1",
+        "0.0.0.0",
+        "255.255.255.255",
+        "::1",
+        "::",
+        "fd00::1",
+        "fe80::1",
+        "::ffff:127.0.0.1",
+        "::ffff:169.254.169.254",
+        "64:ff9b::a00:1",
+    ];
+    for ip in internal {
+        assert!(!is_public(ip.parse::<IpAddr>().unwrap()), "{ip}");
+    }
+}
+
+#[test]
+fn test_webhook_urls() {
+    assert!(webhook_url("https://example.com/hook").is_ok());
+    assert!(webhook_url("http://8.8.8.8:8080/hook").is_ok());
+    // Names are only checked when they're resolved
+    assert!(webhook_url("http://localhost/hook").is_ok());
+    for url in [
+        "ftp://example.com/hook",
+        "file:///etc/passwd",
+        "not a url",
+        "http://127.0.0.1:6379/",
+        "http://169.254.169.254/latest/meta-data",
+        "http://[::1]:5432/",
+        "http://[::ffff:10.0.0.1]/",
+    ] {
+        assert!(webhook_url(url).is_err(), "{url}");
+    }
+}
+
+#[tokio::test]
+async fn test_webhooks_never_reach_internal_hosts() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let notifier = WebhookNotifier::new().unwrap();
+    let event = AlertEvent {
+        alert_id: "alert-1".to_owned(),
+        symbol: "SPY".to_owned(),
+        message: "SPY price above 500".to_owned(),
+        price: 501.0,
+        triggered_at: Utc::now(),
+    };
+
+    for url in [format!("http://localhost:{port}/hook"), format!("http://127.0.0.1:{port}/hook")] {
+        let alert = Alert {
+            id: "alert-1".to_owned(),
+            config: CreateAlertReq {
+                provider: ProviderType::Yahoo,
+                symbol: "SPY".to_owned(),
+                interval: TimeInterval::Minute1,
+                condition: AlertCondition::Price { direction: Direction::Above, price: 500.0 },
+                repeat: false,
+                webhook: Some(url.clone()),
+                email: None,
+            },
+            active: true,
+            created_at: Utc::now(),
+            last_triggered: None,
+        };
+        assert!(notifier.notify(&alert, &event).await.is_err(), "{url}");
+    }
+    let connected = tokio::time::timeout(Duration::from_millis(100), listener.accept()).await;
+    assert!(connected.is_err(), "a webhook reached the local listener");
+}
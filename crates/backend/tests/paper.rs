@@ -0,0 +1,181 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use api::auth::User;
+use api::paper::{PaperEvent, PaperStrategy, StartPaperReq};
+use api::strategy::{CreateStrategyReq, StrategyLanguage};
+use api::ProviderType;
+use async_trait::async_trait;
+use backend::db::Database;
+use backend::services::paper::PaperTrading;
+use backend::services::strategies::Strategies;
+use backend::services::SafeProvider;
+use chrono::{DurationRound, Utc};
+use data::providers::{Provider, ProviderStream};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use risk::RiskLimits;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+/// Live streams stay open as long as the provider lives, and get the
+/// candles sent through [`Self::feed`]
+#[derive(Clone, Default)]
+struct FakeProvider {
+    feeds: Arc<Mutex<Vec<mpsc::UnboundedSender<Candle>>>>,
+}
+
+impl FakeProvider {
+    /// The last stream opened
+    fn feed(&self) -> mpsc::UnboundedSender<Candle> {
+        self.feeds.lock().unwrap().last().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Provider for FakeProvider {
+    async fn stream(&self, _symbol: &str, _interval: TimeInterval) -> anyhow::Result<ProviderStream> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.feeds.lock().unwrap().push(tx);
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+
+    async fn historical(
+        &self,
+        _symbol: &str,
+        _interval: TimeInterval,
+        _start: Timestamp,
+        _end: Timestamp,
+    ) -> anyhow::Result<Vec<Candle>> {
+        Ok(Vec::new())
+    }
+
+    fn get_type(&self) -> ProviderType {
+        ProviderType::Yahoo
+    }
+}
+
+/// A database with users "user-1" and "user-2"
+async fn database() -> Database {
+    let db = Database::in_memory().await.unwrap();
+    for (id, name) in [("user-1", "alice"), ("user-2", "bob")] {
+        let user = User { id: id.to_owned(), username: name.to_owned(), created_at: Utc::now() };
+        assert!(db.insert_user(&user, "hash").await.unwrap());
+    }
+    db
+}
+
+fn paper_trading(db: &Database) -> PaperTrading {
+    PaperTrading::new(db.clone(), Arc::new(Strategies::new(db.clone())), RiskLimits::default())
+}
+
+fn config() -> StartPaperReq {
+    StartPaperReq {
+        provider: ProviderType::Yahoo,
+        symbol: "SPY".to_owned(),
+        interval: TimeInterval::Minute1,
+        strategy: PaperStrategy::SmaCross { short: 5, long: 20 },
+        starting_cash: 10_000.0,
+    }
+}
+
+#[tokio::test]
+async fn test_sessions_belong_to_their_owner() {
+    let db = database().await;
+    let fake = FakeProvider::default();
+    let provider: SafeProvider = Arc::new(Box::new(fake.clone()));
+    let paper = paper_trading(&db);
+
+    let first = paper.start("user-1", config(), provider.clone()).await.unwrap();
+    let second = paper.start("user-1", config(), provider.clone()).await.unwrap();
+    assert_ne!(first.id, second.id);
+    assert_eq!(paper.list("user-1").await.len(), 2);
+    assert!(paper.get("user-1", &first.id).await.is_some());
+
+    // Others can't see, follow or stop them
+    assert!(paper.list("user-2").await.is_empty());
+    assert!(paper.get("user-2", &first.id).await.is_none());
+    assert!(paper.subscribe("user-2", &first.id).await.is_none());
+    assert!(paper.stop("user-2", &first.id).await.is_err());
+    assert!(paper.subscribe("user-1", &first.id).await.is_some());
+
+    // Resumed sessions keep their owner
+    let restarted = paper_trading(&db);
+    for (id, _) in restarted.saved_sessions().await.unwrap() {
+        restarted.resume(&id, provider.clone()).await.unwrap();
+    }
+    assert_eq!(restarted.list("user-1").await.len(), 2);
+    assert!(restarted.list("user-2").await.is_empty());
+
+    paper.stop("user-1", &first.id).await.unwrap();
+    assert_eq!(paper.list("user-1").await.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), [second.id.as_str()]);
+    assert!(db.paper_portfolio(&first.id).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_bars_count_once_they_close() {
+    let db = database().await;
+    let fake = FakeProvider::default();
+    let provider: SafeProvider = Arc::new(Box::new(fake.clone()));
+    let paper = paper_trading(&db);
+    // Taken first, so the bar before has closed by the time the session starts
+    let now = Utc::now().duration_trunc(chrono::Duration::minutes(1)).unwrap();
+    let session = paper.start("user-1", config(), provider).await.unwrap();
+    let (_, mut events) = paper.subscribe("user-1", &session.id).await.unwrap();
+
+    let bar = |minute: i64, close: f64| {
+        Candle::new(now + chrono::Duration::minutes(minute), close, close, close, close, 1_000.0).unwrap()
+    };
+    // A window of old bars, as Yahoo sends on every poll, then the forming
+    // bar twice and the next one, which closes it
+    let window: Vec<_> = (-30..0).map(|minute| bar(minute, 100.0)).collect();
+    let feed = fake.feed();
+    for candle in window.iter().cloned().chain([bar(0, 100.0), bar(0, 101.0), bar(1, 102.0)]) {
+        feed.send(candle).unwrap();
+    }
+    // The next poll repeats it all before the bar after
+    for candle in window.into_iter().chain([bar(0, 101.0), bar(1, 102.5), bar(2, 103.0)]) {
+        feed.send(candle).unwrap();
+    }
+
+    let mut valued = Vec::new();
+    while valued.len() < 2 {
+        let event = tokio::time::timeout(Duration::from_secs(2), events.recv()).await.unwrap().unwrap();
+        if let PaperEvent::Pnl { ts, .. } = event {
+            valued.push(ts);
+        }
+    }
+    assert_eq!(valued, [now, now + chrono::Duration::minutes(1)]);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(events.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn test_saved_strategies_run_the_version_they_started_with() {
+    let db = database().await;
+    let strategies = Strategies::new(db.clone());
+    let req = |code: &str| CreateStrategyReq {
+        name: "Crossover".to_owned(),
+        description: String::new(),
+        language: StrategyLanguage::Builtin,
+        code: code.to_owned(),
+    };
+    let saved = strategies.create("user-1", req(r#"{"name": "ma_cross"}"#)).await.unwrap();
+    let provider: SafeProvider = Arc::new(Box::new(FakeProvider::default()));
+    let paper = paper_trading(&db);
+
+    let with = |strategy_id: &str| StartPaperReq {
+        strategy: PaperStrategy::Saved { strategy_id: strategy_id.to_owned(), version: None },
+        ..config()
+    };
+    let session = paper.start("user-1", with(&saved.id), provider.clone()).await.unwrap();
+    assert_eq!(session.config.strategy, PaperStrategy::Saved { strategy_id: saved.id.clone(), version: Some(1) });
+    // Strategies of others and unknown ones can't be run
+    assert!(paper.start("user-2", with(&saved.id), provider.clone()).await.is_err());
+    assert!(paper.start("user-1", with("strategy-none"), provider.clone()).await.is_err());
+
+    // Edits don't change what a resumed session runs
+    strategies.update("user-1", &saved.id, req(r#"{"name": "rsi_reversion"}"#)).await.unwrap();
+    let restarted = paper_trading(&db);
+    let resumed = restarted.resume(&session.id, provider).await.unwrap();
+    assert_eq!(resumed.config.strategy, session.config.strategy);
+}
@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use api::scanner::{ScannerQuery, ScannerSort};
+use api::ProviderType;
+use async_trait::async_trait;
+use backend::services::scanner::{Scanner, ScannerError, HISTORY_BARS};
+use backend::services::SafeProvider;
+use chrono::Utc;
+use data::providers::{Profile, Provider, ProviderStream};
+use dnn_core::instrument::{AssetClass, Instrument, InstrumentMaster};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+
+/// AAPL rises a dollar a day, MSFT falls one; symbols in `failing` error
+#[derive(Clone, Default)]
+struct FakeProvider {
+    failing: Arc<Mutex<HashSet<String>>>,
+    profile_calls: Arc<AtomicUsize>,
+}
+
+#[async_trait]
+impl Provider for FakeProvider {
+    async fn stream(&self, _symbol: &str, _interval: TimeInterval) -> anyhow::Result<ProviderStream> {
+        anyhow::bail!("no live data")
+    }
+
+    async fn historical(
+        &self,
+        symbol: &str,
+        _interval: TimeInterval,
+        _start: Timestamp,
+        end: Timestamp,
+    ) -> anyhow::Result<Vec<Candle>> {
+        if self.failing.lock().unwrap().contains(symbol) {
+            anyhow::bail!("{symbol} is unavailable");
+        }
+        let step = match symbol {
+            "AAPL" => 1.0,
+            "MSFT" => -1.0,
+            _ => anyhow::bail!("unknown symbol {symbol}"),
+        };
+        Ok((0..30)
+            .map(|day| {
+                let close = 200.0 + step * f64::from(day);
+                let ts = end - chrono::Duration::days(i64::from(30 - day));
+                // The last bar trades twice the usual volume
+                let volume = if day == 29 { 2_000.0 } else { 1_000.0 };
+                Candle::new(ts, close, close, close, close, volume).unwrap()
+            })
+            .collect())
+    }
+
+    async fn profile(&self, symbol: &str) -> anyhow::Result<Profile> {
+        self.profile_calls.fetch_add(1, Ordering::SeqCst);
+        let sector = if symbol == "AAPL" { "Technology" } else { "Software" };
+        Ok(Profile { name: Some(format!("{symbol} Inc")), sector: Some(sector.to_owned()), market_cap: Some(1e12) })
+    }
+
+    fn get_type(&self) -> ProviderType {
+        ProviderType::Yahoo
+    }
+}
+
+async fn scanner(fake: &FakeProvider, instruments: InstrumentMaster) -> Arc<Scanner> {
+    let provider: SafeProvider = Arc::new(Box::new(fake.clone()));
+    let symbols = ["AAPL", "MSFT", "GONE"].map(str::to_owned).to_vec();
+    let scanner = Scanner::new(provider, symbols, instruments, Duration::from_secs(3600));
+    // The first background refresh starts right away, the next in an hour
+    let refreshed = tokio::time::timeout(Duration::from_secs(1), async {
+        while scanner.query(&ScannerQuery::default()).await.unwrap().updated.is_none() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await;
+    assert!(refreshed.is_ok());
+    scanner
+}
+
+fn invalid(result: Result<api::scanner::ScannerPage, ScannerError>) -> String {
+    match result {
+        Err(ScannerError::Invalid(message)) => message,
+        Ok(page) => panic!("expected an error, got {page:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_query_filters_sorts_and_pages() {
+    let fake = FakeProvider::default();
+    let scanner = scanner(&fake, InstrumentMaster::new()).await;
+
+    // Symbols without bars are left out
+    let page = scanner.query(&ScannerQuery::default()).await.unwrap();
+    assert_eq!(page.total, 2);
+    assert!(page.updated.is_some_and(|at| at <= Utc::now()));
+    let aapl = &page.rows[0];
+    assert_eq!((aapl.symbol.as_str(), aapl.price, aapl.change), ("AAPL", 229.0, Some(1.0)));
+    assert_eq!(aapl.avg_volume, Some(1_000.0));
+    assert_eq!(aapl.volume_ratio, Some(2.0));
+    assert_eq!(aapl.name.as_deref(), Some("AAPL Inc"));
+
+    let rising = ScannerQuery { filter: Some("change_pct > 0 AND volume_ratio >= 2".to_owned()), ..ScannerQuery::default() };
+    let page = scanner.query(&rising).await.unwrap();
+    assert_eq!(page.rows.iter().map(|r| r.symbol.as_str()).collect::<Vec<_>>(), ["AAPL"]);
+
+    let by_sector = ScannerQuery { sector: Some("software".to_owned()), ..ScannerQuery::default() };
+    assert_eq!(scanner.query(&by_sector).await.unwrap().rows[0].symbol, "MSFT");
+
+    let sorted = ScannerQuery {
+        columns: vec!["SMA(10)".to_owned()],
+        sort: Some(ScannerSort { by: "change".to_owned(), descending: false }),
+        offset: 1,
+        limit: Some(1),
+        ..ScannerQuery::default()
+    };
+    let page = scanner.query(&sorted).await.unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.rows.len(), 1);
+    assert_eq!(page.rows[0].symbol, "AAPL");
+    assert_eq!(page.rows[0].columns, [Some(224.5)]);
+}
+
+#[tokio::test]
+async fn test_query_rejects_bad_requests() {
+    let fake = FakeProvider::default();
+    let scanner = scanner(&fake, InstrumentMaster::new()).await;
+
+    let no_rows = ScannerQuery { limit: Some(0), ..ScannerQuery::default() };
+    assert!(invalid(scanner.query(&no_rows).await).contains("limit"));
+
+    let unknown = ScannerQuery { filter: Some("nonsense > 1".to_owned()), ..ScannerQuery::default() };
+    assert!(invalid(scanner.query(&unknown).await).starts_with("filter:"));
+
+    // More bars than the scanner keeps, whether filtering, showing or sorting
+    let too_long = format!("SMA({})", HISTORY_BARS + 1);
+    let queries = [
+        ScannerQuery { filter: Some(format!("close > {too_long}")), ..ScannerQuery::default() },
+        ScannerQuery { columns: vec![too_long.clone()], ..ScannerQuery::default() },
+        ScannerQuery { sort: Some(ScannerSort { by: too_long.clone(), descending: true }), ..ScannerQuery::default() },
+    ];
+    for query in &queries {
+        assert!(invalid(scanner.query(query).await).contains(&format!("needs {} daily bars", HISTORY_BARS + 1)));
+    }
+    let longest = ScannerQuery { columns: vec![format!("SMA({HISTORY_BARS})")], ..ScannerQuery::default() };
+    assert!(scanner.query(&longest).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_refresh_keeps_rows_when_a_fetch_fails() {
+    let fake = FakeProvider::default();
+    let scanner = scanner(&fake, InstrumentMaster::new()).await;
+    let profiles = fake.profile_calls.load(Ordering::SeqCst);
+
+    fake.failing.lock().unwrap().insert("AAPL".to_owned());
+    scanner.refresh().await;
+    let page = scanner.query(&ScannerQuery::default()).await.unwrap();
+    assert_eq!(page.total, 2);
+    assert_eq!(page.rows[0].price, 229.0);
+
+    // Profiles aren't fetched again until they're hours old
+    assert_eq!(fake.profile_calls.load(Ordering::SeqCst), profiles);
+}
+
+#[tokio::test]
+async fn test_instrument_master_names_and_sectors() {
+    let fake = FakeProvider::default();
+    let msft = Instrument {
+        name: Some("Microsoft Corporation".to_owned()),
+        sector: Some("Technology".to_owned()),
+        ..Instrument::new("MSFT", AssetClass::Equity, "NASDAQ", "USD")
+    };
+    let mut instruments = InstrumentMaster::new();
+    instruments.insert(msft);
+    let scanner = scanner(&fake, instruments).await;
+
+    // The master wins over profiles, which fill in the rest
+    let tech = ScannerQuery { sector: Some("technology".to_owned()), ..ScannerQuery::default() };
+    let page = scanner.query(&tech).await.unwrap();
+    let rows: Vec<_> = page.rows.iter().map(|r| (r.symbol.as_str(), r.name.as_deref())).collect();
+    assert_eq!(rows, [("AAPL", Some("AAPL Inc")), ("MSFT", Some("Microsoft Corporation"))]);
+    let software = ScannerQuery { sector: Some("software".to_owned()), ..ScannerQuery::default() };
+    assert_eq!(scanner.query(&software).await.unwrap().total, 0);
+}
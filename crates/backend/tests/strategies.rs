@@ -0,0 +1,109 @@
+use api::auth::User;
+use api::strategy::{BacktestResult, CreateStrategyReq, StrategyLanguage, StrategyStatus};
+use backend::db::Database;
+use backend::services::strategies::{StrategyError, Strategies};
+use chrono::Utc;
+
+/// Strategies of users "user-1" and "user-2"
+async fn strategies() -> Strategies {
+    let db = Database::in_memory().await.unwrap();
+    for (id, name) in [("user-1", "alice"), ("user-2", "bob")] {
+        let user = User { id: id.to_owned(), username: name.to_owned(), created_at: Utc::now() };
+        assert!(db.insert_user(&user, "hash").await.unwrap());
+    }
+    Strategies::new(db)
+}
+
+fn req(name: &str, language: StrategyLanguage, code: &str) -> CreateStrategyReq {
+    CreateStrategyReq { name: name.to_owned(), description: String::new(), language, code: code.to_owned() }
+}
+
+fn crossover() -> CreateStrategyReq {
+    req("Crossover", StrategyLanguage::Builtin, r#"{"name": "ma_cross"}"#)
+}
+
+fn invalid(result: Result<impl std::fmt::Debug, StrategyError>) -> String {
+    match result {
+        Err(StrategyError::Invalid(message)) => message,
+        other => panic!("expected a validation error, got {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn test_create_and_list_per_owner() {
+    let strategies = strategies().await;
+    let created = strategies.create("user-1", req("  Crossover ", StrategyLanguage::Builtin, r#"{"name": "ma_cross"}"#)).await.unwrap();
+    assert_eq!(created.name, "Crossover");
+    assert_eq!((created.status, created.version), (StrategyStatus::Draft, 1));
+    assert!(created.backtest_results.is_none());
+    let other = strategies.create("user-1", crossover()).await.unwrap();
+    assert_ne!(created.id, other.id);
+
+    // Stored times are cut to microseconds, so compare the rest
+    let stored = strategies.get("user-1", &created.id).await.unwrap();
+    assert_eq!((stored.name, stored.code, stored.version), (created.name, created.code.clone(), 1));
+    assert_eq!(strategies.list("user-1").await.unwrap().len(), 2);
+    // Nobody else sees them
+    assert!(strategies.list("user-2").await.unwrap().is_empty());
+    assert!(matches!(strategies.get("user-2", &created.id).await, Err(StrategyError::NotFound(_))));
+    assert!(matches!(strategies.runnable("user-2", &created.id).await, Err(StrategyError::NotFound(_))));
+    let (_, version) = strategies.runnable("user-1", &created.id).await.unwrap();
+    assert_eq!(version, 1);
+}
+
+#[tokio::test]
+async fn test_updates_keep_every_version() {
+    let strategies = strategies().await;
+    let created = strategies.create("user-1", crossover()).await.unwrap();
+
+    let rules = req("Dip buyer", StrategyLanguage::Rules, "entry: RSI(14) < 30\nexit: RSI(14) > 70");
+    let updated = strategies.update("user-1", &created.id, rules.clone()).await.unwrap();
+    assert_eq!((updated.name.as_str(), updated.language, updated.version), ("Dip buyer", StrategyLanguage::Rules, 2));
+    assert_eq!(strategies.get("user-1", &created.id).await.unwrap(), updated);
+
+    let versions = strategies.versions("user-1", &created.id).await.unwrap();
+    assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), [1, 2]);
+    assert_eq!((versions[0].language, versions[0].code.as_str()), (created.language, created.code.as_str()));
+    assert_eq!(versions[1].code, rules.code);
+
+    // A failed edit saves nothing
+    assert!(!invalid(strategies.update("user-1", &created.id, req("Broken", StrategyLanguage::Rules, "entry: [")).await).is_empty());
+    assert_eq!(strategies.get("user-1", &created.id).await.unwrap().version, 2);
+    assert!(matches!(strategies.update("user-2", &created.id, crossover()).await, Err(StrategyError::NotFound(_))));
+    assert!(matches!(strategies.versions("user-2", &created.id).await, Err(StrategyError::NotFound(_))));
+
+    let result = BacktestResult { total_return: 2.5, sharpe_ratio: 1.1, max_drawdown: -0.8, win_rate: 50.0, total_trades: 2 };
+    strategies.record_backtest(&created.id, &result).await.unwrap();
+    assert_eq!(strategies.get("user-1", &created.id).await.unwrap().backtest_results, Some(result));
+}
+
+#[tokio::test]
+async fn test_delete() {
+    let strategies = strategies().await;
+    let created = strategies.create("user-1", crossover()).await.unwrap();
+
+    assert!(matches!(strategies.delete("user-2", &created.id).await, Err(StrategyError::NotFound(_))));
+    strategies.delete("user-1", &created.id).await.unwrap();
+    assert!(matches!(strategies.get("user-1", &created.id).await, Err(StrategyError::NotFound(_))));
+    assert!(strategies.list("user-1").await.unwrap().is_empty());
+    assert!(matches!(strategies.delete("user-1", &created.id).await, Err(StrategyError::NotFound(_))));
+}
+
+#[tokio::test]
+async fn test_code_must_compile_in_its_language() {
+    let strategies = strategies().await;
+
+    assert!(invalid(strategies.create("user-1", req(" ", StrategyLanguage::Builtin, r#"{"name": "ma_cross"}"#)).await).contains("name"));
+    let script = "fn on_bar(ctx, bar) {\n  if bar.close > 100.0 { ctx.buy(1); }\n}";
+    assert!(strategies.create("user-1", req("Script", StrategyLanguage::Rhai, script)).await.is_ok());
+    assert!(!invalid(strategies.create("user-1", req("Script", StrategyLanguage::Rhai, "fn on_bar(ctx, bar) { let x = ; }")).await).is_empty());
+
+    assert!(strategies.create("user-1", req("Rules", StrategyLanguage::Rules, "entry: close > SMA(20)")).await.is_ok());
+    assert!(invalid(strategies.create("user-1", req("Rules", StrategyLanguage::Rules, "entry: close > SMA(x)")).await).contains("entry"));
+
+    assert!(invalid(strategies.create("user-1", req("Builtin", StrategyLanguage::Builtin, "ma_cross")).await).starts_with("invalid strategy spec"));
+    assert!(!invalid(strategies.create("user-1", req("Builtin", StrategyLanguage::Builtin, r#"{"name": "no_such_strategy"}"#)).await).is_empty());
+    // Code written for one language doesn't pass as another
+    assert!(strategies.create("user-1", req("Mixed", StrategyLanguage::Rhai, "entry: close > SMA(20)")).await.is_err());
+    assert_eq!(strategies.list("user-1").await.unwrap().len(), 2);
+}
@@ -0,0 +1,371 @@
+use std::collections::{HashMap, VecDeque};
+use anyhow::bail;
+use broker::BrokerError;
+use broker::sim::SimulatedExchange;
+use dnn_core::fx::FxHistory;
+use dnn_core::instrument::InstrumentMaster;
+use dnn_core::ledger::Ledger;
+use dnn_core::market::{Candle, MarketEvent};
+use dnn_core::portfolio::DEFAULT_BASE_CURRENCY;
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionReport, Order};
+use risk::RiskEngine;
+use strats::context::{Command, LogLine};
+use strats::sizing::{FixedFractional, Intent, PositionSizer, SizingLayer, SymbolMetrics};
+use strats::{indicators, Strategy, StrategyContext};
+
+/// Lookback used for the ATR and volatility handed to position sizers
+const SIZING_LOOKBACK: usize = 14;
+/// Guards against strategies that answer every order update with another order
+const MAX_UPDATES_PER_EVENT: usize = 10_000;
+
+/// What happened while processing one market event
+#[derive(Debug, Clone, Default)]
+pub struct BarOutcome {
+    /// Orders the strategy (or its sizer) produced
+    pub orders: Vec<Order>,
+    /// Every report from the exchange, including acknowledgements and cancels
+    pub reports: Vec<ExecutionReport>,
+    /// Full and partial fills
+    pub fills: Vec<ExecutionReport>,
+    /// Orders stopped by the risk engine
+    pub rejections: Vec<ExecutionReport>,
+    /// Lines the strategy logged
+    pub logs: Vec<LogLine>,
+    /// Portfolio value after the bar, in the base currency
+    pub equity: f64,
+}
+
+/// Drives a strategy through its lifecycle hooks: sizing, pre-trade risk and
+/// execution on a [`SimulatedExchange`]. Used by both the backtester and paper trading.
+pub struct TradingEngine<S: Strategy> {
+    strategy: S,
+    starting_cash: f64,
+    base_currency: String,
+    exchange: SimulatedExchange,
+    sizing: SizingLayer,
+    risk: RiskEngine,
+    fx: FxHistory,
+    history: HashMap<String, Vec<Candle>>,
+    history_len: usize,
+    last_prices: HashMap<String, f64>,
+    next_order_id: u64,
+    /// Pending `on_timer` calls, earliest first
+    timers: Vec<Timestamp>,
+    started: bool,
+    last_event: Option<(String, Timestamp)>,
+}
+
+impl<S: Strategy> TradingEngine<S> {
+    pub fn new(strategy: S, starting_cash: f64) -> Self {
+        let history_len = strategy.lookback().max(SIZING_LOOKBACK + 1);
+        Self {
+            strategy,
+            starting_cash,
+            base_currency: DEFAULT_BASE_CURRENCY.to_owned(),
+            exchange: SimulatedExchange::new(DEFAULT_BASE_CURRENCY),
+            sizing: SizingLayer::new(Box::new(FixedFractional { fraction: 0.1 })),
+            risk: RiskEngine::default(),
+            fx: FxHistory::new(),
+            history: HashMap::new(),
+            history_len,
+            last_prices: HashMap::new(),
+            next_order_id: 1,
+            timers: Vec::new(),
+            started: false,
+            last_event: None,
+        }
+    }
+
+    /// Sizer for intents emitted by the strategy (default: 10% of equity per entry)
+    pub fn with_sizer(mut self, sizer: Box<dyn PositionSizer>) -> Self {
+        self.sizing = SizingLayer::new(sizer).with_instruments(self.exchange.instruments().clone());
+        self
+    }
+
+    /// Use instrument reference data to round fill quantities and prices and to value orders in risk checks and sizing
+    pub fn with_instruments(mut self, instruments: InstrumentMaster) -> Self {
+        self.risk = std::mem::take(&mut self.risk).with_instruments(instruments.clone());
+        self.sizing = self.sizing.with_instruments(instruments.clone());
+        self.exchange = self.exchange.with_instruments(instruments);
+        self
+    }
+
+    /// Report equity in `currency`, converting with the given FX history.
+    /// Starting cash is held in this currency.
+    pub fn with_base_currency(mut self, currency: &str, fx: FxHistory) -> Self {
+        self.base_currency = currency.to_owned();
+        self.exchange = self.exchange.with_base_currency(currency);
+        self.fx = fx;
+        self
+    }
+
+    /// Check every order against pre-trade limits before it is filled
+    pub fn with_risk(mut self, risk: RiskEngine) -> Self {
+        self.risk = risk.with_instruments(self.exchange.instruments().clone());
+        self
+    }
+
+    /// Fill at most this fraction of each bar's volume, leaving the rest working
+    pub fn with_max_participation(mut self, fraction: f64) -> Self {
+        self.exchange = self.exchange.with_max_participation(fraction);
+        self
+    }
+
+    /// Bars of history per symbol available to strategy indicators, instead
+    /// of the strategy's [`Strategy::lookback`]
+    pub fn with_history(mut self, bars: usize) -> Self {
+        self.history_len = bars.max(SIZING_LOOKBACK + 1);
+        self
+    }
+
+    /// Continue from an existing ledger instead of funding a new one
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.base_currency = ledger.base_currency().to_owned();
+        self.exchange = self.exchange.with_ledger(ledger);
+        self
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        self.exchange.ledger()
+    }
+
+    pub fn exchange(&self) -> &SimulatedExchange {
+        &self.exchange
+    }
+
+    pub fn risk_mut(&mut self) -> &mut RiskEngine {
+        &mut self.risk
+    }
+
+    pub fn starting_cash(&self) -> f64 {
+        self.starting_cash
+    }
+
+    /// Latest close of every symbol seen so far
+    pub fn last_prices(&self) -> &HashMap<String, f64> {
+        &self.last_prices
+    }
+
+    /// Start over with a fresh ledger and risk state, keeping configuration
+    pub fn reset(&mut self) {
+        self.exchange.reset();
+        self.risk = RiskEngine::new(self.risk.limits().clone()).with_instruments(self.risk.instruments().clone());
+        self.history.clear();
+        self.last_prices.clear();
+        self.next_order_id = 1;
+        self.timers.clear();
+        self.started = false;
+        self.last_event = None;
+    }
+
+    pub fn on_bar(&mut self, symbol: &str, bar: &Candle) -> anyhow::Result<BarOutcome> {
+        self.on_event(&MarketEvent::bar(symbol, bar.clone()))
+    }
+
+    /// Hand one event to the strategy and the simulated exchange.
+    ///
+    /// Only bars and trades move the market the exchange matches against. Quotes and
+    /// book updates reach the strategy and mark positions at their mid, but working
+    /// orders don't fill on them, and orders sent from them match the last bar or trade.
+    pub fn on_event(&mut self, event: &MarketEvent) -> anyhow::Result<BarOutcome> {
+        let symbol = event.symbol();
+        let ts = event.ts();
+        if self.exchange.ledger().entries().is_empty() {
+            self.exchange.deposit(ts, &self.base_currency, self.starting_cash)?;
+        }
+        self.last_event = Some((symbol.to_owned(), ts));
+
+        let mut outcome = BarOutcome::default();
+        let mut pending = VecDeque::new();
+
+        if !self.started {
+            self.started = true;
+            self.call(symbol, ts, &mut outcome, &mut pending, |s, ctx| s.on_start(ctx))?;
+        }
+
+        while let Some(at) = self.timers.first().copied().filter(|at| *at <= ts) {
+            self.timers.remove(0);
+            self.call(symbol, ts, &mut outcome, &mut pending, |s, ctx| s.on_timer(ctx, at))?;
+        }
+
+        // Bars and trades move the market the exchange matches against.
+        // Orders still working from earlier events match first.
+        let tape = match event {
+            MarketEvent::Bar { candle, .. } => Some(candle.clone()),
+            MarketEvent::Trade(trade) => Some(trade.to_candle()),
+            _ => None,
+        };
+        if let Some(bar) = &tape {
+            for report in self.exchange.on_bar(symbol, bar)? {
+                Self::record(report, &mut outcome, &mut pending);
+            }
+            self.risk.on_candle(symbol, bar);
+        }
+        if let Some(price) = event.price() {
+            self.last_prices.insert(symbol.to_owned(), price);
+        }
+        if let Some(bar) = event.as_candle() {
+            let history = self.history.entry(symbol.to_owned()).or_default();
+            history.push(bar.clone());
+            if history.len() > self.history_len {
+                history.drain(..history.len() - self.history_len);
+            }
+        }
+
+        self.call(symbol, ts, &mut outcome, &mut pending, |s, ctx| s.on_event(ctx, event))?;
+        self.drain(symbol, ts, &mut outcome, pending)?;
+
+        outcome.equity = self.equity(ts)?;
+        self.risk.on_equity(outcome.equity, ts);
+        Ok(outcome)
+    }
+
+    /// Run the strategy's `on_end` hook; orders it sends execute at the last prices
+    pub fn finish(&mut self) -> anyhow::Result<BarOutcome> {
+        let mut outcome = BarOutcome::default();
+        let Some((symbol, ts)) = self.last_event.clone() else {
+            return Ok(outcome);
+        };
+
+        let mut pending = VecDeque::new();
+        self.call(&symbol, ts, &mut outcome, &mut pending, |s, ctx| s.on_end(ctx))?;
+        self.drain(&symbol, ts, &mut outcome, pending)?;
+
+        outcome.equity = self.equity(ts)?;
+        Ok(outcome)
+    }
+
+    fn equity(&self, ts: Timestamp) -> anyhow::Result<f64> {
+        let fx = self.fx.rates_at(ts);
+        Ok(self.exchange.portfolio().total_value(&self.last_prices, &fx)?)
+    }
+
+    /// Hand order updates to the strategy until it stops reacting
+    fn drain(
+        &mut self,
+        symbol: &str,
+        ts: Timestamp,
+        outcome: &mut BarOutcome,
+        mut pending: VecDeque<ExecutionReport>,
+    ) -> anyhow::Result<()> {
+        let mut handled = 0;
+        while let Some(report) = pending.pop_front() {
+            handled += 1;
+            if handled > MAX_UPDATES_PER_EVENT {
+                bail!("strategy produced more than {MAX_UPDATES_PER_EVENT} order updates for one event");
+            }
+            self.call(symbol, ts, outcome, &mut pending, |s, ctx| s.on_order_update(ctx, &report))?;
+        }
+        Ok(())
+    }
+
+    /// Run one hook with a fresh context and carry out what it asked for
+    fn call(
+        &mut self,
+        symbol: &str,
+        ts: Timestamp,
+        outcome: &mut BarOutcome,
+        pending: &mut VecDeque<ExecutionReport>,
+        hook: impl FnOnce(&mut S, &mut StrategyContext<'_>),
+    ) -> anyhow::Result<()> {
+        let open_orders: Vec<Order> = self.exchange.open_orders().cloned().collect();
+        let fx = self.fx.rates_at(ts);
+        let mut ctx = StrategyContext::new(
+            ts,
+            symbol,
+            self.exchange.portfolio(),
+            &open_orders,
+            &self.history,
+            &mut self.next_order_id,
+        )
+        .with_fx(&fx);
+        hook(&mut self.strategy, &mut ctx);
+        let (commands, logs, timers) = ctx.into_parts();
+
+        outcome.logs.extend(logs);
+        for at in timers {
+            let pos = self.timers.partition_point(|t| *t <= at);
+            self.timers.insert(pos, at);
+        }
+        self.execute(commands, ts, outcome, pending)
+    }
+
+    fn execute(
+        &mut self,
+        commands: Vec<Command>,
+        ts: Timestamp,
+        outcome: &mut BarOutcome,
+        pending: &mut VecDeque<ExecutionReport>,
+    ) -> anyhow::Result<()> {
+        let fx = self.fx.rates_at(ts);
+        let mut orders = Vec::new();
+        let mut intents = Vec::new();
+        for command in commands {
+            match command {
+                Command::Submit(order) => orders.push(order),
+                Command::Intent(intent) => intents.push(intent),
+                Command::Cancel(id) => match self.exchange.cancel(id) {
+                    Ok(report) => Self::record(report, outcome, pending),
+                    // Already filled or cancelled
+                    Err(BrokerError::UnknownOrder(_)) => {}
+                    Err(e) => return Err(e.into()),
+                },
+            }
+        }
+
+        if !intents.is_empty() {
+            let metrics = self.sizing_metrics(&intents);
+            let portfolio = self.exchange.portfolio();
+            orders.extend(self.sizing.orders(&intents, portfolio, &self.last_prices, &metrics, &fx, &mut self.next_order_id)?);
+        }
+
+        for order in orders {
+            outcome.orders.push(order.clone());
+
+            let qty = self.exchange.round_qty(&order.symbol, order.qty);
+            if qty <= 0.0 {
+                continue;
+            }
+
+            let checked = Order { qty, ..order.clone() };
+            if let Err(rejection) = self.risk.pre_trade(&checked, self.exchange.portfolio(), &fx, ts) {
+                Self::record(rejection, outcome, pending);
+                continue;
+            }
+
+            for report in self.exchange.submit(order)? {
+                Self::record(report, outcome, pending);
+            }
+        }
+        Ok(())
+    }
+
+    fn sizing_metrics(&self, intents: &[Intent]) -> HashMap<String, SymbolMetrics> {
+        intents
+            .iter()
+            .filter_map(|intent| {
+                let symbol = intent.symbol();
+                let price = *self.last_prices.get(symbol)?;
+                let bars = self.history.get(symbol).map_or(&[][..], Vec::as_slice);
+                let closes: Vec<f64> = bars.iter().map(|c| c.close).collect();
+                Some((symbol.to_owned(), SymbolMetrics {
+                    price,
+                    atr: indicators::atr(bars, SIZING_LOOKBACK),
+                    volatility: indicators::volatility(&closes, SIZING_LOOKBACK),
+                }))
+            })
+            .collect()
+    }
+
+    /// File a report in the outcome and queue it for the strategy
+    fn record(report: ExecutionReport, outcome: &mut BarOutcome, pending: &mut VecDeque<ExecutionReport>) {
+        if report.is_fill() {
+            outcome.fills.push(report.clone());
+        } else if report.is_rejected() {
+            outcome.rejections.push(report.clone());
+        }
+        outcome.reports.push(report.clone());
+        pending.push_back(report);
+    }
+}
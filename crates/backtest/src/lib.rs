@@ -1,80 +1,209 @@
+mod engine;
+
 use std::collections::HashMap;
+use std::ops::ControlFlow;
+
+pub use engine::{BarOutcome, TradingEngine};
+
 use strats::{ExecutionReport, Strategy};
-use dnn_core::portfolio::Portfolio;
+use strats::sizing::PositionSizer;
+use dnn_core::fx::FxHistory;
+use risk::RiskEngine;
+use dnn_core::instrument::InstrumentMaster;
+use dnn_core::market::{Candle, CandleRange};
+use dnn_core::ledger::{Ledger, LedgerEvent};
+use dnn_core::time::Timestamp;
+use dnn_core::{Order, OrderSide};
 
 #[derive(Debug)]
 pub struct BacktestResult {
+    /// Every order sent, filled or not, so fills can be traced to their
+    /// symbol and side
+    pub orders: Vec<Order>,
     pub trades: Vec<ExecutionReport>,
+    /// Orders stopped by the risk engine
+    pub rejected: Vec<ExecutionReport>,
     pub equity_curve: Vec<f64>,
     pub final_pnl: f64,
     pub return_pct: f64,
     pub max_drawdown: f64,
     pub sharpe_ratio: f64,
+    /// Every cash movement and fill of the run
+    pub ledger: Ledger,
+}
+
+impl BacktestResult {
+    /// Fraction of closing fills (those that reduce a long or short position)
+    /// that realized a profit; 0 if nothing was closed
+    pub fn win_rate(&self) -> f64 {
+        // Signed quantity and average entry price per symbol
+        let mut open: HashMap<&str, (f64, f64)> = HashMap::new();
+        let (mut closes, mut wins) = (0_u32, 0_u32);
+        for entry in self.ledger.entries() {
+            let LedgerEvent::Fill { symbol, side, qty, price, .. } = &entry.event else {
+                continue;
+            };
+            let signed = match side {
+                OrderSide::Buy => *qty,
+                OrderSide::Sell => -qty,
+            };
+            let (held, avg) = open.entry(symbol).or_insert((0.0, 0.0));
+            if *held * signed < 0.0 {
+                let closed = signed.abs().min(held.abs());
+                closes += 1;
+                if (price - *avg) * closed * held.signum() > 0.0 {
+                    wins += 1;
+                }
+                if signed.abs() > closed {
+                    // Flipped through flat: the remainder opens at this price
+                    *avg = *price;
+                }
+            } else {
+                *avg = (*avg * held.abs() + price * signed.abs()) / (held.abs() + signed.abs());
+            }
+            *held += signed;
+        }
+        if closes == 0 { 0.0 } else { f64::from(wins) / f64::from(closes) }
+    }
+}
+
+/// How far a run has got, reported once per timestamp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub bars_done: usize,
+    pub bars_total: usize,
+    /// Time of the bars just processed
+    pub ts: Timestamp,
+    /// Equity after those bars
+    pub equity: f64,
+}
+
+impl Progress {
+    /// Fraction of bars processed, 0 to 1
+    pub fn fraction(&self) -> f64 {
+        if self.bars_total == 0 { 1.0 } else { self.bars_done as f64 / self.bars_total as f64 }
+    }
 }
 
 pub struct Backtester<S: Strategy> {
-    strategy: S,
-    starting_cash: f64,
+    engine: TradingEngine<S>,
 }
 
 impl<S: Strategy> Backtester<S> {
     pub fn new(strategy: S, starting_cash: f64) -> Self {
-        Self { strategy, starting_cash }
+        Self { engine: TradingEngine::new(strategy, starting_cash) }
     }
 
-    pub fn run(&mut self, data: Portfolio) -> BacktestResult {
-        todo!();
-        /*
-        let mut trades = Vec::new();
-        let mut equity_curve = Vec::new();
+    /// Sizer for intents emitted by the strategy (default: 10% of equity per entry)
+    pub fn with_sizer(mut self, sizer: Box<dyn PositionSizer>) -> Self {
+        self.engine = self.engine.with_sizer(sizer);
+        self
+    }
 
-        for bar in data {
-            let orders = self.strategy.on_market_event(&bar);
+    /// Use instrument reference data to round fill quantities and prices
+    pub fn with_instruments(mut self, instruments: InstrumentMaster) -> Self {
+        self.engine = self.engine.with_instruments(instruments);
+        self
+    }
 
-            for order in orders {
-                let report = ExecutionReport {
-                    order_id: order.id,
-                    filled_qty: order.qty,
-                    fill_price: bar.close,
-                    ts: bar.timestamp,
-                };
+    /// Report equity in `currency`, converting with the given FX history.
+    /// Starting cash is held in this currency.
+    pub fn with_base_currency(mut self, currency: &str, fx: FxHistory) -> Self {
+        self.engine = self.engine.with_base_currency(currency, fx);
+        self
+    }
 
-                self.strategy.on_fill(&report);
-                trades.push(report.clone());
+    /// Check every order against pre-trade limits before it is filled
+    pub fn with_risk(mut self, risk: RiskEngine) -> Self {
+        self.engine = self.engine.with_risk(risk);
+        self
+    }
 
-                portfolio.apply_fill(data.symbol, order.side, report.filled_qty, report.fill_price);
-            }
+    /// Fill at most this fraction of each bar's volume, leaving the rest working
+    pub fn with_max_participation(mut self, fraction: f64) -> Self {
+        self.engine = self.engine.with_max_participation(fraction);
+        self
+    }
+
+    pub fn run(&mut self, data: &CandleRange) -> anyhow::Result<BacktestResult> {
+        self.run_many(std::slice::from_ref(data))
+    }
+
+    /// Backtest over several symbols at once. Bars are replayed in time order
+    /// (ties in the order of `data`) and equity is sampled once per timestamp.
+    pub fn run_many(&mut self, data: &[CandleRange]) -> anyhow::Result<BacktestResult> {
+        self.run_with_progress(data, |_| ControlFlow::Continue(()))
+    }
+
+    /// Like [`run_many`](Self::run_many), calling `on_progress` after each
+    /// equity sample. Returning `Break` stops the run with an error.
+    pub fn run_with_progress(
+        &mut self,
+        data: &[CandleRange],
+        mut on_progress: impl FnMut(&Progress) -> ControlFlow<()>,
+    ) -> anyhow::Result<BacktestResult> {
+        self.engine.reset();
+        let starting_cash = self.engine.starting_cash();
+        let mut orders = Vec::new();
+        let mut trades = Vec::new();
+        let mut rejected = Vec::new();
+        let mut equity_curve = Vec::new();
 
-            // compute current portfolio value
-            let mut prices = HashMap::new();
-            prices.insert(bar.symbol.clone(), bar.close);
-            let value = portfolio.total_value(&prices);
+        let mut bars: Vec<(&str, &Candle)> = data
+            .iter()
+            .flat_map(|range| range.data.iter().map(|bar| (range.symbol.as_str(), bar)))
+            .collect();
+        bars.sort_by_key(|(_, bar)| bar.timestamp);
+
+        for (i, (symbol, bar)) in bars.iter().enumerate() {
+            let outcome = self.engine.on_bar(symbol, bar)?;
+            orders.extend(outcome.orders);
+            trades.extend(outcome.fills);
+            rejected.extend(outcome.rejections);
+            if bars.get(i + 1).is_none_or(|(_, next)| next.timestamp != bar.timestamp) {
+                equity_curve.push(outcome.equity);
+                let progress = Progress { bars_done: i + 1, bars_total: bars.len(), ts: bar.timestamp, equity: outcome.equity };
+                if on_progress(&progress).is_break() {
+                    anyhow::bail!("backtest cancelled after {} of {} bars", i + 1, bars.len());
+                }
+            }
+        }
 
-            equity_curve.push(value);
+        let end = self.engine.finish()?;
+        orders.extend(end.orders);
+        trades.extend(end.fills);
+        rejected.extend(end.rejections);
+        if let Some(last) = equity_curve.last_mut() {
+            *last = end.equity;
         }
 
-        let final_value = *equity_curve.last().unwrap_or(&self.starting_cash);
-        let final_pnl = final_value - self.starting_cash;
-        let return_pct = final_pnl / self.starting_cash;
+        let final_value = *equity_curve.last().unwrap_or(&starting_cash);
+        let final_pnl = final_value - starting_cash;
+        let return_pct = final_pnl / starting_cash;
 
         let max_drawdown = calc_max_drawdown(&equity_curve);
         let sharpe_ratio = calc_sharpe_ratio(&equity_curve);
 
-        BacktestResult {
+        Ok(BacktestResult {
+            orders,
             trades,
+            rejected,
             equity_curve,
             final_pnl,
             return_pct,
             max_drawdown,
             sharpe_ratio,
-        }
-        */
+            ledger: self.engine.ledger().clone(),
+        })
     }
 }
 
 /// Calculate max drawdown as % drop from peak to trough
 fn calc_max_drawdown(equity: &[f64]) -> f64 {
-    let mut peak = equity[0];
+    let Some(&first) = equity.first() else {
+        return 0.0;
+    };
+    let mut peak = first;
     let mut max_dd = 0.0;
 
     for &v in equity {
@@ -0,0 +1,207 @@
+use std::ops::ControlFlow;
+use chrono::{Duration, TimeZone, Utc};
+use backtest::{Backtester, TradingEngine};
+use dnn_core::market::{Candle, CandleRange, MarketEvent, Quote};
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionReport, ExecutionStatus, OrderSide};
+use strats::sma_cross::SmaCross;
+use strats::{Strategy, StrategyContext};
+
+fn ts(day: i64) -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 5, 1, 20, 0, 0).unwrap() + Duration::days(day)
+}
+
+fn bar(day: i64, close: f64) -> Candle {
+    Candle::new(ts(day), close, close + 1.0, close - 1.0, close, 1_000.0).unwrap()
+}
+
+/// Records every hook and exercises the context API
+#[derive(Default)]
+struct Recorder {
+    calls: Vec<String>,
+    limit_id: Option<u64>,
+}
+
+impl Strategy for Recorder {
+    fn on_start(&mut self, ctx: &mut StrategyContext) {
+        self.calls.push(format!("start cash={}", ctx.cash()));
+        ctx.schedule(ts(2));
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        self.calls.push(format!("bar {}", bar.close));
+        if self.limit_id.is_none() {
+            // Far below the market, stays working
+            self.limit_id = Some(ctx.submit("AAPL", OrderSide::Buy, 5.0, Some(50.0)));
+            ctx.buy(10.0);
+        }
+    }
+
+    fn on_timer(&mut self, ctx: &mut StrategyContext, at: Timestamp) {
+        self.calls.push(format!("timer {}", at == ts(2)));
+        assert_eq!(ctx.open_orders().len(), 1);
+        if let Some(id) = self.limit_id {
+            ctx.cancel(id);
+        }
+        assert_eq!(ctx.sma("AAPL", 2), Some(100.5));
+    }
+
+    fn on_order_update(&mut self, ctx: &mut StrategyContext, report: &ExecutionReport) {
+        let status = match report.status {
+            ExecutionStatus::New => "new",
+            ExecutionStatus::PartiallyFilled => "partial",
+            ExecutionStatus::Filled => "filled",
+            ExecutionStatus::Cancelled => "cancelled",
+            ExecutionStatus::Rejected { .. } => "rejected",
+        };
+        self.calls.push(format!("order {} {status}", report.order_id));
+        if report.is_fill() {
+            ctx.log(format!("filled {}", report.filled_qty));
+        }
+    }
+
+    fn on_end(&mut self, ctx: &mut StrategyContext) {
+        self.calls.push(format!("end qty={}", ctx.position_qty("AAPL")));
+        ctx.flatten("AAPL");
+    }
+}
+
+#[test]
+fn test_lifecycle_hooks_and_context() {
+    let mut engine = TradingEngine::new(Recorder::default(), 10_000.0);
+
+    let first = engine.on_bar("AAPL", &bar(0, 100.0)).unwrap();
+    assert_eq!(first.fills.len(), 1);
+    assert_eq!(first.logs[0].message, "filled 10");
+
+    engine.on_bar("AAPL", &bar(1, 101.0)).unwrap();
+    let third = engine.on_bar("AAPL", &bar(2, 102.0)).unwrap();
+    assert_eq!(third.reports[0].status, ExecutionStatus::Cancelled);
+
+    let end = engine.finish().unwrap();
+    assert_eq!(end.fills.len(), 1);
+    assert!(engine.ledger().portfolio().positions["AAPL"].qty.abs() < 1e-9);
+    assert!((end.equity - 10_020.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_hook_order() {
+    let mut data = CandleRange::new("AAPL".into());
+    for (day, close) in [100.0, 101.0, 102.0].into_iter().enumerate() {
+        data.add(bar(day as i64, close));
+    }
+
+    let mut engine = TradingEngine::new(Recorder::default(), 10_000.0);
+    for candle in &data.data {
+        engine.on_bar("AAPL", candle).unwrap();
+    }
+    engine.finish().unwrap();
+
+    // Backtester drives the same hooks
+    let result = Backtester::new(Recorder::default(), 10_000.0).run(&data).unwrap();
+    assert_eq!(result.trades.len(), 2);
+    assert!((result.final_pnl - 20.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_progress_and_cancel() {
+    let mut data = CandleRange::new("AAPL".into());
+    for (day, close) in [100.0, 101.0, 102.0, 103.0].into_iter().enumerate() {
+        data.add(bar(day as i64, close));
+    }
+
+    let mut seen = Vec::new();
+    let result = Backtester::new(Recorder::default(), 10_000.0)
+        .run_with_progress(std::slice::from_ref(&data), |p| {
+            seen.push((p.bars_done, p.fraction(), p.ts));
+            ControlFlow::Continue(())
+        })
+        .unwrap();
+    assert_eq!(seen.len(), result.equity_curve.len());
+    assert_eq!(seen[3], (4, 1.0, ts(3)));
+
+    // Breaking stops the run at that bar
+    let mut calls = 0;
+    let err = Backtester::new(Recorder::default(), 10_000.0)
+        .run_with_progress(std::slice::from_ref(&data), |p| {
+            calls += 1;
+            if p.bars_done == 2 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        })
+        .unwrap_err();
+    assert_eq!(calls, 2);
+    assert_eq!(err.to_string(), "backtest cancelled after 2 of 4 bars");
+}
+
+/// Logs how much history it gets
+struct Lookback(usize);
+
+impl Strategy for Lookback {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        ctx.log(ctx.bars("AAPL").len().to_string());
+    }
+
+    fn lookback(&self) -> usize {
+        self.0
+    }
+}
+
+#[test]
+fn test_history_follows_strategy_lookback() {
+    let mut engine = TradingEngine::new(Lookback(40), 10_000.0);
+    let seen: Vec<usize> = (0..100)
+        .map(|day| engine.on_bar("AAPL", &bar(day, 100.0)).unwrap().logs[0].message.parse().unwrap())
+        .collect();
+    // Grows to the lookback, then stays there
+    assert_eq!(seen[..40], (1..=40).collect::<Vec<_>>()[..]);
+    assert!(seen[40..].iter().all(|n| *n == 40));
+
+    // Never less than the sizers need
+    let mut engine = TradingEngine::new(Lookback(2), 10_000.0);
+    let last = (0..30).map(|day| engine.on_bar("AAPL", &bar(day, 100.0)).unwrap()).last().unwrap();
+    assert_eq!(last.logs[0].message, "15");
+}
+
+#[test]
+fn test_long_averages_get_their_history() {
+    // Longer than the default history
+    let mut data = CandleRange::new("AAPL".into());
+    for day in 0..300 {
+        data.add(bar(day, 100.0 + day as f64));
+    }
+    let result = Backtester::new(SmaCross::new(10, 280), 10_000.0).run(&data).unwrap();
+    assert_eq!(result.trades.len(), 1);
+}
+
+/// Rests a limit buy on the first bar and logs the quotes it sees
+struct QuoteWatcher;
+
+impl Strategy for QuoteWatcher {
+    fn on_bar(&mut self, _ctx: &mut StrategyContext, _bar: &Candle) {}
+
+    fn on_event(&mut self, ctx: &mut StrategyContext, event: &MarketEvent) {
+        match event {
+            MarketEvent::Bar { .. } if ctx.open_orders().is_empty() && ctx.position_qty("AAPL") == 0.0 => {
+                ctx.submit("AAPL", OrderSide::Buy, 5.0, Some(95.0));
+            }
+            MarketEvent::Quote(quote) => ctx.log(format!("quote {}", quote.mid())),
+            _ => {}
+        }
+    }
+}
+
+#[test]
+fn test_quotes_do_not_fill_working_orders() {
+    let mut engine = TradingEngine::new(QuoteWatcher, 10_000.0);
+    engine.on_bar("AAPL", &bar(0, 100.0)).unwrap();
+
+    // The quote trades through the limit, but the exchange only matches bars and trades
+    let quote = Quote { symbol: "AAPL".into(), ts: ts(0) + Duration::hours(1), bid: 90.0, bid_size: 100.0, ask: 90.5, ask_size: 100.0 };
+    let outcome = engine.on_event(&MarketEvent::Quote(quote)).unwrap();
+    assert!(outcome.fills.is_empty());
+    assert_eq!(outcome.logs[0].message, "quote 90.25");
+
+    let outcome = engine.on_bar("AAPL", &bar(1, 94.0)).unwrap();
+    assert_eq!(outcome.fills.len(), 1);
+    // Opens below the limit, so fills at the open
+    assert_eq!(outcome.fills[0].fill_price, 94.0);
+}
@@ -0,0 +1,142 @@
+//! Golden-output backtests of the built-in strategies on fixed synthetic data.
+//! A change in any of these numbers means a strategy's behaviour changed.
+
+use chrono::{Duration, TimeZone, Utc};
+use backtest::{BacktestResult, Backtester};
+use dnn_core::fx::FxRates;
+use dnn_core::market::{Candle, CandleRange};
+use strats::bollinger::BollingerReversion;
+use strats::buy_and_hold::BuyAndHold;
+use strats::donchian::DonchianBreakout;
+use strats::dual_momentum::DualMomentum;
+use strats::ma_cross::{MaCross, MovingAverage};
+use strats::pairs::PairsTrading;
+use strats::rsi::RsiReversion;
+use strats::rules::RuleStrategy;
+use strats::signal::Ensemble;
+use strats::Strategy;
+
+const CASH: f64 = 100_000.0;
+const BARS: usize = 300;
+
+/// Daily bars whose closes follow `close(t)`
+fn series(symbol: &str, close: impl Fn(f64) -> f64) -> CandleRange {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 21, 0, 0).unwrap();
+    let mut range = CandleRange::new(symbol.into());
+    let mut prev = close(0.0);
+    for i in 0..BARS {
+        let c = close(i as f64);
+        let bar = Candle::new(start + Duration::days(i as i64), prev, prev.max(c) + 0.5, prev.min(c) - 0.5, c, 1e6).unwrap();
+        range.add(bar);
+        prev = c;
+    }
+    range
+}
+
+/// Repeatable pseudo-random value in [-1, 1)
+fn noise(t: f64) -> f64 {
+    ((t * 12.9898).sin() * 43_758.545_3).fract() * 2.0 - 1.0
+}
+
+fn wave() -> CandleRange {
+    series("WAVE", |t| 100.0 + 8.0 * (t / 6.0).sin() + 0.04 * t + 2.0 * noise(t))
+}
+
+fn trend() -> CandleRange {
+    series("TRND", |t| 100.0 + 0.15 * t + 10.0 * (t / 12.0).sin() + 1.5 * noise(t))
+}
+
+fn backtest(strategy: impl Strategy, data: &[CandleRange]) -> BacktestResult {
+    Backtester::new(strategy, CASH).run_many(data).unwrap()
+}
+
+fn check(name: &str, result: &BacktestResult, trades: usize, final_pnl: f64, realized_pnl: f64) {
+    assert_eq!(result.trades.len(), trades, "{name} trade count");
+    // Every fill traces back to the order it filled
+    assert!(result.trades.iter().all(|t| result.orders.iter().any(|o| o.id == t.order_id)), "{name} untraced fill");
+    assert!((result.final_pnl - final_pnl).abs() < 1e-4, "{name} pnl {} != {final_pnl}", result.final_pnl);
+    let realized = result.ledger.portfolio().realized_pnl(&FxRates::new()).unwrap();
+    assert!((realized - realized_pnl).abs() < 1e-4, "{name} realized pnl {realized} != {realized_pnl}");
+}
+
+#[test]
+fn test_buy_and_hold() {
+    let result = backtest(BuyAndHold::default(), &[trend()]);
+    check("buy_and_hold", &result, 1, 46036.3172, 0.0);
+}
+
+#[test]
+fn test_ma_cross() {
+    let sma = backtest(MaCross::new(5, 20, MovingAverage::Sma), &[wave()]);
+    check("sma", &sma, 14, 11552.0041, 11552.0041);
+    let ema = backtest(MaCross::new(5, 20, MovingAverage::Ema).with_short(), &[wave()]);
+    check("ema", &ema, 16, 814.5733, 814.5733);
+}
+
+#[test]
+fn test_bollinger_reversion() {
+    let result = backtest(BollingerReversion::new(30, 2.0).with_short(), &[wave()]);
+    check("bollinger", &result, 24, 216523.5520, 216523.5520);
+}
+
+#[test]
+fn test_rsi_reversion() {
+    let result = backtest(RsiReversion::new(14, 30.0, 70.0), &[wave()]);
+    check("rsi", &result, 4, 17446.7290, 17446.7290);
+    assert_eq!(result.win_rate(), 1.0);
+}
+
+#[test]
+fn test_donchian_breakout() {
+    let result = backtest(DonchianBreakout::new(20, 10), &[trend()]);
+    check("donchian", &result, 7, 55296.1219, 49383.5718);
+}
+
+#[test]
+fn test_dual_momentum() {
+    let data = [
+        series("UP", |t| if t < 150.0 { 100.0 + 0.3 * t } else { 145.0 - 0.3 * (t - 150.0) }),
+        series("CYC", |t| 100.0 + 15.0 * (t / 40.0).sin() + noise(t)),
+        series("BOND", |t| 100.0 + 0.01 * t),
+    ];
+    let result = backtest(DualMomentum::new(vec!["UP".into(), "CYC".into()], 20).with_safe("BOND"), &data);
+    check("dual_momentum", &result, 31, 74366.1941, 33217.0404);
+}
+
+#[test]
+fn test_pairs_trading() {
+    let b = |t: f64| 50.0 + 5.0 * (t / 25.0).sin() + 0.02 * t;
+    let data = [
+        series("AAA", move |t| 2.0 * b(t) + 2.0 * noise(t)),
+        series("BBB", b),
+    ];
+    let result = backtest(PairsTrading::new("AAA", "BBB", 40).with_thresholds(1.5, 0.25), &data);
+    check("pairs", &result, 58, 36767.1344, 36749.9275);
+    // Both legs close on each exit; short covers count too
+    assert_eq!(result.win_rate(), 0.8125);
+}
+
+#[test]
+fn test_rule_strategy() {
+    let rules = RuleStrategy::from_yaml(
+        "entry: close crosses above SMA(10) AND close > SMA(100)\nexit: RSI(14) > 70\nstop_loss: { percent: 5 }\nsizing: { weight: 1.0 }\n",
+    )
+    .unwrap();
+    let result = backtest(rules, &[wave()]);
+    check("rules", &result, 6, -18894.7407, -18894.7407);
+}
+
+#[test]
+fn test_ensemble() {
+    // Trend and momentum vote on each symbol; equal weight across the longs
+    let ensemble = Ensemble::from_yaml(
+        "symbols: [UP, CYC]\nalpha:\n  type: vote\n  quorum: 0.6\n  members:\n    - { type: ma_cross, fast: 10, slow: 40 }\n    - { type: momentum, lookback: 20 }\n    - { type: bollinger, period: 20, k: 2.0 }\nconstruction: { type: equal_weight }\n",
+    )
+    .unwrap();
+    let data = [
+        series("UP", |t| if t < 150.0 { 100.0 + 0.3 * t } else { 145.0 - 0.3 * (t - 150.0) }),
+        series("CYC", |t| 100.0 + 15.0 * (t / 40.0).sin() + noise(t)),
+    ];
+    let result = backtest(ensemble, &data);
+    check("ensemble", &result, 14, 62670.5437, 24371.2003);
+}
@@ -0,0 +1,48 @@
+pub mod sim;
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use thiserror::Error;
+use tokio::sync::broadcast;
+use dnn_core::error::LedgerError;
+use dnn_core::{ExecutionReport, Order, Position};
+
+/// Execution reports for every order of a broker, in the order they happened
+pub type ExecutionStream = broadcast::Receiver<ExecutionReport>;
+
+#[derive(Error, Debug)]
+pub enum BrokerError {
+    #[error("Unknown or closed order {0}")]
+    UnknownOrder(u64),
+    #[error("Order {0} already exists")]
+    DuplicateOrder(u64),
+    #[error(transparent)]
+    Ledger(#[from] LedgerError),
+    #[error("Broker unavailable: {0}")]
+    Unavailable(String),
+}
+
+/// Where orders go. Separate from `Provider`, which only supplies market data,
+/// so strategies run unchanged against the simulated exchange or a real venue.
+#[async_trait]
+pub trait Broker: Send + Sync {
+    /// Send a new order. Returns the acknowledgement (`New` or `Rejected`);
+    /// fills are delivered through [`Broker::subscribe`].
+    async fn submit(&self, order: Order) -> Result<ExecutionReport, BrokerError>;
+
+    async fn cancel(&self, order_id: u64) -> Result<ExecutionReport, BrokerError>;
+
+    /// Change the remaining quantity and/or limit price of a working order
+    async fn replace(&self, order_id: u64, qty: f64, price: Option<f64>) -> Result<ExecutionReport, BrokerError>;
+
+    /// Working orders, with `qty` set to the unfilled quantity
+    async fn open_orders(&self) -> Result<Vec<Order>, BrokerError>;
+
+    async fn positions(&self) -> Result<Vec<Position>, BrokerError>;
+
+    /// Cash per currency
+    async fn balances(&self) -> Result<HashMap<String, f64>, BrokerError>;
+
+    /// Reports produced after this call
+    fn subscribe(&self) -> ExecutionStream;
+}
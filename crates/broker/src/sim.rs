@@ -0,0 +1,348 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::sync::broadcast;
+use dnn_core::instrument::InstrumentMaster;
+use dnn_core::ledger::{Ledger, LedgerEvent};
+use dnn_core::market::Candle;
+use dnn_core::portfolio::{Portfolio, DEFAULT_BASE_CURRENCY};
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionReport, Order, OrderSide, Position};
+use crate::{Broker, BrokerError, ExecutionStream};
+
+/// Where in a bar an order is matched
+#[derive(Debug, Clone, Copy)]
+enum MatchPoint {
+    /// Right after submission, against the close of the last bar
+    Immediate,
+    /// Against the open, low and high of a new bar
+    Bar,
+}
+
+/// In-process exchange that matches orders against incoming bars.
+///
+/// Market orders fill at the last close when submitted, or at the next open
+/// while working. Limit orders fill at the open if it is through the limit,
+/// otherwise at the limit once the bar's range reaches it. Working orders are
+/// matched in submission order. Fills are booked to its own [`Ledger`].
+#[derive(Debug, Clone)]
+pub struct SimulatedExchange {
+    instruments: InstrumentMaster,
+    max_participation: Option<f64>,
+    ledger: Ledger,
+    /// Working orders in arrival order, `qty` is the unfilled quantity
+    working: Vec<Order>,
+    last_bars: HashMap<String, Candle>,
+    /// Volume already taken from the last bar of each symbol
+    used_volume: HashMap<String, f64>,
+    clock: Option<Timestamp>,
+}
+
+impl Default for SimulatedExchange {
+    fn default() -> Self {
+        Self::new(DEFAULT_BASE_CURRENCY)
+    }
+}
+
+impl SimulatedExchange {
+    pub fn new(base_currency: &str) -> Self {
+        Self {
+            instruments: InstrumentMaster::new(),
+            max_participation: None,
+            ledger: Ledger::new(base_currency),
+            working: Vec::new(),
+            last_bars: HashMap::new(),
+            used_volume: HashMap::new(),
+            clock: None,
+        }
+    }
+
+    /// Round quantities to lots and fill prices to ticks
+    pub fn with_instruments(mut self, instruments: InstrumentMaster) -> Self {
+        self.instruments = instruments;
+        self
+    }
+
+    /// Fill at most this fraction of each bar's volume, leaving the rest working
+    pub fn with_max_participation(mut self, fraction: f64) -> Self {
+        self.max_participation = Some(fraction);
+        self
+    }
+
+    /// Hold cash and book fills in a ledger with this base currency
+    pub fn with_base_currency(mut self, currency: &str) -> Self {
+        self.ledger = Ledger::new(currency);
+        self
+    }
+
+    /// Continue from an existing ledger
+    pub fn with_ledger(mut self, ledger: Ledger) -> Self {
+        self.clock = ledger.entries().last().map(|e| e.ts);
+        self.ledger = ledger;
+        self
+    }
+
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    pub fn portfolio(&self) -> &Portfolio {
+        self.ledger.portfolio()
+    }
+
+    pub fn instruments(&self) -> &InstrumentMaster {
+        &self.instruments
+    }
+
+    /// Working orders in time priority, with `qty` set to the unfilled quantity
+    pub fn open_orders(&self) -> impl Iterator<Item = &Order> {
+        self.working.iter()
+    }
+
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.last_bars.get(symbol).map(|b| b.close)
+    }
+
+    /// Time of the latest bar seen
+    pub fn clock(&self) -> Option<Timestamp> {
+        self.clock
+    }
+
+    /// Drop all orders, market state and ledger entries, keeping configuration
+    pub fn reset(&mut self) {
+        self.ledger = Ledger::new(self.ledger.base_currency());
+        self.working.clear();
+        self.last_bars.clear();
+        self.used_volume.clear();
+        self.clock = None;
+    }
+
+    /// Quantity rounded down to the symbol's lot size
+    pub fn round_qty(&self, symbol: &str, qty: f64) -> f64 {
+        self.instruments.get(symbol).map_or(qty, |i| i.round_qty(qty))
+    }
+
+    pub fn deposit(&mut self, ts: Timestamp, currency: &str, amount: f64) -> Result<(), BrokerError> {
+        self.ledger.record(ts, LedgerEvent::CashMovement { currency: currency.to_owned(), amount })?;
+        Ok(())
+    }
+
+    /// Advance the market and match working orders of `symbol` against the bar
+    pub fn on_bar(&mut self, symbol: &str, bar: &Candle) -> Result<Vec<ExecutionReport>, BrokerError> {
+        self.clock = Some(bar.timestamp);
+        self.last_bars.insert(symbol.to_owned(), bar.clone());
+        self.used_volume.insert(symbol.to_owned(), 0.0);
+
+        let ids: Vec<u64> = self.working
+            .iter()
+            .filter(|o| o.symbol == symbol)
+            .map(|o| o.id)
+            .collect();
+
+        let mut reports = Vec::new();
+        for id in ids {
+            reports.extend(self.try_match(id, MatchPoint::Bar)?);
+        }
+        Ok(reports)
+    }
+
+    /// Accept an order and match it against the last close.
+    /// Returns the acknowledgement followed by any immediate fill.
+    pub fn submit(&mut self, order: Order) -> Result<Vec<ExecutionReport>, BrokerError> {
+        let ts = self.now();
+        if self.working.iter().any(|o| o.id == order.id) {
+            return Err(BrokerError::DuplicateOrder(order.id));
+        }
+
+        let qty = self.round_qty(&order.symbol, order.qty);
+        if !qty.is_finite() || qty <= 0.0 {
+            return Ok(vec![ExecutionReport::rejected(order.id, "Quantity rounds to zero", ts)]);
+        }
+        if order.price.is_some_and(|p| !p.is_finite() || p <= 0.0) {
+            return Ok(vec![ExecutionReport::rejected(order.id, "Invalid limit price", ts)]);
+        }
+
+        let id = order.id;
+        self.working.push(Order { qty, ..order });
+
+        let mut reports = vec![ExecutionReport::accepted(id, qty, ts)];
+        reports.extend(self.try_match(id, MatchPoint::Immediate)?);
+        Ok(reports)
+    }
+
+    pub fn cancel(&mut self, order_id: u64) -> Result<ExecutionReport, BrokerError> {
+        self.take(order_id)?;
+        Ok(ExecutionReport::cancelled(order_id, self.now()))
+    }
+
+    /// Change the unfilled quantity and limit price of a working order.
+    /// The order keeps its id but loses time priority; a rejected or failed replace leaves it untouched.
+    pub fn replace(&mut self, order_id: u64, qty: f64, price: Option<f64>) -> Result<Vec<ExecutionReport>, BrokerError> {
+        let index = self.working
+            .iter()
+            .position(|o| o.id == order_id)
+            .ok_or(BrokerError::UnknownOrder(order_id))?;
+        let order = self.working.remove(index);
+        let result = self.submit(Order { qty, price, ..order.clone() });
+        let replaced = result.as_ref().is_ok_and(|reports| !reports.first().is_some_and(ExecutionReport::is_rejected));
+        if !replaced {
+            // A failed submit may have queued the new order before matching it
+            self.working.retain(|o| o.id != order_id);
+            self.working.insert(index, order);
+        }
+        result
+    }
+
+    fn take(&mut self, order_id: u64) -> Result<Order, BrokerError> {
+        let index = self.working
+            .iter()
+            .position(|o| o.id == order_id)
+            .ok_or(BrokerError::UnknownOrder(order_id))?;
+        Ok(self.working.remove(index))
+    }
+
+    fn now(&self) -> Timestamp {
+        self.clock.unwrap_or_else(Utc::now)
+    }
+
+    fn match_price(order: &Order, bar: &Candle, point: MatchPoint) -> Option<f64> {
+        match (point, order.price, order.side) {
+            (MatchPoint::Immediate, None, _) => Some(bar.close),
+            (MatchPoint::Immediate, Some(limit), OrderSide::Buy) => (bar.close <= limit).then_some(bar.close),
+            (MatchPoint::Immediate, Some(limit), OrderSide::Sell) => (bar.close >= limit).then_some(bar.close),
+            (MatchPoint::Bar, None, _) => Some(bar.open),
+            (MatchPoint::Bar, Some(limit), OrderSide::Buy) if bar.open <= limit => Some(bar.open),
+            (MatchPoint::Bar, Some(limit), OrderSide::Buy) => (bar.low <= limit).then_some(limit),
+            (MatchPoint::Bar, Some(limit), OrderSide::Sell) if bar.open >= limit => Some(bar.open),
+            (MatchPoint::Bar, Some(limit), OrderSide::Sell) => (bar.high >= limit).then_some(limit),
+        }
+    }
+
+    fn try_match(&mut self, id: u64, point: MatchPoint) -> Result<Option<ExecutionReport>, BrokerError> {
+        let Some(order) = self.working.iter().find(|o| o.id == id) else {
+            return Ok(None);
+        };
+        let Some(bar) = self.last_bars.get(&order.symbol) else {
+            return Ok(None);
+        };
+        let Some(price) = Self::match_price(order, bar, point) else {
+            return Ok(None);
+        };
+
+        let available = match self.max_participation {
+            Some(fraction) => {
+                let used = self.used_volume.get(&order.symbol).copied().unwrap_or_default();
+                (bar.volume * fraction - used).max(0.0)
+            }
+            None => f64::INFINITY,
+        };
+        let qty = self.round_qty(&order.symbol, order.qty.min(available));
+        if qty <= 0.0 {
+            return Ok(None);
+        }
+
+        let instrument = self.instruments.get(&order.symbol);
+        let price = instrument.map_or(price, |i| i.round_price(price));
+        let currency = instrument.map_or(self.ledger.base_currency(), |i| i.currency.as_str()).to_owned();
+        let multiplier = instrument.map_or(1.0, |i| i.multiplier);
+        let ts = bar.timestamp;
+        let (symbol, side, leaves) = (order.symbol.clone(), order.side, order.qty - qty);
+
+        self.ledger.record(ts, LedgerEvent::Fill { symbol: symbol.clone(), currency, side, qty, price, multiplier })?;
+        *self.used_volume.entry(symbol).or_default() += qty;
+
+        let report = if leaves > f64::EPSILON {
+            if let Some(order) = self.working.iter_mut().find(|o| o.id == id) {
+                order.qty = leaves;
+            }
+            ExecutionReport::partial(id, qty, price, leaves, ts)
+        } else {
+            self.working.retain(|o| o.id != id);
+            ExecutionReport::filled(id, qty, price, ts)
+        };
+        Ok(Some(report))
+    }
+}
+
+/// [`Broker`] over a [`SimulatedExchange`], publishing every report to subscribers.
+/// Market data is pushed in with [`SimulatedBroker::on_bar`].
+pub struct SimulatedBroker {
+    exchange: Mutex<SimulatedExchange>,
+    reports: broadcast::Sender<ExecutionReport>,
+}
+
+impl SimulatedBroker {
+    pub fn new(exchange: SimulatedExchange) -> Self {
+        let (reports, _) = broadcast::channel(1024);
+        Self {
+            exchange: Mutex::new(exchange),
+            reports,
+        }
+    }
+
+    fn exchange(&self) -> MutexGuard<'_, SimulatedExchange> {
+        // The exchange has no invariants a panicking caller could break halfway
+        self.exchange.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn publish(&self, reports: &[ExecutionReport]) {
+        for report in reports {
+            // No subscribers is fine
+            let _ = self.reports.send(report.clone());
+        }
+    }
+
+    pub fn deposit(&self, ts: Timestamp, currency: &str, amount: f64) -> Result<(), BrokerError> {
+        self.exchange().deposit(ts, currency, amount)
+    }
+
+    pub fn on_bar(&self, symbol: &str, bar: &Candle) -> Result<Vec<ExecutionReport>, BrokerError> {
+        let reports = self.exchange().on_bar(symbol, bar)?;
+        self.publish(&reports);
+        Ok(reports)
+    }
+
+    /// Copy of the exchange ledger
+    pub fn ledger(&self) -> Ledger {
+        self.exchange().ledger().clone()
+    }
+}
+
+#[async_trait]
+impl Broker for SimulatedBroker {
+    async fn submit(&self, order: Order) -> Result<ExecutionReport, BrokerError> {
+        let id = order.id;
+        let reports = self.exchange().submit(order)?;
+        self.publish(&reports);
+        reports.into_iter().next().ok_or(BrokerError::UnknownOrder(id))
+    }
+
+    async fn cancel(&self, order_id: u64) -> Result<ExecutionReport, BrokerError> {
+        let report = self.exchange().cancel(order_id)?;
+        self.publish(std::slice::from_ref(&report));
+        Ok(report)
+    }
+
+    async fn replace(&self, order_id: u64, qty: f64, price: Option<f64>) -> Result<ExecutionReport, BrokerError> {
+        let reports = self.exchange().replace(order_id, qty, price)?;
+        self.publish(&reports);
+        reports.into_iter().next().ok_or(BrokerError::UnknownOrder(order_id))
+    }
+
+    async fn open_orders(&self) -> Result<Vec<Order>, BrokerError> {
+        Ok(self.exchange().open_orders().cloned().collect())
+    }
+
+    async fn positions(&self) -> Result<Vec<Position>, BrokerError> {
+        Ok(self.exchange().portfolio().positions.values().cloned().collect())
+    }
+
+    async fn balances(&self) -> Result<HashMap<String, f64>, BrokerError> {
+        Ok(self.exchange().portfolio().cash.clone())
+    }
+
+    fn subscribe(&self) -> ExecutionStream {
+        self.reports.subscribe()
+    }
+}
@@ -0,0 +1,127 @@
+use chrono::{Duration, TimeZone, Utc};
+use broker::sim::{SimulatedBroker, SimulatedExchange};
+use broker::{Broker, BrokerError};
+use dnn_core::market::Candle;
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionStatus, Order, OrderSide};
+
+fn ts(minutes: i64) -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap() + Duration::minutes(minutes)
+}
+
+fn bar(minutes: i64, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Candle {
+    Candle::new(ts(minutes), open, high, low, close, volume).unwrap()
+}
+
+fn order(id: u64, side: OrderSide, qty: f64, price: Option<f64>) -> Order {
+    Order { id, symbol: "AAPL".into(), qty, price, side }
+}
+
+fn exchange() -> SimulatedExchange {
+    let mut ex = SimulatedExchange::new("USD");
+    ex.deposit(ts(0), "USD", 10_000.0).unwrap();
+    ex
+}
+
+#[test]
+fn test_market_order_fills_at_last_close() {
+    let mut ex = exchange();
+    ex.on_bar("AAPL", &bar(0, 99.0, 101.0, 98.0, 100.0, 1_000.0)).unwrap();
+
+    let reports = ex.submit(order(1, OrderSide::Buy, 10.0, None)).unwrap();
+    assert_eq!(reports[0].status, ExecutionStatus::New);
+    assert_eq!(reports[1].status, ExecutionStatus::Filled);
+    assert_eq!(reports[1].fill_price, 100.0);
+    assert_eq!(ex.portfolio().positions["AAPL"].qty, 10.0);
+    assert_eq!(ex.portfolio().cash_in("USD"), 9_000.0);
+    assert_eq!(ex.open_orders().count(), 0);
+}
+
+#[test]
+fn test_limit_order_rests_until_touched() {
+    let mut ex = exchange();
+    ex.on_bar("AAPL", &bar(0, 99.0, 101.0, 98.0, 100.0, 1_000.0)).unwrap();
+
+    let reports = ex.submit(order(1, OrderSide::Buy, 5.0, Some(95.0))).unwrap();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(ex.open_orders().count(), 1);
+
+    assert!(ex.on_bar("AAPL", &bar(1, 99.0, 100.0, 96.0, 97.0, 1_000.0)).unwrap().is_empty());
+
+    // Range reaches the limit: fill at the limit
+    let reports = ex.on_bar("AAPL", &bar(2, 97.0, 98.0, 94.0, 96.0, 1_000.0)).unwrap();
+    assert_eq!(reports[0].status, ExecutionStatus::Filled);
+    assert_eq!(reports[0].fill_price, 95.0);
+
+    // Gapping through a sell limit fills at the open
+    ex.submit(order(2, OrderSide::Sell, 5.0, Some(100.0))).unwrap();
+    let reports = ex.on_bar("AAPL", &bar(3, 103.0, 104.0, 102.0, 103.0, 1_000.0)).unwrap();
+    assert_eq!(reports[0].fill_price, 103.0);
+}
+
+#[test]
+fn test_participation_produces_partial_fills() {
+    let mut ex = exchange().with_max_participation(0.1);
+    ex.on_bar("AAPL", &bar(0, 99.0, 101.0, 98.0, 100.0, 50.0)).unwrap();
+
+    let reports = ex.submit(order(1, OrderSide::Buy, 8.0, None)).unwrap();
+    assert_eq!(reports[1].status, ExecutionStatus::PartiallyFilled);
+    assert_eq!(reports[1].filled_qty, 5.0);
+    assert_eq!(reports[1].leaves_qty, 3.0);
+
+    let reports = ex.on_bar("AAPL", &bar(1, 101.0, 102.0, 100.0, 101.0, 50.0)).unwrap();
+    assert_eq!(reports[0].status, ExecutionStatus::Filled);
+    assert_eq!(reports[0].filled_qty, 3.0);
+    assert_eq!(reports[0].fill_price, 101.0);
+    assert_eq!(ex.portfolio().positions["AAPL"].qty, 8.0);
+}
+
+#[test]
+fn test_cancel_and_replace() {
+    let mut ex = exchange();
+    ex.on_bar("AAPL", &bar(0, 99.0, 101.0, 98.0, 100.0, 1_000.0)).unwrap();
+    ex.submit(order(1, OrderSide::Buy, 5.0, Some(90.0))).unwrap();
+    ex.submit(order(2, OrderSide::Buy, 5.0, Some(90.0))).unwrap();
+
+    // Invalid replace keeps the original order
+    let reports = ex.replace(1, 0.0, Some(90.0)).unwrap();
+    assert!(reports[0].is_rejected());
+    assert_eq!(ex.open_orders().map(|o| o.id).collect::<Vec<_>>(), vec![1, 2]);
+
+    // So does one whose fill can't be booked, here before a later deposit
+    ex.deposit(ts(5), "USD", 1_000.0).unwrap();
+    assert!(ex.replace(1, 3.0, Some(100.0)).is_err());
+    let open: Vec<_> = ex.open_orders().map(|o| (o.id, o.qty, o.price)).collect();
+    assert_eq!(open, vec![(1, 5.0, Some(90.0)), (2, 5.0, Some(90.0))]);
+    ex.on_bar("AAPL", &bar(5, 99.0, 101.0, 98.0, 100.0, 1_000.0)).unwrap();
+
+    // Marketable replace fills immediately
+    let reports = ex.replace(1, 3.0, Some(100.0)).unwrap();
+    assert_eq!(reports[1].status, ExecutionStatus::Filled);
+    assert_eq!(reports[1].filled_qty, 3.0);
+
+    assert_eq!(ex.cancel(2).unwrap().status, ExecutionStatus::Cancelled);
+    assert!(matches!(ex.cancel(2), Err(BrokerError::UnknownOrder(2))));
+    assert_eq!(ex.open_orders().count(), 0);
+}
+
+#[tokio::test]
+async fn test_broker_publishes_reports() {
+    let broker = SimulatedBroker::new(SimulatedExchange::new("USD"));
+    broker.deposit(ts(0), "USD", 10_000.0).unwrap();
+    let mut reports = broker.subscribe();
+
+    let ack = broker.submit(order(1, OrderSide::Buy, 10.0, Some(99.0))).await.unwrap();
+    assert_eq!(ack.status, ExecutionStatus::New);
+    assert_eq!(broker.open_orders().await.unwrap().len(), 1);
+
+    broker.on_bar("AAPL", &bar(1, 98.0, 99.0, 97.0, 98.5, 1_000.0)).unwrap();
+    assert_eq!(reports.recv().await.unwrap().status, ExecutionStatus::New);
+    let fill = reports.recv().await.unwrap();
+    assert_eq!(fill.status, ExecutionStatus::Filled);
+    assert_eq!(fill.fill_price, 98.0);
+
+    let positions = broker.positions().await.unwrap();
+    assert_eq!(positions[0].qty, 10.0);
+    assert_eq!(broker.balances().await.unwrap()["USD"], 9_020.0);
+}
@@ -0,0 +1,357 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use thiserror::Error;
+use toml_edit::{Document, Item, Table, Value};
+
+/// Config file looked for when none is given with `--config`
+pub const CONFIG_FILE: &str = "traiter.toml";
+/// Public `.env` style file of shared defaults, looked for alongside the
+/// config file; the config file overrides it
+pub const ENV_FILE: &str = ".env.public";
+
+/// A setting a binary understands.
+///
+/// `name` is dotted the way it's nested in the TOML file, e.g.
+/// `database.url`. The environment variable is the same name upper cased
+/// with `_` for `.` (`DATABASE_URL`), and the flag is `--database.url` or
+/// `--database-url`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub name: &'static str,
+    pub default: Option<&'static str>,
+    /// Never echoed back in errors or debug output
+    pub secret: bool,
+}
+
+impl Key {
+    pub const fn new(name: &'static str) -> Self {
+        Self { name, default: None, secret: false }
+    }
+
+    pub const fn with_default(mut self, value: &'static str) -> Self {
+        self.default = Some(value);
+        self
+    }
+
+    pub const fn secret(mut self) -> Self {
+        self.secret = true;
+        self
+    }
+
+    pub fn env_var(&self) -> String {
+        self.name.replace('.', "_").to_uppercase()
+    }
+}
+
+/// Where a setting's value came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Origin {
+    Default,
+    File(PathBuf),
+    Env(String),
+    /// Read from the file named by a `*_FILE` variable
+    SecretFile { var: String, path: PathBuf },
+    Flag(String),
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Default => write!(f, "the defaults"),
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Env(var) => write!(f, "env {var}"),
+            Self::SecretFile { var, path } => write!(f, "{} (from env {var})", path.display()),
+            Self::Flag(flag) => write!(f, "flag --{flag}"),
+        }
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("unknown setting `{key}` in {origin}")]
+    Unknown { key: String, origin: Origin },
+    #[error("`{key}` must be set")]
+    Missing { key: String },
+    #[error("invalid `{key}` from {origin}: {message}")]
+    Invalid { key: String, origin: Origin, message: String },
+    #[error("failed to read {}: {message}", path.display())]
+    File { path: PathBuf, message: String },
+    #[error("bad arguments: {0}")]
+    Args(String),
+}
+
+/// What settings are read from; [`Sources::from_process`] for the real thing
+#[derive(Debug, Clone, Default)]
+pub struct Sources {
+    /// Command line arguments, without the program name
+    pub args: Vec<String>,
+    pub vars: HashMap<String, String>,
+    /// Where the search for [`CONFIG_FILE`] and [`ENV_FILE`] starts. Parent
+    /// directories are tried too, so a binary finds them from anywhere in
+    /// the checkout.
+    pub dir: PathBuf,
+}
+
+impl Sources {
+    pub fn from_process() -> Self {
+        Self {
+            args: std::env::args().skip(1).collect(),
+            vars: std::env::vars().collect(),
+            dir: std::env::current_dir().unwrap_or_else(|_err| PathBuf::from(".")),
+        }
+    }
+}
+
+/// Settings merged from, in increasing precedence: the keys' defaults,
+/// [`ENV_FILE`], a TOML file, environment variables and command line flags.
+///
+/// Values stay strings until read with one of the typed getters, which
+/// report the key and the layer a bad value came from.
+#[derive(Clone)]
+pub struct Layers {
+    keys: Vec<Key>,
+    values: BTreeMap<&'static str, (String, Origin)>,
+}
+
+impl Layers {
+    /// Only the defaults of `keys`
+    pub fn new(keys: &[Key]) -> Self {
+        let values = keys
+            .iter()
+            .filter_map(|k| Some((k.name, (k.default?.to_owned(), Origin::Default))))
+            .collect();
+        Self { keys: keys.to_vec(), values }
+    }
+
+    pub fn load(keys: &[Key], sources: &Sources) -> Result<Self, ConfigError> {
+        let args = parse_args(&sources.args)?;
+        let mut layers = Self::new(keys);
+
+        if let Some(path) = find_upwards(&sources.dir, ENV_FILE) {
+            let file_error = |e: dotenvy::Error| ConfigError::File { path: path.clone(), message: e.to_string() };
+            let vars = dotenvy::from_path_iter(&path)
+                .map_err(file_error)?
+                .collect::<Result<HashMap<_, _>, _>>()
+                .map_err(file_error)?;
+            layers.merge_env(&vars, |_| Origin::File(path.clone()))?;
+        }
+
+        let config = args.config
+            .or_else(|| sources.vars.get("TRAITER_CONFIG").map(PathBuf::from))
+            .or_else(|| find_upwards(&sources.dir, CONFIG_FILE));
+        if let Some(path) = config {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| ConfigError::File { path: path.clone(), message: e.to_string() })?;
+            layers.merge_toml(&text, &path)?;
+        }
+        layers.merge_env(&sources.vars, |var| Origin::Env(var.to_owned()))?;
+
+        for (flag, value) in args.flags {
+            let name = flag.replace(['-', '.'], "_");
+            let key = layers
+                .keys
+                .iter()
+                .find(|k| k.name.replace('.', "_") == name)
+                .copied()
+                .ok_or_else(|| ConfigError::Unknown { key: flag.clone(), origin: Origin::Flag(flag.clone()) })?;
+            layers.values.insert(key.name, (value, Origin::Flag(flag)));
+        }
+        Ok(layers)
+    }
+
+    /// Overlay the settings of a TOML document read from `path`
+    pub fn merge_toml(&mut self, text: &str, path: &Path) -> Result<(), ConfigError> {
+        let doc = text
+            .parse::<Document>()
+            .map_err(|e| ConfigError::File { path: path.to_owned(), message: e.to_string() })?;
+        let mut flat = Vec::new();
+        flatten_table("", doc.as_table(), &mut flat);
+
+        let origin = Origin::File(path.to_owned());
+        for (name, value) in flat {
+            let key = self.key(&name).ok_or_else(|| ConfigError::Unknown { key: name.clone(), origin: origin.clone() })?;
+            let value = value.ok_or_else(|| ConfigError::Invalid {
+                key: name.clone(),
+                origin: origin.clone(),
+                message: "expected a value, list or table".to_owned(),
+            })?;
+            self.values.insert(key.name, (value, origin.clone()));
+        }
+        Ok(())
+    }
+
+    /// Overlay the keys' environment variables, reading `NAME_FILE` for a
+    /// `NAME` that is kept in a file, the way compose mounts secrets
+    pub fn merge_env(
+        &mut self,
+        vars: &HashMap<String, String>,
+        origin: impl Fn(&str) -> Origin,
+    ) -> Result<(), ConfigError> {
+        for key in &self.keys {
+            let var = key.env_var();
+            let file_var = format!("{var}_FILE");
+            let setting = match (vars.get(&var), vars.get(&file_var)) {
+                (None, None) => continue,
+                (Some(value), None) => (value.clone(), origin(&var)),
+                (None, Some(path)) => {
+                    let origin = Origin::SecretFile { var: file_var, path: PathBuf::from(path) };
+                    let value = std::fs::read_to_string(path).map_err(|e| ConfigError::Invalid {
+                        key: key.name.to_owned(),
+                        origin: origin.clone(),
+                        message: e.to_string(),
+                    })?;
+                    (value.trim().to_owned(), origin)
+                }
+                (Some(_), Some(_)) => {
+                    return Err(ConfigError::Invalid {
+                        key: key.name.to_owned(),
+                        origin: origin(&var),
+                        message: format!("set both {var} and {file_var}"),
+                    })
+                }
+            };
+            self.values.insert(key.name, setting);
+        }
+        Ok(())
+    }
+
+    /// Layer a value came from, `None` if unset
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.values.get(key).map(|(_, origin)| origin)
+    }
+
+    /// A required setting
+    pub fn get<T>(&self, key: &str) -> Result<T, ConfigError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.get_opt(key)?.ok_or_else(|| ConfigError::Missing { key: key.to_owned() })
+    }
+
+    /// A setting that may be left out; empty counts as unset
+    pub fn get_opt<T>(&self, key: &str) -> Result<Option<T>, ConfigError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let Some(raw) = self.raw(key) else {
+            return Ok(None);
+        };
+        raw.parse().map(Some).map_err(|e| self.invalid(key, self.bad_value(key, raw, &e)))
+    }
+
+    /// A comma separated list, or a TOML array; empty if unset
+    pub fn get_list<T>(&self, key: &str) -> Result<Vec<T>, ConfigError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let Some(raw) = self.raw(key) else {
+            return Ok(Vec::new());
+        };
+        raw.split(',')
+            .map(str::trim)
+            .filter(|item| !item.is_empty())
+            .map(|item| item.parse().map_err(|e| self.invalid(key, self.bad_value(key, item, &e))))
+            .collect()
+    }
+
+    /// An error for a value that parsed but doesn't make sense
+    pub fn invalid(&self, key: &str, message: impl fmt::Display) -> ConfigError {
+        ConfigError::Invalid {
+            key: key.to_owned(),
+            origin: self.origin(key).cloned().unwrap_or(Origin::Default),
+            message: message.to_string(),
+        }
+    }
+
+    fn raw(&self, key: &str) -> Option<&str> {
+        assert!(self.key(key).is_some(), "undeclared config key {key}");
+        self.values.get(key).map(|(value, _)| value.as_str()).filter(|value| !value.is_empty())
+    }
+
+    fn bad_value(&self, key: &str, value: &str, e: &impl fmt::Display) -> String {
+        if self.key(key).is_some_and(|k| k.secret) {
+            format!("bad value ({e})")
+        } else {
+            format!("bad value `{value}` ({e})")
+        }
+    }
+
+    fn key(&self, name: &str) -> Option<Key> {
+        self.keys.iter().find(|k| k.name == name).copied()
+    }
+}
+
+impl fmt::Debug for Layers {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = f.debug_map();
+        for (name, (value, origin)) in &self.values {
+            let secret = self.key(name).is_some_and(|k| k.secret);
+            map.entry(name, &(if secret { "<redacted>" } else { value.as_str() }, origin));
+        }
+        map.finish()
+    }
+}
+
+/// Command line flags; values follow either `=` or a space
+struct Args {
+    config: Option<PathBuf>,
+    flags: Vec<(String, String)>,
+}
+
+fn parse_args(args: &[String]) -> Result<Args, ConfigError> {
+    let mut parsed = Args { config: None, flags: Vec::new() };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let Some(flag) = arg.strip_prefix("--") else {
+            return Err(ConfigError::Args(format!("unexpected argument `{arg}`")));
+        };
+        let (name, value) = if let Some((name, value)) = flag.split_once('=') {
+            (name, value.to_owned())
+        } else {
+            let value = args.next().ok_or_else(|| ConfigError::Args(format!("--{flag} needs a value")))?;
+            (flag, value.clone())
+        };
+        if name == "config" {
+            parsed.config = Some(PathBuf::from(value));
+        } else {
+            parsed.flags.push((name.to_owned(), value));
+        }
+    }
+    Ok(parsed)
+}
+
+fn find_upwards(dir: &Path, name: &str) -> Option<PathBuf> {
+    dir.ancestors().map(|d| d.join(name)).find(|path| path.is_file())
+}
+
+/// Dotted names with their values rendered as strings; `None` for values
+/// that can't be a setting, like arrays of tables
+fn flatten_table(prefix: &str, table: &Table, out: &mut Vec<(String, Option<String>)>) {
+    for (name, item) in table {
+        let name = format!("{prefix}{name}");
+        match item {
+            Item::None => {}
+            Item::Value(Value::InlineTable(inline)) => flatten_table(&format!("{name}."), &inline.clone().into_table(), out),
+            Item::Value(value) => out.push((name, render(value))),
+            Item::Table(table) => flatten_table(&format!("{name}."), table, out),
+            Item::ArrayOfTables(_) => out.push((name, None)),
+        }
+    }
+}
+
+fn render(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::String(s) => s.value().clone(),
+        Value::Integer(i) => i.value().to_string(),
+        Value::Float(f) => f.value().to_string(),
+        Value::Boolean(b) => b.value().to_string(),
+        Value::Datetime(d) => d.value().to_string(),
+        Value::Array(items) => items.iter().map(render).collect::<Option<Vec<_>>>()?.join(","),
+        Value::InlineTable(_) => return None,
+    })
+}
@@ -1,17 +1,64 @@
-use std::env;
+mod layers;
 
+use std::fmt;
+use std::str::FromStr;
+
+pub use layers::{ConfigError, Key, Layers, Origin, Sources, CONFIG_FILE, ENV_FILE};
+
+/// Settings every binary shares
 #[derive(Debug, Clone)]
 pub struct BaseConfig {
-    pub log_level: String,
+    pub log_level: LogLevel,
 }
 
 impl BaseConfig {
-    pub fn load() -> anyhow::Result<Self> {
-        dotenvy::from_path(".env.public")?;
+    pub const KEYS: &[Key] = &[Key::new("log_level").with_default("info")];
 
-        Ok(BaseConfig {
-            log_level: env::var("LOG_LEVEL")
-                .unwrap_or_else(|_| "info".to_string()),
+    pub fn from_layers(layers: &Layers) -> Result<Self, ConfigError> {
+        Ok(Self {
+            log_level: layers.get("log_level")?,
         })
     }
-}
\ No newline at end of file
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Error => "error",
+            Self::Warn => "warn",
+            Self::Info => "info",
+            Self::Debug => "debug",
+            Self::Trace => "trace",
+        }
+    }
+}
+
+impl FromStr for LogLevel {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "error" => Ok(Self::Error),
+            "warn" => Ok(Self::Warn),
+            "info" => Ok(Self::Info),
+            "debug" => Ok(Self::Debug),
+            "trace" => Ok(Self::Trace),
+            _ => Err("expected one of error, warn, info, debug, trace".to_owned()),
+        }
+    }
+}
+
+impl fmt::Display for LogLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use config::{BaseConfig, ConfigError, Key, Layers, LogLevel, Origin, Sources};
+
+const KEYS: &[Key] = &[
+    Key::new("port").with_default("3000"),
+    Key::new("database.url"),
+    Key::new("database.password").secret(),
+    Key::new("providers").with_default("yahoo"),
+];
+
+/// A fresh directory to hold config files for one test
+fn scratch(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("traiter-config-{name}-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn sources(dir: &std::path::Path, args: &[&str], vars: &[(&str, &str)]) -> Sources {
+    Sources {
+        args: args.iter().map(|a| (*a).to_owned()).collect(),
+        vars: vars.iter().map(|(k, v)| ((*k).to_owned(), (*v).to_owned())).collect::<HashMap<_, _>>(),
+        dir: dir.to_owned(),
+    }
+}
+
+#[test]
+fn test_later_layers_win() {
+    let dir = scratch("layers");
+    std::fs::write(dir.join("traiter.toml"), "port = 4000\n[database]\nurl = \"sqlite://file.db\"\n").unwrap();
+    std::fs::write(dir.join(".env.public"), "PORT=5000\nPROVIDERS=yahoo,binance\n").unwrap();
+
+    // The TOML file wins over the shared defaults of `.env.public`
+    let layers = Layers::load(KEYS, &sources(&dir, &[], &[])).unwrap();
+    assert_eq!(layers.get::<u16>("port").unwrap(), 4000);
+    assert_eq!(layers.get::<String>("database.url").unwrap(), "sqlite://file.db");
+    assert_eq!(layers.origin("providers"), Some(&Origin::File(dir.join(".env.public"))));
+
+    let layers = Layers::load(KEYS, &sources(&dir, &["--port", "7000"], &[("PORT", "6000")])).unwrap();
+    assert_eq!(layers.get::<u16>("port").unwrap(), 7000);
+    let layers = Layers::load(KEYS, &sources(&dir, &["--database-url=postgres://db"], &[("PORT", "6000")])).unwrap();
+    assert_eq!(layers.get::<u16>("port").unwrap(), 6000);
+    assert_eq!(layers.get::<String>("database.url").unwrap(), "postgres://db");
+
+    // Files are found from subdirectories too
+    let nested = dir.join("crates").join("backend");
+    std::fs::create_dir_all(&nested).unwrap();
+    let layers = Layers::load(KEYS, &sources(&nested, &[], &[])).unwrap();
+    assert_eq!(layers.get::<u16>("port").unwrap(), 4000);
+    assert_eq!(layers.get_list::<String>("providers").unwrap(), ["yahoo", "binance"]);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_errors_name_the_key() {
+    let dir = scratch("errors");
+    let layers = Layers::load(KEYS, &sources(&dir, &[], &[("PORT", "3000.")])).unwrap();
+    let err = layers.get::<u16>("port").unwrap_err();
+    assert!(matches!(&err, ConfigError::Invalid { key, origin: Origin::Env(var), .. } if key == "port" && var == "PORT"));
+    assert!(err.to_string().contains("`3000.`"));
+
+    let err = layers.get::<String>("database.url").unwrap_err();
+    assert!(matches!(err, ConfigError::Missing { key } if key == "database.url"));
+
+    std::fs::write(dir.join("traiter.toml"), "[database]\nuri = \"typo\"\n").unwrap();
+    let err = Layers::load(KEYS, &sources(&dir, &[], &[])).unwrap_err();
+    assert!(matches!(err, ConfigError::Unknown { key, .. } if key == "database.uri"));
+
+    let err = Layers::load(KEYS, &sources(&dir, &["--config", "missing.toml"], &[])).unwrap_err();
+    assert!(matches!(err, ConfigError::File { .. }));
+    let err = Layers::load(KEYS, &sources(&dir, &["--config", "/dev/null", "--colour", "red"], &[])).unwrap_err();
+    assert!(matches!(err, ConfigError::Unknown { key, .. } if key == "colour"));
+    let err = Layers::load(KEYS, &sources(&dir, &["--port"], &[])).unwrap_err();
+    assert!(matches!(err, ConfigError::Args(_)));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_secrets_from_files() {
+    let dir = scratch("secrets");
+    let secret = dir.join("database_password");
+    std::fs::write(&secret, "hunter2\n").unwrap();
+    let path = secret.to_str().unwrap();
+
+    let layers = Layers::load(KEYS, &sources(&dir, &[], &[("DATABASE_PASSWORD_FILE", path)])).unwrap();
+    assert_eq!(layers.get::<String>("database.password").unwrap(), "hunter2");
+    assert!(!format!("{layers:?}").contains("hunter2"));
+
+    let both = [("DATABASE_PASSWORD_FILE", path), ("DATABASE_PASSWORD", "other")];
+    let err = Layers::load(KEYS, &sources(&dir, &[], &both)).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid { key, .. } if key == "database.password"));
+
+    let missing = [("DATABASE_PASSWORD_FILE", "/nonexistent/secret")];
+    let err = Layers::load(KEYS, &sources(&dir, &[], &missing)).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid { key, origin: Origin::SecretFile { .. }, .. } if key == "database.password"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_lists_and_base_config() {
+    let dir = scratch("lists");
+    std::fs::write(dir.join("traiter.toml"), "log_level = \"DEBUG\"\nproviders = [\"yahoo\", \"binance\"]\n").unwrap();
+    let keys = [KEYS, BaseConfig::KEYS].concat();
+
+    let layers = Layers::load(&keys, &sources(&dir, &[], &[])).unwrap();
+    assert_eq!(layers.get_list::<String>("providers").unwrap(), ["yahoo", "binance"]);
+    assert_eq!(BaseConfig::from_layers(&layers).unwrap().log_level, LogLevel::Debug);
+
+    let layers = Layers::load(&keys, &sources(&dir, &["--log-level", "loud"], &[("PROVIDERS", "")])).unwrap();
+    assert!(layers.get_list::<String>("providers").unwrap().is_empty());
+    let err = BaseConfig::from_layers(&layers).unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid { key, .. } if key == "log_level"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,39 @@
+//! Key-value and pub/sub store shared by the providers of every backend
+//! replica, see [`CachedProvider`](crate::providers::CachedProvider)
+
+mod memory;
+#[cfg(feature = "redis")]
+mod redis;
+
+use std::time::Duration;
+use anyhow::Result;
+use async_trait::async_trait;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+
+pub use memory::MemoryCache;
+#[cfg(feature = "redis")]
+pub use redis::RedisCache;
+
+/// Messages of one channel, until the stream is dropped
+pub type Subscription = UnboundedReceiverStream<String>;
+
+#[async_trait]
+pub trait Cache: Send + Sync {
+    async fn get(&self, key: &str) -> Result<Option<String>>;
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<()>;
+
+    /// Set `key` only if it holds nothing; `false` if it did
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool>;
+
+    /// Extend the TTL of `key` only if it still holds `owner`; `false` if it doesn't
+    async fn renew_if_owner(&self, key: &str, owner: &str, ttl: Duration) -> Result<bool>;
+
+    /// Delete `key` only if it still holds `owner`; `false` if it doesn't
+    async fn release_if_owner(&self, key: &str, owner: &str) -> Result<bool>;
+
+    async fn publish(&self, channel: &str, message: &str) -> Result<()>;
+
+    /// Messages published to `channel` from now on
+    async fn subscribe(&self, channel: &str) -> Result<Subscription>;
+}
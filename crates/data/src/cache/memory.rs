@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use tokio::sync::{broadcast, mpsc};
+use tokio::time::Instant;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use crate::cache::{Cache, Subscription};
+
+/// Messages a slow subscriber may fall behind by before it misses some
+const CHANNEL_CAPACITY: usize = 256;
+
+/// [`Cache`] for a single process, and for tests
+#[derive(Default)]
+pub struct MemoryCache {
+    values: Mutex<HashMap<String, (String, Instant)>>,
+    channels: Mutex<HashMap<String, broadcast::Sender<String>>>,
+}
+
+impl MemoryCache {
+    fn lock_values(&self) -> Result<std::sync::MutexGuard<'_, HashMap<String, (String, Instant)>>> {
+        self.values.lock().map_err(|_err| anyhow!("memory cache poisoned"))
+    }
+}
+
+#[async_trait]
+impl Cache for MemoryCache {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let values = self.lock_values()?;
+        Ok(values
+            .get(key)
+            .filter(|(_, expires)| *expires > Instant::now())
+            .map(|(value, _)| value.clone()))
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        let now = Instant::now();
+        let mut values = self.lock_values()?;
+        values.retain(|_, (_, expires)| *expires > now);
+        values.insert(key.to_owned(), (value.to_owned(), now + ttl));
+        Ok(())
+    }
+
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool> {
+        let now = Instant::now();
+        let mut values = self.lock_values()?;
+        if values.get(key).is_some_and(|(_, expires)| *expires > now) {
+            return Ok(false);
+        }
+        values.insert(key.to_owned(), (value.to_owned(), now + ttl));
+        Ok(true)
+    }
+
+    async fn renew_if_owner(&self, key: &str, owner: &str, ttl: Duration) -> Result<bool> {
+        let now = Instant::now();
+        let mut values = self.lock_values()?;
+        match values.get_mut(key) {
+            Some((value, expires)) if value == owner && *expires > now => {
+                *expires = now + ttl;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn release_if_owner(&self, key: &str, owner: &str) -> Result<bool> {
+        let now = Instant::now();
+        let mut values = self.lock_values()?;
+        if values.get(key).is_some_and(|(value, expires)| value == owner && *expires > now) {
+            values.remove(key);
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    async fn publish(&self, channel: &str, message: &str) -> Result<()> {
+        let mut channels = self.channels.lock().map_err(|_err| anyhow!("memory cache poisoned"))?;
+        // Nobody listening any more
+        if let Some(sender) = channels.get(channel)
+            && sender.send(message.to_owned()).is_err()
+        {
+            channels.remove(channel);
+        }
+        Ok(())
+    }
+
+    async fn subscribe(&self, channel: &str) -> Result<Subscription> {
+        let mut receiver = self.channels
+            .lock()
+            .map_err(|_err| anyhow!("memory cache poisoned"))?
+            .entry(channel.to_owned())
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        tokio::spawn(async move {
+            loop {
+                tokio::select! {
+                    () = tx.closed() => break,
+                    message = receiver.recv() => match message {
+                        Ok(message) => {
+                            if tx.send(message).is_err() {
+                                break;
+                            }
+                        }
+                        Err(broadcast::error::RecvError::Lagged(_)) => {}
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        });
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+}
@@ -0,0 +1,147 @@
+use std::time::Duration;
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::warn;
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Client, ExistenceCheck, IntoConnectionInfo, Script, SetExpiry, SetOptions};
+use tokio::sync::{mpsc, Mutex};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use crate::cache::{Cache, Subscription};
+
+/// Extend `KEYS[1]` by `ARGV[2]` milliseconds if it still holds `ARGV[1]`
+const RENEW_IF_OWNER: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('PEXPIRE', KEYS[1], ARGV[2])
+end
+return 0
+";
+
+/// Delete `KEYS[1]` if it still holds `ARGV[1]`
+const RELEASE_IF_OWNER: &str = r"
+if redis.call('GET', KEYS[1]) == ARGV[1] then
+    return redis.call('DEL', KEYS[1])
+end
+return 0
+";
+
+/// [`Cache`] on a Redis server, so replicas share cached candles and live
+/// streams. Commands share one multiplexed connection, each subscription
+/// opens its own.
+pub struct RedisCache {
+    client: Client,
+    conn: Mutex<Option<MultiplexedConnection>>,
+    renew: Script,
+    release: Script,
+}
+
+impl RedisCache {
+    /// `redis://[[user]:password@]host[:port][/db]`. `password`, if given,
+    /// wins over one in `url`. Nothing connects until the first command.
+    pub fn new(url: &str, password: Option<String>) -> Result<Self> {
+        let mut info = url.into_connection_info()?;
+        if password.is_some() {
+            info.redis.password = password;
+        }
+        Ok(Self {
+            client: Client::open(info)?,
+            conn: Mutex::new(None),
+            renew: Script::new(RENEW_IF_OWNER),
+            release: Script::new(RELEASE_IF_OWNER),
+        })
+    }
+
+    /// The shared connection, opened on first use or after a failure
+    async fn connection(&self) -> Result<MultiplexedConnection> {
+        let mut conn = self.conn.lock().await;
+        if let Some(conn) = conn.as_ref() {
+            return Ok(conn.clone());
+        }
+        let opened = self.client.get_multiplexed_async_connection().await?;
+        *conn = Some(opened.clone());
+        Ok(opened)
+    }
+
+    /// Drop a connection that failed so the next command opens a new one
+    async fn reset<T>(&self, result: redis::RedisResult<T>) -> Result<T> {
+        if let Err(e) = &result
+            && (e.is_io_error() || e.is_connection_dropped())
+        {
+            *self.conn.lock().await = None;
+        }
+        Ok(result?)
+    }
+}
+
+#[async_trait]
+impl Cache for RedisCache {
+    async fn get(&self, key: &str) -> Result<Option<String>> {
+        let mut conn = self.connection().await?;
+        self.reset(conn.get(key).await).await
+    }
+
+    async fn set(&self, key: &str, value: &str, ttl: Duration) -> Result<()> {
+        let mut conn = self.connection().await?;
+        let options = SetOptions::default().with_expiration(SetExpiry::PX(millis(ttl)));
+        self.reset(conn.set_options(key, value, options).await).await
+    }
+
+    async fn set_if_absent(&self, key: &str, value: &str, ttl: Duration) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let options = SetOptions::default()
+            .conditional_set(ExistenceCheck::NX)
+            .with_expiration(SetExpiry::PX(millis(ttl)));
+        let set: Option<String> = self.reset(conn.set_options(key, value, options).await).await?;
+        Ok(set.is_some())
+    }
+
+    async fn renew_if_owner(&self, key: &str, owner: &str, ttl: Duration) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let renewed: i64 = self.reset(self.renew.key(key).arg(owner).arg(millis(ttl)).invoke_async(&mut conn).await).await?;
+        Ok(renewed == 1)
+    }
+
+    async fn release_if_owner(&self, key: &str, owner: &str) -> Result<bool> {
+        let mut conn = self.connection().await?;
+        let released: i64 = self.reset(self.release.key(key).arg(owner).invoke_async(&mut conn).await).await?;
+        Ok(released == 1)
+    }
+
+    async fn publish(&self, channel: &str, message: &str) -> Result<()> {
+        let mut conn = self.connection().await?;
+        self.reset(conn.publish(channel, message).await).await
+    }
+
+    async fn subscribe(&self, channel: &str) -> Result<Subscription> {
+        let mut pubsub = self.client.get_async_pubsub().await?;
+        pubsub.subscribe(channel).await?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let channel = channel.to_owned();
+        tokio::spawn(async move {
+            let mut messages = pubsub.into_on_message();
+            loop {
+                tokio::select! {
+                    () = tx.closed() => break,
+                    message = messages.next() => match message.map(|m| m.get_payload::<String>()) {
+                        Some(Ok(message)) => {
+                            if tx.send(message).is_err() {
+                                break;
+                            }
+                        }
+                        Some(Err(e)) => warn!("ignoring bad message on {channel}: {e}"),
+                        None => {
+                            warn!("redis subscription to {channel} ended");
+                            break;
+                        }
+                    },
+                }
+            }
+        });
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+}
+
+fn millis(ttl: Duration) -> u64 {
+    u64::try_from(ttl.as_millis()).unwrap_or(u64::MAX)
+}
@@ -0,0 +1,43 @@
+use dnn_core::fx::FxHistory;
+use dnn_core::time::{TimeInterval, Timestamp};
+use crate::providers::Provider;
+
+/// Loads historical FX rates as candles from any [`Provider`]
+pub struct FxSource<'a> {
+    provider: &'a (dyn Provider + Send + Sync),
+    pair_symbol: fn(&str, &str) -> String,
+}
+
+impl<'a> FxSource<'a> {
+    /// Uses Yahoo-style pair symbols, e.g. `EURUSD=X`
+    pub fn new(provider: &'a (dyn Provider + Send + Sync)) -> Self {
+        Self {
+            provider,
+            pair_symbol: |from, to| format!("{from}{to}=X"),
+        }
+    }
+
+    /// Override how a currency pair maps to a provider symbol (e.g. `ETH-USD` style)
+    pub fn with_pair_symbol(mut self, pair_symbol: fn(&str, &str) -> String) -> Self {
+        self.pair_symbol = pair_symbol;
+        self
+    }
+
+    /// Fetch the rate history of every `currency/base` pair over a time range
+    pub async fn history(
+        &self,
+        currencies: &[&str],
+        base: &str,
+        interval: TimeInterval,
+        start: Timestamp,
+        end: Timestamp,
+    ) -> anyhow::Result<FxHistory> {
+        let mut history = FxHistory::new();
+        for ccy in currencies.iter().filter(|c| **c != base) {
+            let symbol = (self.pair_symbol)(ccy, base);
+            let candles = self.provider.historical(&symbol, interval, start, end).await?;
+            history.insert_candles(ccy, base, &candles);
+        }
+        Ok(history)
+    }
+}
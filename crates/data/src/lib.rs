@@ -1,3 +1,5 @@
+pub mod cache;
+pub mod fx;
 pub mod providers;
 mod replay;
 
@@ -1,6 +1,8 @@
+mod cached;
 mod yahoo;
 
 use std::pin::Pin;
+pub use cached::CachedProvider;
 pub use yahoo::Yahoo;
 
 use anyhow::Result;
@@ -8,12 +10,21 @@ use async_trait::async_trait;
 use yahoo_finance_api::time::OffsetDateTime;
 use futures::{StreamExt as _, Stream, stream};
 use tokio_stream::wrappers::UnboundedReceiverStream;
+use serde::{Deserialize, Serialize};
 use api::ProviderType;
 use dnn_core::market::Candle;
 use dnn_core::time::{TimeInterval, Timestamp};
 
 pub type ProviderStream = UnboundedReceiverStream<Candle>;
 
+/// What a provider knows about the company behind a symbol
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: Option<String>,
+    pub sector: Option<String>,
+    pub market_cap: Option<f64>,
+}
+
 #[async_trait]
 pub trait Provider {
     async fn stream(&self, symbol: &str, interval: TimeInterval) -> anyhow::Result<ProviderStream>;
@@ -25,6 +36,22 @@ pub trait Provider {
         start: Timestamp,
         end: Timestamp
     ) -> anyhow::Result<Vec<Candle>>;
-    
+
+    /// [`Provider::historical`] with prices adjusted for splits and
+    /// dividends, for providers that know about them
+    async fn historical_adjusted(
+        &self,
+        _symbol: &str,
+        _interval: TimeInterval,
+        _start: Timestamp,
+        _end: Timestamp
+    ) -> anyhow::Result<Vec<Candle>> {
+        anyhow::bail!("{} has no adjusted prices", self.get_type().as_ref())
+    }
+
+    async fn profile(&self, _symbol: &str) -> anyhow::Result<Profile> {
+        anyhow::bail!("{} has no company profiles", self.get_type().as_ref())
+    }
+
     fn get_type(&self) -> ProviderType;
 }
\ No newline at end of file
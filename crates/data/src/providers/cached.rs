@@ -0,0 +1,234 @@
+use std::sync::Arc;
+use std::time::Duration;
+use anyhow::Result;
+use async_trait::async_trait;
+use futures::StreamExt;
+use log::{info, warn};
+use rand::distr::{Alphanumeric, SampleString};
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use api::ProviderType;
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+use crate::cache::{Cache, Subscription};
+use crate::providers::{Profile, Provider, ProviderStream};
+
+/// How long a replica feeding a live stream keeps the job without renewing it
+const LEASE: Duration = Duration::from_secs(15);
+
+/// Puts a [`Cache`] in front of a provider.
+///
+/// `historical` responses are kept for the configured TTL. Live streams go
+/// through the cache's pub/sub: whoever holds a stream's lease polls the
+/// provider and publishes, every subscriber on every replica reads the
+/// channel. When the holder goes away its lease lapses and another
+/// subscriber takes over, so a stream is polled once however many replicas
+/// serve it.
+pub struct CachedProvider<P> {
+    inner: Arc<P>,
+    cache: Arc<dyn Cache>,
+    history_ttl: Duration,
+}
+
+impl<P: Provider + Send + Sync + 'static> CachedProvider<P> {
+    /// A zero `history_ttl` turns off caching of `historical`
+    pub fn new(inner: P, cache: Arc<dyn Cache>, history_ttl: Duration) -> Self {
+        Self { inner: Arc::new(inner), cache, history_ttl }
+    }
+
+    fn history_key(&self, kind: &str, symbol: &str, interval: TimeInterval, start: Timestamp, end: Timestamp) -> String {
+        format!(
+            "{kind}:{}:{symbol}:{interval}:{}:{}",
+            self.get_type().as_ref(),
+            start.timestamp(),
+            end.timestamp()
+        )
+    }
+
+    /// Candles under `key`, else from `fetch` and kept for the TTL
+    async fn cached(&self, key: &str, fetch: impl Future<Output = Result<Vec<Candle>>> + Send) -> Result<Vec<Candle>> {
+        // A broken cache only costs a trip to the provider
+        match self.cache.get(key).await {
+            Ok(Some(json)) => match serde_json::from_str(&json) {
+                Ok(candles) => return Ok(candles),
+                Err(e) => warn!("ignoring bad cached candles {key}: {e}"),
+            },
+            Ok(None) => {}
+            Err(e) => warn!("failed to read cached candles {key}: {e:#}"),
+        }
+
+        let candles = fetch.await?;
+        if !self.history_ttl.is_zero()
+            && let Err(e) = self.cache.set(key, &serde_json::to_string(&candles)?, self.history_ttl).await
+        {
+            warn!("failed to cache candles {key}: {e:#}");
+        }
+        Ok(candles)
+    }
+}
+
+#[async_trait]
+impl<P: Provider + Send + Sync + 'static> Provider for CachedProvider<P> {
+    async fn stream(&self, symbol: &str, interval: TimeInterval) -> Result<ProviderStream> {
+        let channel = format!("stream:{}:{symbol}:{interval}", self.get_type().as_ref());
+        let subscription = self.cache.subscribe(&channel).await?;
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let feed = Feed {
+            inner: self.inner.clone(),
+            cache: self.cache.clone(),
+            symbol: symbol.to_owned(),
+            interval,
+            lease: format!("lease:{channel}"),
+            owner: Alphanumeric.sample_string(&mut rand::rng(), 16),
+            channel,
+        };
+        tokio::spawn(feed.run(subscription, tx));
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+
+    async fn historical(
+        &self,
+        symbol: &str,
+        interval: TimeInterval,
+        start: Timestamp,
+        end: Timestamp
+    ) -> Result<Vec<Candle>> {
+        let key = self.history_key("candles", symbol, interval, start, end);
+        self.cached(&key, self.inner.historical(symbol, interval, start, end)).await
+    }
+
+    async fn historical_adjusted(
+        &self,
+        symbol: &str,
+        interval: TimeInterval,
+        start: Timestamp,
+        end: Timestamp
+    ) -> Result<Vec<Candle>> {
+        let key = self.history_key("adjusted", symbol, interval, start, end);
+        self.cached(&key, self.inner.historical_adjusted(symbol, interval, start, end)).await
+    }
+
+    async fn profile(&self, symbol: &str) -> Result<Profile> {
+        let key = format!("profile:{}:{symbol}", self.get_type().as_ref());
+        if let Ok(Some(json)) = self.cache.get(&key).await
+            && let Ok(profile) = serde_json::from_str(&json)
+        {
+            return Ok(profile);
+        }
+        let profile = self.inner.profile(symbol).await?;
+        if !self.history_ttl.is_zero()
+            && let Err(e) = self.cache.set(&key, &serde_json::to_string(&profile)?, self.history_ttl).await
+        {
+            warn!("failed to cache profile {key}: {e:#}");
+        }
+        Ok(profile)
+    }
+
+    fn get_type(&self) -> ProviderType {
+        self.inner.get_type()
+    }
+}
+
+/// Forwards one channel to one subscriber, feeding the channel from the
+/// provider while it holds the lease
+struct Feed<P> {
+    inner: Arc<P>,
+    cache: Arc<dyn Cache>,
+    symbol: String,
+    interval: TimeInterval,
+    channel: String,
+    lease: String,
+    /// Identifies this feed as the lease holder
+    owner: String,
+}
+
+impl<P: Provider + Send + Sync> Feed<P> {
+    async fn run(self, mut subscription: Subscription, tx: mpsc::UnboundedSender<Candle>) {
+        let mut upstream: Option<ProviderStream> = None;
+        let mut renew = tokio::time::interval(LEASE / 3);
+
+        loop {
+            tokio::select! {
+                () = tx.closed() => break,
+                message = subscription.next() => {
+                    let Some(message) = message else {
+                        warn!("subscription to {} ended", self.channel);
+                        break;
+                    };
+                    match serde_json::from_str::<Candle>(&message) {
+                        Ok(candle) => {
+                            if tx.send(candle).is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => warn!("ignoring bad candle on {}: {e}", self.channel),
+                    }
+                }
+                _ = renew.tick() => {
+                    let leading = self.hold_lease(upstream.is_some()).await;
+                    if leading && upstream.is_none() {
+                        match self.inner.stream(&self.symbol, self.interval).await {
+                            Ok(stream) => {
+                                info!("feeding {} from the provider", self.channel);
+                                upstream = Some(stream);
+                            }
+                            Err(e) => warn!("failed to open provider stream for {}: {e:#}", self.channel),
+                        }
+                    } else if !leading && upstream.is_some() {
+                        info!("lost the lease on {}", self.channel);
+                        upstream = None;
+                    }
+                }
+                candle = next_candle(&mut upstream) => match candle {
+                    Some(candle) => self.publish(&candle).await,
+                    // Reopened at the next renewal
+                    None => upstream = None,
+                },
+            }
+        }
+
+        if upstream.is_some() {
+            self.release_lease().await;
+        }
+    }
+
+    /// Take the lease if it's free, or renew it if it's ours. Renewing only
+    /// extends a lease that still names us, so one taken over by another
+    /// replica in between isn't stolen back. On cache errors carry on as before.
+    async fn hold_lease(&self, leading: bool) -> bool {
+        let held = match self.cache.renew_if_owner(&self.lease, &self.owner, LEASE).await {
+            Ok(true) => Ok(true),
+            Ok(false) => self.cache.set_if_absent(&self.lease, &self.owner, LEASE).await,
+            Err(e) => Err(e),
+        };
+        held.unwrap_or_else(|e| {
+            warn!("failed to hold the lease on {}: {e:#}", self.channel);
+            leading
+        })
+    }
+
+    /// Let another subscriber take over without waiting for the lease to lapse
+    async fn release_lease(&self) {
+        if let Err(e) = self.cache.release_if_owner(&self.lease, &self.owner).await {
+            warn!("failed to release the lease on {}: {e:#}", self.channel);
+        }
+    }
+
+    async fn publish(&self, candle: &Candle) {
+        let published = match serde_json::to_string(candle) {
+            Ok(json) => self.cache.publish(&self.channel, &json).await,
+            Err(e) => Err(e.into()),
+        };
+        if let Err(e) = published {
+            warn!("failed to publish to {}: {e:#}", self.channel);
+        }
+    }
+}
+
+async fn next_candle(upstream: &mut Option<ProviderStream>) -> Option<Candle> {
+    match upstream {
+        Some(stream) => stream.next().await,
+        None => std::future::pending().await,
+    }
+}
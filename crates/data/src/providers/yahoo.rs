@@ -1,5 +1,5 @@
 // crates/data/src/providers/yahoo.rs
-use crate::providers::{Provider, ProviderStream};
+use crate::providers::{Profile, Provider, ProviderStream};
 use anyhow::Result;
 use chrono::{DateTime, NaiveDateTime, Utc, Duration};
 use yahoo_finance_api as yahoo;
@@ -16,12 +16,16 @@ use log::{error, info};
 
 pub struct Yahoo {
     connector: Arc<yahoo::YahooConnector>,
+    /// Profiles need a session (cookie and crumb), which the connector
+    /// keeps to itself and refreshes as it goes
+    info: tokio::sync::Mutex<yahoo::YahooConnector>,
 }
 
 impl Yahoo {
     pub fn new() -> Result<Self> {
         Ok(Self {
             connector: Arc::new(yahoo::YahooConnector::new()?),
+            info: tokio::sync::Mutex::new(yahoo::YahooConnector::new()?),
         })
     }
 }
@@ -35,9 +39,9 @@ impl Provider for Yahoo {
         let symbol = symbol.to_string();
         let connector = self.connector.clone();
 
-        // Poll Yahoo Finance every N seconds
+        // Poll Yahoo Finance every N seconds, until the stream is dropped
         tokio::spawn(async move {
-            loop {
+            while !tx.is_closed() {
                 match connector.get_latest_quotes(&symbol, &*interval.to_string()).await {
                     Ok(resp) => {
                         let quotes = resp.quotes().unwrap();
@@ -67,6 +71,27 @@ impl Provider for Yahoo {
         ).await?.quotes()?.into_iter().map(|q| convert_quote_to_candle(&q, symbol)).collect()
     }
 
+    async fn historical_adjusted(&self, symbol: &str, interval: TimeInterval, start: Timestamp, end: Timestamp) -> Result<Vec<Candle>> {
+        self.connector.get_quote_history_interval(
+            symbol,
+            OffsetDateTime::from_unix_timestamp(start.timestamp())?,
+            OffsetDateTime::from_unix_timestamp(end.timestamp())?,
+            &interval.to_string()
+        ).await?.quotes()?.into_iter().map(|q| adjust(convert_quote_to_candle(&q, symbol)?, q.adjclose)).collect()
+    }
+
+    async fn profile(&self, symbol: &str) -> Result<Profile> {
+        let summary = self.info.lock().await.get_ticker_info(symbol).await?;
+        let Some(data) = summary.quote_summary.and_then(|s| s.result).and_then(|r| r.into_iter().next()) else {
+            anyhow::bail!("Yahoo has no profile for {}", symbol);
+        };
+        Ok(Profile {
+            name: data.quote_type.and_then(|q| q.long_name.or(q.short_name)),
+            sector: data.asset_profile.and_then(|p| p.sector),
+            market_cap: data.summary_detail.and_then(|d| d.market_cap).map(|cap| cap as f64),
+        })
+    }
+
     fn get_type(&self) -> ProviderType {
         ProviderType::Yahoo
     }
@@ -93,4 +118,21 @@ fn convert_quote_to_candle(quote: &yahoo::Quote, symbol: &str) -> Result<Candle>
         quote.close,
         quote.volume as f64
     ).map_err(|e| anyhow::anyhow!("Failed to create candle: {}", e))
-}
\ No newline at end of file
+}
+
+/// Scale a candle's prices to its adjusted close, and its volume the other
+/// way so traded value stays the same
+fn adjust(candle: Candle, adjclose: f64) -> Result<Candle> {
+    if candle.close <= 0.0 {
+        return Ok(candle);
+    }
+    let factor = adjclose / candle.close;
+    Candle::new(
+        candle.timestamp,
+        candle.open * factor,
+        candle.high * factor,
+        candle.low * factor,
+        adjclose,
+        candle.volume / factor
+    ).map_err(|e| anyhow::anyhow!("Failed to adjust candle: {}", e))
+}
@@ -0,0 +1,278 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
+use futures::StreamExt;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use api::ProviderType;
+use data::cache::{Cache, MemoryCache};
+use data::providers::{CachedProvider, Provider, ProviderStream};
+use dnn_core::market::Candle;
+use dnn_core::time::{TimeInterval, Timestamp};
+
+/// Counts upstream calls; live candles are pushed through `feeds`
+#[derive(Clone, Default)]
+struct FakeProvider {
+    historical_calls: Arc<AtomicUsize>,
+    feeds: Arc<Mutex<Vec<mpsc::UnboundedSender<Candle>>>>,
+}
+
+#[async_trait]
+impl Provider for FakeProvider {
+    async fn stream(&self, _symbol: &str, _interval: TimeInterval) -> anyhow::Result<ProviderStream> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        self.feeds.lock().unwrap().push(tx);
+        Ok(UnboundedReceiverStream::new(rx))
+    }
+
+    async fn historical(
+        &self,
+        _symbol: &str,
+        _interval: TimeInterval,
+        start: Timestamp,
+        _end: Timestamp,
+    ) -> anyhow::Result<Vec<Candle>> {
+        self.historical_calls.fetch_add(1, Ordering::SeqCst);
+        Ok(vec![candle(start, 100.0)])
+    }
+
+    fn get_type(&self) -> ProviderType {
+        ProviderType::Yahoo
+    }
+}
+
+fn at(day: u32) -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 1, day, 0, 0, 0).unwrap()
+}
+
+fn candle(timestamp: Timestamp, close: f64) -> Candle {
+    Candle::new(timestamp, close, close, close, close, 1000.0).unwrap()
+}
+
+#[tokio::test]
+async fn test_memory_cache_expires_values() {
+    let cache = MemoryCache::default();
+    cache.set("a", "1", Duration::from_millis(30)).await.unwrap();
+    assert_eq!(cache.get("a").await.unwrap().as_deref(), Some("1"));
+    assert!(!cache.set_if_absent("a", "2", Duration::from_secs(1)).await.unwrap());
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(cache.get("a").await.unwrap(), None);
+    assert!(cache.set_if_absent("a", "2", Duration::from_secs(1)).await.unwrap());
+    assert_eq!(cache.get("a").await.unwrap().as_deref(), Some("2"));
+}
+
+#[tokio::test]
+async fn test_memory_cache_pub_sub() {
+    let cache = MemoryCache::default();
+    let mut first = cache.subscribe("news").await.unwrap();
+    let mut second = cache.subscribe("news").await.unwrap();
+    cache.publish("other", "ignored").await.unwrap();
+    cache.publish("news", "hello").await.unwrap();
+
+    assert_eq!(first.next().await.as_deref(), Some("hello"));
+    assert_eq!(second.next().await.as_deref(), Some("hello"));
+}
+
+#[tokio::test]
+async fn test_historical_is_cached_until_ttl() {
+    let fake = FakeProvider::default();
+    let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
+    let provider = CachedProvider::new(fake.clone(), cache, Duration::from_millis(50));
+
+    let first = provider.historical("AAPL", TimeInterval::Day1, at(1), at(5)).await.unwrap();
+    let second = provider.historical("AAPL", TimeInterval::Day1, at(1), at(5)).await.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(fake.historical_calls.load(Ordering::SeqCst), 1);
+
+    provider.historical("AAPL", TimeInterval::Day1, at(2), at(5)).await.unwrap();
+    provider.historical("MSFT", TimeInterval::Day1, at(1), at(5)).await.unwrap();
+    assert_eq!(fake.historical_calls.load(Ordering::SeqCst), 3);
+
+    tokio::time::sleep(Duration::from_millis(70)).await;
+    provider.historical("AAPL", TimeInterval::Day1, at(1), at(5)).await.unwrap();
+    assert_eq!(fake.historical_calls.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test]
+async fn test_adjusted_history_is_cached_apart() {
+    let fake = FakeProvider::default();
+    let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
+    let provider = CachedProvider::new(fake.clone(), cache, Duration::from_secs(60));
+
+    provider.historical("AAPL", TimeInterval::Day1, at(1), at(5)).await.unwrap();
+    // Unadjusted candles aren't handed out as adjusted ones
+    let err = provider.historical_adjusted("AAPL", TimeInterval::Day1, at(1), at(5)).await.unwrap_err();
+    assert!(err.to_string().contains("no adjusted prices"));
+}
+
+#[tokio::test]
+async fn test_replicas_share_one_upstream_stream() {
+    let fake = FakeProvider::default();
+    let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
+    // Two backends talking to the same cache
+    let replica_a = CachedProvider::new(fake.clone(), cache.clone(), Duration::ZERO);
+    let replica_b = CachedProvider::new(fake.clone(), cache, Duration::ZERO);
+
+    let mut stream_a = replica_a.stream("AAPL", TimeInterval::Minute1).await.unwrap();
+    // The first subscriber takes the lease and opens the provider stream
+    let upstream = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            if let Some(feed) = fake.feeds.lock().unwrap().first() {
+                return feed.clone();
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .unwrap();
+    let mut stream_b = replica_b.stream("AAPL", TimeInterval::Minute1).await.unwrap();
+    let mut stream_c = replica_b.stream("AAPL", TimeInterval::Minute1).await.unwrap();
+
+    upstream.send(candle(at(1), 101.0)).unwrap();
+    for stream in [&mut stream_a, &mut stream_b, &mut stream_c] {
+        let received = tokio::time::timeout(Duration::from_secs(1), stream.next()).await.unwrap();
+        assert_eq!(received.map(|c| c.close), Some(101.0));
+    }
+    assert_eq!(fake.feeds.lock().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_memory_cache_only_renews_and_releases_its_own_lease() {
+    let cache = MemoryCache::default();
+    assert!(cache.set_if_absent("lease", "a", Duration::from_millis(30)).await.unwrap());
+    assert!(!cache.renew_if_owner("lease", "b", Duration::from_secs(1)).await.unwrap());
+    assert!(!cache.release_if_owner("lease", "b").await.unwrap());
+
+    assert!(cache.renew_if_owner("lease", "a", Duration::from_secs(1)).await.unwrap());
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(cache.get("lease").await.unwrap().as_deref(), Some("a"));
+
+    assert!(cache.release_if_owner("lease", "a").await.unwrap());
+    assert_eq!(cache.get("lease").await.unwrap(), None);
+    // A lapsed lease can't be renewed
+    assert!(!cache.renew_if_owner("lease", "a", Duration::from_secs(1)).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_leader_releases_the_lease_when_its_subscriber_leaves() {
+    let fake = FakeProvider::default();
+    let cache: Arc<dyn Cache> = Arc::new(MemoryCache::default());
+    let provider = CachedProvider::new(fake.clone(), cache.clone(), Duration::ZERO);
+    let lease = format!("lease:stream:{}:AAPL:{}", ProviderType::Yahoo.as_ref(), TimeInterval::Minute1);
+
+    let stream = provider.stream("AAPL", TimeInterval::Minute1).await.unwrap();
+    let held = tokio::time::timeout(Duration::from_secs(1), async {
+        while cache.get(&lease).await.unwrap().is_none() || fake.feeds.lock().unwrap().is_empty() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await;
+    assert!(held.is_ok());
+
+    // Freed right away rather than after the lease lapses
+    drop(stream);
+    let released = tokio::time::timeout(Duration::from_secs(1), async {
+        while cache.get(&lease).await.unwrap().is_some() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await;
+    assert!(released.is_ok());
+}
+
+/// Enough of a Redis server for [`RedisCache`]: strings with expiry ignored,
+/// and the two lease scripts told apart by their argument count
+#[cfg(feature = "redis")]
+async fn fake_redis(listener: tokio::net::TcpListener, commands: Arc<Mutex<Vec<Vec<String>>>>) {
+    use std::collections::HashMap;
+    use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+
+    let values = Arc::new(Mutex::new(HashMap::<String, String>::new()));
+    while let Ok((socket, _)) = listener.accept().await {
+        let (values, commands) = (values.clone(), commands.clone());
+        tokio::spawn(async move {
+            let mut socket = BufReader::new(socket);
+            let mut line = String::new();
+            let mut loaded = false;
+            loop {
+                line.clear();
+                if socket.read_line(&mut line).await.unwrap_or(0) == 0 {
+                    return;
+                }
+                let count: usize = line.trim_start_matches('*').trim().parse().unwrap();
+                let mut args = Vec::with_capacity(count);
+                for _ in 0..count {
+                    line.clear();
+                    socket.read_line(&mut line).await.unwrap();
+                    let len: usize = line.trim_start_matches('$').trim().parse().unwrap();
+                    let mut arg = vec![0; len + 2];
+                    socket.read_exact(&mut arg).await.unwrap();
+                    arg.truncate(len);
+                    args.push(String::from_utf8(arg).unwrap());
+                }
+                commands.lock().unwrap().push(args.clone());
+
+                let bulk = |value: Option<&String>| value.map_or("$-1\r\n".to_owned(), |v| format!("${}\r\n{v}\r\n", v.len()));
+                let reply = {
+                    let mut values = values.lock().unwrap();
+                    match args[0].to_uppercase().as_str() {
+                        "GET" => bulk(values.get(&args[1])),
+                        "SET" if args.iter().any(|a| a == "NX") && values.contains_key(&args[1]) => "$-1\r\n".to_owned(),
+                        "SET" => {
+                            values.insert(args[1].clone(), args[2].clone());
+                            "+OK\r\n".to_owned()
+                        }
+                        "SCRIPT" => {
+                            loaded = true;
+                            "$1\r\nx\r\n".to_owned()
+                        }
+                        "EVALSHA" if !loaded => "-NOSCRIPT No matching script\r\n".to_owned(),
+                        // EVALSHA sha 1 key owner [ttl]
+                        "EVALSHA" => {
+                            let owns = values.get(&args[3]) == Some(&args[4]);
+                            if owns && args.len() == 5 {
+                                values.remove(&args[3]);
+                            }
+                            format!(":{}\r\n", i32::from(owns))
+                        }
+                        "PUBLISH" => ":0\r\n".to_owned(),
+                        _ => "+OK\r\n".to_owned(),
+                    }
+                };
+                socket.get_mut().write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+    }
+}
+
+#[cfg(feature = "redis")]
+#[tokio::test]
+async fn test_redis_cache_commands() {
+    use data::cache::RedisCache;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let commands = Arc::new(Mutex::new(Vec::new()));
+    tokio::spawn(fake_redis(listener, commands.clone()));
+
+    let cache = RedisCache::new(&format!("redis://{addr}"), Some("secret".to_owned())).unwrap();
+    cache.set("key", "fresh", Duration::from_secs(5)).await.unwrap();
+    assert!(!cache.set_if_absent("key", "stale", Duration::from_secs(5)).await.unwrap());
+    assert_eq!(cache.get("key").await.unwrap().as_deref(), Some("fresh"));
+    assert_eq!(cache.get("gone").await.unwrap(), None);
+
+    cache.set("lease", "a", Duration::from_secs(5)).await.unwrap();
+    assert!(!cache.renew_if_owner("lease", "b", Duration::from_secs(5)).await.unwrap());
+    assert!(cache.renew_if_owner("lease", "a", Duration::from_secs(5)).await.unwrap());
+    assert!(!cache.release_if_owner("lease", "b").await.unwrap());
+    assert!(cache.release_if_owner("lease", "a").await.unwrap());
+    assert_eq!(cache.get("lease").await.unwrap(), None);
+
+    let commands = commands.lock().unwrap();
+    assert_eq!(commands[0], ["AUTH", "secret"]);
+    assert!(commands.contains(&["SET", "key", "fresh", "PX", "5000"].map(str::to_owned).to_vec()));
+    assert!(commands.iter().any(|c| c[0] == "SET" && c.iter().any(|a| a == "NX")));
+}
@@ -1,4 +1,5 @@
 use thiserror::Error;
+use crate::time::Timestamp;
 
 #[derive(Error, Debug, Clone)]
 pub enum PriceError {
@@ -9,3 +10,31 @@ pub enum PriceError {
     #[error("Calculation error: {0}")]
     CalculationError(String),
 }
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FxError {
+    #[error("No FX rate available for {from}/{to}")]
+    MissingRate { from: String, to: String },
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum LedgerError {
+    #[error("Ledger event at {ts} is earlier than the last recorded event at {last}")]
+    OutOfOrder { ts: Timestamp, last: Timestamp },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BookError {
+    #[error("Order {0} is already in the book")]
+    DuplicateOrder(u64),
+    #[error("Order {0} is not in the book")]
+    UnknownOrder(u64),
+    #[error("Order for {0} sent to the wrong book")]
+    WrongSymbol(String),
+    #[error("Invalid quantity {0}")]
+    InvalidQuantity(f64),
+    #[error("Invalid price {0}")]
+    InvalidPrice(f64),
+    #[error("Invalid tick size {0}")]
+    InvalidTickSize(f64),
+}
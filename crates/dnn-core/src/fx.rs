@@ -0,0 +1,115 @@
+use std::collections::{BTreeSet, HashMap};
+use serde::{Deserialize, Serialize};
+use crate::error::FxError;
+use crate::market::Candle;
+use crate::time::Timestamp;
+
+/// Snapshot of FX rates at a single point in time.
+///
+/// A rate for `from/to` is the amount of `to` received for one unit of `from`.
+/// Inverse and one-hop cross rates (e.g. EUR/GBP via USD) are derived on lookup.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FxRates {
+    rates: HashMap<String, HashMap<String, f64>>,
+}
+
+impl FxRates {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the rate for one unit of `from` expressed in `to`
+    pub fn set(&mut self, from: &str, to: &str, rate: f64) {
+        self.rates
+            .entry(from.to_owned())
+            .or_default()
+            .insert(to.to_owned(), rate);
+    }
+
+    fn direct(&self, from: &str, to: &str) -> Option<f64> {
+        if from == to {
+            return Some(1.0);
+        }
+        if let Some(rate) = self.rates.get(from).and_then(|m| m.get(to)) {
+            return Some(*rate);
+        }
+        self.rates
+            .get(to)
+            .and_then(|m| m.get(from))
+            .filter(|r| **r != 0.0)
+            .map(|r| 1.0 / r)
+    }
+
+    /// Look up a rate, falling back to the inverse or a cross through a common
+    /// currency: the first in alphabetical order that has both rates
+    pub fn rate(&self, from: &str, to: &str) -> Result<f64, FxError> {
+        if let Some(rate) = self.direct(from, to) {
+            return Ok(rate);
+        }
+
+        // Sorted, so a cross goes through the same currency every time
+        let intermediates: BTreeSet<&str> = self.rates.iter().flat_map(|(k, m)| {
+            std::iter::once(k.as_str()).chain(m.keys().map(String::as_str))
+        }).collect();
+        for via in intermediates {
+            if let (Some(a), Some(b)) = (self.direct(from, via), self.direct(via, to)) {
+                return Ok(a * b);
+            }
+        }
+
+        Err(FxError::MissingRate { from: from.to_owned(), to: to.to_owned() })
+    }
+
+    /// Convert an amount between currencies
+    pub fn convert(&self, amount: f64, from: &str, to: &str) -> Result<f64, FxError> {
+        Ok(amount * self.rate(from, to)?)
+    }
+}
+
+/// Time series of FX rates per currency pair, used to value portfolios historically
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FxHistory {
+    series: HashMap<String, HashMap<String, Vec<(Timestamp, f64)>>>,
+}
+
+impl FxHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a single observation for a pair
+    pub fn insert(&mut self, from: &str, to: &str, ts: Timestamp, rate: f64) {
+        let series = self.series
+            .entry(from.to_owned())
+            .or_default()
+            .entry(to.to_owned())
+            .or_default();
+        let idx = series.partition_point(|(t, _)| *t <= ts);
+        series.insert(idx, (ts, rate));
+    }
+
+    /// Add FX candles for a pair, using the close as the rate
+    pub fn insert_candles(&mut self, from: &str, to: &str, candles: &[Candle]) {
+        for candle in candles {
+            self.insert(from, to, candle.timestamp, candle.close);
+        }
+    }
+
+    /// Latest known rate of every pair at or before `ts`
+    pub fn rates_at(&self, ts: Timestamp) -> FxRates {
+        let mut rates = FxRates::new();
+        for (from, pairs) in &self.series {
+            for (to, series) in pairs {
+                let idx = series.partition_point(|(t, _)| *t <= ts);
+                if idx > 0 {
+                    rates.set(from, to, series[idx - 1].1);
+                }
+            }
+        }
+        rates
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.is_empty()
+    }
+}
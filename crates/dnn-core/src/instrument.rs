@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use anyhow::Context;
+use chrono::{Datelike, Duration, FixedOffset, NaiveDate, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use crate::time::Timestamp;
+
+/// Broad asset class of a tradable instrument
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AssetClass {
+    Equity,
+    Crypto,
+    Future,
+    Option,
+}
+
+impl fmt::Display for AssetClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Self::Equity => "equity",
+            Self::Crypto => "crypto",
+            Self::Future => "future",
+            Self::Option => "option",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// When a timezone observes daylight saving time, one hour ahead of its
+/// standard UTC offset
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaylightSaving {
+    /// Second Sunday of March to first Sunday of November, switching at
+    /// 02:00 local time (US and Canada)
+    UnitedStates,
+    /// Last Sunday of March to last Sunday of October, switching at 01:00 UTC
+    European,
+}
+
+impl DaylightSaving {
+    /// Whether daylight saving time is in effect at `ts` in a timezone
+    /// `standard_offset` seconds east of UTC
+    fn in_effect(self, ts: Timestamp, standard_offset: i32) -> bool {
+        let year = ts.year();
+        let sunday = |month, n| NaiveDate::from_weekday_of_month_opt(year, month, Weekday::Sun, n);
+        let last_sunday = |month| sunday(month, 5).or_else(|| sunday(month, 4));
+        let (start, end) = match self {
+            Self::UnitedStates => {
+                let local = |date: Option<NaiveDate>, offset: i32| {
+                    date.and_then(|d| d.and_hms_opt(2, 0, 0)).map(|t| t.and_utc() - Duration::seconds(i64::from(offset)))
+                };
+                (local(sunday(3, 2), standard_offset), local(sunday(11, 1), standard_offset + 3600))
+            }
+            Self::European => {
+                let utc = |date: Option<NaiveDate>| date.and_then(|d| d.and_hms_opt(1, 0, 0)).map(|t| t.and_utc());
+                (utc(last_sunday(3)), utc(last_sunday(10)))
+            }
+        };
+        matches!((start, end), (Some(start), Some(end)) if ts >= start && ts < end)
+    }
+}
+
+/// Regular trading session of an exchange.
+///
+/// Session times are exchange-local: a UTC offset of standard time, an hour
+/// later while `daylight_saving` is in effect. A session where `open == close`
+/// trades around the clock.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TradingCalendar {
+    /// Of standard time
+    pub utc_offset_minutes: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub daylight_saving: Option<DaylightSaving>,
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+    pub weekdays: Vec<Weekday>,
+    #[serde(default)]
+    pub holidays: Vec<NaiveDate>,
+}
+
+impl TradingCalendar {
+    /// 24/7 calendar, used for crypto
+    pub fn continuous() -> Self {
+        Self {
+            utc_offset_minutes: 0,
+            daylight_saving: None,
+            open: NaiveTime::MIN,
+            close: NaiveTime::MIN,
+            weekdays: vec![
+                Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu,
+                Weekday::Fri, Weekday::Sat, Weekday::Sun,
+            ],
+            holidays: Vec::new(),
+        }
+    }
+
+    /// NYSE/NASDAQ regular hours (09:30-16:00 US Eastern)
+    pub fn us_equities() -> Self {
+        Self {
+            utc_offset_minutes: -5 * 60,
+            daylight_saving: Some(DaylightSaving::UnitedStates),
+            open: NaiveTime::from_hms_opt(9, 30, 0).unwrap_or(NaiveTime::MIN),
+            close: NaiveTime::from_hms_opt(16, 0, 0).unwrap_or(NaiveTime::MIN),
+            weekdays: vec![Weekday::Mon, Weekday::Tue, Weekday::Wed, Weekday::Thu, Weekday::Fri],
+            holidays: Vec::new(),
+        }
+    }
+
+    /// Calendar of instruments that don't name one: continuous for crypto,
+    /// US equity hours otherwise
+    pub fn for_asset_class(asset_class: AssetClass) -> Self {
+        match asset_class {
+            AssetClass::Crypto => Self::continuous(),
+            _ => Self::us_equities(),
+        }
+    }
+
+    /// Check whether the market is open at the given instant
+    pub fn is_open(&self, ts: Timestamp) -> bool {
+        let standard = self.utc_offset_minutes * 60;
+        let dst = self.daylight_saving.is_some_and(|rule| rule.in_effect(ts, standard));
+        let Some(offset) = FixedOffset::east_opt(if dst { standard + 3600 } else { standard }) else {
+            return false;
+        };
+        let local = ts.with_timezone(&offset).naive_local();
+
+        if !self.weekdays.contains(&local.weekday()) || self.holidays.contains(&local.date()) {
+            return false;
+        }
+        if self.open == self.close {
+            return true;
+        }
+
+        let time = local.time();
+        time >= self.open && time < self.close
+    }
+}
+
+impl Default for TradingCalendar {
+    fn default() -> Self {
+        Self::continuous()
+    }
+}
+
+fn default_tick_size() -> f64 { 0.01 }
+pub(crate) fn default_one() -> f64 { 1.0 }
+
+/// Static reference data describing a tradable instrument
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "InstrumentRecord")]
+pub struct Instrument {
+    pub symbol: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    pub asset_class: AssetClass,
+    pub exchange: String,
+    pub currency: String,
+    #[serde(default = "default_tick_size")]
+    pub tick_size: f64,
+    #[serde(default = "default_one")]
+    pub lot_size: f64,
+    #[serde(default = "default_one")]
+    pub multiplier: f64,
+    #[serde(default)]
+    pub sector: Option<String>,
+    /// [`TradingCalendar::for_asset_class`] unless given
+    pub calendar: TradingCalendar,
+}
+
+/// An [`Instrument`] as stored, where fields may be left out
+#[derive(Deserialize)]
+struct InstrumentRecord {
+    symbol: String,
+    #[serde(default)]
+    name: Option<String>,
+    asset_class: AssetClass,
+    exchange: String,
+    currency: String,
+    #[serde(default = "default_tick_size")]
+    tick_size: f64,
+    #[serde(default = "default_one")]
+    lot_size: f64,
+    #[serde(default = "default_one")]
+    multiplier: f64,
+    #[serde(default)]
+    sector: Option<String>,
+    #[serde(default)]
+    calendar: Option<TradingCalendar>,
+}
+
+impl From<InstrumentRecord> for Instrument {
+    fn from(record: InstrumentRecord) -> Self {
+        Self {
+            calendar: record.calendar.unwrap_or_else(|| TradingCalendar::for_asset_class(record.asset_class)),
+            symbol: record.symbol,
+            name: record.name,
+            asset_class: record.asset_class,
+            exchange: record.exchange,
+            currency: record.currency,
+            tick_size: record.tick_size,
+            lot_size: record.lot_size,
+            multiplier: record.multiplier,
+            sector: record.sector,
+        }
+    }
+}
+
+impl Instrument {
+    pub fn new(symbol: impl Into<String>, asset_class: AssetClass, exchange: impl Into<String>, currency: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            name: None,
+            asset_class,
+            exchange: exchange.into(),
+            currency: currency.into(),
+            tick_size: default_tick_size(),
+            lot_size: 1.0,
+            multiplier: 1.0,
+            sector: None,
+            calendar: TradingCalendar::for_asset_class(asset_class),
+        }
+    }
+
+    /// Round a price to the nearest valid tick
+    pub fn round_price(&self, price: f64) -> f64 {
+        if self.tick_size <= 0.0 {
+            return price;
+        }
+        (price / self.tick_size).round() * self.tick_size
+    }
+
+    /// Round a quantity down (towards zero) to a whole number of lots
+    pub fn round_qty(&self, qty: f64) -> f64 {
+        if self.lot_size <= 0.0 {
+            return qty;
+        }
+        // Small epsilon so that e.g. 0.3 / 0.1 doesn't truncate to 2 lots
+        let lots = qty / self.lot_size;
+        (lots + lots.signum() * 1e-9).trunc() * self.lot_size
+    }
+
+    /// Cash value of `qty` units traded at `price`
+    pub fn notional(&self, qty: f64, price: f64) -> f64 {
+        qty * price * self.multiplier
+    }
+
+    pub fn is_open(&self, ts: Timestamp) -> bool {
+        self.calendar.is_open(ts)
+    }
+}
+
+/// Lookup table of instruments keyed by symbol
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentMaster {
+    instruments: HashMap<String, Instrument>,
+}
+
+impl InstrumentMaster {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load an instrument master from a JSON file containing an array of instruments
+    pub fn load(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read instrument master {}", path.display()))?;
+        Self::from_json(&text)
+            .with_context(|| format!("Failed to parse instrument master {}", path.display()))
+    }
+
+    pub fn from_json(json: &str) -> anyhow::Result<Self> {
+        let instruments: Vec<Instrument> = serde_json::from_str(json)?;
+        Ok(instruments.into_iter().collect())
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        let mut instruments: Vec<&Instrument> = self.instruments.values().collect();
+        instruments.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+        Ok(serde_json::to_string_pretty(&instruments)?)
+    }
+
+    /// Add or replace an instrument
+    pub fn insert(&mut self, instrument: Instrument) {
+        self.instruments.insert(instrument.symbol.clone(), instrument);
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<&Instrument> {
+        self.instruments.get(symbol)
+    }
+
+    pub fn contains(&self, symbol: &str) -> bool {
+        self.instruments.contains_key(symbol)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.values()
+    }
+
+    /// All instruments in a sector (case-insensitive)
+    pub fn by_sector<'a>(&'a self, sector: &'a str) -> impl Iterator<Item = &'a Instrument> + 'a {
+        self.iter().filter(move |i| {
+            i.sector.as_deref().is_some_and(|s| s.eq_ignore_ascii_case(sector))
+        })
+    }
+
+    pub fn by_asset_class(&self, asset_class: AssetClass) -> impl Iterator<Item = &Instrument> {
+        self.iter().filter(move |i| i.asset_class == asset_class)
+    }
+
+    pub fn by_exchange<'a>(&'a self, exchange: &'a str) -> impl Iterator<Item = &'a Instrument> + 'a {
+        self.iter().filter(move |i| i.exchange.eq_ignore_ascii_case(exchange))
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+}
+
+impl FromIterator<Instrument> for InstrumentMaster {
+    fn from_iter<T: IntoIterator<Item = Instrument>>(iter: T) -> Self {
+        Self {
+            instruments: iter.into_iter().map(|i| (i.symbol.clone(), i)).collect(),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::error::{FxError, LedgerError};
+use crate::fx::FxRates;
+use crate::instrument::default_one;
+use crate::portfolio::Portfolio;
+use crate::time::Timestamp;
+use crate::OrderSide;
+
+/// Something that changed the portfolio. Events are immutable once recorded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LedgerEvent {
+    Fill {
+        symbol: String,
+        currency: String,
+        side: OrderSide,
+        qty: f64,
+        price: f64,
+        /// Contract multiplier of the instrument: cash moves by `qty * price * multiplier`
+        #[serde(default = "default_one")]
+        multiplier: f64,
+    },
+    Fee {
+        currency: String,
+        amount: f64,
+        symbol: Option<String>,
+    },
+    Dividend {
+        symbol: String,
+        currency: String,
+        amount: f64,
+    },
+    /// Deposit (positive) or withdrawal (negative)
+    CashMovement {
+        currency: String,
+        amount: f64,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub seq: u64,
+    pub ts: Timestamp,
+    pub event: LedgerEvent,
+}
+
+/// Portfolio state right after the entry with sequence number `seq` was applied
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub seq: u64,
+    pub ts: Timestamp,
+    pub portfolio: Portfolio,
+}
+
+/// Append-only record of every portfolio event.
+///
+/// The current [`Portfolio`] is derived by applying events in order; past states
+/// are rebuilt from the closest earlier snapshot plus the events after it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "LedgerRecord", into = "LedgerRecord")]
+pub struct Ledger {
+    base_currency: String,
+    snapshot_every: usize,
+    entries: Vec<LedgerEntry>,
+    snapshots: Vec<Snapshot>,
+    state: Portfolio,
+}
+
+impl Ledger {
+    pub fn new(base_currency: &str) -> Self {
+        Self {
+            base_currency: base_currency.to_owned(),
+            snapshot_every: 0,
+            entries: Vec::new(),
+            snapshots: Vec::new(),
+            state: Portfolio::with_base_currency(base_currency, 0.0),
+        }
+    }
+
+    /// Take a snapshot automatically every `n` events (0 disables)
+    pub fn with_snapshot_interval(mut self, n: usize) -> Self {
+        self.snapshot_every = n;
+        self
+    }
+
+    /// Append an event and apply it to the current state
+    pub fn record(&mut self, ts: Timestamp, event: LedgerEvent) -> Result<&LedgerEntry, LedgerError> {
+        if let Some(last) = self.entries.last()
+            && ts < last.ts
+        {
+            return Err(LedgerError::OutOfOrder { ts, last: last.ts });
+        }
+
+        self.state.apply_event(&event);
+        let seq = self.entries.len() as u64 + 1;
+        self.entries.push(LedgerEntry { seq, ts, event });
+
+        if self.snapshot_every > 0 && self.entries.len().is_multiple_of(self.snapshot_every) {
+            self.snapshot();
+        }
+
+        Ok(&self.entries[self.entries.len() - 1])
+    }
+
+    /// Snapshot the current state (e.g. at end of day)
+    pub fn snapshot(&mut self) {
+        let Some(last) = self.entries.last() else {
+            return;
+        };
+        if self.snapshots.last().is_some_and(|s| s.seq == last.seq) {
+            return;
+        }
+        self.snapshots.push(Snapshot {
+            seq: last.seq,
+            ts: last.ts,
+            portfolio: self.state.clone(),
+        });
+    }
+
+    /// Current portfolio state
+    pub fn portfolio(&self) -> &Portfolio {
+        &self.state
+    }
+
+    pub fn base_currency(&self) -> &str {
+        &self.base_currency
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    /// Entries with `start <= ts < end`
+    pub fn entries_between(&self, start: Timestamp, end: Timestamp) -> &[LedgerEntry] {
+        let from = self.entries.partition_point(|e| e.ts < start);
+        let to = self.entries.partition_point(|e| e.ts < end);
+        &self.entries[from..to.max(from)]
+    }
+
+    /// Portfolio as it was after every event at or before `ts`
+    pub fn state_at(&self, ts: Timestamp) -> Portfolio {
+        let snapshot = self.snapshots.iter().rev().find(|s| s.ts <= ts);
+        let (mut portfolio, after_seq) = match snapshot {
+            Some(s) => (s.portfolio.clone(), s.seq),
+            None => (Portfolio::with_base_currency(&self.base_currency, 0.0), 0),
+        };
+
+        // seq is 1-based, so entries after `after_seq` start at that index
+        for entry in self.entries[after_seq as usize..].iter().take_while(|e| e.ts <= ts) {
+            portfolio.apply_event(&entry.event);
+        }
+        portfolio
+    }
+
+    /// Net deposits minus withdrawals with `start < ts <= end`, in the base currency
+    pub fn net_cash_flows(&self, start: Timestamp, end: Timestamp, fx: &FxRates) -> Result<f64, FxError> {
+        self.entries
+            .iter()
+            .filter(|e| e.ts > start && e.ts <= end)
+            .filter_map(|e| match &e.event {
+                LedgerEvent::CashMovement { currency, amount } => Some((currency, *amount)),
+                _ => None,
+            })
+            .map(|(ccy, amount)| fx.convert(amount, ccy, &self.base_currency))
+            .sum()
+    }
+
+    /// Change in portfolio value between two instants, excluding deposits and withdrawals.
+    ///
+    /// Used for the daily (start of day to now) and total (inception to now) profit figures.
+    pub fn pnl_between(
+        &self,
+        start: Timestamp,
+        end: Timestamp,
+        start_prices: &HashMap<String, f64>,
+        end_prices: &HashMap<String, f64>,
+        fx: &FxRates,
+    ) -> Result<f64, FxError> {
+        let start_value = self.state_at(start).total_value(start_prices, fx)?;
+        let end_value = self.state_at(end).total_value(end_prices, fx)?;
+        Ok(end_value - start_value - self.net_cash_flows(start, end, fx)?)
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string(self)
+    }
+
+    pub fn from_json(json: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(json)
+    }
+}
+
+/// Serialized form of a [`Ledger`]; the current state is rebuilt on load
+#[derive(Serialize, Deserialize)]
+struct LedgerRecord {
+    base_currency: String,
+    #[serde(default)]
+    snapshot_every: usize,
+    entries: Vec<LedgerEntry>,
+    #[serde(default)]
+    snapshots: Vec<Snapshot>,
+}
+
+impl From<LedgerRecord> for Ledger {
+    fn from(record: LedgerRecord) -> Self {
+        let mut state = Portfolio::with_base_currency(&record.base_currency, 0.0);
+        for entry in &record.entries {
+            state.apply_event(&entry.event);
+        }
+
+        // A snapshot past the last entry doesn't describe this ledger, and `state_at` would
+        // index out of bounds replaying from it
+        let mut snapshots = record.snapshots;
+        snapshots.retain(|s| s.seq as usize <= record.entries.len());
+
+        Self {
+            base_currency: record.base_currency,
+            snapshot_every: record.snapshot_every,
+            entries: record.entries,
+            snapshots,
+            state,
+        }
+    }
+}
+
+impl From<Ledger> for LedgerRecord {
+    fn from(ledger: Ledger) -> Self {
+        Self {
+            base_currency: ledger.base_currency,
+            snapshot_every: ledger.snapshot_every,
+            entries: ledger.entries,
+            snapshots: ledger.snapshots,
+        }
+    }
+}
@@ -1,13 +1,19 @@
+pub mod fx;
+pub mod instrument;
+pub mod ledger;
 pub mod market;
+pub mod orderbook;
 mod tests;
 pub mod portfolio;
+pub mod resample;
 pub mod time;
-mod error;
+pub mod error;
 
 use std::fmt;
 use serde::{Deserialize, Serialize};
+use crate::time::Timestamp;
 
-#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Serialize, Deserialize)]
 pub enum OrderSide { Buy, Sell }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,6 +25,83 @@ pub struct Order {
     pub side: OrderSide,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ExecutionStatus {
+    /// Accepted and working, nothing filled yet
+    New,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    Rejected { reason: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExecutionReport {
+    pub order_id: u64,
+    pub status: ExecutionStatus,
+    /// Quantity of this fill (not cumulative)
+    pub filled_qty: f64,
+    pub fill_price: f64,
+    /// Quantity still working after this report
+    #[serde(default)]
+    pub leaves_qty: f64,
+    pub ts: Timestamp,
+}
+
+impl ExecutionReport {
+    pub fn accepted(order_id: u64, leaves_qty: f64, ts: Timestamp) -> Self {
+        Self { order_id, status: ExecutionStatus::New, filled_qty: 0.0, fill_price: 0.0, leaves_qty, ts }
+    }
+
+    pub fn filled(order_id: u64, qty: f64, price: f64, ts: Timestamp) -> Self {
+        Self { order_id, status: ExecutionStatus::Filled, filled_qty: qty, fill_price: price, leaves_qty: 0.0, ts }
+    }
+
+    pub fn partial(order_id: u64, qty: f64, price: f64, leaves_qty: f64, ts: Timestamp) -> Self {
+        Self {
+            order_id,
+            status: ExecutionStatus::PartiallyFilled,
+            filled_qty: qty,
+            fill_price: price,
+            leaves_qty,
+            ts,
+        }
+    }
+
+    pub fn cancelled(order_id: u64, ts: Timestamp) -> Self {
+        Self { order_id, status: ExecutionStatus::Cancelled, filled_qty: 0.0, fill_price: 0.0, leaves_qty: 0.0, ts }
+    }
+
+    pub fn rejected(order_id: u64, reason: impl Into<String>, ts: Timestamp) -> Self {
+        Self {
+            order_id,
+            status: ExecutionStatus::Rejected { reason: reason.into() },
+            filled_qty: 0.0,
+            fill_price: 0.0,
+            leaves_qty: 0.0,
+            ts,
+        }
+    }
+
+    pub fn is_rejected(&self) -> bool {
+        matches!(self.status, ExecutionStatus::Rejected { .. })
+    }
+
+    /// Whether this report carries a (partial) fill
+    pub fn is_fill(&self) -> bool {
+        matches!(self.status, ExecutionStatus::Filled | ExecutionStatus::PartiallyFilled)
+    }
+
+    /// Whether the order is done and will produce no more reports
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self.status,
+            ExecutionStatus::Filled | ExecutionStatus::Cancelled | ExecutionStatus::Rejected { .. }
+        )
+    }
+}
+
 /// Trading signal types
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Signal {
@@ -46,54 +129,65 @@ pub enum OrderType {
     StopLimit { stop_price: f64, limit_price: f64 },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub symbol: String,
+    pub currency: String,
     pub qty: f64,
     pub avg_price: f64,
     pub realized_pnl: f64,
+    /// Contract multiplier: value and `PnL` are per `qty * price * multiplier`
+    #[serde(default = "instrument::default_one")]
+    pub multiplier: f64,
 }
 
 impl Position {
-    pub fn new(symbol: String) -> Self {
+    pub fn new(symbol: String, currency: String) -> Self {
         Self {
             symbol,
+            currency,
             qty: 0.0,
             avg_price: 0.0,
             realized_pnl: 0.0,
+            multiplier: 1.0,
         }
     }
 
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    /// Applies a fill. Fills on the side of the position (or opening one)
+    /// move the average price; fills against it realize `PnL` on the part
+    /// they close, and any rest opens the other side at `price`.
     pub fn update(&mut self, side: OrderSide, qty: f64, price: f64) {
-        match side {
-            OrderSide::Buy => {
-                let new_qty = self.qty + qty;
-                self.avg_price = (self.avg_price * self.qty + price * qty) / new_qty;
-                self.qty = new_qty;
-            }
-            OrderSide::Sell => {
-                if qty <= self.qty {
-                    // closing long
-                    let pnl = (price - self.avg_price) * qty;
-                    self.realized_pnl += pnl;
-                    self.qty -= qty;
-                } else {
-                    // shorting more than current long (flip)
-                    let pnl = (price - self.avg_price) * self.qty;
-                    self.realized_pnl += pnl;
-                    self.qty = -(qty - self.qty);
-                    self.avg_price = price;
-                }
+        if qty == 0.0 {
+            return;
+        }
+        let signed = match side {
+            OrderSide::Buy => qty,
+            OrderSide::Sell => -qty,
+        };
+        let new_qty = self.qty + signed;
+        if self.qty == 0.0 || self.qty.signum() == signed.signum() {
+            self.avg_price = (self.avg_price * self.qty.abs() + price * qty) / new_qty.abs();
+        } else {
+            let closed = qty.min(self.qty.abs());
+            self.realized_pnl += (price - self.avg_price) * closed * self.qty.signum() * self.multiplier;
+            if new_qty != 0.0 && new_qty.signum() != self.qty.signum() {
+                self.avg_price = price;
             }
         }
+        self.qty = new_qty;
     }
 
     pub fn market_value(&self, price: f64) -> f64 {
-        self.qty * price
+        self.qty * price * self.multiplier
     }
 
     pub fn unrealized_pnl(&self, price: f64) -> f64 {
-        (price - self.avg_price) * self.qty
+        (price - self.avg_price) * self.qty * self.multiplier
     }
 }
 
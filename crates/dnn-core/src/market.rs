@@ -3,7 +3,9 @@ use std::ops::Index;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use crate::error::PriceError;
+use crate::orderbook::Level;
 use crate::time::{TimeInterval, Timestamp};
+use crate::OrderSide;
 
 /// Core OHLCV (Open, High, Low, Close, Volume) data structure
 /// This is the fundamental building block for all market data
@@ -171,4 +173,168 @@ impl IntoIterator for CandleRange {
     fn into_iter(self) -> Self::IntoIter {
         self.data.into_iter()
     }
-}
\ No newline at end of file
+}
+
+/// A single print on the tape
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub symbol: String,
+    pub ts: Timestamp,
+    pub price: f64,
+    pub size: f64,
+    /// Side of the order that took liquidity, if the venue reports it
+    pub aggressor: Option<OrderSide>,
+}
+
+impl Trade {
+    /// One-trade candle, so ticks can go wherever bars go
+    pub fn to_candle(&self) -> Candle {
+        Candle {
+            timestamp: self.ts,
+            open: self.price,
+            high: self.price,
+            low: self.price,
+            close: self.price,
+            volume: self.size,
+        }
+    }
+}
+
+/// Top of book
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quote {
+    pub symbol: String,
+    pub ts: Timestamp,
+    pub bid: f64,
+    pub bid_size: f64,
+    pub ask: f64,
+    pub ask_size: f64,
+}
+
+impl Quote {
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    pub fn spread(&self) -> f64 {
+        self.ask - self.bid
+    }
+}
+
+/// Full L2 book, best prices first
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookSnapshot {
+    pub symbol: String,
+    pub ts: Timestamp,
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+/// Change to one L2 price level. A `qty` of zero removes the level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BookDelta {
+    pub symbol: String,
+    pub ts: Timestamp,
+    pub side: OrderSide,
+    pub price: f64,
+    pub qty: f64,
+    pub orders: usize,
+}
+
+impl BookSnapshot {
+    pub fn best_bid(&self) -> Option<&Level> {
+        self.bids.first()
+    }
+
+    pub fn best_ask(&self) -> Option<&Level> {
+        self.asks.first()
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+
+    /// Apply an incremental update, keeping both sides sorted best first
+    pub fn apply(&mut self, delta: &BookDelta) {
+        self.ts = delta.ts;
+        let levels = match delta.side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        };
+        // Bids descend, asks ascend
+        let better = |a: f64, b: f64| match delta.side {
+            OrderSide::Buy => a > b,
+            OrderSide::Sell => a < b,
+        };
+
+        let pos = levels.iter().position(|l| !better(l.price, delta.price));
+        match pos {
+            Some(i) if (levels[i].price - delta.price).abs() < f64::EPSILON => {
+                if delta.qty > 0.0 {
+                    levels[i].qty = delta.qty;
+                    levels[i].orders = delta.orders;
+                } else {
+                    levels.remove(i);
+                }
+            }
+            _ if delta.qty <= 0.0 => {}
+            Some(i) => levels.insert(i, Level { price: delta.price, qty: delta.qty, orders: delta.orders }),
+            None => levels.push(Level { price: delta.price, qty: delta.qty, orders: delta.orders }),
+        }
+    }
+}
+
+/// Anything a strategy can react to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    Bar { symbol: String, candle: Candle },
+    Trade(Trade),
+    Quote(Quote),
+    Book(BookSnapshot),
+    BookDelta(BookDelta),
+}
+
+impl MarketEvent {
+    pub fn bar(symbol: &str, candle: Candle) -> Self {
+        Self::Bar { symbol: symbol.to_owned(), candle }
+    }
+
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::Bar { symbol, .. } => symbol,
+            Self::Trade(t) => &t.symbol,
+            Self::Quote(q) => &q.symbol,
+            Self::Book(b) => &b.symbol,
+            Self::BookDelta(d) => &d.symbol,
+        }
+    }
+
+    pub fn ts(&self) -> Timestamp {
+        match self {
+            Self::Bar { candle, .. } => candle.timestamp,
+            Self::Trade(t) => t.ts,
+            Self::Quote(q) => q.ts,
+            Self::Book(b) => b.ts,
+            Self::BookDelta(d) => d.ts,
+        }
+    }
+
+    /// Best estimate of the current price: close, last trade or mid
+    pub fn price(&self) -> Option<f64> {
+        match self {
+            Self::Bar { candle, .. } => Some(candle.close),
+            Self::Trade(t) => Some(t.price),
+            Self::Quote(q) => Some(q.mid()),
+            Self::Book(b) => b.mid(),
+            Self::BookDelta(_) => None,
+        }
+    }
+
+    pub fn as_candle(&self) -> Option<&Candle> {
+        match self {
+            Self::Bar { candle, .. } => Some(candle),
+            _ => None,
+        }
+    }
+}
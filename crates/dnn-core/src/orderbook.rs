@@ -0,0 +1,340 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use serde::{Deserialize, Serialize};
+use crate::error::BookError;
+use crate::{Order, OrderSide};
+
+/// How long an order may rest in the book
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeInForce {
+    /// Good till cancelled: any unfilled quantity rests in the book
+    #[default]
+    Gtc,
+    /// Immediate or cancel: fill what is possible now, cancel the rest
+    Ioc,
+    /// Fill or kill: fill the whole quantity now or nothing at all
+    Fok,
+}
+
+/// A match between an incoming (taker) order and a resting (maker) order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trade {
+    pub maker_order_id: u64,
+    pub taker_order_id: u64,
+    /// Side of the taker
+    pub aggressor: OrderSide,
+    /// Always the maker's price
+    pub price: f64,
+    pub qty: f64,
+}
+
+/// Aggregated quantity at one price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Level {
+    pub price: f64,
+    pub qty: f64,
+    pub orders: usize,
+}
+
+/// L2 snapshot, best prices first
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Depth {
+    pub bids: Vec<Level>,
+    pub asks: Vec<Level>,
+}
+
+/// What happened to an order sent to the book
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchResult {
+    pub trades: Vec<Trade>,
+    /// Quantity left resting in the book
+    pub resting: f64,
+    /// Quantity cancelled because of the order type (market, IOC, FOK)
+    pub cancelled: f64,
+}
+
+impl MatchResult {
+    pub fn filled_qty(&self) -> f64 {
+        self.trades.iter().map(|t| t.qty).sum()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Resting {
+    id: u64,
+    qty: f64,
+}
+
+/// Price-time priority limit order book for one symbol.
+///
+/// Prices are held as integer multiples of the tick size. Orders use the
+/// regular [`Order`] type: `price: None` is a market order, which never rests.
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    symbol: String,
+    tick_size: f64,
+    bids: BTreeMap<i64, VecDeque<Resting>>,
+    asks: BTreeMap<i64, VecDeque<Resting>>,
+    /// Side and price level of every resting order
+    index: HashMap<u64, (OrderSide, i64)>,
+}
+
+impl OrderBook {
+    /// Fails unless `tick_size` is positive and finite
+    pub fn new(symbol: &str, tick_size: f64) -> Result<Self, BookError> {
+        if !(tick_size.is_finite() && tick_size > 0.0) {
+            return Err(BookError::InvalidTickSize(tick_size));
+        }
+        Ok(Self {
+            symbol: symbol.to_owned(),
+            tick_size,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            index: HashMap::new(),
+        })
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    fn to_ticks(&self, price: f64) -> i64 {
+        (price / self.tick_size).round() as i64
+    }
+
+    fn to_price(&self, ticks: i64) -> f64 {
+        ticks as f64 * self.tick_size
+    }
+
+    pub fn best_bid(&self) -> Option<f64> {
+        self.bids.keys().next_back().map(|t| self.to_price(*t))
+    }
+
+    pub fn best_ask(&self) -> Option<f64> {
+        self.asks.keys().next().map(|t| self.to_price(*t))
+    }
+
+    pub fn mid(&self) -> Option<f64> {
+        Some((self.best_bid()? + self.best_ask()?) / 2.0)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()? - self.best_bid()?)
+    }
+
+    /// Number of resting orders
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    pub fn contains(&self, order_id: u64) -> bool {
+        self.index.contains_key(&order_id)
+    }
+
+    /// Unfilled quantity of a resting order
+    pub fn resting_qty(&self, order_id: u64) -> Option<f64> {
+        let (side, ticks) = self.index.get(&order_id)?;
+        self.side(*side)
+            .get(ticks)?
+            .iter()
+            .find(|r| r.id == order_id)
+            .map(|r| r.qty)
+    }
+
+    /// Orders ahead of a resting order at its price level
+    pub fn queue_position(&self, order_id: u64) -> Option<usize> {
+        let (side, ticks) = self.index.get(&order_id)?;
+        self.side(*side).get(ticks)?.iter().position(|r| r.id == order_id)
+    }
+
+    fn side(&self, side: OrderSide) -> &BTreeMap<i64, VecDeque<Resting>> {
+        match side {
+            OrderSide::Buy => &self.bids,
+            OrderSide::Sell => &self.asks,
+        }
+    }
+
+    fn side_mut(&mut self, side: OrderSide) -> &mut BTreeMap<i64, VecDeque<Resting>> {
+        match side {
+            OrderSide::Buy => &mut self.bids,
+            OrderSide::Sell => &mut self.asks,
+        }
+    }
+
+    /// Opposite-side price levels an order may trade against, best first
+    fn crossable(&self, side: OrderSide, limit: Option<i64>) -> Vec<i64> {
+        match side {
+            OrderSide::Buy => self.asks
+                .keys()
+                .take_while(|t| limit.is_none_or(|l| **t <= l))
+                .copied()
+                .collect(),
+            OrderSide::Sell => self.bids
+                .keys()
+                .rev()
+                .take_while(|t| limit.is_none_or(|l| **t >= l))
+                .copied()
+                .collect(),
+        }
+    }
+
+    /// Match an order against the book and rest any remainder allowed by `tif`
+    pub fn add(&mut self, order: &Order, tif: TimeInForce) -> Result<MatchResult, BookError> {
+        if order.symbol != self.symbol {
+            return Err(BookError::WrongSymbol(order.symbol.clone()));
+        }
+        if self.index.contains_key(&order.id) {
+            return Err(BookError::DuplicateOrder(order.id));
+        }
+        if !order.qty.is_finite() || order.qty <= 0.0 {
+            return Err(BookError::InvalidQuantity(order.qty));
+        }
+        if let Some(price) = order.price
+            && (!price.is_finite() || price <= 0.0)
+        {
+            return Err(BookError::InvalidPrice(price));
+        }
+
+        let limit = order.price.map(|p| self.to_ticks(p));
+        let levels = self.crossable(order.side, limit);
+
+        if tif == TimeInForce::Fok {
+            let available: f64 = levels
+                .iter()
+                .flat_map(|t| &self.side(opposite(order.side))[t])
+                .map(|r| r.qty)
+                .sum();
+            if available + f64::EPSILON < order.qty {
+                return Ok(MatchResult { cancelled: order.qty, ..MatchResult::default() });
+            }
+        }
+
+        let mut remaining = order.qty;
+        let mut trades = Vec::new();
+        for ticks in levels {
+            if remaining <= f64::EPSILON {
+                break;
+            }
+            let price = self.to_price(ticks);
+            let book = self.side_mut(opposite(order.side));
+            let Some(queue) = book.get_mut(&ticks) else {
+                continue;
+            };
+
+            let mut done = Vec::new();
+            for maker in queue.iter_mut() {
+                if remaining <= f64::EPSILON {
+                    break;
+                }
+                let qty = maker.qty.min(remaining);
+                maker.qty -= qty;
+                remaining -= qty;
+                trades.push(Trade {
+                    maker_order_id: maker.id,
+                    taker_order_id: order.id,
+                    aggressor: order.side,
+                    price,
+                    qty,
+                });
+                if maker.qty <= f64::EPSILON {
+                    done.push(maker.id);
+                }
+            }
+            queue.retain(|r| r.qty > f64::EPSILON);
+            if queue.is_empty() {
+                book.remove(&ticks);
+            }
+            for id in done {
+                self.index.remove(&id);
+            }
+        }
+
+        let remaining = if remaining <= f64::EPSILON { 0.0 } else { remaining };
+        let mut result = MatchResult { trades, ..MatchResult::default() };
+        match (limit, tif) {
+            (Some(ticks), TimeInForce::Gtc) if remaining > 0.0 => {
+                self.side_mut(order.side)
+                    .entry(ticks)
+                    .or_default()
+                    .push_back(Resting { id: order.id, qty: remaining });
+                self.index.insert(order.id, (order.side, ticks));
+                result.resting = remaining;
+            }
+            _ => result.cancelled = remaining,
+        }
+        Ok(result)
+    }
+
+    /// Remove a resting order, returning its unfilled quantity
+    pub fn cancel(&mut self, order_id: u64) -> Result<f64, BookError> {
+        let (side, ticks) = self.index
+            .remove(&order_id)
+            .ok_or(BookError::UnknownOrder(order_id))?;
+        let book = self.side_mut(side);
+        let mut qty = 0.0;
+        if let Some(queue) = book.get_mut(&ticks) {
+            if let Some(pos) = queue.iter().position(|r| r.id == order_id)
+                && let Some(resting) = queue.remove(pos)
+            {
+                qty = resting.qty;
+            }
+            if queue.is_empty() {
+                book.remove(&ticks);
+            }
+        }
+        Ok(qty)
+    }
+
+    /// Change the quantity and/or price of a resting order.
+    ///
+    /// Reducing the quantity at the same price keeps queue position; any other
+    /// change re-enters the order at the back of the queue, and may trade.
+    pub fn modify(&mut self, order_id: u64, qty: f64, price: f64) -> Result<MatchResult, BookError> {
+        let (side, ticks) = *self.index.get(&order_id).ok_or(BookError::UnknownOrder(order_id))?;
+        if !qty.is_finite() || qty <= 0.0 {
+            return Err(BookError::InvalidQuantity(qty));
+        }
+        if !price.is_finite() || price <= 0.0 {
+            return Err(BookError::InvalidPrice(price));
+        }
+
+        let new_ticks = self.to_ticks(price);
+        if new_ticks == ticks
+            && let Some(resting) = self.side_mut(side)
+                .get_mut(&ticks)
+                .and_then(|q| q.iter_mut().find(|r| r.id == order_id))
+            && qty <= resting.qty
+        {
+            resting.qty = qty;
+            return Ok(MatchResult { resting: qty, ..MatchResult::default() });
+        }
+
+        self.cancel(order_id)?;
+        let order = Order { id: order_id, symbol: self.symbol.clone(), qty, price: Some(price), side };
+        self.add(&order, TimeInForce::Gtc)
+    }
+
+    /// Aggregated quantity of the best `levels` prices on each side
+    pub fn depth(&self, levels: usize) -> Depth {
+        let level = |(ticks, queue): (&i64, &VecDeque<Resting>)| Level {
+            price: self.to_price(*ticks),
+            qty: queue.iter().map(|r| r.qty).sum(),
+            orders: queue.len(),
+        };
+        Depth {
+            bids: self.bids.iter().rev().take(levels).map(level).collect(),
+            asks: self.asks.iter().take(levels).map(level).collect(),
+        }
+    }
+}
+
+fn opposite(side: OrderSide) -> OrderSide {
+    match side {
+        OrderSide::Buy => OrderSide::Sell,
+        OrderSide::Sell => OrderSide::Buy,
+    }
+}
@@ -1,46 +1,125 @@
 use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::error::FxError;
+use crate::fx::FxRates;
+use crate::ledger::LedgerEvent;
 use crate::{OrderSide, Position};
 
-#[derive(Debug)]
+pub const DEFAULT_BASE_CURRENCY: &str = "USD";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Portfolio {
-    pub cash: f64,
+    /// Currency valuation and `PnL` are reported in
+    pub base_currency: String,
+    /// Cash balance per currency
+    pub cash: HashMap<String, f64>,
     pub positions: HashMap<String, Position>,
 }
 
 impl Portfolio {
     pub fn new(starting_cash: f64) -> Self {
+        Self::with_base_currency(DEFAULT_BASE_CURRENCY, starting_cash)
+    }
+
+    pub fn with_base_currency(base_currency: &str, starting_cash: f64) -> Self {
+        let mut cash = HashMap::new();
+        cash.insert(base_currency.to_owned(), starting_cash);
         Self {
-            cash: starting_cash,
+            base_currency: base_currency.to_owned(),
+            cash,
             positions: HashMap::new(),
         }
     }
 
+    /// Cash balance held in a single currency
+    pub fn cash_in(&self, currency: &str) -> f64 {
+        self.cash.get(currency).copied().unwrap_or(0.0)
+    }
+
+    /// Add (or withdraw, if negative) cash in the given currency
+    pub fn deposit(&mut self, currency: &str, amount: f64) {
+        *self.cash.entry(currency.to_owned()).or_insert(0.0) += amount;
+    }
+
+    /// Apply a fill settled in the base currency
     pub fn apply_fill(&mut self, symbol: &str, side: OrderSide, qty: f64, price: f64) {
-        let entry = self.positions
-            .entry(symbol.to_string())
-            .or_insert_with(|| Position::new(symbol.to_string()));
-
-        // cash movement
-        match side {
-            OrderSide::Buy => {
-                self.cash -= qty * price;
+        let currency = self.positions
+            .get(symbol)
+            .map_or_else(|| self.base_currency.clone(), |p| p.currency.clone());
+        self.apply_fill_in(symbol, &currency, side, qty, price);
+    }
+
+    /// Apply a fill for an instrument quoted in `currency`
+    pub fn apply_fill_in(&mut self, symbol: &str, currency: &str, side: OrderSide, qty: f64, price: f64) {
+        self.apply_event(&LedgerEvent::Fill {
+            symbol: symbol.to_owned(),
+            currency: currency.to_owned(),
+            side,
+            qty,
+            price,
+            multiplier: 1.0,
+        });
+    }
+
+    /// Apply a single ledger event. This is the only place portfolio state changes.
+    pub fn apply_event(&mut self, event: &LedgerEvent) {
+        match event {
+            LedgerEvent::Fill { symbol, currency, side, qty, price, multiplier } => {
+                // cash movement
+                let notional = qty * price * multiplier;
+                match side {
+                    OrderSide::Buy => self.deposit(currency, -notional),
+                    OrderSide::Sell => self.deposit(currency, notional),
+                }
+
+                // update position
+                self.positions
+                    .entry(symbol.clone())
+                    .or_insert_with(|| Position::new(symbol.clone(), currency.clone()).with_multiplier(*multiplier))
+                    .update(*side, *qty, *price);
             }
-            OrderSide::Sell => {
-                self.cash += qty * price;
+            LedgerEvent::Fee { currency, amount, .. } => self.deposit(currency, -amount),
+            LedgerEvent::Dividend { currency, amount, .. }
+            | LedgerEvent::CashMovement { currency, amount } => self.deposit(currency, *amount),
+        }
+    }
+
+    /// Total cash across all currencies, in the base currency
+    pub fn total_cash(&self, fx: &FxRates) -> Result<f64, FxError> {
+        self.cash
+            .iter()
+            .map(|(ccy, amount)| fx.convert(*amount, ccy, &self.base_currency))
+            .sum()
+    }
+
+    /// Cash plus marked-to-market positions, in the base currency.
+    /// Positions without a price in `prices` are left out.
+    pub fn total_value(&self, prices: &HashMap<String, f64>, fx: &FxRates) -> Result<f64, FxError> {
+        let mut value = self.total_cash(fx)?;
+        for (sym, pos) in &self.positions {
+            if let Some(price) = prices.get(sym) {
+                value += fx.convert(pos.market_value(*price), &pos.currency, &self.base_currency)?;
             }
         }
+        Ok(value)
+    }
 
-        // update position
-        entry.update(side, qty, price);
+    /// Realized `PnL` of all positions, converted to the base currency at current rates
+    pub fn realized_pnl(&self, fx: &FxRates) -> Result<f64, FxError> {
+        self.positions
+            .values()
+            .map(|pos| fx.convert(pos.realized_pnl, &pos.currency, &self.base_currency))
+            .sum()
     }
 
-    pub fn total_value(&self, prices: &HashMap<String, f64>) -> f64 {
-        let mut value = self.cash;
+    /// Unrealized `PnL` of priced positions, in the base currency
+    pub fn unrealized_pnl(&self, prices: &HashMap<String, f64>, fx: &FxRates) -> Result<f64, FxError> {
+        let mut pnl = 0.0;
         for (sym, pos) in &self.positions {
             if let Some(price) = prices.get(sym) {
-                value += pos.market_value(*price);
+                pnl += fx.convert(pos.unrealized_pnl(*price), &pos.currency, &self.base_currency)?;
             }
         }
-        value
+        Ok(pnl)
     }
 }
@@ -0,0 +1,73 @@
+use crate::market::{Candle, Trade};
+use crate::time::{TimeInterval, Timestamp};
+
+/// Builds candles of a fixed interval from finer candles or trades.
+///
+/// Input must arrive in time order. A bar is returned once an input falls
+/// into a later interval; call [`Resampler::flush`] for the last, partial one.
+#[derive(Debug, Clone)]
+pub struct Resampler {
+    interval: TimeInterval,
+    current: Option<Candle>,
+}
+
+impl Resampler {
+    pub fn new(interval: TimeInterval) -> Self {
+        Self { interval, current: None }
+    }
+
+    pub fn interval(&self) -> TimeInterval {
+        self.interval
+    }
+
+    /// The bar being built, if any
+    pub fn current(&self) -> Option<&Candle> {
+        self.current.as_ref()
+    }
+
+    /// Merge a finer candle, returning the previous bar if it is complete
+    pub fn push_candle(&mut self, candle: &Candle) -> Option<Candle> {
+        self.push(candle.timestamp, candle.open, candle.high, candle.low, candle.close, candle.volume)
+    }
+
+    /// Merge a trade print, returning the previous bar if it is complete
+    pub fn push_trade(&mut self, trade: &Trade) -> Option<Candle> {
+        self.push(trade.ts, trade.price, trade.price, trade.price, trade.price, trade.size)
+    }
+
+    /// Finish the bar in progress
+    pub fn flush(&mut self) -> Option<Candle> {
+        self.current.take()
+    }
+
+    fn push(&mut self, ts: Timestamp, open: f64, high: f64, low: f64, close: f64, volume: f64) -> Option<Candle> {
+        let bucket = self.interval.bucket_start(ts);
+        if let Some(bar) = &mut self.current
+            && bar.timestamp == bucket
+        {
+            bar.high = bar.high.max(high);
+            bar.low = bar.low.min(low);
+            bar.close = close;
+            bar.volume += volume;
+            return None;
+        }
+
+        self.current.replace(Candle { timestamp: bucket, open, high, low, close, volume })
+    }
+}
+
+/// Resample a whole series of candles to a coarser interval
+pub fn resample(candles: &[Candle], interval: TimeInterval) -> Vec<Candle> {
+    let mut resampler = Resampler::new(interval);
+    let mut out: Vec<Candle> = candles.iter().filter_map(|c| resampler.push_candle(c)).collect();
+    out.extend(resampler.flush());
+    out
+}
+
+/// Aggregate trade prints into candles
+pub fn aggregate_trades(trades: &[Trade], interval: TimeInterval) -> Vec<Candle> {
+    let mut resampler = Resampler::new(interval);
+    let mut out: Vec<Candle> = trades.iter().filter_map(|t| resampler.push_trade(t)).collect();
+    out.extend(resampler.flush());
+    out
+}
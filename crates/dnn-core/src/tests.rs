@@ -2,8 +2,16 @@
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::Utc;
-    use crate::market::{CandleRange, Candle};
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+    use crate::fx::{FxHistory, FxRates};
+    use crate::ledger::{Ledger, LedgerEvent};
+    use crate::portfolio::Portfolio;
+    use crate::{Order, OrderSide, Position};
+    use crate::instrument::{AssetClass, DaylightSaving, Instrument, InstrumentMaster, TradingCalendar};
+    use crate::market::{BookDelta, BookSnapshot, CandleRange, Candle, MarketEvent, Trade};
+    use crate::resample::{aggregate_trades, resample};
+    use crate::orderbook::{Level, OrderBook, TimeInForce};
     use crate::time::TimeInterval;
 
     #[test]
@@ -28,5 +36,379 @@ mod tests {
         let result = Candle::new(timestamp, -100.0, 105.0, 98.0, 102.0, 1000.0);
         assert!(result.is_err());
     }
-    
-}
\ No newline at end of file
+
+    #[test]
+    fn test_instrument_rounding() {
+        let mut inst = Instrument::new("ES", AssetClass::Future, "CME", "USD");
+        inst.tick_size = 0.25;
+        inst.lot_size = 1.0;
+        inst.multiplier = 50.0;
+
+        assert_eq!(inst.round_price(4500.13), 4500.25);
+        assert_eq!(inst.round_price(4500.12), 4500.0);
+        assert_eq!(inst.round_qty(2.7), 2.0);
+        assert_eq!(inst.round_qty(-2.7), -2.0);
+        assert_eq!(inst.notional(2.0, 4500.0), 450_000.0);
+
+        let mut eth = Instrument::new("ETH-USD", AssetClass::Crypto, "COINBASE", "USD");
+        eth.lot_size = 0.1;
+        assert!((eth.round_qty(0.3) - 0.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_trading_calendar() {
+        let cal = TradingCalendar::us_equities();
+        // Wednesday 2024-01-10 15:00 UTC = 10:00 EST
+        let open = Utc.with_ymd_and_hms(2024, 1, 10, 15, 0, 0).unwrap();
+        // Saturday
+        let weekend = Utc.with_ymd_and_hms(2024, 1, 13, 15, 0, 0).unwrap();
+        // 22:00 UTC = 17:00 EST, after close
+        let after_close = Utc.with_ymd_and_hms(2024, 1, 10, 22, 0, 0).unwrap();
+
+        assert!(cal.is_open(open));
+        assert!(!cal.is_open(weekend));
+        assert!(!cal.is_open(after_close));
+        assert!(TradingCalendar::continuous().is_open(weekend));
+
+        // Summer hours are an hour earlier in UTC: 13:30 UTC = 09:30 EDT
+        let summer_open = Utc.with_ymd_and_hms(2024, 7, 10, 13, 30, 0).unwrap();
+        let summer_close = Utc.with_ymd_and_hms(2024, 7, 10, 20, 0, 0).unwrap();
+        assert!(cal.is_open(summer_open));
+        assert!(!cal.is_open(summer_close));
+        // DST began 2024-03-10 and ended 2024-11-03
+        assert!(cal.is_open(Utc.with_ymd_and_hms(2024, 3, 11, 13, 30, 0).unwrap()));
+        assert!(!cal.is_open(Utc.with_ymd_and_hms(2024, 3, 8, 13, 30, 0).unwrap()));
+        assert!(!cal.is_open(Utc.with_ymd_and_hms(2024, 11, 4, 13, 30, 0).unwrap()));
+        assert!(cal.is_open(Utc.with_ymd_and_hms(2024, 11, 1, 13, 30, 0).unwrap()));
+
+        // Xetra: 09:00 CET, 08:00 UTC in winter and 07:00 UTC in summer
+        let xetra = TradingCalendar {
+            utc_offset_minutes: 60,
+            daylight_saving: Some(DaylightSaving::European),
+            open: chrono::NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+            ..TradingCalendar::us_equities()
+        };
+        assert!(xetra.is_open(Utc.with_ymd_and_hms(2024, 7, 10, 7, 0, 0).unwrap()));
+        assert!(!xetra.is_open(Utc.with_ymd_and_hms(2024, 1, 10, 7, 0, 0).unwrap()));
+        assert!(xetra.is_open(Utc.with_ymd_and_hms(2024, 1, 10, 8, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn test_instrument_master_load() {
+        let json = r#"[
+            { "symbol": "AAPL", "asset_class": "equity", "exchange": "NASDAQ", "currency": "USD", "sector": "Technology" },
+            { "symbol": "JPM", "asset_class": "equity", "exchange": "NYSE", "currency": "USD", "sector": "Financial" },
+            { "symbol": "ETH-USD", "asset_class": "crypto", "exchange": "COINBASE", "currency": "USD", "tick_size": 0.01, "lot_size": 0.0001 }
+        ]"#;
+
+        let master = InstrumentMaster::from_json(json).unwrap();
+        assert_eq!(master.len(), 3);
+        assert_eq!(master.get("AAPL").unwrap().lot_size, 1.0);
+        assert_eq!(master.by_sector("technology").count(), 1);
+        assert_eq!(master.by_asset_class(AssetClass::Crypto).next().unwrap().symbol, "ETH-USD");
+
+        // Calendars default by asset class, as with Instrument::new
+        assert_eq!(master.get("AAPL").unwrap().calendar, TradingCalendar::us_equities());
+        assert_eq!(
+            master.get("ETH-USD").unwrap(),
+            &Instrument { tick_size: 0.01, lot_size: 0.0001, ..Instrument::new("ETH-USD", AssetClass::Crypto, "COINBASE", "USD") }
+        );
+
+        let round_trip = InstrumentMaster::from_json(&master.to_json().unwrap()).unwrap();
+        assert_eq!(round_trip.get("JPM"), master.get("JPM"));
+
+        // The shipped master follows daylight saving time
+        let shipped = InstrumentMaster::load(concat!(env!("CARGO_MANIFEST_DIR"), "/../../instruments.json")).unwrap();
+        assert!(shipped.iter().all(|i| i.asset_class == AssetClass::Crypto || i.calendar.daylight_saving.is_some()));
+        assert_eq!(shipped.get("SPY").unwrap().calendar, TradingCalendar::us_equities());
+        assert_eq!(shipped.get("SAP.DE").unwrap().calendar.daylight_saving, Some(DaylightSaving::European));
+    }
+
+    #[test]
+    fn test_fx_rates_inverse_and_cross() {
+        let mut fx = FxRates::new();
+        fx.set("EUR", "USD", 1.10);
+        fx.set("GBP", "USD", 1.25);
+
+        assert_eq!(fx.rate("USD", "USD").unwrap(), 1.0);
+        assert!((fx.rate("USD", "EUR").unwrap() - 1.0 / 1.10).abs() < 1e-12);
+        assert!((fx.rate("EUR", "GBP").unwrap() - 1.10 / 1.25).abs() < 1e-12);
+        assert!(fx.rate("JPY", "USD").is_err());
+
+        // With two ways to cross, always the same one
+        let mut fx = FxRates::new();
+        fx.set("CHF", "USD", 1.10);
+        fx.set("CHF", "EUR", 1.00);
+        fx.set("JPY", "USD", 0.0070);
+        fx.set("JPY", "EUR", 0.0062);
+        for _ in 0..10 {
+            assert!((fx.rate("JPY", "CHF").unwrap() - 0.0062).abs() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn test_fills_use_the_contract_multiplier() {
+        let mut portfolio = Portfolio::new(1_000_000.0);
+        portfolio.apply_event(&LedgerEvent::Fill {
+            symbol: "ES".into(), currency: "USD".into(), side: OrderSide::Buy, qty: 2.0, price: 4_500.0, multiplier: 50.0,
+        });
+        assert_eq!(portfolio.cash_in("USD"), 550_000.0);
+        let es = &portfolio.positions["ES"];
+        assert_eq!(es.market_value(4_510.0), 451_000.0);
+        assert_eq!(es.unrealized_pnl(4_510.0), 1_000.0);
+
+        portfolio.apply_event(&LedgerEvent::Fill {
+            symbol: "ES".into(), currency: "USD".into(), side: OrderSide::Sell, qty: 1.0, price: 4_520.0, multiplier: 50.0,
+        });
+        assert_eq!(portfolio.cash_in("USD"), 776_000.0);
+        assert_eq!(portfolio.positions["ES"].realized_pnl, 1_000.0);
+
+        // Records from before multipliers read as 1
+        let event: LedgerEvent = serde_json::from_str(
+            r#"{"type": "fill", "symbol": "AAPL", "currency": "USD", "side": "Buy", "qty": 1.0, "price": 10.0}"#,
+        ).unwrap();
+        assert!(matches!(event, LedgerEvent::Fill { multiplier, .. } if multiplier == 1.0));
+    }
+
+    #[test]
+    fn test_short_round_trip() {
+        let mut position = Position::new("AAPL".into(), "USD".into());
+        position.update(OrderSide::Sell, 10.0, 100.0);
+        // Adding to a short averages its entry without realizing anything
+        position.update(OrderSide::Sell, 10.0, 110.0);
+        assert_eq!((position.qty, position.avg_price, position.realized_pnl), (-20.0, 105.0, 0.0));
+        assert_eq!(position.unrealized_pnl(95.0), 200.0);
+
+        position.update(OrderSide::Buy, 5.0, 95.0);
+        assert_eq!((position.qty, position.avg_price, position.realized_pnl), (-15.0, 105.0, 50.0));
+        position.update(OrderSide::Buy, 15.0, 100.0);
+        assert_eq!((position.qty, position.realized_pnl), (0.0, 125.0));
+        assert!(position.avg_price.is_finite());
+
+        // Buying through a short closes it and opens a long at the fill
+        position.update(OrderSide::Sell, 4.0, 50.0);
+        position.update(OrderSide::Buy, 10.0, 60.0);
+        assert_eq!((position.qty, position.avg_price, position.realized_pnl), (6.0, 60.0, 85.0));
+        position.update(OrderSide::Sell, 10.0, 70.0);
+        assert_eq!((position.qty, position.avg_price, position.realized_pnl), (-4.0, 70.0, 145.0));
+    }
+
+    #[test]
+    fn test_multi_currency_portfolio_value() {
+        let mut portfolio = Portfolio::with_base_currency("USD", 10_000.0);
+        portfolio.deposit("EUR", 1_000.0);
+        portfolio.apply_fill_in("SAP.DE", "EUR", OrderSide::Buy, 5.0, 100.0);
+        portfolio.apply_fill("ETH-USD", OrderSide::Buy, 1.0, 2_000.0);
+
+        assert_eq!(portfolio.cash_in("EUR"), 500.0);
+        assert_eq!(portfolio.cash_in("USD"), 8_000.0);
+
+        let mut history = FxHistory::new();
+        let t0 = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let t1 = Utc.with_ymd_and_hms(2024, 1, 2, 0, 0, 0).unwrap();
+        history.insert("EUR", "USD", t0, 1.10);
+        history.insert("EUR", "USD", t1, 1.20);
+
+        let prices = HashMap::from([
+            ("SAP.DE".to_string(), 110.0),
+            ("ETH-USD".to_string(), 2_100.0),
+        ]);
+
+        let fx = history.rates_at(t1);
+        // 8000 + 500 EUR + 5 * 110 EUR + 2100
+        let expected = 8_000.0 + 1_050.0 * 1.20 + 2_100.0;
+        assert!((portfolio.total_value(&prices, &fx).unwrap() - expected).abs() < 1e-9);
+
+        let pnl = portfolio.unrealized_pnl(&prices, &fx).unwrap();
+        assert!((pnl - (50.0 * 1.20 + 100.0)).abs() < 1e-9);
+
+        // No EUR rate known yet
+        let before = Utc.with_ymd_and_hms(2023, 12, 31, 0, 0, 0).unwrap();
+        assert!(portfolio.total_value(&prices, &history.rates_at(before)).is_err());
+    }
+
+    #[test]
+    fn test_ledger_point_in_time_and_snapshots() {
+        let day = |d| Utc.with_ymd_and_hms(2024, 3, d, 16, 0, 0).unwrap();
+        let mut ledger = Ledger::new("USD").with_snapshot_interval(2);
+
+        ledger.record(day(1), LedgerEvent::CashMovement { currency: "USD".into(), amount: 10_000.0 }).unwrap();
+        ledger.record(day(2), LedgerEvent::Fill {
+            symbol: "AAPL".into(), currency: "USD".into(), side: OrderSide::Buy, qty: 10.0, price: 100.0, multiplier: 1.0,
+        }).unwrap();
+        ledger.record(day(2), LedgerEvent::Fee { currency: "USD".into(), amount: 1.0, symbol: Some("AAPL".into()) }).unwrap();
+        ledger.record(day(4), LedgerEvent::Dividend { symbol: "AAPL".into(), currency: "USD".into(), amount: 5.0 }).unwrap();
+        ledger.record(day(5), LedgerEvent::Fill {
+            symbol: "AAPL".into(), currency: "USD".into(), side: OrderSide::Sell, qty: 4.0, price: 110.0, multiplier: 1.0,
+        }).unwrap();
+
+        assert_eq!(ledger.snapshots().len(), 2);
+        assert!(ledger.record(day(3), LedgerEvent::CashMovement { currency: "USD".into(), amount: 1.0 }).is_err());
+
+        let on_day3 = ledger.state_at(day(3));
+        assert_eq!(on_day3.positions["AAPL"].qty, 10.0);
+        assert_eq!(on_day3.cash_in("USD"), 8_999.0);
+        assert!(ledger.state_at(day(1) - chrono::Duration::days(1)).positions.is_empty());
+
+        let now = ledger.portfolio();
+        assert_eq!(now.positions["AAPL"].qty, 6.0);
+        assert_eq!(now.cash_in("USD"), 8_999.0 + 5.0 + 440.0);
+
+        // Deposits don't count as PnL
+        let fx = FxRates::new();
+        let prices = HashMap::from([("AAPL".to_string(), 110.0)]);
+        let total = ledger.pnl_between(day(1) - chrono::Duration::days(1), day(5), &HashMap::new(), &prices, &fx).unwrap();
+        assert!((total - (100.0 - 1.0 + 5.0)).abs() < 1e-9);
+
+        let restored = Ledger::from_json(&ledger.to_json().unwrap()).unwrap();
+        assert_eq!(restored.entries(), ledger.entries());
+        assert_eq!(restored.portfolio().cash_in("USD"), now.cash_in("USD"));
+        assert_eq!(restored.state_at(day(3)).positions["AAPL"].qty, 10.0);
+
+        // Snapshots that claim more entries than the ledger holds are dropped on load
+        let mut json: serde_json::Value = serde_json::from_str(&ledger.to_json().unwrap()).unwrap();
+        json["entries"].as_array_mut().unwrap().truncate(1);
+        let truncated = Ledger::from_json(&json.to_string()).unwrap();
+        assert!(truncated.snapshots().is_empty());
+        assert_eq!(truncated.state_at(day(5)).cash_in("USD"), 10_000.0);
+    }
+
+    fn book_order(id: u64, side: OrderSide, qty: f64, price: Option<f64>) -> Order {
+        Order { id, symbol: "AAPL".into(), qty, price, side }
+    }
+
+    #[test]
+    fn test_order_book_price_time_priority() {
+        let mut book = OrderBook::new("AAPL", 0.01).unwrap();
+        book.add(&book_order(1, OrderSide::Sell, 5.0, Some(101.0)), TimeInForce::Gtc).unwrap();
+        book.add(&book_order(2, OrderSide::Sell, 5.0, Some(100.5)), TimeInForce::Gtc).unwrap();
+        book.add(&book_order(3, OrderSide::Sell, 5.0, Some(100.5)), TimeInForce::Gtc).unwrap();
+        book.add(&book_order(4, OrderSide::Buy, 10.0, Some(99.0)), TimeInForce::Gtc).unwrap();
+        assert_eq!(book.best_bid(), Some(99.0));
+        assert_eq!(book.best_ask(), Some(100.5));
+        assert_eq!(book.queue_position(3), Some(1));
+
+        // Crosses two orders at 100.50 in arrival order, then part of 101
+        let result = book.add(&book_order(5, OrderSide::Buy, 12.0, Some(101.0)), TimeInForce::Gtc).unwrap();
+        let makers: Vec<(u64, f64, f64)> = result.trades.iter().map(|t| (t.maker_order_id, t.price, t.qty)).collect();
+        assert_eq!(makers, vec![(2, 100.5, 5.0), (3, 100.5, 5.0), (1, 101.0, 2.0)]);
+        assert_eq!(result.resting, 0.0);
+        assert_eq!(book.resting_qty(1), Some(3.0));
+        assert!(!book.contains(2));
+
+        let depth = book.depth(5);
+        assert_eq!(depth.asks.len(), 1);
+        assert_eq!((depth.asks[0].price, depth.asks[0].qty, depth.asks[0].orders), (101.0, 3.0, 1));
+        assert_eq!((depth.bids[0].price, depth.bids[0].qty), (99.0, 10.0));
+    }
+
+    #[test]
+    fn test_order_book_order_types() {
+        let mut book = OrderBook::new("AAPL", 0.01).unwrap();
+        book.add(&book_order(1, OrderSide::Sell, 5.0, Some(100.0)), TimeInForce::Gtc).unwrap();
+
+        // FOK larger than the book is killed without trading
+        let fok = book.add(&book_order(2, OrderSide::Buy, 6.0, Some(100.0)), TimeInForce::Fok).unwrap();
+        assert!(fok.trades.is_empty());
+        assert_eq!(fok.cancelled, 6.0);
+        assert_eq!(book.resting_qty(1), Some(5.0));
+
+        // IOC takes what is there and cancels the rest
+        let ioc = book.add(&book_order(3, OrderSide::Buy, 3.0, Some(100.0)), TimeInForce::Ioc).unwrap();
+        assert_eq!(ioc.filled_qty(), 3.0);
+
+        // Market order sweeps the book, remainder cancelled rather than rested
+        let market = book.add(&book_order(4, OrderSide::Buy, 4.0, None), TimeInForce::Gtc).unwrap();
+        assert_eq!(market.filled_qty(), 2.0);
+        assert_eq!(market.cancelled, 2.0);
+        assert!(book.is_empty());
+
+        assert!(book.add(&book_order(5, OrderSide::Buy, 0.0, Some(1.0)), TimeInForce::Gtc).is_err());
+    }
+
+    #[test]
+    fn test_order_book_cancel_and_modify() {
+        let mut book = OrderBook::new("AAPL", 0.01).unwrap();
+        book.add(&book_order(1, OrderSide::Buy, 5.0, Some(99.0)), TimeInForce::Gtc).unwrap();
+        book.add(&book_order(2, OrderSide::Buy, 5.0, Some(99.0)), TimeInForce::Gtc).unwrap();
+        book.add(&book_order(3, OrderSide::Sell, 5.0, Some(101.0)), TimeInForce::Gtc).unwrap();
+
+        // Size down keeps priority, size up loses it
+        book.modify(1, 4.0, 99.0).unwrap();
+        assert_eq!(book.queue_position(1), Some(0));
+        book.modify(1, 6.0, 99.0).unwrap();
+        assert_eq!(book.queue_position(1), Some(1));
+
+        // Repricing through the spread trades
+        let result = book.modify(2, 5.0, 101.0).unwrap();
+        assert_eq!(result.filled_qty(), 5.0);
+        assert!(!book.contains(3));
+
+        assert_eq!(book.cancel(1).unwrap(), 6.0);
+        assert!(book.cancel(1).is_err());
+        assert!(book.is_empty());
+
+        for tick in [0.0, -0.01, f64::NAN] {
+            assert!(OrderBook::new("AAPL", tick).is_err());
+        }
+    }
+
+    #[test]
+    fn test_resample_and_tick_aggregation() {
+        let t0 = Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap();
+        let minute = |m: i64| t0 + chrono::Duration::minutes(m);
+
+        let candles: Vec<Candle> = (0..12)
+            .map(|m| Candle::new(minute(m), 100.0 + m as f64, 101.0 + m as f64, 99.0, 100.5 + m as f64, 10.0).unwrap())
+            .collect();
+        let bars = resample(&candles, TimeInterval::Minute5);
+        assert_eq!(bars.len(), 3);
+        assert_eq!(bars[0].timestamp, t0);
+        assert_eq!((bars[0].open, bars[0].high, bars[0].close, bars[0].volume), (100.0, 105.0, 104.5, 50.0));
+        assert_eq!(bars[2].volume, 20.0);
+
+        let trade = |secs: i64, price: f64, size: f64| Trade {
+            symbol: "AAPL".into(),
+            ts: t0 + chrono::Duration::seconds(secs),
+            price,
+            size,
+            aggressor: Some(OrderSide::Buy),
+        };
+        let trades = vec![trade(1, 100.0, 5.0), trade(20, 102.0, 1.0), trade(50, 99.0, 2.0), trade(61, 101.0, 3.0)];
+        let bars = aggregate_trades(&trades, TimeInterval::Minute1);
+        assert_eq!(bars.len(), 2);
+        assert_eq!((bars[0].open, bars[0].high, bars[0].low, bars[0].close, bars[0].volume), (100.0, 102.0, 99.0, 99.0, 8.0));
+        assert_eq!(bars[1].timestamp, minute(1));
+
+        assert_eq!(TimeInterval::Week1.bucket_start(t0), Utc.with_ymd_and_hms(2024, 4, 29, 0, 0, 0).unwrap());
+        assert_eq!(TimeInterval::Month1.bucket_start(t0), Utc.with_ymd_and_hms(2024, 5, 1, 0, 0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_book_snapshot_deltas_and_events() {
+        let ts = Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap();
+        let level = |price: f64, qty: f64| Level { price, qty, orders: 1 };
+        let mut book = BookSnapshot {
+            symbol: "AAPL".into(),
+            ts,
+            bids: vec![level(99.0, 5.0), level(98.0, 5.0)],
+            asks: vec![level(101.0, 5.0)],
+        };
+        let delta = |side, price, qty| BookDelta { symbol: "AAPL".into(), ts, side, price, qty, orders: 1 };
+
+        book.apply(&delta(OrderSide::Buy, 99.5, 2.0));
+        book.apply(&delta(OrderSide::Buy, 98.0, 0.0));
+        book.apply(&delta(OrderSide::Sell, 100.5, 1.0));
+        book.apply(&delta(OrderSide::Sell, 101.0, 7.0));
+        let bids: Vec<f64> = book.bids.iter().map(|l| l.price).collect();
+        assert_eq!(bids, vec![99.5, 99.0]);
+        assert_eq!(book.asks, vec![level(100.5, 1.0), level(101.0, 7.0)]);
+        assert_eq!(book.mid(), Some(100.0));
+
+        let event = MarketEvent::Book(book);
+        assert_eq!(event.symbol(), "AAPL");
+        assert_eq!(event.price(), Some(100.0));
+        let json = serde_json::to_string(&event).unwrap();
+        assert_eq!(serde_json::from_str::<MarketEvent>(&json).unwrap(), event);
+    }
+}
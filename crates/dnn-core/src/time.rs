@@ -1,20 +1,30 @@
 use std::fmt;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
 
 pub type Timestamp = DateTime<Utc>;
 
-/// Represents different timeframes for market data
+/// Represents different timeframes for market data. Also read from the
+/// short forms it displays as, e.g. `"1m"` or `"1d"`.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum TimeInterval {
+    #[serde(alias = "1m")]
     Minute1,
+    #[serde(alias = "5m")]
     Minute5,
+    #[serde(alias = "15m")]
     Minute15,
+    #[serde(alias = "30m")]
     Minute30,
+    #[serde(alias = "1h")]
     Hour1,
+    #[serde(alias = "4h")]
     Hour4,
+    #[serde(alias = "1d")]
     Day1,
+    #[serde(alias = "1w")]
     Week1,
+    #[serde(alias = "1M")]
     Month1,
 }
 
@@ -33,6 +43,26 @@ impl TimeInterval {
             TimeInterval::Month1 => 2592000, // Approximate
         }
     }
+
+    /// Start of the interval containing `ts`. Weeks start on Monday and
+    /// months on the 1st, both at midnight UTC.
+    pub fn bucket_start(&self, ts: Timestamp) -> Timestamp {
+        match self {
+            Self::Week1 => {
+                let monday = ts.date_naive() - Duration::days(i64::from(ts.weekday().num_days_from_monday()));
+                Utc.from_utc_datetime(&monday.and_time(chrono::NaiveTime::MIN))
+            }
+            Self::Month1 => Utc
+                .with_ymd_and_hms(ts.year(), ts.month(), 1, 0, 0, 0)
+                .single()
+                .unwrap_or(ts),
+            _ => {
+                let secs = self.to_seconds();
+                let start = ts.timestamp().div_euclid(secs) * secs;
+                DateTime::from_timestamp(start, 0).unwrap_or(ts)
+            }
+        }
+    }
 }
 
 impl fmt::Display for TimeInterval {
@@ -2,7 +2,12 @@ use yew::prelude::*;
 use yew_router::prelude::*;
 
 use crate::components::{navbar::Navbar, sidebar::Sidebar};
-use crate::pages::{ChartView, Dashboard, Scanner, Strategies};
+use api::auth::AuthSession;
+use crate::pages::{ChartView, Dashboard, LiveTrading, Login, Scanner, Strategies};
+use crate::session;
+
+/// The signed in session, shared so the navbar follows logins and logouts
+pub type SessionContext = UseStateHandle<Option<AuthSession>>;
 
 #[derive(Routable, PartialEq, Clone, Debug)]
 pub enum Route {
@@ -16,8 +21,10 @@ pub enum Route {
     Strategies,
     //#[at("/backtest")]
     //Backtest,
-    //#[at("/live")]
-    //LiveTrading,
+    #[at("/live")]
+    LiveTrading,
+    #[at("/login")]
+    Login,
     //#[at("/settings")]
     //Settings,
     #[not_found]
@@ -27,7 +34,10 @@ pub enum Route {
 
 #[function_component(App)]
 pub fn app() -> Html {
+    let session = use_state(session::load);
+
     html! {
+        <ContextProvider<SessionContext> context={session}>
         <BrowserRouter>
             <div class="app-container flex h-screen bg-gradient-to-br from-[#c0f0ff] via-[#a0e0ff] to-[#c8ffe0] 
                 font-frutiger text-gray-900 dark:text-white p-8">
@@ -40,17 +50,22 @@ pub fn app() -> Html {
                 </div>
             </div>
         </BrowserRouter>
+        </ContextProvider<SessionContext>>
     }
 }
 
 fn switch(route: Route) -> Html {
+    if route != Route::Login && session::load().is_none() {
+        return html! { <Redirect<Route> to={Route::Login} /> };
+    }
     match route {
         Route::Dashboard => html! { <Dashboard /> },
         Route::ChartView => html! { <ChartView /> },
         Route::Scanner => html! { <Scanner /> },
         Route::Strategies => html! { <Strategies /> },
         //Route::Backtest => html! { <Backtest /> },
-        //Route::LiveTrading => html! { <LiveTrading /> },
+        Route::LiveTrading => html! { <LiveTrading /> },
+        Route::Login => html! { <Login /> },
         //Route::Settings => html! { <Settings /> },
         Route::NotFound => html! { <h1>{ "404 - Page Not Found" }</h1> },
     }
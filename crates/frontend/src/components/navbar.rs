@@ -1,11 +1,35 @@
 use yew::prelude::*;
+use yew_router::prelude::*;
+use crate::app::{Route, SessionContext};
+use crate::session;
 
 #[function_component(Navbar)]
 pub fn navbar() -> Html {
+    let ctx = use_context::<SessionContext>().expect("session context");
+    let navigator = use_navigator().expect("router");
+
+    let logout = {
+        let ctx = ctx.clone();
+        Callback::from(move |_: MouseEvent| {
+            session::clear();
+            ctx.set(None);
+            navigator.push(&Route::Login);
+        })
+    };
+
     html! {
         <header class="bg-gray-800 text-white p-4 flex justify-between items-center">
             <h1 class="text-xl font-bold">{ "Trading Engine" }</h1>
-            <div>{ "User / Account" }</div>
+            if let Some(s) = &*ctx {
+                <div class="flex items-center gap-3">
+                    <span>{ &s.user.username }</span>
+                    <button class="px-3 py-1 rounded bg-gray-600 hover:bg-gray-700 text-sm" onclick={logout}>
+                        { "Log out" }
+                    </button>
+                </div>
+            } else {
+                <div>{ "Signed out" }</div>
+            }
         </header>
     }
 }
@@ -14,7 +14,7 @@ pub fn sidebar() -> Html {
                 <li><Link<Route> to={Route::Scanner} classes="block p-3 rounded hover:bg-gray-700">{ "Scanner" }</Link<Route>></li>
                 <li><Link<Route> to={Route::Strategies} classes="block p-3 rounded hover:bg-gray-700">{ "Strategies" }</Link<Route>></li>
                 //<li><Link<Route> to={Route::Backtest}>{ "Backtest" }</Link<Route>></li>
-                //<li><Link<Route> to={Route::LiveTrading}>{ "Live Trading" }</Link<Route>></li>
+                <li><Link<Route> to={Route::LiveTrading} classes="block p-3 rounded hover:bg-gray-700">{ "Live Trading" }</Link<Route>></li>
                 //<li><Link<Route> to={Route::Settings}>{ "Settings" }</Link<Route>></li>
             </ul>
         </nav>
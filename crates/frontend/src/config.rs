@@ -0,0 +1,14 @@
+//! Where the backend is. Both URLs can be set when building, through
+//! `TRAITER_API_URL` and `TRAITER_WS_URL`.
+
+/// Base URL of the REST API
+pub const API_URL: &str = match option_env!("TRAITER_API_URL") {
+    Some(url) => url,
+    None => "http://localhost:3000",
+};
+
+/// Base URL of the websocket endpoints
+pub const WS_URL: &str = match option_env!("TRAITER_WS_URL") {
+    Some(url) => url,
+    None => "ws://localhost:3000",
+};
@@ -1,6 +1,8 @@
 mod app;
+mod config;
 mod pages;
 mod components;
+mod session;
 
 use crate::app::App;
 
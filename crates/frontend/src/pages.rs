@@ -2,8 +2,12 @@ mod dashboard;
 mod chart_view;
 mod scanner;
 mod strategies;
+mod live_trading;
+mod login;
 
 pub use dashboard::*;
 pub use chart_view::*;
 pub use scanner::*;
-pub use strategies::*;
\ No newline at end of file
+pub use strategies::*;
+pub use live_trading::*;
+pub use login::*;
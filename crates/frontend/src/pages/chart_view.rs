@@ -1,3 +1,5 @@
+use chrono::{Duration, SecondsFormat, Utc};
+use gloo::net::http::Request;
 use gloo::net::websocket::{futures::WebSocket, Message};
 use wasm_bindgen::JsCast;
 use yew::prelude::*;
@@ -7,10 +9,17 @@ use std::rc::Rc;
 use std::cell::RefCell;
 use wasm_bindgen::prelude::*;
 use api::ProviderType;
-use api::stock::{StockWatchReqMsg, StockWatchResMsg};
+use api::candles::CandlePage;
+use api::stock::{StockWatchReq, StockWatchReqMsg, StockWatchResMsg};
+use dnn_core::market::Candle;
 use dnn_core::time::TimeInterval;
+use crate::config::{API_URL, WS_URL};
+use crate::session;
 use serde::{Deserialize, Serialize};
 
+/// Days of history drawn before live candles arrive
+const HISTORY_DAYS: i64 = 30;
+
 #[wasm_bindgen(module = "/js/price_chart.js")]
 extern "C" {
     pub type PriceChart;
@@ -75,6 +84,8 @@ pub enum Msg {
     WebSocketError(String),
     SelectSymbol(String),
     SendSubscription,
+    LoadHistory,
+    HistoryLoaded(String, Vec<Candle>),
     TestChart,
     ToggleIndicator(String),
     ToggleIndicatorPanel,
@@ -112,7 +123,7 @@ impl Component for ChartView {
             Msg::ConnectWebSocket => {
                 let link = ctx.link().clone();
                 wasm_bindgen_futures::spawn_local(async move {
-                    match WebSocket::open("ws://localhost:3000/live/stock") {
+                    match WebSocket::open(&session::ws_url(&format!("{WS_URL}/live/stock"))) {
                         Ok(ws) => {
                             link.send_message(Msg::WebSocketConnected(ws));
                         }
@@ -158,9 +169,9 @@ impl Component for ChartView {
 
                     // Unsubscribe from previous subscription if exists
                     if let Some(old_id) = &self.current_subscription_id {
-                        let unsubscribe_msg = StockWatchReqMsg::Unsubscribe {
+                        let unsubscribe_msg = StockWatchReq::new(StockWatchReqMsg::Unsubscribe {
                             id: old_id.clone(),
-                        };
+                        });
 
                         if let Ok(json) = serde_json::to_string(&unsubscribe_msg) {
                             let sender_clone = ws_sender.clone();
@@ -172,17 +183,19 @@ impl Component for ChartView {
                         }
                     }
 
+                    ctx.link().send_message(Msg::LoadHistory);
+
                     // Create new subscription ID
                     let subscription_id = format!("{}_{}", selected_symbol, js_sys::Date::now() as u64);
                     self.current_subscription_id = Some(subscription_id.clone());
 
                     // Subscribe to new symbol
-                    let subscribe_msg = StockWatchReqMsg::Day {
+                    let subscribe_msg = StockWatchReq::new(StockWatchReqMsg::Day {
                         id: subscription_id,
                         provider: ProviderType::Yahoo,
                         symbol: selected_symbol,
                         interval: TimeInterval::Hour1,
-                    };
+                    });
 
                     if let Ok(json) = serde_json::to_string(&subscribe_msg) {
                         let sender_clone = ws_sender.clone();
@@ -198,6 +211,58 @@ impl Component for ChartView {
                 true
             }
 
+            Msg::LoadHistory => {
+                let link = ctx.link().clone();
+                let symbol = self.selected_symbol.clone();
+                let end = Utc::now();
+                let start = end - Duration::days(HISTORY_DAYS);
+                let url = format!(
+                    "{API_URL}/candles?provider=yahoo&symbol={}&interval={}&start={}&end={}&limit=5000",
+                    js_sys::encode_uri_component(&symbol),
+                    TimeInterval::Hour1,
+                    start.to_rfc3339_opts(SecondsFormat::Secs, true),
+                    end.to_rfc3339_opts(SecondsFormat::Secs, true),
+                );
+                wasm_bindgen_futures::spawn_local(async move {
+                    match session::authorized(Request::get(&url)).send().await {
+                        Ok(resp) if resp.ok() => match resp.json::<CandlePage>().await {
+                            Ok(page) => link.send_message(Msg::HistoryLoaded(symbol, page.candles)),
+                            Err(e) => web_sys::console::error_1(&format!("Bad candle history: {e}").into()),
+                        },
+                        Ok(resp) => web_sys::console::error_1(&format!("Failed to load history: {}", resp.status()).into()),
+                        Err(e) => web_sys::console::error_1(&format!("Failed to load history: {e}").into()),
+                    }
+                });
+                false
+            }
+
+            Msg::HistoryLoaded(symbol, candles) => {
+                // The user may have moved on while it loaded
+                if symbol != self.selected_symbol {
+                    return false;
+                }
+                let bars: Vec<serde_json::Value> = candles
+                    .iter()
+                    .map(|c| serde_json::json!({
+                        "timestamp": c.timestamp.timestamp_millis(),
+                        "open": c.open,
+                        "high": c.high,
+                        "low": c.low,
+                        "close": c.close,
+                        "volume": c.volume,
+                    }))
+                    .collect();
+                if let Some(last) = candles.last() {
+                    self.price_data.insert(symbol.clone(), last.close);
+                }
+                let data = serde_json::to_string(&bars)
+                    .ok()
+                    .and_then(|json| js_sys::JSON::parse(&json).ok())
+                    .unwrap_or(JsValue::NULL);
+                self.chart.switch_symbol(&symbol, data);
+                true
+            }
+
             Msg::WebSocketMessage(text) => {
                 web_sys::console::log_1(&format!("Received WebSocket message: {}", text).into());
 
@@ -239,9 +304,17 @@ impl Component for ChartView {
                         self.price_data.insert(symbol, candle.close);
                         web_sys::console::log_1(&"Chart updated successfully".into());
                     }
-                    Ok(StockWatchResMsg::Error { message }) => {
-                        web_sys::console::error_1(&format!("Server error: {}", message).into());
+                    Ok(StockWatchResMsg::Error(error)) => {
+                        web_sys::console::error_1(&format!(
+                            "Server error {:?} for {:?}: {}",
+                            error.code, error.id, error.message
+                        ).into());
+                    }
+                    Ok(StockWatchResMsg::End { id }) => {
+                        web_sys::console::warn_1(&format!("Subscription {id} ended").into());
                     }
+                    // Hello, acks, subscription lists and pongs need no handling
+                    Ok(_) => {}
                     Err(e) => {
                         web_sys::console::error_1(&format!("Failed to parse message: {} - Error: {:?}", text, e).into());
                     }
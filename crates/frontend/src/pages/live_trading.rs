@@ -0,0 +1,438 @@
+use gloo::net::http::Request;
+use gloo::net::websocket::{futures::WebSocket, Message};
+use futures::channel::oneshot;
+use futures::future::{self, Either};
+use futures::StreamExt;
+use yew::prelude::*;
+use web_sys::HtmlInputElement;
+use api::ProviderType;
+use api::paper::{PaperEvent, PaperSessionInfo, PaperStrategy, StartPaperReq};
+use dnn_core::time::TimeInterval;
+use crate::components::card::Card;
+use crate::config::{API_URL, WS_URL};
+use crate::session;
+
+/// Most recent events kept in the activity log
+const MAX_LOG: usize = 100;
+
+pub struct LiveTrading {
+    sessions: Vec<PaperSessionInfo>,
+    selected: Option<PaperSessionInfo>,
+    log: Vec<String>,
+    error: Option<String>,
+    symbol: String,
+    short: usize,
+    long: usize,
+    starting_cash: f64,
+    /// Bumped on every selection so events from an old stream are ignored
+    stream_gen: u32,
+    /// Dropping this closes the stream of the selected session
+    stream_close: Option<oneshot::Sender<()>>,
+}
+
+pub enum Msg {
+    LoadSessions,
+    SessionsLoaded(Vec<PaperSessionInfo>),
+    Select(String),
+    Event(u32, PaperEvent),
+    StreamClosed(u32),
+    Start,
+    Started(PaperSessionInfo),
+    Stop(String),
+    Stopped(String),
+    UpdateSymbol(String),
+    UpdateShort(String),
+    UpdateLong(String),
+    UpdateCash(String),
+    Error(String),
+}
+
+impl Component for LiveTrading {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Msg::LoadSessions);
+
+        Self {
+            sessions: Vec::new(),
+            selected: None,
+            log: Vec::new(),
+            error: None,
+            symbol: "AAPL".to_owned(),
+            short: 10,
+            long: 30,
+            starting_cash: 100_000.0,
+            stream_gen: 0,
+            stream_close: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::LoadSessions => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let res = session::authorized(Request::get(&format!("{API_URL}/paper"))).send().await;
+                    match res {
+                        Ok(resp) => match resp.json::<Vec<PaperSessionInfo>>().await {
+                            Ok(sessions) => link.send_message(Msg::SessionsLoaded(sessions)),
+                            Err(e) => link.send_message(Msg::Error(format!("Bad session list: {e}"))),
+                        },
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to load sessions: {e}"))),
+                    }
+                });
+                false
+            }
+
+            Msg::SessionsLoaded(sessions) => {
+                if self.selected.is_none()
+                    && let Some(first) = sessions.first()
+                {
+                    ctx.link().send_message(Msg::Select(first.id.clone()));
+                }
+                self.sessions = sessions;
+                true
+            }
+
+            Msg::Select(id) => {
+                self.stream_gen += 1;
+                self.log.clear();
+                self.selected = self.sessions.iter().find(|s| s.id == id).cloned();
+
+                let generation = self.stream_gen;
+                let (close, mut closed) = oneshot::channel();
+                // Replacing the sender closes the previous session's stream
+                self.stream_close = Some(close);
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let ws = match WebSocket::open(&session::ws_url(&format!("{WS_URL}/paper/{id}/stream"))) {
+                        Ok(ws) => ws,
+                        Err(e) => {
+                            link.send_message(Msg::Error(format!("Failed to connect: {e:?}")));
+                            return;
+                        }
+                    };
+                    let (write, mut read) = ws.split();
+                    loop {
+                        let msg = match future::select(read.next(), &mut closed).await {
+                            Either::Left((msg, _)) => msg,
+                            Either::Right(_) => {
+                                if let Ok(ws) = write.reunite(read) {
+                                    let _ = ws.close(Some(1000), None);
+                                }
+                                return;
+                            }
+                        };
+                        match msg {
+                            Some(Ok(Message::Text(text))) => match serde_json::from_str::<PaperEvent>(&text) {
+                                Ok(event) => link.send_message(Msg::Event(generation, event)),
+                                Err(e) => link.send_message(Msg::Error(format!("Bad event: {e}"))),
+                            },
+                            Some(Ok(Message::Bytes(_))) => {}
+                            Some(Err(_)) | None => break,
+                        }
+                    }
+                    link.send_message(Msg::StreamClosed(generation));
+                });
+                true
+            }
+
+            Msg::Event(generation, event) => {
+                if generation != self.stream_gen {
+                    return false;
+                }
+                self.apply_event(event);
+                true
+            }
+
+            Msg::StreamClosed(generation) => {
+                if generation == self.stream_gen
+                    && let Some(info) = &mut self.selected
+                {
+                    info.running = false;
+                }
+                true
+            }
+
+            Msg::Start => {
+                let req = StartPaperReq {
+                    provider: ProviderType::Yahoo,
+                    symbol: self.symbol.trim().to_uppercase(),
+                    interval: TimeInterval::Minute1,
+                    strategy: PaperStrategy::SmaCross { short: self.short, long: self.long },
+                    starting_cash: self.starting_cash,
+                };
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    let res = match session::authorized(Request::post(&format!("{API_URL}/paper"))).json(&req) {
+                        Ok(r) => r.send().await,
+                        Err(e) => {
+                            link.send_message(Msg::Error(e.to_string()));
+                            return;
+                        }
+                    };
+                    match res {
+                        Ok(resp) if resp.ok() => match resp.json::<PaperSessionInfo>().await {
+                            Ok(info) => link.send_message(Msg::Started(info)),
+                            Err(e) => link.send_message(Msg::Error(format!("Bad response: {e}"))),
+                        },
+                        Ok(resp) => {
+                            let text = resp.text().await.unwrap_or_default();
+                            link.send_message(Msg::Error(format!("Failed to start session: {text}")));
+                        }
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to start session: {e}"))),
+                    }
+                });
+                false
+            }
+
+            Msg::Started(info) => {
+                let id = info.id.clone();
+                self.sessions.push(info);
+                self.error = None;
+                ctx.link().send_message(Msg::Select(id));
+                true
+            }
+
+            Msg::Stop(id) => {
+                let link = ctx.link().clone();
+                wasm_bindgen_futures::spawn_local(async move {
+                    match session::authorized(Request::delete(&format!("{API_URL}/paper/{id}"))).send().await {
+                        Ok(resp) if resp.ok() => link.send_message(Msg::Stopped(id)),
+                        Ok(resp) => link.send_message(Msg::Error(format!("Failed to stop session: {}", resp.status()))),
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to stop session: {e}"))),
+                    }
+                });
+                false
+            }
+
+            Msg::Stopped(id) => {
+                self.sessions.retain(|s| s.id != id);
+                if self.selected.as_ref().is_some_and(|s| s.id == id) {
+                    self.selected = None;
+                    self.stream_gen += 1;
+                    self.stream_close = None;
+                    self.log.clear();
+                }
+                true
+            }
+
+            Msg::UpdateSymbol(value) => {
+                self.symbol = value;
+                false
+            }
+
+            Msg::UpdateShort(value) => {
+                if let Ok(v) = value.parse() {
+                    self.short = v;
+                }
+                false
+            }
+
+            Msg::UpdateLong(value) => {
+                if let Ok(v) = value.parse() {
+                    self.long = v;
+                }
+                false
+            }
+
+            Msg::UpdateCash(value) => {
+                if let Ok(v) = value.parse() {
+                    self.starting_cash = v;
+                }
+                false
+            }
+
+            Msg::Error(e) => {
+                self.error = Some(e);
+                true
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let link = ctx.link();
+        let on_input = |f: fn(String) -> Msg| {
+            link.callback(move |e: InputEvent| {
+                let input: HtmlInputElement = e.target_unchecked_into();
+                f(input.value())
+            })
+        };
+
+        html! {
+            <div class="space-y-6">
+                <div class="bg-gray-800 rounded-xl shadow p-4 flex flex-wrap items-end gap-4">
+                    <label class="text-sm text-gray-400">{ "Symbol" }
+                        <input class="block mt-1 p-2 rounded bg-gray-700 text-white w-28"
+                            value={self.symbol.clone()} oninput={on_input(Msg::UpdateSymbol)} />
+                    </label>
+                    <label class="text-sm text-gray-400">{ "Short SMA" }
+                        <input type="number" class="block mt-1 p-2 rounded bg-gray-700 text-white w-24"
+                            value={self.short.to_string()} oninput={on_input(Msg::UpdateShort)} />
+                    </label>
+                    <label class="text-sm text-gray-400">{ "Long SMA" }
+                        <input type="number" class="block mt-1 p-2 rounded bg-gray-700 text-white w-24"
+                            value={self.long.to_string()} oninput={on_input(Msg::UpdateLong)} />
+                    </label>
+                    <label class="text-sm text-gray-400">{ "Starting Cash" }
+                        <input type="number" class="block mt-1 p-2 rounded bg-gray-700 text-white w-32"
+                            value={self.starting_cash.to_string()} oninput={on_input(Msg::UpdateCash)} />
+                    </label>
+                    <button class="px-4 py-2 rounded bg-blue-600 hover:bg-blue-700 text-white"
+                        onclick={link.callback(|_| Msg::Start)}>
+                        { "Start Paper Trading" }
+                    </button>
+                </div>
+
+                if let Some(e) = &self.error {
+                    <div class="bg-red-900 text-red-200 rounded p-3 text-sm">{ e }</div>
+                }
+
+                <div class="bg-gray-800 rounded-xl shadow p-4">
+                    <h3 class="text-sm font-semibold text-gray-400 mb-2">{ "Sessions" }</h3>
+                    <table class="w-full text-left">
+                        <thead>
+                            <tr class="text-gray-400 text-sm">
+                                <th class="pb-2">{ "Symbol" }</th>
+                                <th class="pb-2">{ "Strategy" }</th>
+                                <th class="pb-2">{ "Equity" }</th>
+                                <th class="pb-2">{ "Status" }</th>
+                                <th class="pb-2"></th>
+                            </tr>
+                        </thead>
+                        <tbody>
+                            { for self.sessions.iter().map(|s| self.view_session_row(ctx, s)) }
+                        </tbody>
+                    </table>
+                </div>
+
+                { self.view_selected() }
+            </div>
+        }
+    }
+}
+
+impl LiveTrading {
+    fn apply_event(&mut self, event: PaperEvent) {
+        let line = match event {
+            PaperEvent::Session { info } => {
+                self.selected = Some(info);
+                return;
+            }
+            PaperEvent::Pnl { equity, cash, realized, unrealized, positions, .. } => {
+                if let Some(info) = &mut self.selected {
+                    info.equity = equity;
+                    info.cash = cash;
+                    info.realized_pnl = realized;
+                    info.unrealized_pnl = unrealized;
+                    info.positions = positions;
+                }
+                if let Some(s) = self.sessions.iter_mut().find(|s| Some(&s.id) == self.selected.as_ref().map(|i| &i.id)) {
+                    s.equity = equity;
+                }
+                return;
+            }
+            PaperEvent::Order { order } => format!("Order #{} {:?} {} {}", order.id, order.side, order.qty, order.symbol),
+            PaperEvent::Fill { report } => format!("Filled #{} {} @ {:.2}", report.order_id, report.filled_qty, report.fill_price),
+            PaperEvent::Rejected { report } => match report.status {
+                dnn_core::ExecutionStatus::Rejected { reason } => format!("Rejected #{}: {reason}", report.order_id),
+                _ => format!("Rejected #{}", report.order_id),
+            },
+            PaperEvent::Stopped { reason } => {
+                if let Some(info) = &mut self.selected {
+                    info.running = false;
+                }
+                format!("Stopped{}", reason.map(|r| format!(": {r}")).unwrap_or_default())
+            }
+            PaperEvent::Log { ts, message } => format!("{} {message}", ts.format("%H:%M:%S")),
+            PaperEvent::Error { message } => format!("Error: {message}"),
+        };
+
+        self.log.insert(0, line);
+        self.log.truncate(MAX_LOG);
+    }
+
+    fn view_session_row(&self, ctx: &Context<Self>, s: &PaperSessionInfo) -> Html {
+        let id = s.id.clone();
+        let stop_id = s.id.clone();
+        let selected = self.selected.as_ref().is_some_and(|i| i.id == s.id);
+        let strategy = match &s.config.strategy {
+            PaperStrategy::SmaCross { short, long } => format!("SMA {short}/{long}"),
+            PaperStrategy::Saved { strategy_id, version: Some(version) } => format!("{strategy_id} v{version}"),
+            PaperStrategy::Saved { strategy_id, version: None } => strategy_id.clone(),
+        };
+
+        html! {
+            <tr class={classes!("border-t", "border-gray-700", "text-sm", "cursor-pointer", selected.then_some("bg-gray-700"))}
+                onclick={ctx.link().callback(move |_| Msg::Select(id.clone()))}>
+                <td class="py-2">{ &s.config.symbol }</td>
+                <td class="py-2">{ strategy }</td>
+                <td class="py-2">{ format!("${:.2}", s.equity) }</td>
+                <td class="py-2">{ if s.running { "Running" } else { "Stopped" } }</td>
+                <td class="py-2 text-right">
+                    <button class="px-2 py-1 rounded bg-red-600 hover:bg-red-700 text-white text-xs"
+                        onclick={ctx.link().callback(move |e: MouseEvent| {
+                            e.stop_propagation();
+                            Msg::Stop(stop_id.clone())
+                        })}>
+                        { "Stop" }
+                    </button>
+                </td>
+            </tr>
+        }
+    }
+
+    fn view_selected(&self) -> Html {
+        let Some(info) = &self.selected else {
+            return html! {};
+        };
+        let pnl_class = |v: f64| if v >= 0.0 { "text-green-400" } else { "text-red-400" };
+
+        html! {
+            <>
+                <div class="grid grid-cols-1 md:grid-cols-4 gap-4">
+                    <Card title="Equity">{ format!("${:.2}", info.equity) }</Card>
+                    <Card title="Cash">{ format!("${:.2}", info.cash) }</Card>
+                    <Card title="Realized PnL">
+                        <span class={pnl_class(info.realized_pnl)}>{ format!("{:.2}", info.realized_pnl) }</span>
+                    </Card>
+                    <Card title="Unrealized PnL">
+                        <span class={pnl_class(info.unrealized_pnl)}>{ format!("{:.2}", info.unrealized_pnl) }</span>
+                    </Card>
+                </div>
+
+                <div class="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    <div class="bg-gray-800 rounded-xl shadow p-4">
+                        <h3 class="text-sm font-semibold text-gray-400 mb-2">{ "Positions" }</h3>
+                        <table class="w-full text-left">
+                            <thead>
+                                <tr class="text-gray-400 text-sm">
+                                    <th class="pb-2">{ "Symbol" }</th>
+                                    <th class="pb-2">{ "Qty" }</th>
+                                    <th class="pb-2">{ "Avg Price" }</th>
+                                </tr>
+                            </thead>
+                            <tbody>
+                                { for info.positions.iter().filter(|p| p.qty != 0.0).map(|p| html! {
+                                    <tr class="border-t border-gray-700 text-sm">
+                                        <td class="py-2">{ &p.symbol }</td>
+                                        <td class="py-2">{ p.qty }</td>
+                                        <td class="py-2">{ format!("{:.2}", p.avg_price) }</td>
+                                    </tr>
+                                }) }
+                            </tbody>
+                        </table>
+                    </div>
+
+                    <div class="bg-gray-800 rounded-xl shadow p-4">
+                        <h3 class="text-sm font-semibold text-gray-400 mb-2">{ "Activity" }</h3>
+                        <ul class="text-sm space-y-1 max-h-64 overflow-y-auto">
+                            { for self.log.iter().map(|line| html! { <li>{ line }</li> }) }
+                        </ul>
+                    </div>
+                </div>
+            </>
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use gloo::net::http::Request;
+use yew::prelude::*;
+use yew_router::prelude::*;
+use web_sys::HtmlInputElement;
+use api::auth::{AuthSession, Credentials};
+use crate::app::{Route, SessionContext};
+use crate::config::API_URL;
+use crate::session;
+
+#[function_component(Login)]
+pub fn login() -> Html {
+    let ctx = use_context::<SessionContext>().expect("session context");
+    let navigator = use_navigator().expect("router");
+    let username = use_state(String::new);
+    let password = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    let on_input = |state: UseStateHandle<String>| {
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            state.set(input.value());
+        })
+    };
+
+    // Register and sign in share a body and a response, only the path differs
+    let submit = |path: &'static str| {
+        let (ctx, navigator) = (ctx.clone(), navigator.clone());
+        let (username, password, error) = (username.clone(), password.clone(), error.clone());
+        Callback::from(move |_: MouseEvent| {
+            let credentials = Credentials { username: (*username).clone(), password: (*password).clone() };
+            let (ctx, navigator, error) = (ctx.clone(), navigator.clone(), error.clone());
+            wasm_bindgen_futures::spawn_local(async move {
+                let res = match Request::post(&format!("{API_URL}/auth/{path}")).json(&credentials) {
+                    Ok(req) => req.send().await,
+                    Err(e) => return error.set(Some(format!("Bad request: {e}"))),
+                };
+                match res {
+                    Ok(resp) if resp.ok() => match resp.json::<AuthSession>().await {
+                        Ok(s) => {
+                            session::save(&s);
+                            ctx.set(Some(s));
+                            navigator.push(&Route::Dashboard);
+                        }
+                        Err(e) => error.set(Some(format!("Bad session: {e}"))),
+                    },
+                    Ok(resp) => error.set(Some(resp.text().await.unwrap_or_else(|_err| resp.status_text()))),
+                    Err(e) => error.set(Some(format!("Request failed: {e}"))),
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="max-w-sm mx-auto mt-16 bg-gray-800 rounded-xl shadow p-6 space-y-4">
+            <h2 class="text-lg font-semibold text-white">{ "Sign in" }</h2>
+            <label class="block text-sm text-gray-400">{ "Username" }
+                <input class="block w-full mt-1 p-2 rounded bg-gray-700 text-white"
+                    value={(*username).clone()} oninput={on_input(username.clone())} />
+            </label>
+            <label class="block text-sm text-gray-400">{ "Password" }
+                <input type="password" class="block w-full mt-1 p-2 rounded bg-gray-700 text-white"
+                    value={(*password).clone()} oninput={on_input(password.clone())} />
+            </label>
+            if let Some(e) = &*error {
+                <div class="bg-red-900 text-red-200 rounded p-3 text-sm">{ e }</div>
+            }
+            <div class="flex gap-2">
+                <button class="flex-1 px-4 py-2 rounded bg-blue-600 hover:bg-blue-700 text-white"
+                    onclick={submit("login")}>
+                    { "Sign in" }
+                </button>
+                <button class="flex-1 px-4 py-2 rounded bg-gray-600 hover:bg-gray-700 text-white"
+                    onclick={submit("register")}>
+                    { "Register" }
+                </button>
+            </div>
+        </div>
+    }
+}
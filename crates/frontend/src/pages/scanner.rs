@@ -1,21 +1,16 @@
 use yew::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use wasm_bindgen_futures::spawn_local;
 use gloo::net::http::Request;
 use web_sys::HtmlInputElement;
+use api::scanner::{ScannerPage, ScannerQuery, ScannerRow, ScannerSort};
+use api::watchlist::{Watchlist, WatchlistReq};
+use crate::config::API_URL;
+use crate::session;
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct StockInfo {
-    pub symbol: String,
-    pub name: String,
-    pub price: f64,
-    pub change: f64,
-    pub change_percent: f64,
-    pub volume: u64,
-    pub market_cap: Option<f64>,
-    pub sector: Option<String>,
-}
+/// Name of the watchlist made for users without one
+const DEFAULT_WATCHLIST: &str = "Default";
+/// Rows asked for at once
+const PAGE_ROWS: usize = 100;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ScanFilter {
@@ -58,25 +53,58 @@ impl ScanFilter {
             ScanFilter::FinancialStocks,
         ]
     }
+
+    /// Scanner expression of the filter
+    fn expression(&self) -> Option<&'static str> {
+        match self {
+            ScanFilter::GainersBig => Some("change_pct > 5"),
+            ScanFilter::GainersSmall => Some("change_pct > 1 AND change_pct <= 5"),
+            ScanFilter::LosersBig => Some("change_pct < -5"),
+            ScanFilter::LosersSmall => Some("change_pct >= -5 AND change_pct < -1"),
+            ScanFilter::HighVolume => Some("volume_ratio > 1.5"),
+            _ => None,
+        }
+    }
+
+    fn sector(&self) -> Option<&'static str> {
+        match self {
+            ScanFilter::TechStocks => Some("Technology"),
+            ScanFilter::HealthStocks => Some("Healthcare"),
+            ScanFilter::FinancialStocks => Some("Financial Services"),
+            _ => None,
+        }
+    }
 }
 
 pub struct Scanner {
-    stocks: Vec<StockInfo>,
-    filtered_stocks: Vec<StockInfo>,
+    stocks: Vec<ScannerRow>,
+    filtered_stocks: Vec<ScannerRow>,
+    total: usize,
     selected_filter: ScanFilter,
+    /// Typed by the user, ANDed with the selected filter
+    custom_filter: String,
     search_term: String,
     loading: bool,
+    error: Option<String>,
+    /// Symbols of the user's first watchlist, saved on every change
     watchlist: Vec<String>,
+    watchlist_id: Option<String>,
+    watchlist_name: String,
     sort_column: String,
     sort_ascending: bool,
 }
 
 pub enum Msg {
     LoadStocks,
-    StocksLoaded(Vec<StockInfo>),
+    StocksLoaded(ScannerPage),
     LoadError(String),
     SetFilter(ScanFilter),
+    CustomFilterInput(String),
+    ApplyCustomFilter,
     SearchInput(String),
+    LoadWatchlist,
+    WatchlistLoaded(Watchlist),
+    WatchlistError(String),
     AddToWatchlist(String),
     RemoveFromWatchlist(String),
     RefreshData,
@@ -88,177 +116,128 @@ impl Component for Scanner {
     type Properties = ();
 
     fn create(ctx: &Context<Self>) -> Self {
-        let mut scanner = Self {
+        ctx.link().send_message(Msg::LoadStocks);
+        ctx.link().send_message(Msg::LoadWatchlist);
+        Self {
             stocks: Vec::new(),
             filtered_stocks: Vec::new(),
+            total: 0,
             selected_filter: ScanFilter::All,
+            custom_filter: String::new(),
             search_term: String::new(),
             loading: true,
+            error: None,
             watchlist: Vec::new(),
+            watchlist_id: None,
+            watchlist_name: DEFAULT_WATCHLIST.to_owned(),
             sort_column: "symbol".to_string(),
             sort_ascending: true,
-        };
-
-        ctx.link().send_message(Msg::LoadStocks);
-        scanner
+        }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::LoadStocks => {
                 self.loading = true;
-                let link = _ctx.link().clone();
+                let link = ctx.link().clone();
+                let query = self.query();
 
                 spawn_local(async move {
-                    // Mock data - replace with actual API call
-                    let mock_stocks = vec![
-                        StockInfo {
-                            symbol: "AAPL".to_string(),
-                            name: "Apple Inc.".to_string(),
-                            price: 192.34,
-                            change: 2.45,
-                            change_percent: 1.29,
-                            volume: 45_234_567,
-                            market_cap: Some(3_000_000_000_000.0),
-                            sector: Some("Technology".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "TSLA".to_string(),
-                            name: "Tesla Inc.".to_string(),
-                            price: 245.67,
-                            change: -12.45,
-                            change_percent: -4.83,
-                            volume: 89_123_456,
-                            market_cap: Some(800_000_000_000.0),
-                            sector: Some("Automotive".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "NVDA".to_string(),
-                            name: "NVIDIA Corporation".to_string(),
-                            price: 467.89,
-                            change: 34.56,
-                            change_percent: 7.97,
-                            volume: 67_890_123,
-                            market_cap: Some(1_200_000_000_000.0),
-                            sector: Some("Technology".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "MSFT".to_string(),
-                            name: "Microsoft Corporation".to_string(),
-                            price: 334.12,
-                            change: 1.89,
-                            change_percent: 0.57,
-                            volume: 23_456_789,
-                            market_cap: Some(2_500_000_000_000.0),
-                            sector: Some("Technology".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "GOOGL".to_string(),
-                            name: "Alphabet Inc.".to_string(),
-                            price: 142.67,
-                            change: 8.92,
-                            change_percent: 6.67,
-                            volume: 34_567_890,
-                            market_cap: Some(1_800_000_000_000.0),
-                            sector: Some("Technology".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "JPM".to_string(),
-                            name: "JPMorgan Chase & Co.".to_string(),
-                            price: 156.78,
-                            change: -2.34,
-                            change_percent: -1.47,
-                            volume: 12_345_678,
-                            market_cap: Some(450_000_000_000.0),
-                            sector: Some("Financial".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "JNJ".to_string(),
-                            name: "Johnson & Johnson".to_string(),
-                            price: 167.89,
-                            change: 3.21,
-                            change_percent: 1.95,
-                            volume: 8_901_234,
-                            market_cap: Some(420_000_000_000.0),
-                            sector: Some("Healthcare".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "AMD".to_string(),
-                            name: "Advanced Micro Devices".to_string(),
-                            price: 118.45,
-                            change: -8.76,
-                            change_percent: -6.88,
-                            volume: 78_901_234,
-                            market_cap: Some(190_000_000_000.0),
-                            sector: Some("Technology".to_string()),
+                    let req = match session::authorized(Request::post(&format!("{API_URL}/scanner/query"))).json(&query) {
+                        Ok(req) => req,
+                        Err(e) => return link.send_message(Msg::LoadError(e.to_string())),
+                    };
+                    match req.send().await {
+                        Ok(resp) if resp.ok() => match resp.json::<ScannerPage>().await {
+                            Ok(page) => link.send_message(Msg::StocksLoaded(page)),
+                            Err(e) => link.send_message(Msg::LoadError(format!("Bad scanner results: {e}"))),
                         },
-                        StockInfo {
-                            symbol: "BAC".to_string(),
-                            name: "Bank of America Corp".to_string(),
-                            price: 34.56,
-                            change: -1.23,
-                            change_percent: -3.44,
-                            volume: 56_789_012,
-                            market_cap: Some(280_000_000_000.0),
-                            sector: Some("Financial".to_string()),
-                        },
-                        StockInfo {
-                            symbol: "PFE".to_string(),
-                            name: "Pfizer Inc.".to_string(),
-                            price: 45.67,
-                            change: 2.34,
-                            change_percent: 5.39,
-                            volume: 23_456_789,
-                            market_cap: Some(260_000_000_000.0),
-                            sector: Some("Healthcare".to_string()),
-                        },
-                    ];
-
-                    link.send_message(Msg::StocksLoaded(mock_stocks));
+                        // The reason an expression doesn't parse, for one
+                        Ok(resp) => link.send_message(Msg::LoadError(resp.text().await.unwrap_or_default())),
+                        Err(e) => link.send_message(Msg::LoadError(e.to_string())),
+                    }
                 });
                 true
             }
 
-            Msg::StocksLoaded(stocks) => {
+            Msg::StocksLoaded(page) => {
                 self.loading = false;
-                self.stocks = stocks;
-                self.apply_filters();
+                self.error = None;
+                self.total = page.total;
+                self.stocks = page.rows;
+                self.apply_search();
                 true
             }
 
             Msg::LoadError(error) => {
                 self.loading = false;
                 web_sys::console::error_1(&format!("Failed to load stocks: {}", error).into());
+                self.error = Some(error);
                 true
             }
 
             Msg::SetFilter(filter) => {
                 self.selected_filter = filter;
-                self.apply_filters();
+                ctx.link().send_message(Msg::LoadStocks);
                 true
             }
 
+            Msg::CustomFilterInput(filter) => {
+                self.custom_filter = filter;
+                false
+            }
+
+            Msg::ApplyCustomFilter => {
+                ctx.link().send_message(Msg::LoadStocks);
+                false
+            }
+
             Msg::SearchInput(term) => {
                 self.search_term = term;
-                self.apply_filters();
+                self.apply_search();
+                true
+            }
+
+            Msg::LoadWatchlist => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match load_watchlist().await {
+                        Ok(watchlist) => link.send_message(Msg::WatchlistLoaded(watchlist)),
+                        Err(e) => link.send_message(Msg::WatchlistError(e)),
+                    }
+                });
+                false
+            }
+
+            Msg::WatchlistLoaded(watchlist) => {
+                self.watchlist = watchlist.symbols;
+                self.watchlist_id = Some(watchlist.id);
+                self.watchlist_name = watchlist.name;
+                true
+            }
+
+            Msg::WatchlistError(error) => {
+                web_sys::console::error_1(&format!("Failed to save the watchlist: {}", error).into());
+                self.error = Some(error);
                 true
             }
 
             Msg::AddToWatchlist(symbol) => {
                 if !self.watchlist.contains(&symbol) {
                     self.watchlist.push(symbol);
+                    self.save_watchlist(ctx);
                 }
                 true
             }
 
             Msg::RemoveFromWatchlist(symbol) => {
                 self.watchlist.retain(|s| s != &symbol);
+                self.save_watchlist(ctx);
                 true
             }
 
             Msg::RefreshData => {
-                self.loading = true;
-                _ctx.link().send_message(Msg::LoadStocks);
+                ctx.link().send_message(Msg::LoadStocks);
                 true
             }
 
@@ -269,7 +248,7 @@ impl Component for Scanner {
                     self.sort_column = column;
                     self.sort_ascending = true;
                 }
-                self.apply_sort();
+                ctx.link().send_message(Msg::LoadStocks);
                 true
             }
         }
@@ -307,6 +286,32 @@ impl Component for Scanner {
                         />
                     </div>
 
+                    // Expression Filter
+                    <div>
+                        <label class="block text-sm font-medium text-gray-400 mb-2">{"Expression"}</label>
+                        <div class="flex gap-2">
+                            <input
+                                type="text"
+                                placeholder="e.g. volume_ratio > 2 AND RSI(14) < 70 AND close > SMA(50)"
+                                class="flex-1 px-3 py-2 bg-gray-700 text-white rounded-lg border border-gray-600 focus:ring-2 focus:ring-blue-500 font-mono text-sm"
+                                value={self.custom_filter.clone()}
+                                oninput={ctx.link().callback(|e: InputEvent| {
+                                    let input: HtmlInputElement = e.target_unchecked_into();
+                                    Msg::CustomFilterInput(input.value())
+                                })}
+                                onkeypress={ctx.link().batch_callback(|e: KeyboardEvent| {
+                                    (e.key() == "Enter").then_some(Msg::ApplyCustomFilter)
+                                })}
+                            />
+                            <button
+                                class="px-4 py-2 bg-gray-700 text-gray-300 rounded-lg hover:bg-gray-600"
+                                onclick={ctx.link().callback(|_| Msg::ApplyCustomFilter)}
+                            >
+                                {"Apply"}
+                            </button>
+                        </div>
+                    </div>
+
                     // Filter Buttons
                     <div>
                         <label class="block text-sm font-medium text-gray-400 mb-2">{"Filters"}</label>
@@ -333,8 +338,13 @@ impl Component for Scanner {
                 // Results Count
                 <div class="bg-gray-800 rounded-xl shadow p-4">
                     <p class="text-sm text-gray-400">
-                        {format!("Showing {} stocks", self.filtered_stocks.len())}
+                        {format!("Showing {} of {} stocks", self.filtered_stocks.len(), self.total)}
                     </p>
+                    {if let Some(error) = &self.error {
+                        html! { <p class="text-sm text-red-400 mt-2">{error}</p> }
+                    } else {
+                        html! {}
+                    }}
                 </div>
 
                 // Stock Table
@@ -361,51 +371,53 @@ impl Component for Scanner {
 }
 
 impl Scanner {
-    fn apply_filters(&mut self) {
-        let mut filtered = self.stocks.clone();
-
-        // Apply search filter
-        if !self.search_term.is_empty() {
-            let search_lower = self.search_term.to_lowercase();
-            filtered.retain(|stock| {
-                stock.symbol.to_lowercase().contains(&search_lower) ||
-                    stock.name.to_lowercase().contains(&search_lower)
-            });
+    /// Filters and sorting run on the backend; only the search box is local
+    fn query(&self) -> ScannerQuery {
+        let custom = self.custom_filter.trim();
+        let filter = match (self.selected_filter.expression(), custom.is_empty()) {
+            (Some(selected), false) => Some(format!("({selected}) AND ({custom})")),
+            (Some(selected), true) => Some(selected.to_string()),
+            (None, false) => Some(custom.to_string()),
+            (None, true) => None,
+        };
+        ScannerQuery {
+            filter,
+            sector: self.selected_filter.sector().map(str::to_string),
+            sort: Some(ScannerSort { by: self.sort_column.clone(), descending: !self.sort_ascending }),
+            limit: Some(PAGE_ROWS),
+            ..ScannerQuery::default()
         }
+    }
 
-        // Apply category filter
-        filtered.retain(|stock| {
-            match self.selected_filter {
-                ScanFilter::All => true,
-                ScanFilter::GainersBig => stock.change_percent > 5.0,
-                ScanFilter::GainersSmall => stock.change_percent > 1.0 && stock.change_percent <= 5.0,
-                ScanFilter::LosersBig => stock.change_percent < -5.0,
-                ScanFilter::LosersSmall => stock.change_percent >= -5.0 && stock.change_percent < -1.0,
-                ScanFilter::HighVolume => stock.volume > 50_000_000,
-                ScanFilter::TechStocks => stock.sector.as_deref() == Some("Technology"),
-                ScanFilter::HealthStocks => stock.sector.as_deref() == Some("Healthcare"),
-                ScanFilter::FinancialStocks => stock.sector.as_deref() == Some("Financial"),
+    fn save_watchlist(&self, ctx: &Context<Self>) {
+        // Still loading; the change stays local until then
+        let Some(id) = self.watchlist_id.clone() else { return };
+        let req = WatchlistReq { name: self.watchlist_name.clone(), symbols: self.watchlist.clone() };
+        let link = ctx.link().clone();
+        spawn_local(async move {
+            let result = match session::authorized(Request::put(&format!("{API_URL}/watchlists/{id}"))).json(&req) {
+                Ok(request) => send_watchlist(request).await,
+                Err(e) => Err(e.to_string()),
+            };
+            match result {
+                Ok(watchlist) => link.send_message(Msg::WatchlistLoaded(watchlist)),
+                Err(e) => link.send_message(Msg::WatchlistError(e)),
             }
         });
-
-        self.filtered_stocks = filtered;
-        self.apply_sort();
     }
 
-    fn apply_sort(&mut self) {
-        self.filtered_stocks.sort_by(|a, b| {
-            let ordering = match self.sort_column.as_str() {
-                "symbol" => a.symbol.cmp(&b.symbol),
-                "name" => a.name.cmp(&b.name),
-                "price" => a.price.partial_cmp(&b.price).unwrap_or(std::cmp::Ordering::Equal),
-                "change" => a.change.partial_cmp(&b.change).unwrap_or(std::cmp::Ordering::Equal),
-                "change_percent" => a.change_percent.partial_cmp(&b.change_percent).unwrap_or(std::cmp::Ordering::Equal),
-                "volume" => a.volume.cmp(&b.volume),
-                _ => std::cmp::Ordering::Equal,
-            };
-
-            if self.sort_ascending { ordering } else { ordering.reverse() }
-        });
+    fn apply_search(&mut self) {
+        let search_lower = self.search_term.to_lowercase();
+        self.filtered_stocks = self
+            .stocks
+            .iter()
+            .filter(|stock| {
+                search_lower.is_empty()
+                    || stock.symbol.to_lowercase().contains(&search_lower)
+                    || stock.name.as_deref().is_some_and(|name| name.to_lowercase().contains(&search_lower))
+            })
+            .cloned()
+            .collect();
     }
 
     fn render_stock_table(&self, ctx: &Context<Self>) -> Html {
@@ -453,10 +465,10 @@ impl Scanner {
                             <th class="px-4 py-3 text-left text-xs font-medium text-gray-400 uppercase tracking-wider">
                                 <button
                                     class="hover:text-white"
-                                    onclick={ctx.link().callback(|_| Msg::SortBy("change_percent".to_string()))}
+                                    onclick={ctx.link().callback(|_| Msg::SortBy("change_pct".to_string()))}
                                 >
                                     {"% Change"}
-                                    {self.sort_indicator("change_percent")}
+                                    {self.sort_indicator("change_pct")}
                                 </button>
                             </th>
                             <th class="px-4 py-3 text-left text-xs font-medium text-gray-400 uppercase tracking-wider">
@@ -468,6 +480,24 @@ impl Scanner {
                                     {self.sort_indicator("volume")}
                                 </button>
                             </th>
+                            <th class="px-4 py-3 text-left text-xs font-medium text-gray-400 uppercase tracking-wider">
+                                <button
+                                    class="hover:text-white"
+                                    onclick={ctx.link().callback(|_| Msg::SortBy("volume_ratio".to_string()))}
+                                >
+                                    {"Rel. Volume"}
+                                    {self.sort_indicator("volume_ratio")}
+                                </button>
+                            </th>
+                            <th class="px-4 py-3 text-left text-xs font-medium text-gray-400 uppercase tracking-wider">
+                                <button
+                                    class="hover:text-white"
+                                    onclick={ctx.link().callback(|_| Msg::SortBy("market_cap".to_string()))}
+                                >
+                                    {"Market Cap"}
+                                    {self.sort_indicator("market_cap")}
+                                </button>
+                            </th>
                             <th class="px-4 py-3 text-left text-xs font-medium text-gray-400 uppercase tracking-wider">
                                 {"Sector"}
                             </th>
@@ -484,7 +514,7 @@ impl Scanner {
         }
     }
 
-    fn render_stock_row(&self, ctx: &Context<Self>, stock: &StockInfo) -> Html {
+    fn render_stock_row(&self, ctx: &Context<Self>, stock: &ScannerRow) -> Html {
         let is_in_watchlist = self.watchlist.contains(&stock.symbol);
         let symbol_clone = stock.symbol.clone();
 
@@ -494,7 +524,7 @@ impl Scanner {
                     <div class="text-sm font-medium text-white">{&stock.symbol}</div>
                 </td>
                 <td class="px-4 py-3 whitespace-nowrap">
-                    <div class="text-sm text-gray-300">{&stock.name}</div>
+                    <div class="text-sm text-gray-300">{stock.name.as_deref().unwrap_or("")}</div>
                 </td>
                 <td class="px-4 py-3 whitespace-nowrap">
                     <div class="text-sm text-white">{format!("${:.2}", stock.price)}</div>
@@ -502,21 +532,31 @@ impl Scanner {
                 <td class="px-4 py-3 whitespace-nowrap">
                     <div class={classes!(
                         "text-sm",
-                        if stock.change >= 0.0 { "text-green-400" } else { "text-red-400" }
+                        if stock.change.unwrap_or_default() >= 0.0 { "text-green-400" } else { "text-red-400" }
                     )}>
-                        {format!("{:+.2}", stock.change)}
+                        {stock.change.map_or_else(|| "N/A".to_string(), |change| format!("{:+.2}", change))}
                     </div>
                 </td>
                 <td class="px-4 py-3 whitespace-nowrap">
                     <div class={classes!(
                         "text-sm",
-                        if stock.change_percent >= 0.0 { "text-green-400" } else { "text-red-400" }
+                        if stock.change_pct.unwrap_or_default() >= 0.0 { "text-green-400" } else { "text-red-400" }
                     )}>
-                        {format!("{:+.2}%", stock.change_percent)}
+                        {stock.change_pct.map_or_else(|| "N/A".to_string(), |pct| format!("{:+.2}%", pct))}
+                    </div>
+                </td>
+                <td class="px-4 py-3 whitespace-nowrap">
+                    <div class="text-sm text-gray-300">{self.format_amount(stock.volume)}</div>
+                </td>
+                <td class="px-4 py-3 whitespace-nowrap">
+                    <div class="text-sm text-gray-300">
+                        {stock.volume_ratio.map_or_else(|| "N/A".to_string(), |ratio| format!("{:.2}x", ratio))}
                     </div>
                 </td>
                 <td class="px-4 py-3 whitespace-nowrap">
-                    <div class="text-sm text-gray-300">{self.format_volume(stock.volume)}</div>
+                    <div class="text-sm text-gray-300">
+                        {stock.market_cap.map_or_else(|| "N/A".to_string(), |cap| self.format_amount(cap))}
+                    </div>
                 </td>
                 <td class="px-4 py-3 whitespace-nowrap">
                     <div class="text-sm text-gray-300">
@@ -560,13 +600,45 @@ impl Scanner {
         }
     }
 
-    fn format_volume(&self, volume: u64) -> String {
-        if volume >= 1_000_000 {
-            format!("{:.1}M", volume as f64 / 1_000_000.0)
-        } else if volume >= 1_000 {
-            format!("{:.1}K", volume as f64 / 1_000.0)
+    fn format_amount(&self, volume: f64) -> String {
+        if volume >= 1_000_000_000_000.0 {
+            format!("{:.2}T", volume / 1_000_000_000_000.0)
+        } else if volume >= 1_000_000_000.0 {
+            format!("{:.1}B", volume / 1_000_000_000.0)
+        } else if volume >= 1_000_000.0 {
+            format!("{:.1}M", volume / 1_000_000.0)
+        } else if volume >= 1_000.0 {
+            format!("{:.1}K", volume / 1_000.0)
         } else {
-            volume.to_string()
+            format!("{volume:.0}")
         }
     }
-}
\ No newline at end of file
+}
+
+/// The user's first watchlist, created if they have none
+async fn load_watchlist() -> Result<Watchlist, String> {
+    let resp = session::authorized(Request::get(&format!("{API_URL}/watchlists")))
+        .send()
+        .await
+        .map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.unwrap_or_default());
+    }
+    let watchlists: Vec<Watchlist> = resp.json().await.map_err(|e| e.to_string())?;
+    if let Some(watchlist) = watchlists.into_iter().next() {
+        return Ok(watchlist);
+    }
+    let req = WatchlistReq { name: DEFAULT_WATCHLIST.to_owned(), symbols: Vec::new() };
+    let request = session::authorized(Request::post(&format!("{API_URL}/watchlists")))
+        .json(&req)
+        .map_err(|e| e.to_string())?;
+    send_watchlist(request).await
+}
+
+async fn send_watchlist(request: Request) -> Result<Watchlist, String> {
+    let resp = request.send().await.map_err(|e| e.to_string())?;
+    if !resp.ok() {
+        return Err(resp.text().await.unwrap_or_default());
+    }
+    resp.json().await.map_err(|e| e.to_string())
+}
@@ -1,46 +1,18 @@
+use chrono::{Duration, Utc};
+use gloo::net::http::Request;
+use gloo::net::websocket::{futures::WebSocket, Message};
+use futures::StreamExt;
 use yew::prelude::*;
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use wasm_bindgen_futures::spawn_local;
 use web_sys::HtmlInputElement;
-use chrono::{DateTime, Utc};
-
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct Strategy {
-    pub id: String,
-    pub name: String,
-    pub description: String,
-    pub language: StrategyLanguage,
-    pub code: String,
-    pub status: StrategyStatus,
-    pub created_at: DateTime<Utc>,
-    pub backtest_results: Option<BacktestResult>,
-}
+use api::ProviderType;
+use api::strategy::{BacktestEvent, BacktestRun, CreateStrategyReq, JobStatus, RunBacktestReq, Strategy, StrategyLanguage};
+use dnn_core::time::TimeInterval;
+use crate::config::{API_URL, WS_URL};
+use crate::session;
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub enum StrategyLanguage {
-    Python,
-    CSharp,
-}
 
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub enum StrategyStatus {
-    Draft,
-    Testing,
-    Active,
-    Paused,
-}
-
-#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
-pub struct BacktestResult {
-    pub total_return: f64,
-    pub sharpe_ratio: f64,
-    pub max_drawdown: f64,
-    pub win_rate: f64,
-    pub total_trades: i32,
-}
-
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum StrategyTemplate {
     Blank,
     MeanReversion,
@@ -62,47 +34,42 @@ impl StrategyTemplate {
         }
     }
 
-    pub fn get_code(&self) -> String {
+    pub fn language(&self) -> StrategyLanguage {
         match self {
-            Self::Blank => r#"from AlgorithmImports import *
-
-class MyStrategy(QCAlgorithm):
-    def Initialize(self):
-        self.SetStartDate(2020, 1, 1)
-        self.SetEndDate(2023, 1, 1)
-        self.SetCash(100000)
-
-    def OnData(self, data):
-        pass"#.to_string(),
-
-            Self::MeanReversion => r#"from AlgorithmImports import *
-
-class MeanReversionStrategy(QCAlgorithm):
-    def Initialize(self):
-        self.SetStartDate(2020, 1, 1)
-        self.SetEndDate(2023, 1, 1)
-        self.SetCash(100000)
-
-        self.symbol = self.AddEquity("SPY", Resolution.Daily).Symbol
-        self.lookback = 20
-        self.sma = SimpleMovingAverage(self.lookback)
-
-    def OnData(self, data):
-        if not data.ContainsKey(self.symbol):
-            return
-
-        price = data[self.symbol].Close
-        self.sma.Update(data[self.symbol].EndTime, price)
-
-        if not self.sma.IsReady:
-            return
-
-        if price < self.sma.Current.Value * 0.95:
-            self.SetHoldings(self.symbol, 1.0)
-        elif price > self.sma.Current.Value * 1.05:
-            self.Liquidate(self.symbol)"#.to_string(),
+            Self::Blank | Self::MomentumTrading => StrategyLanguage::Rhai,
+            Self::MeanReversion => StrategyLanguage::Rules,
+            Self::MovingAverageCrossover => StrategyLanguage::Builtin,
+        }
+    }
 
-            _ => "// Code template".to_string()
+    pub fn get_code(&self) -> String {
+        match self {
+            Self::Blank => r#"fn on_bar(ctx, bar) {
+}"#.to_owned(),
+
+            Self::MeanReversion => r#"entry: close < SMA(20) * 0.95
+exit: close > SMA(20) * 1.05
+stop_loss: { percent: 10 }
+sizing: { weight: 1.0 }"#.to_owned(),
+
+            Self::MovingAverageCrossover => r#"{
+  "name": "ma_cross",
+  "params": { "fast": 10, "slow": 30, "average": "ema" }
+}"#.to_owned(),
+
+            Self::MomentumTrading => r#"// Long while the 20-bar return is positive
+fn on_bar(ctx, bar) {
+    let closes = ctx.closes(ctx.symbol);
+    let n = closes.len();
+    if n <= 20 { return; }
+
+    let ret = closes[n - 1] / closes[n - 21] - 1.0;
+    if ret > 0.0 && ctx.position(ctx.symbol) <= 0.0 {
+        ctx.target_weight(ctx.symbol, 1.0);
+    } else if ret < 0.0 && ctx.position(ctx.symbol) > 0.0 {
+        ctx.flatten(ctx.symbol);
+    }
+}"#.to_owned(),
         }
     }
 }
@@ -113,6 +80,11 @@ pub struct Strategies {
     new_strategy_name: String,
     new_strategy_description: String,
     selected_template: StrategyTemplate,
+    /// Symbol backtests run on, over the last year of daily bars
+    backtest_symbol: String,
+    /// Backtest job in flight, as last reported by its stream
+    backtesting: Option<BacktestRun>,
+    error: Option<String>,
     loading: bool,
 }
 
@@ -125,8 +97,14 @@ pub enum Msg {
     UpdateStrategyDescription(String),
     SelectTemplate(StrategyTemplate),
     CreateStrategy,
+    StrategyCreated,
+    UpdateBacktestSymbol(String),
     RunBacktest(String),
+    BacktestQueued(BacktestRun),
+    BacktestEvent(BacktestEvent),
+    CancelBacktest,
     DeleteStrategy(String),
+    Error(String),
 }
 
 impl Component for Strategies {
@@ -141,35 +119,28 @@ impl Component for Strategies {
             new_strategy_name: String::new(),
             new_strategy_description: String::new(),
             selected_template: StrategyTemplate::Blank,
+            backtest_symbol: "SPY".to_owned(),
+            backtesting: None,
+            error: None,
             loading: true,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
             Msg::LoadStrategies => {
                 self.loading = true;
-                let link = _ctx.link().clone();
+                let link = ctx.link().clone();
                 spawn_local(async move {
-                    let mock_strategies = vec![
-                        Strategy {
-                            id: "1".to_string(),
-                            name: "Mean Reversion SPY".to_string(),
-                            description: "Mean reversion strategy for SPY".to_string(),
-                            language: StrategyLanguage::Python,
-                            code: "# Strategy code here".to_string(),
-                            status: StrategyStatus::Active,
-                            created_at: Utc::now(),
-                            backtest_results: Some(BacktestResult {
-                                total_return: 15.67,
-                                sharpe_ratio: 1.34,
-                                max_drawdown: -8.23,
-                                win_rate: 62.5,
-                                total_trades: 87,
-                            }),
+                    let res = session::authorized(Request::get(&format!("{API_URL}/strategies"))).send().await;
+                    match res {
+                        Ok(resp) if resp.ok() => match resp.json::<Vec<Strategy>>().await {
+                            Ok(strategies) => link.send_message(Msg::StrategiesLoaded(strategies)),
+                            Err(e) => link.send_message(Msg::Error(format!("Bad response: {e}"))),
                         },
-                    ];
-                    link.send_message(Msg::StrategiesLoaded(mock_strategies));
+                        Ok(resp) => link.send_message(Msg::Error(format!("Failed to load strategies: {}", resp.status()))),
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to load strategies: {e}"))),
+                    }
                 });
                 true
             }
@@ -208,20 +179,171 @@ impl Component for Strategies {
             }
 
             Msg::CreateStrategy => {
-                // TODO: Create strategy via API
-                self.show_create_modal = false;
-                _ctx.link().send_message(Msg::LoadStrategies);
+                let req = CreateStrategyReq {
+                    name: self.new_strategy_name.trim().to_owned(),
+                    description: self.new_strategy_description.trim().to_owned(),
+                    language: self.selected_template.language(),
+                    code: self.selected_template.get_code(),
+                };
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let res = match session::authorized(Request::post(&format!("{API_URL}/strategies"))).json(&req) {
+                        Ok(r) => r.send().await,
+                        Err(e) => {
+                            link.send_message(Msg::Error(e.to_string()));
+                            return;
+                        }
+                    };
+                    match res {
+                        Ok(resp) if resp.ok() => link.send_message(Msg::StrategyCreated),
+                        Ok(resp) => {
+                            let text = resp.text().await.unwrap_or_default();
+                            link.send_message(Msg::Error(format!("Failed to create strategy: {text}")));
+                        }
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to create strategy: {e}"))),
+                    }
+                });
+                false
+            }
+
+            Msg::StrategyCreated => {
+                self.error = None;
+                ctx.link().send_message(Msg::HideCreateModal);
+                ctx.link().send_message(Msg::LoadStrategies);
+                false
+            }
+
+            Msg::UpdateBacktestSymbol(symbol) => {
+                self.backtest_symbol = symbol;
                 true
             }
 
-            Msg::RunBacktest(_strategy_id) => {
-                // TODO: Run backtest via API
+            Msg::RunBacktest(strategy_id) => {
+                let end = Utc::now();
+                let req = RunBacktestReq {
+                    provider: ProviderType::Yahoo,
+                    symbol: self.backtest_symbol.trim().to_uppercase(),
+                    interval: TimeInterval::Day1,
+                    start: end - Duration::days(365),
+                    end,
+                    starting_cash: 100_000.0,
+                };
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let url = format!("{API_URL}/strategies/{strategy_id}/backtest");
+                    let res = match session::authorized(Request::post(&url)).json(&req) {
+                        Ok(r) => r.send().await,
+                        Err(e) => {
+                            link.send_message(Msg::Error(e.to_string()));
+                            return;
+                        }
+                    };
+                    match res {
+                        Ok(resp) if resp.ok() => match resp.json::<BacktestRun>().await {
+                            Ok(run) => link.send_message(Msg::BacktestQueued(run)),
+                            Err(e) => link.send_message(Msg::Error(format!("Bad response: {e}"))),
+                        },
+                        Ok(resp) => {
+                            let text = resp.text().await.unwrap_or_default();
+                            link.send_message(Msg::Error(format!("Backtest failed: {text}")));
+                        }
+                        Err(e) => link.send_message(Msg::Error(format!("Backtest failed: {e}"))),
+                    }
+                });
                 true
             }
 
-            Msg::DeleteStrategy(_strategy_id) => {
-                // TODO: Delete strategy via API
-                _ctx.link().send_message(Msg::LoadStrategies);
+            Msg::BacktestQueued(run) => {
+                let url = format!("{WS_URL}/strategies/{}/backtest/{}/stream", run.strategy_id, run.id);
+                self.backtesting = Some(run);
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    let ws = match WebSocket::open(&session::ws_url(&url)) {
+                        Ok(ws) => ws,
+                        Err(e) => {
+                            link.send_message(Msg::Error(format!("Failed to follow backtest: {e:?}")));
+                            return;
+                        }
+                    };
+                    let (_write, mut read) = ws.split();
+                    while let Some(Ok(msg)) = read.next().await {
+                        if let Message::Text(text) = msg {
+                            match serde_json::from_str::<BacktestEvent>(&text) {
+                                Ok(event) => link.send_message(Msg::BacktestEvent(event)),
+                                Err(e) => link.send_message(Msg::Error(format!("Bad event: {e}"))),
+                            }
+                        }
+                    }
+                });
+                true
+            }
+
+            Msg::BacktestEvent(event) => match event {
+                BacktestEvent::Snapshot { run } if !run.status.is_finished() => {
+                    self.backtesting = Some(run);
+                    true
+                }
+                BacktestEvent::Started { .. } => {
+                    if let Some(run) = &mut self.backtesting {
+                        run.status = JobStatus::Running;
+                    }
+                    true
+                }
+                BacktestEvent::Progress { progress, .. } => {
+                    if let Some(run) = &mut self.backtesting {
+                        run.progress = progress;
+                    }
+                    true
+                }
+                BacktestEvent::Snapshot { run } | BacktestEvent::Finished { run } => {
+                    self.backtesting = None;
+                    self.error = match run.status {
+                        JobStatus::Failed => Some(format!("Backtest failed: {}", run.error.unwrap_or_default())),
+                        _ => None,
+                    };
+                    // Results and status live on the strategy
+                    ctx.link().send_message(Msg::LoadStrategies);
+                    true
+                }
+                BacktestEvent::Error { message } => {
+                    ctx.link().send_message(Msg::Error(message));
+                    false
+                }
+            },
+
+            Msg::CancelBacktest => {
+                let Some(run) = &self.backtesting else {
+                    return false;
+                };
+                let url = format!("{API_URL}/strategies/{}/backtest/{}", run.strategy_id, run.id);
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match session::authorized(Request::delete(&url)).send().await {
+                        // The stream reports the cancellation
+                        Ok(resp) if resp.ok() => {}
+                        Ok(resp) => link.send_message(Msg::Error(format!("Failed to cancel backtest: {}", resp.status()))),
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to cancel backtest: {e}"))),
+                    }
+                });
+                false
+            }
+
+            Msg::DeleteStrategy(strategy_id) => {
+                let link = ctx.link().clone();
+                spawn_local(async move {
+                    match session::authorized(Request::delete(&format!("{API_URL}/strategies/{strategy_id}"))).send().await {
+                        Ok(resp) if resp.ok() => link.send_message(Msg::LoadStrategies),
+                        Ok(resp) => link.send_message(Msg::Error(format!("Failed to delete strategy: {}", resp.status()))),
+                        Err(e) => link.send_message(Msg::Error(format!("Failed to delete strategy: {e}"))),
+                    }
+                });
+                false
+            }
+
+            Msg::Error(message) => {
+                self.loading = false;
+                self.backtesting = None;
+                self.error = Some(message);
                 true
             }
         }
@@ -232,14 +354,30 @@ impl Component for Strategies {
             <div class="space-y-6">
                 <div class="flex justify-between items-center">
                     <h1 class="text-2xl font-bold text-white">{"Trading Strategies"}</h1>
-                    <button
-                        class="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
-                        onclick={ctx.link().callback(|_| Msg::ShowCreateModal)}
-                    >
-                        {"Create Strategy"}
-                    </button>
+                    <div class="flex items-center space-x-3">
+                        <label class="text-sm text-gray-400">{"Backtest on"}</label>
+                        <input
+                            type="text"
+                            class="w-24 px-3 py-2 bg-gray-700 text-white rounded-lg border border-gray-600"
+                            value={self.backtest_symbol.clone()}
+                            oninput={ctx.link().callback(|e: InputEvent| {
+                                let input: HtmlInputElement = e.target_unchecked_into();
+                                Msg::UpdateBacktestSymbol(input.value())
+                            })}
+                        />
+                        <button
+                            class="px-4 py-2 bg-blue-600 text-white rounded-lg hover:bg-blue-700"
+                            onclick={ctx.link().callback(|_| Msg::ShowCreateModal)}
+                        >
+                            {"Create Strategy"}
+                        </button>
+                    </div>
                 </div>
 
+                if let Some(e) = &self.error {
+                    <div class="bg-red-900 text-red-200 rounded p-3 text-sm">{ e }</div>
+                }
+
                 {if self.loading {
                     html! { <div class="text-center py-8 text-gray-400">{"Loading..."}</div> }
                 } else {
@@ -271,6 +409,7 @@ impl Strategies {
                     <div>
                         <h3 class="text-lg font-semibold text-white mb-1">{&strategy.name}</h3>
                         <span class="text-sm text-green-400">{format!("{:?}", strategy.status)}</span>
+                        <span class="text-xs text-gray-500 ml-2">{format!("v{}", strategy.version)}</span>
                     </div>
                 </div>
 
@@ -297,6 +436,10 @@ impl Strategies {
                                     <span class="text-gray-400">{"Trades: "}</span>
                                     <span class="text-white">{results.total_trades}</span>
                                 </div>
+                                <div>
+                                    <span class="text-gray-400">{"Win rate: "}</span>
+                                    <span class="text-white">{format!("{:.1}%", results.win_rate)}</span>
+                                </div>
                             </div>
                         </div>
                     }
@@ -309,12 +452,29 @@ impl Strategies {
                 }}
 
                 <div class="flex space-x-2">
-                    <button
-                        class="flex-1 px-3 py-2 bg-blue-600 text-white text-sm rounded-lg hover:bg-blue-700"
-                        onclick={ctx.link().callback(move |_| Msg::RunBacktest(strategy_id.clone()))}
-                    >
-                        {"Backtest"}
-                    </button>
+                    {match self.backtesting.as_ref().filter(|run| run.strategy_id == strategy.id) {
+                        Some(run) => html! {
+                            <button
+                                class="flex-1 px-3 py-2 bg-yellow-600 text-white text-sm rounded-lg hover:bg-yellow-700"
+                                onclick={ctx.link().callback(|_| Msg::CancelBacktest)}
+                            >
+                                {if run.status == JobStatus::Queued {
+                                    "Queued - Cancel".to_owned()
+                                } else {
+                                    format!("{:.0}% - Cancel", run.progress * 100.0)
+                                }}
+                            </button>
+                        },
+                        None => html! {
+                            <button
+                                class="flex-1 px-3 py-2 bg-blue-600 text-white text-sm rounded-lg hover:bg-blue-700"
+                                onclick={ctx.link().callback(move |_| Msg::RunBacktest(strategy_id.clone()))}
+                                disabled={self.backtesting.is_some()}
+                            >
+                                {"Backtest"}
+                            </button>
+                        },
+                    }}
                     <button
                         class="px-3 py-2 bg-red-600 text-white text-sm rounded-lg hover:bg-red-700"
                         onclick={ctx.link().callback(move |_| Msg::DeleteStrategy(strategy_id_del.clone()))}
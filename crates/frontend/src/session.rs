@@ -0,0 +1,38 @@
+use api::auth::AuthSession;
+use chrono::Utc;
+use gloo::net::http::RequestBuilder;
+use gloo::storage::{LocalStorage, Storage};
+
+const KEY: &str = "traiter.session";
+
+/// The signed in session, if it hasn't expired
+pub fn load() -> Option<AuthSession> {
+    LocalStorage::get::<AuthSession>(KEY).ok().filter(|s| s.expires_at > Utc::now())
+}
+
+pub fn save(session: &AuthSession) {
+    if let Err(e) = LocalStorage::set(KEY, session) {
+        web_sys::console::warn_1(&format!("Failed to store session: {e}").into());
+    }
+}
+
+pub fn clear() {
+    LocalStorage::delete(KEY);
+}
+
+/// Sign a request with the session token
+pub fn authorized(req: RequestBuilder) -> RequestBuilder {
+    match load() {
+        Some(session) => req.header("Authorization", &format!("Bearer {}", session.token)),
+        None => req,
+    }
+}
+
+/// Browsers can't set headers on a WebSocket upgrade, so the token goes in
+/// the query
+pub fn ws_url(url: &str) -> String {
+    match load() {
+        Some(session) => format!("{url}?token={}", session.token),
+        None => url.to_owned(),
+    }
+}
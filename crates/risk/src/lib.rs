@@ -0,0 +1,258 @@
+mod limits;
+
+pub use limits::{RiskLimits, RiskViolation};
+
+use std::collections::{HashMap, VecDeque};
+use chrono::NaiveDate;
+use dnn_core::fx::FxRates;
+use dnn_core::instrument::InstrumentMaster;
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionReport, Order, OrderSide};
+
+/// Checks every order against [`RiskLimits`] before it reaches execution.
+///
+/// Orders that only reduce an existing position skip the exposure and loss
+/// checks so a breached book can still be flattened; the kill switch blocks everything.
+#[derive(Debug, Clone, Default)]
+pub struct RiskEngine {
+    limits: RiskLimits,
+    instruments: InstrumentMaster,
+    kill_switch: bool,
+    last_candles: HashMap<String, Candle>,
+    recent_orders: VecDeque<Timestamp>,
+    day_start: Option<(NaiveDate, f64)>,
+    last_equity: Option<f64>,
+}
+
+impl RiskEngine {
+    pub fn new(limits: RiskLimits) -> Self {
+        Self { limits, ..Self::default() }
+    }
+
+    /// Use instrument reference data for the currency and contract multiplier of new positions
+    pub fn with_instruments(mut self, instruments: InstrumentMaster) -> Self {
+        self.instruments = instruments;
+        self
+    }
+
+    pub fn instruments(&self) -> &InstrumentMaster {
+        &self.instruments
+    }
+
+    pub fn limits(&self) -> &RiskLimits {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: RiskLimits) {
+        self.limits = limits;
+    }
+
+    /// Reject every order until released
+    pub fn engage_kill_switch(&mut self) {
+        self.kill_switch = true;
+    }
+
+    pub fn release_kill_switch(&mut self) {
+        self.kill_switch = false;
+    }
+
+    pub fn is_killed(&self) -> bool {
+        self.kill_switch
+    }
+
+    /// Record the latest bar of a symbol, used as the reference price
+    pub fn on_candle(&mut self, symbol: &str, candle: &Candle) {
+        self.last_candles.insert(symbol.to_owned(), candle.clone());
+    }
+
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.last_candles.get(symbol).map(|c| c.close)
+    }
+
+    /// Record the account's equity after an event. The last equity of a day is
+    /// where the next day's loss is measured from.
+    pub fn on_equity(&mut self, equity: f64, ts: Timestamp) {
+        self.day_start_equity(ts.date_naive(), equity);
+        self.last_equity = Some(equity);
+    }
+
+    /// Check an order and count it against the rate limit if accepted,
+    /// or produce a rejected execution report
+    pub fn pre_trade(
+        &mut self,
+        order: &Order,
+        portfolio: &Portfolio,
+        fx: &FxRates,
+        ts: Timestamp,
+    ) -> Result<(), ExecutionReport> {
+        self.check(order, portfolio, fx, ts)
+            .map_err(|v| ExecutionReport::rejected(order.id, v.to_string(), ts))
+    }
+
+    pub fn check(
+        &mut self,
+        order: &Order,
+        portfolio: &Portfolio,
+        fx: &FxRates,
+        ts: Timestamp,
+    ) -> Result<(), RiskViolation> {
+        if self.kill_switch {
+            return Err(RiskViolation::KillSwitch);
+        }
+
+        let symbol = &order.symbol;
+        let restricted = self.limits.restricted_symbols.contains(symbol)
+            || self.limits.allowed_symbols.as_ref().is_some_and(|a| !a.contains(symbol));
+        if restricted {
+            return Err(RiskViolation::RestrictedSymbol(symbol.clone()));
+        }
+
+        let reference = self.last_price(symbol);
+        if let (Some(band), Some(limit_price), Some(reference)) = (self.limits.price_band_pct, order.price, reference)
+            && reference > 0.0
+            && ((limit_price - reference) / reference).abs() > band
+        {
+            return Err(RiskViolation::PriceBand { price: limit_price, reference });
+        }
+
+        if let Some(limit) = self.limits.max_orders_per_minute {
+            while self.recent_orders.front().is_some_and(|t| ts - *t >= chrono::Duration::minutes(1)) {
+                self.recent_orders.pop_front();
+            }
+            if self.recent_orders.len() >= limit {
+                return Err(RiskViolation::OrderRate { limit });
+            }
+        }
+
+        let price = order.price
+            .or(reference)
+            .ok_or_else(|| RiskViolation::NoReferencePrice(symbol.clone()))?;
+        let instrument = self.instruments.get(symbol);
+        let (currency, multiplier) = match portfolio.positions.get(symbol) {
+            Some(p) => (p.currency.as_str(), p.multiplier),
+            None => instrument.map_or((portfolio.base_currency.as_str(), 1.0), |i| (i.currency.as_str(), i.multiplier)),
+        };
+        let to_base = |amount: f64| {
+            fx.convert(amount, currency, &portfolio.base_currency)
+                .map_err(|e| RiskViolation::Valuation(e.to_string()))
+        };
+
+        let current = portfolio.positions.get(symbol).map_or(0.0, |p| p.qty);
+        let signed = match order.side {
+            OrderSide::Buy => order.qty,
+            OrderSide::Sell => -order.qty,
+        };
+        let after = current + signed;
+        let reducing = after.abs() <= current.abs() && after * current >= 0.0;
+
+        if let Some(limit) = self.limits.max_order_notional {
+            let notional = to_base(order.qty * price * multiplier)?.abs();
+            if notional > limit {
+                return Err(RiskViolation::MaxNotional { notional, limit });
+            }
+        }
+
+        if !reducing {
+            if let Some(limit) = self.limits.max_position_qty
+                && after.abs() > limit
+            {
+                return Err(RiskViolation::MaxPosition { qty: after.abs(), limit });
+            }
+
+            self.check_exposure(portfolio, fx, symbol, signed * multiplier, price, currency)?;
+            self.check_daily_loss(portfolio, fx, ts)?;
+        }
+
+        self.recent_orders.push_back(ts);
+        Ok(())
+    }
+
+    fn mark_prices(&self, portfolio: &Portfolio) -> HashMap<String, f64> {
+        portfolio.positions
+            .iter()
+            .map(|(s, p)| (s.clone(), self.last_price(s).unwrap_or(p.avg_price)))
+            .collect()
+    }
+
+    fn check_exposure(
+        &self,
+        portfolio: &Portfolio,
+        fx: &FxRates,
+        symbol: &str,
+        signed_units: f64,
+        price: f64,
+        currency: &str,
+    ) -> Result<(), RiskViolation> {
+        if self.limits.max_gross_exposure.is_none() && self.limits.max_net_exposure.is_none() {
+            return Ok(());
+        }
+
+        let prices = self.mark_prices(portfolio);
+        let mut gross = 0.0;
+        let mut net = 0.0;
+        let mut seen = false;
+        for (sym, pos) in &portfolio.positions {
+            // Contract units, so the multiplier is applied once
+            let mut units = pos.qty * pos.multiplier;
+            let mut mark = prices.get(sym).copied().unwrap_or(pos.avg_price);
+            if sym == symbol {
+                units += signed_units;
+                mark = price;
+                seen = true;
+            }
+            let value = fx.convert(units * mark, &pos.currency, &portfolio.base_currency)
+                .map_err(|e| RiskViolation::Valuation(e.to_string()))?;
+            gross += value.abs();
+            net += value;
+        }
+        if !seen {
+            let value = fx.convert(signed_units * price, currency, &portfolio.base_currency)
+                .map_err(|e| RiskViolation::Valuation(e.to_string()))?;
+            gross += value.abs();
+            net += value;
+        }
+
+        if let Some(limit) = self.limits.max_gross_exposure
+            && gross > limit
+        {
+            return Err(RiskViolation::GrossExposure { exposure: gross, limit });
+        }
+        if let Some(limit) = self.limits.max_net_exposure
+            && net.abs() > limit
+        {
+            return Err(RiskViolation::NetExposure { exposure: net.abs(), limit });
+        }
+        Ok(())
+    }
+
+    fn check_daily_loss(&mut self, portfolio: &Portfolio, fx: &FxRates, ts: Timestamp) -> Result<(), RiskViolation> {
+        let Some(limit) = self.limits.max_daily_loss else {
+            return Ok(());
+        };
+
+        let equity = portfolio.total_value(&self.mark_prices(portfolio), fx)
+            .map_err(|e| RiskViolation::Valuation(e.to_string()))?;
+        let start = self.day_start_equity(ts.date_naive(), equity);
+
+        let loss = start - equity;
+        if loss > limit {
+            return Err(RiskViolation::DailyLoss { loss, limit });
+        }
+        Ok(())
+    }
+
+    /// Equity the day started with, rolling over to the previous day's last
+    /// equity (or `equity` if none was recorded) on the first call of a new day
+    fn day_start_equity(&mut self, today: NaiveDate, equity: f64) -> f64 {
+        if let Some((day, start)) = self.day_start
+            && day == today
+        {
+            return start;
+        }
+        let start = self.last_equity.unwrap_or(equity);
+        self.day_start = Some((today, start));
+        start
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashSet;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Configurable pre-trade limits. `None` disables a limit.
+/// Notional and exposure limits are in the portfolio base currency.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RiskLimits {
+    /// Largest absolute position allowed in any symbol after the order
+    pub max_position_qty: Option<f64>,
+    /// Largest value of a single order
+    pub max_order_notional: Option<f64>,
+    /// Sum of absolute position values after the order
+    pub max_gross_exposure: Option<f64>,
+    /// Absolute value of long minus short positions after the order
+    pub max_net_exposure: Option<f64>,
+    pub max_orders_per_minute: Option<usize>,
+    /// Largest drop in equity since the first order check of the (UTC) day
+    pub max_daily_loss: Option<f64>,
+    /// Symbols that may not be traded
+    pub restricted_symbols: HashSet<String>,
+    /// If set, only these symbols may be traded
+    pub allowed_symbols: Option<HashSet<String>>,
+    /// Largest allowed distance of a limit price from the last close, as a fraction (0.05 = 5%)
+    pub price_band_pct: Option<f64>,
+}
+
+/// Why an order was rejected
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    #[error("Kill switch engaged")]
+    KillSwitch,
+    #[error("Symbol {0} is restricted")]
+    RestrictedSymbol(String),
+    #[error("Position of {qty} exceeds limit {limit}")]
+    MaxPosition { qty: f64, limit: f64 },
+    #[error("Order notional {notional:.2} exceeds limit {limit:.2}")]
+    MaxNotional { notional: f64, limit: f64 },
+    #[error("Gross exposure {exposure:.2} exceeds limit {limit:.2}")]
+    GrossExposure { exposure: f64, limit: f64 },
+    #[error("Net exposure {exposure:.2} exceeds limit {limit:.2}")]
+    NetExposure { exposure: f64, limit: f64 },
+    #[error("More than {limit} orders in the last minute")]
+    OrderRate { limit: usize },
+    #[error("Daily loss {loss:.2} exceeds limit {limit:.2}")]
+    DailyLoss { loss: f64, limit: f64 },
+    #[error("Price {price} is outside the allowed band around last close {reference}")]
+    PriceBand { price: f64, reference: f64 },
+    #[error("No reference price for {0}")]
+    NoReferencePrice(String),
+    #[error("Valuation failed: {0}")]
+    Valuation(String),
+}
@@ -0,0 +1,163 @@
+use std::collections::HashSet;
+use chrono::{Duration, TimeZone, Utc};
+use dnn_core::fx::FxRates;
+use dnn_core::instrument::{AssetClass, Instrument, InstrumentMaster};
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionStatus, Order, OrderSide};
+use risk::{RiskEngine, RiskLimits, RiskViolation};
+
+fn ts() -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 5, 1, 14, 0, 0).unwrap()
+}
+
+fn order(id: u64, side: OrderSide, qty: f64, price: Option<f64>) -> Order {
+    Order { id, symbol: "AAPL".into(), qty, price, side }
+}
+
+fn engine(limits: RiskLimits) -> RiskEngine {
+    let mut engine = RiskEngine::new(limits);
+    engine.on_candle("AAPL", &Candle::new(ts(), 99.0, 101.0, 98.0, 100.0, 1_000.0).unwrap());
+    engine
+}
+
+#[test]
+fn test_position_and_notional_limits() {
+    let portfolio = Portfolio::new(1_000_000.0);
+    let fx = FxRates::new();
+
+    let mut risk = engine(RiskLimits { max_position_qty: Some(100.0), ..RiskLimits::default() });
+    assert!(risk.check(&order(1, OrderSide::Buy, 100.0, None), &portfolio, &fx, ts()).is_ok());
+    assert!(matches!(
+        risk.check(&order(2, OrderSide::Buy, 101.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::MaxPosition { .. })
+    ));
+
+    let mut risk = engine(RiskLimits { max_order_notional: Some(5_000.0), ..RiskLimits::default() });
+    assert!(matches!(
+        risk.check(&order(3, OrderSide::Buy, 60.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::MaxNotional { .. })
+    ));
+}
+
+#[test]
+fn test_exposure_limits_allow_reducing_orders() {
+    let mut portfolio = Portfolio::new(100_000.0);
+    portfolio.apply_fill("AAPL", OrderSide::Buy, 100.0, 100.0);
+    let fx = FxRates::new();
+
+    let mut risk = engine(RiskLimits {
+        max_gross_exposure: Some(15_000.0),
+        max_net_exposure: Some(12_000.0),
+        ..RiskLimits::default()
+    });
+    assert!(matches!(
+        risk.check(&order(1, OrderSide::Buy, 30.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::NetExposure { .. })
+    ));
+    assert!(matches!(
+        risk.check(&order(2, OrderSide::Buy, 60.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::GrossExposure { .. })
+    ));
+    assert!(risk.check(&order(3, OrderSide::Sell, 50.0, None), &portfolio, &fx, ts()).is_ok());
+}
+
+#[test]
+fn test_order_rate_and_price_band() {
+    let portfolio = Portfolio::new(1_000_000.0);
+    let fx = FxRates::new();
+
+    let mut risk = engine(RiskLimits { max_orders_per_minute: Some(2), price_band_pct: Some(0.05), ..RiskLimits::default() });
+    assert!(matches!(
+        risk.check(&order(1, OrderSide::Buy, 1.0, Some(110.0)), &portfolio, &fx, ts()),
+        Err(RiskViolation::PriceBand { .. })
+    ));
+    assert!(risk.check(&order(2, OrderSide::Buy, 1.0, Some(104.0)), &portfolio, &fx, ts()).is_ok());
+    assert!(risk.check(&order(3, OrderSide::Buy, 1.0, None), &portfolio, &fx, ts()).is_ok());
+    assert!(matches!(
+        risk.check(&order(4, OrderSide::Buy, 1.0, None), &portfolio, &fx, ts() + Duration::seconds(30)),
+        Err(RiskViolation::OrderRate { limit: 2 })
+    ));
+    assert!(risk.check(&order(5, OrderSide::Buy, 1.0, None), &portfolio, &fx, ts() + Duration::seconds(61)).is_ok());
+}
+
+#[test]
+fn test_daily_loss_restrictions_and_kill_switch() {
+    let mut portfolio = Portfolio::new(10_000.0);
+    portfolio.apply_fill("AAPL", OrderSide::Buy, 50.0, 100.0);
+    let fx = FxRates::new();
+
+    let mut risk = engine(RiskLimits {
+        max_daily_loss: Some(500.0),
+        restricted_symbols: HashSet::from(["GME".to_owned()]),
+        ..RiskLimits::default()
+    });
+    assert!(risk.check(&order(1, OrderSide::Buy, 1.0, None), &portfolio, &fx, ts()).is_ok());
+
+    // Price drops 20% -> 1000 loss on 50 shares
+    risk.on_candle("AAPL", &Candle::new(ts(), 80.0, 80.0, 80.0, 80.0, 1_000.0).unwrap());
+    assert!(matches!(
+        risk.check(&order(2, OrderSide::Buy, 1.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::DailyLoss { .. })
+    ));
+    assert!(risk.check(&order(3, OrderSide::Sell, 50.0, None), &portfolio, &fx, ts()).is_ok());
+
+    let gme = Order { symbol: "GME".into(), ..order(4, OrderSide::Buy, 1.0, Some(10.0)) };
+    assert_eq!(risk.check(&gme, &portfolio, &fx, ts()), Err(RiskViolation::RestrictedSymbol("GME".into())));
+
+    risk.engage_kill_switch();
+    let report = risk.pre_trade(&order(5, OrderSide::Sell, 1.0, None), &portfolio, &fx, ts()).unwrap_err();
+    assert_eq!(report.order_id, 5);
+    assert_eq!(report.status, ExecutionStatus::Rejected { reason: "Kill switch engaged".into() });
+
+    risk.release_kill_switch();
+    assert!(risk.check(&order(6, OrderSide::Sell, 1.0, None), &portfolio, &fx, ts()).is_ok());
+}
+
+#[test]
+fn test_new_positions_use_instrument_currency_and_multiplier() {
+    let portfolio = Portfolio::new(1_000_000.0);
+    let mut fx = FxRates::new();
+    fx.set("EUR", "USD", 1.5);
+
+    let mut future = Instrument::new("AAPL", AssetClass::Future, "CME", "EUR");
+    future.multiplier = 50.0;
+    let mut instruments = InstrumentMaster::new();
+    instruments.insert(future);
+
+    // 2 contracts * 50 * 100 EUR = 15,000 USD
+    let mut risk = engine(RiskLimits { max_order_notional: Some(14_000.0), ..RiskLimits::default() })
+        .with_instruments(instruments.clone());
+    assert_eq!(
+        risk.check(&order(1, OrderSide::Buy, 2.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::MaxNotional { notional: 15_000.0, limit: 14_000.0 })
+    );
+
+    let mut risk = engine(RiskLimits { max_gross_exposure: Some(14_000.0), ..RiskLimits::default() })
+        .with_instruments(instruments);
+    assert_eq!(
+        risk.check(&order(2, OrderSide::Buy, 2.0, None), &portfolio, &fx, ts()),
+        Err(RiskViolation::GrossExposure { exposure: 15_000.0, limit: 14_000.0 })
+    );
+}
+
+#[test]
+fn test_daily_loss_counts_from_the_previous_close() {
+    let mut portfolio = Portfolio::new(100_000.0);
+    portfolio.apply_fill("AAPL", OrderSide::Buy, 50.0, 100.0);
+    let fx = FxRates::new();
+
+    let mut risk = engine(RiskLimits { max_daily_loss: Some(500.0), ..RiskLimits::default() });
+    risk.on_equity(100_000.0, ts());
+
+    // The next day opens 20% lower; the loss since yesterday's close counts even
+    // though no order was checked before the drop
+    let tomorrow = ts() + Duration::days(1);
+    risk.on_candle("AAPL", &Candle::new(tomorrow, 80.0, 80.0, 80.0, 80.0, 1_000.0).unwrap());
+    risk.on_equity(99_000.0, tomorrow);
+    assert!(matches!(
+        risk.check(&order(1, OrderSide::Buy, 1.0, None), &portfolio, &fx, tomorrow),
+        Err(RiskViolation::DailyLoss { .. })
+    ));
+}
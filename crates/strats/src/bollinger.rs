@@ -0,0 +1,78 @@
+use dnn_core::market::Candle;
+use crate::indicators::bollinger;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{hold_side, Strategy, StrategyContext};
+
+/// Buy a close below the lower Bollinger band and sell once price returns
+/// to the middle band. With `allow_short`, mirrors this above the upper band.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BollingerReversion {
+    pub period: usize,
+    /// Band width in standard deviations
+    pub k: f64,
+    pub allow_short: bool,
+    pub weight: f64,
+}
+
+impl BollingerReversion {
+    pub fn new(period: usize, k: f64) -> Self {
+        Self { period, k, allow_short: false, weight: 1.0 }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+}
+
+impl Strategy for BollingerReversion {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        let Some(bands) = bollinger(&ctx.closes(&symbol), self.period, self.k) else {
+            return;
+        };
+
+        let held = ctx.position_qty(&symbol);
+        let side = if bar.close < bands.lower {
+            1
+        } else if bar.close > bands.upper && self.allow_short {
+            -1
+        } else if (held > 0.0 && bar.close >= bands.middle) || (held < 0.0 && bar.close <= bands.middle) {
+            0
+        } else {
+            return;
+        };
+        hold_side(ctx, &symbol, side, self.weight);
+    }
+
+    fn lookback(&self) -> usize {
+        self.period
+    }
+}
+
+impl Parameterized for BollingerReversion {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("bollinger_reversion", "Fade moves outside the Bollinger bands, exit at the mean")
+            .param(ParamSpec::new("period", 20_i64).range(2.0, 500.0).describe("Moving average period"))
+            .param(ParamSpec::new("k", 2.0).range(0.1, 5.0).describe("Band width in standard deviations"))
+            .param(ParamSpec::new("allow_short", false).describe("Also short above the upper band"))
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 1.0).describe("Fraction of equity per position"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Ok(Self {
+            period: params.usize("period")?,
+            k: params.float("k")?,
+            allow_short: params.bool("allow_short")?,
+            weight: params.float("weight")?,
+        })
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("period", self.period)
+            .set("k", self.k)
+            .set("allow_short", self.allow_short)
+            .set("weight", self.weight)
+    }
+}
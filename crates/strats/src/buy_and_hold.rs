@@ -1,15 +1,46 @@
 use dnn_core::market::Candle;
-use dnn_core::Order;
-use crate::{ExecutionReport, Strategy};
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::sizing::Intent;
+use crate::{Strategy, StrategyContext};
 
-pub struct BuyAndHold;
+/// Put `weight` of equity into the symbol on the first bar and hold it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BuyAndHold {
+    pub weight: f64,
+}
+
+impl BuyAndHold {
+    pub fn new(weight: f64) -> Self {
+        Self { weight }
+    }
+}
+
+impl Default for BuyAndHold {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
 
 impl Strategy for BuyAndHold {
-    fn on_market_event(&mut self, event: &Candle) -> Vec<Order> {
-        todo!()
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        if ctx.position_qty(&symbol) == 0.0 && ctx.open_orders().is_empty() {
+            ctx.intent(Intent::TargetWeight { symbol, weight: self.weight });
+        }
+    }
+}
+
+impl Parameterized for BuyAndHold {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("buy_and_hold", "Buy on the first bar and hold")
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 1.0).describe("Fraction of equity to invest"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Ok(Self::new(params.float("weight")?))
     }
 
-    fn on_fill(&mut self, report: &ExecutionReport) {
-        todo!()
+    fn params(&self) -> Params {
+        Params::default().set("weight", self.weight)
     }
-}
\ No newline at end of file
+}
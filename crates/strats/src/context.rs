@@ -0,0 +1,237 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use dnn_core::fx::FxRates;
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::time::Timestamp;
+use dnn_core::{Order, OrderSide, Position};
+use crate::indicators;
+use crate::sizing::Intent;
+
+/// Something a strategy asked for while handling a hook
+#[derive(Debug, Clone)]
+pub enum Command {
+    Submit(Order),
+    Cancel(u64),
+    /// Size-free order, turned into an [`Order`] by the sizing layer
+    Intent(Intent),
+}
+
+/// A line written through [`StrategyContext::log`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    pub ts: Timestamp,
+    pub message: String,
+}
+
+/// Everything a strategy can see and do from inside a hook.
+///
+/// Built by the engine for each call; commands and log lines are collected
+/// and acted on once the hook returns.
+pub struct StrategyContext<'a> {
+    now: Timestamp,
+    symbol: &'a str,
+    portfolio: &'a Portfolio,
+    open_orders: &'a [Order],
+    history: &'a HashMap<String, Vec<Candle>>,
+    fx: Cow<'a, FxRates>,
+    next_order_id: &'a mut u64,
+    commands: Vec<Command>,
+    logs: Vec<LogLine>,
+    timers: Vec<Timestamp>,
+}
+
+impl<'a> StrategyContext<'a> {
+    pub fn new(
+        now: Timestamp,
+        symbol: &'a str,
+        portfolio: &'a Portfolio,
+        open_orders: &'a [Order],
+        history: &'a HashMap<String, Vec<Candle>>,
+        next_order_id: &'a mut u64,
+    ) -> Self {
+        Self {
+            now,
+            symbol,
+            portfolio,
+            open_orders,
+            history,
+            fx: Cow::Owned(FxRates::new()),
+            next_order_id,
+            commands: Vec::new(),
+            logs: Vec::new(),
+            timers: Vec::new(),
+        }
+    }
+
+    /// Rates for valuing holdings in other currencies than the base one
+    pub fn with_fx(mut self, fx: &'a FxRates) -> Self {
+        self.fx = Cow::Borrowed(fx);
+        self
+    }
+
+    /// Time of the event being handled
+    pub fn now(&self) -> Timestamp {
+        self.now
+    }
+
+    /// Symbol of the event being handled
+    pub fn symbol(&self) -> &str {
+        self.symbol
+    }
+
+    pub fn portfolio(&self) -> &Portfolio {
+        self.portfolio
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.portfolio.positions.get(symbol)
+    }
+
+    /// Signed quantity held (0 if flat)
+    pub fn position_qty(&self, symbol: &str) -> f64 {
+        self.position(symbol).map_or(0.0, |p| p.qty)
+    }
+
+    /// Cash in the portfolio's base currency
+    pub fn cash(&self) -> f64 {
+        self.portfolio.cash_in(&self.portfolio.base_currency)
+    }
+
+    /// Cash and positions marked at their last close (average price before
+    /// the first bar), in the base currency. Without the FX rates to convert
+    /// everything, just the base-currency cash.
+    pub fn equity(&self) -> f64 {
+        let prices: HashMap<String, f64> = self.portfolio
+            .positions
+            .values()
+            .map(|p| (p.symbol.clone(), self.last_price(&p.symbol).unwrap_or(p.avg_price)))
+            .collect();
+        self.portfolio.total_value(&prices, &self.fx).unwrap_or_else(|_| self.cash())
+    }
+
+    /// Signed fraction of [`equity`](Self::equity) held in a symbol
+    pub fn weight(&self, symbol: &str) -> f64 {
+        let equity = self.equity();
+        let value = match (self.position(symbol), self.last_price(symbol)) {
+            (Some(p), Some(price)) => self.fx.convert(p.market_value(price), &p.currency, &self.portfolio.base_currency),
+            _ => return 0.0,
+        };
+        match value {
+            Ok(value) if equity > 0.0 => value / equity,
+            _ => 0.0,
+        }
+    }
+
+    /// Working orders, with `qty` set to the unfilled quantity
+    pub fn open_orders(&self) -> &[Order] {
+        self.open_orders
+    }
+
+    /// Recent bars of a symbol, oldest first
+    pub fn bars(&self, symbol: &str) -> &[Candle] {
+        self.history.get(symbol).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn closes(&self, symbol: &str) -> Vec<f64> {
+        self.bars(symbol).iter().map(|c| c.close).collect()
+    }
+
+    /// Close of the latest bar
+    pub fn last_price(&self, symbol: &str) -> Option<f64> {
+        self.bars(symbol).last().map(|c| c.close)
+    }
+
+    pub fn sma(&self, symbol: &str, period: usize) -> Option<f64> {
+        indicators::sma(&self.closes(symbol), period)
+    }
+
+    pub fn ema(&self, symbol: &str, period: usize) -> Option<f64> {
+        indicators::ema(&self.closes(symbol), period)
+    }
+
+    pub fn rsi(&self, symbol: &str, period: usize) -> Option<f64> {
+        indicators::rsi(&self.closes(symbol), period)
+    }
+
+    pub fn stddev(&self, symbol: &str, period: usize) -> Option<f64> {
+        indicators::stddev(&self.closes(symbol), period)
+    }
+
+    pub fn atr(&self, symbol: &str, period: usize) -> Option<f64> {
+        indicators::atr(self.bars(symbol), period)
+    }
+
+    pub fn volatility(&self, symbol: &str, period: usize) -> Option<f64> {
+        indicators::volatility(&self.closes(symbol), period)
+    }
+
+    /// Id the next submitted order will get
+    pub fn next_order_id(&self) -> u64 {
+        *self.next_order_id
+    }
+
+    /// Send an order; returns its id. `price: None` is a market order.
+    pub fn submit(&mut self, symbol: &str, side: OrderSide, qty: f64, price: Option<f64>) -> u64 {
+        let id = *self.next_order_id;
+        *self.next_order_id += 1;
+        self.commands.push(Command::Submit(Order { id, symbol: symbol.to_owned(), qty, price, side }));
+        id
+    }
+
+    /// Market buy of the current symbol
+    pub fn buy(&mut self, qty: f64) -> u64 {
+        let symbol = self.symbol;
+        self.submit(symbol, OrderSide::Buy, qty, None)
+    }
+
+    /// Market sell of the current symbol
+    pub fn sell(&mut self, qty: f64) -> u64 {
+        let symbol = self.symbol;
+        self.submit(symbol, OrderSide::Sell, qty, None)
+    }
+
+    pub fn cancel(&mut self, order_id: u64) {
+        self.commands.push(Command::Cancel(order_id));
+    }
+
+    pub fn cancel_all(&mut self) {
+        let ids: Vec<u64> = self.open_orders.iter().map(|o| o.id).collect();
+        for id in ids {
+            self.cancel(id);
+        }
+    }
+
+    /// Let the sizing layer pick the quantity
+    pub fn intent(&mut self, intent: Intent) {
+        self.commands.push(Command::Intent(intent));
+    }
+
+    /// Hold `weight` of equity in a symbol (negative = short, 0 = flat)
+    pub fn target_weight(&mut self, symbol: &str, weight: f64) {
+        if weight == 0.0 {
+            self.flatten(symbol);
+        } else {
+            self.intent(Intent::TargetWeight { symbol: symbol.to_owned(), weight });
+        }
+    }
+
+    /// Close the position in a symbol
+    pub fn flatten(&mut self, symbol: &str) {
+        self.intent(Intent::Exit { symbol: symbol.to_owned() });
+    }
+
+    /// Call `on_timer` once the clock reaches `at`
+    pub fn schedule(&mut self, at: Timestamp) {
+        self.timers.push(at);
+    }
+
+    pub fn log(&mut self, message: impl Into<String>) {
+        self.logs.push(LogLine { ts: self.now, message: message.into() });
+    }
+
+    /// Commands, log lines and timers collected during the hook
+    pub fn into_parts(self) -> (Vec<Command>, Vec<LogLine>, Vec<Timestamp>) {
+        (self.commands, self.logs, self.timers)
+    }
+}
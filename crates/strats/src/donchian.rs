@@ -0,0 +1,86 @@
+use dnn_core::market::Candle;
+use crate::indicators::{highest, lowest};
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{hold_side, Strategy, StrategyContext};
+
+/// Turtle-style channel breakout: buy a close above the highest high of the
+/// previous `entry` bars, exit below the lowest low of the previous `exit` bars.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DonchianBreakout {
+    pub entry: usize,
+    pub exit: usize,
+    pub allow_short: bool,
+    pub weight: f64,
+}
+
+impl DonchianBreakout {
+    pub fn new(entry: usize, exit: usize) -> Self {
+        Self { entry, exit, allow_short: false, weight: 1.0 }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+}
+
+impl Strategy for DonchianBreakout {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        let bars = ctx.bars(&symbol);
+        // Channels exclude the bar being traded on
+        let Some(prior) = bars.len().checked_sub(1).map(|n| &bars[..n]) else {
+            return;
+        };
+        let (Some(entry_high), Some(entry_low)) = (highest(prior, self.entry), lowest(prior, self.entry)) else {
+            return;
+        };
+        let (Some(exit_high), Some(exit_low)) = (highest(prior, self.exit), lowest(prior, self.exit)) else {
+            return;
+        };
+
+        let held = ctx.position_qty(&symbol);
+        let side = if bar.close > entry_high {
+            1
+        } else if bar.close < entry_low && self.allow_short {
+            -1
+        } else if (held > 0.0 && bar.close < exit_low) || (held < 0.0 && bar.close > exit_high) {
+            0
+        } else {
+            return;
+        };
+        hold_side(ctx, &symbol, side, self.weight);
+    }
+
+    fn lookback(&self) -> usize {
+        // Channels exclude the current bar
+        self.entry.max(self.exit) + 1
+    }
+}
+
+impl Parameterized for DonchianBreakout {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("donchian_breakout", "Trade breakouts of the Donchian channel")
+            .param(ParamSpec::new("entry", 20_i64).range(2.0, 500.0).describe("Breakout channel length"))
+            .param(ParamSpec::new("exit", 10_i64).range(1.0, 500.0).describe("Exit channel length"))
+            .param(ParamSpec::new("allow_short", false).describe("Also short downside breakouts"))
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 1.0).describe("Fraction of equity per position"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Ok(Self {
+            entry: params.usize("entry")?,
+            exit: params.usize("exit")?,
+            allow_short: params.bool("allow_short")?,
+            weight: params.float("weight")?,
+        })
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("entry", self.entry)
+            .set("exit", self.exit)
+            .set("allow_short", self.allow_short)
+            .set("weight", self.weight)
+    }
+}
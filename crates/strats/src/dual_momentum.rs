@@ -0,0 +1,110 @@
+use dnn_core::market::Candle;
+use crate::indicators::rate_of_change;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{Strategy, StrategyContext};
+
+/// Dual momentum rotation: hold whichever symbol had the best return over
+/// `lookback` bars (relative momentum), but only while that return is positive
+/// (absolute momentum); otherwise hold `safe`, or cash if there is none.
+///
+/// Rebalances once every symbol has a bar at the current time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DualMomentum {
+    pub symbols: Vec<String>,
+    pub safe: Option<String>,
+    pub lookback: usize,
+    pub weight: f64,
+}
+
+impl DualMomentum {
+    pub fn new(symbols: Vec<String>, lookback: usize) -> Self {
+        Self { symbols, safe: None, lookback, weight: 1.0 }
+    }
+
+    pub fn with_safe(mut self, symbol: &str) -> Self {
+        self.safe = Some(symbol.to_owned());
+        self
+    }
+
+    fn universe(&self) -> impl Iterator<Item = &String> {
+        self.symbols.iter().chain(&self.safe)
+    }
+
+    /// Symbol to hold now, `None` for cash
+    fn pick(&self, ctx: &StrategyContext) -> Option<String> {
+        let best = self.symbols
+            .iter()
+            .filter_map(|s| Some((s, rate_of_change(&ctx.closes(s), self.lookback)?)))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match best {
+            Some((symbol, ret)) if ret > 0.0 => Some(symbol.clone()),
+            _ => self.safe.clone(),
+        }
+    }
+}
+
+impl Strategy for DualMomentum {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let now = ctx.now();
+        if !self.universe().all(|s| ctx.bars(s).last().is_some_and(|c| c.timestamp == now)) {
+            return;
+        }
+        if self.symbols.iter().any(|s| ctx.bars(s).len() <= self.lookback) {
+            return;
+        }
+
+        let pick = self.pick(ctx);
+        let stale: Vec<String> = self.universe()
+            .filter(|s| Some(*s) != pick.as_ref() && ctx.position_qty(s) != 0.0)
+            .cloned()
+            .collect();
+        let enter = pick.as_ref().filter(|s| ctx.position_qty(s) == 0.0).cloned();
+        if stale.is_empty() && enter.is_none() {
+            return;
+        }
+
+        ctx.log(format!("Rotating into {}", pick.as_deref().unwrap_or("cash")));
+        for symbol in stale {
+            ctx.flatten(&symbol);
+        }
+        if let Some(symbol) = enter {
+            ctx.target_weight(&symbol, self.weight);
+        }
+    }
+
+    fn lookback(&self) -> usize {
+        self.lookback + 1
+    }
+}
+
+impl Parameterized for DualMomentum {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("dual_momentum", "Rotate into the strongest symbol while its momentum is positive")
+            .param(ParamSpec::new("symbols", "").describe("Comma-separated symbols to rotate between"))
+            .param(ParamSpec::new("safe", "").describe("Symbol held when momentum is negative (empty = cash)"))
+            .param(ParamSpec::new("lookback", 126_i64).range(1.0, 1000.0).describe("Momentum lookback in bars"))
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 1.0).describe("Fraction of equity invested"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        let symbols = params.list("symbols")?;
+        if symbols.is_empty() {
+            return Err(ParamError::Invalid("symbols must list at least one symbol".to_owned()));
+        }
+        let safe = params.string("safe")?.trim();
+        Ok(Self {
+            symbols,
+            safe: (!safe.is_empty()).then(|| safe.to_owned()),
+            lookback: params.usize("lookback")?,
+            weight: params.float("weight")?,
+        })
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("symbols", self.symbols.join(",").as_str())
+            .set("safe", self.safe.as_deref().unwrap_or_default())
+            .set("lookback", self.lookback)
+            .set("weight", self.weight)
+    }
+}
@@ -0,0 +1,144 @@
+use dnn_core::market::Candle;
+
+/// Simple moving average of the last `period` values
+pub fn sma(values: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || values.len() < period {
+        return None;
+    }
+    Some(values[values.len() - period..].iter().sum::<f64>() / period as f64)
+}
+
+/// Population standard deviation of the last `period` values
+pub fn stddev(values: &[f64], period: usize) -> Option<f64> {
+    let mean = sma(values, period)?;
+    let var = values[values.len() - period..]
+        .iter()
+        .map(|v| (v - mean).powi(2))
+        .sum::<f64>() / period as f64;
+    Some(var.sqrt())
+}
+
+/// True range of a bar given the previous close
+pub fn true_range(bar: &Candle, prev_close: Option<f64>) -> f64 {
+    match prev_close {
+        Some(pc) => (bar.high - bar.low)
+            .max((bar.high - pc).abs())
+            .max((bar.low - pc).abs()),
+        None => bar.high - bar.low,
+    }
+}
+
+/// Average true range over the last `period` bars
+pub fn atr(bars: &[Candle], period: usize) -> Option<f64> {
+    if period == 0 || bars.len() < period + 1 {
+        return None;
+    }
+    let start = bars.len() - period;
+    let sum: f64 = (start..bars.len())
+        .map(|i| true_range(&bars[i], Some(bars[i - 1].close)))
+        .sum();
+    Some(sum / period as f64)
+}
+
+/// Standard deviation of simple returns over the last `period` bars
+pub fn volatility(closes: &[f64], period: usize) -> Option<f64> {
+    if closes.len() < period + 1 {
+        return None;
+    }
+    let returns: Vec<f64> = closes[closes.len() - period - 1..]
+        .windows(2)
+        .map(|w| w[1] / w[0] - 1.0)
+        .collect();
+    stddev(&returns, period)
+}
+
+/// Periods of values an [`ema`] is computed over. Older values would weigh
+/// about e^-8 of the average.
+pub const EMA_WINDOW_PERIODS: usize = 4;
+
+/// Exponential moving average seeded with an SMA. Only the last
+/// `EMA_WINDOW_PERIODS * period` values count, so it doesn't shift with how
+/// much history happens to be kept.
+pub fn ema(values: &[f64], period: usize) -> Option<f64> {
+    let values = &values[values.len().saturating_sub(EMA_WINDOW_PERIODS * period)..];
+    let seed = sma(&values[..period.min(values.len())], period)?;
+    let alpha = 2.0 / (period as f64 + 1.0);
+    Some(values[period..].iter().fold(seed, |avg, v| avg + alpha * (v - avg)))
+}
+
+/// Periods of changes an [`rsi`] is computed over. Wilder's smoothing
+/// forgets slower than an EMA, so the window is longer than
+/// [`EMA_WINDOW_PERIODS`].
+pub const RSI_WINDOW_PERIODS: usize = 8;
+
+/// Wilder's relative strength index (0-100). Only the last
+/// `RSI_WINDOW_PERIODS * period + 1` values count, like for [`ema`].
+pub fn rsi(values: &[f64], period: usize) -> Option<f64> {
+    if period == 0 || values.len() < period + 1 {
+        return None;
+    }
+    let values = &values[values.len().saturating_sub(RSI_WINDOW_PERIODS * period + 1)..];
+    let changes: Vec<f64> = values.windows(2).map(|w| w[1] - w[0]).collect();
+    let mut gain = changes[..period].iter().map(|c| c.max(0.0)).sum::<f64>() / period as f64;
+    let mut loss = changes[..period].iter().map(|c| (-c).max(0.0)).sum::<f64>() / period as f64;
+    let n = period as f64;
+    for c in &changes[period..] {
+        gain = (gain * (n - 1.0) + c.max(0.0)) / n;
+        loss = (loss * (n - 1.0) + (-c).max(0.0)) / n;
+    }
+    if loss == 0.0 {
+        return Some(if gain == 0.0 { 50.0 } else { 100.0 });
+    }
+    Some(100.0 - 100.0 / (1.0 + gain / loss))
+}
+
+/// Moving average with bands `k` standard deviations either side
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bands {
+    pub lower: f64,
+    pub middle: f64,
+    pub upper: f64,
+}
+
+pub fn bollinger(values: &[f64], period: usize, k: f64) -> Option<Bands> {
+    let middle = sma(values, period)?;
+    let width = k * stddev(values, period)?;
+    Some(Bands { lower: middle - width, middle, upper: middle + width })
+}
+
+/// Highest high of the last `period` bars
+pub fn highest(bars: &[Candle], period: usize) -> Option<f64> {
+    if period == 0 || bars.len() < period {
+        return None;
+    }
+    bars[bars.len() - period..].iter().map(|c| c.high).reduce(f64::max)
+}
+
+/// Lowest low of the last `period` bars
+pub fn lowest(bars: &[Candle], period: usize) -> Option<f64> {
+    if period == 0 || bars.len() < period {
+        return None;
+    }
+    bars[bars.len() - period..].iter().map(|c| c.low).reduce(f64::min)
+}
+
+/// Return over the last `period` values
+pub fn rate_of_change(values: &[f64], period: usize) -> Option<f64> {
+    if values.len() < period + 1 {
+        return None;
+    }
+    let past = values[values.len() - period - 1];
+    (past != 0.0).then(|| values[values.len() - 1] / past - 1.0)
+}
+
+/// Least-squares slope of `y` on `x` over the last `period` points
+pub fn regression_slope(x: &[f64], y: &[f64], period: usize) -> Option<f64> {
+    if period < 2 || x.len() < period || y.len() < period {
+        return None;
+    }
+    let (x, y) = (&x[x.len() - period..], &y[y.len() - period..]);
+    let (mx, my) = (sma(x, period)?, sma(y, period)?);
+    let cov: f64 = x.iter().zip(y).map(|(a, b)| (a - mx) * (b - my)).sum();
+    let var: f64 = x.iter().map(|a| (a - mx).powi(2)).sum();
+    (var > 0.0).then(|| cov / var)
+}
@@ -1,21 +1,101 @@
-mod buy_and_hold;
-mod sma_cross;
+pub mod bollinger;
+pub mod buy_and_hold;
+pub mod context;
+pub mod donchian;
+pub mod dual_momentum;
+pub mod indicators;
+pub mod ma_cross;
+pub mod pairs;
+pub mod registry;
+pub mod rsi;
+pub mod rules;
+#[cfg(feature = "scripting")]
+pub mod script;
+pub mod signal;
+pub mod sizing;
+pub mod sma_cross;
 
-use serde::{Deserialize, Serialize};
-use dnn_core::market::Candle;
-use dnn_core::Order;
+use dnn_core::market::{Candle, MarketEvent};
 use dnn_core::time::Timestamp;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ExecutionReport {
-    pub order_id: u64,
-    pub filled_qty: f64,
-    pub fill_price: f64,
-    pub ts: Timestamp,
-}
+pub use context::StrategyContext;
+pub use registry::{Parameterized, StrategyRegistry};
+pub use dnn_core::{ExecutionReport, ExecutionStatus};
+
+/// Bars of history kept per symbol for strategies that don't say how many they need
+pub const DEFAULT_LOOKBACK: usize = 250;
 
+/// A trading strategy, driven through lifecycle hooks.
+///
+/// Every hook gets a [`StrategyContext`] for reading portfolio state and
+/// market history and for submitting or cancelling orders.
 pub trait Strategy {
-    fn on_market_event(&mut self, event: &Candle) -> Vec<Order>;
-    fn on_fill(&mut self, report: &ExecutionReport);
+    /// Before the first event
+    fn on_start(&mut self, _ctx: &mut StrategyContext) {}
+
+    /// A new bar of `ctx.symbol()`
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle);
+
+    /// Every market event, including ticks, quotes and book updates.
+    /// Bar-based strategies can leave this alone: bars go to `on_bar`.
+    fn on_event(&mut self, ctx: &mut StrategyContext, event: &MarketEvent) {
+        if let MarketEvent::Bar { candle, .. } = event {
+            self.on_bar(ctx, candle);
+        }
+    }
+
+    /// A time set with [`StrategyContext::schedule`] was reached
+    fn on_timer(&mut self, _ctx: &mut StrategyContext, _at: Timestamp) {}
+
+    /// Acknowledgements, fills, cancels and rejections of the strategy's orders
+    fn on_order_update(&mut self, _ctx: &mut StrategyContext, _report: &ExecutionReport) {}
+
+    /// After the last event
+    fn on_end(&mut self, _ctx: &mut StrategyContext) {}
+
+    /// Bars of history per symbol the strategy reads through
+    /// [`StrategyContext::bars`]; the engine keeps exactly this many
+    fn lookback(&self) -> usize {
+        DEFAULT_LOOKBACK
+    }
 }
 
+/// Move a position to `side` (1 long, -1 short, 0 flat) at `weight` of equity,
+/// unless it is already on that side
+pub(crate) fn hold_side(ctx: &mut StrategyContext, symbol: &str, side: i8, weight: f64) {
+    let held = ctx.position_qty(symbol);
+    let current = if held > 0.0 { 1 } else if held < 0.0 { -1 } else { 0 };
+    if current != side {
+        ctx.target_weight(symbol, f64::from(side) * weight);
+    }
+}
+
+impl<S: Strategy + ?Sized> Strategy for Box<S> {
+    fn on_start(&mut self, ctx: &mut StrategyContext) {
+        (**self).on_start(ctx);
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        (**self).on_bar(ctx, bar);
+    }
+
+    fn on_event(&mut self, ctx: &mut StrategyContext, event: &MarketEvent) {
+        (**self).on_event(ctx, event);
+    }
+
+    fn on_timer(&mut self, ctx: &mut StrategyContext, at: Timestamp) {
+        (**self).on_timer(ctx, at);
+    }
+
+    fn on_order_update(&mut self, ctx: &mut StrategyContext, report: &ExecutionReport) {
+        (**self).on_order_update(ctx, report);
+    }
+
+    fn on_end(&mut self, ctx: &mut StrategyContext) {
+        (**self).on_end(ctx);
+    }
+
+    fn lookback(&self) -> usize {
+        (**self).lookback()
+    }
+}
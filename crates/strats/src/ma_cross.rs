@@ -0,0 +1,122 @@
+use serde::{Deserialize, Serialize};
+use dnn_core::market::Candle;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::indicators::EMA_WINDOW_PERIODS;
+use crate::{hold_side, Strategy, StrategyContext};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MovingAverage {
+    #[default]
+    Sma,
+    Ema,
+}
+
+impl MovingAverage {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Sma => "sma",
+            Self::Ema => "ema",
+        }
+    }
+}
+
+/// Long while the fast average is above the slow one. Exits (or reverses,
+/// with `allow_short`) when it crosses back below.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MaCross {
+    pub fast: usize,
+    pub slow: usize,
+    pub average: MovingAverage,
+    pub allow_short: bool,
+    /// Fraction of equity per position
+    pub weight: f64,
+}
+
+impl MaCross {
+    pub fn new(fast: usize, slow: usize, average: MovingAverage) -> Self {
+        Self { fast, slow, average, allow_short: false, weight: 1.0 }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+
+    pub fn with_weight(mut self, weight: f64) -> Self {
+        self.weight = weight;
+        self
+    }
+
+    fn average(&self, ctx: &StrategyContext, symbol: &str, period: usize) -> Option<f64> {
+        match self.average {
+            MovingAverage::Sma => ctx.sma(symbol, period),
+            MovingAverage::Ema => ctx.ema(symbol, period),
+        }
+    }
+}
+
+impl Strategy for MaCross {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        let (Some(fast), Some(slow)) = (self.average(ctx, &symbol, self.fast), self.average(ctx, &symbol, self.slow)) else {
+            return;
+        };
+
+        let side = if fast > slow {
+            1
+        } else if fast < slow && self.allow_short {
+            -1
+        } else if fast < slow {
+            0
+        } else {
+            return;
+        };
+        hold_side(ctx, &symbol, side, self.weight);
+    }
+
+    fn lookback(&self) -> usize {
+        match self.average {
+            MovingAverage::Sma => self.slow,
+            MovingAverage::Ema => EMA_WINDOW_PERIODS * self.slow,
+        }
+    }
+}
+
+impl Parameterized for MaCross {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("ma_cross", "Fast/slow moving average crossover with exits")
+            .param(ParamSpec::new("fast", 10_i64).range(1.0, 500.0).describe("Fast average period"))
+            .param(ParamSpec::new("slow", 30_i64).range(2.0, 1000.0).describe("Slow average period"))
+            .param(ParamSpec::new("average", "sma").choices(&["sma", "ema"]).describe("Moving average type"))
+            .param(ParamSpec::new("allow_short", false).describe("Go short on a bearish cross instead of flat"))
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 1.0).describe("Fraction of equity per position"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        let (fast, slow) = (params.usize("fast")?, params.usize("slow")?);
+        if fast >= slow {
+            return Err(ParamError::Invalid(format!("fast ({fast}) must be below slow ({slow})")));
+        }
+        let average = match params.string("average")? {
+            "ema" => MovingAverage::Ema,
+            _ => MovingAverage::Sma,
+        };
+        Ok(Self {
+            fast,
+            slow,
+            average,
+            allow_short: params.bool("allow_short")?,
+            weight: params.float("weight")?,
+        })
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("fast", self.fast)
+            .set("slow", self.slow)
+            .set("average", self.average.as_str())
+            .set("allow_short", self.allow_short)
+            .set("weight", self.weight)
+    }
+}
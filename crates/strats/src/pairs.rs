@@ -0,0 +1,124 @@
+use dnn_core::market::Candle;
+use crate::indicators::{regression_slope, sma, stddev};
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{Strategy, StrategyContext};
+
+/// Statistical arbitrage on two co-moving symbols.
+///
+/// The spread is `ln(a) - beta * ln(b)`, with `beta` fitted by least squares
+/// over `lookback` bars. When its z-score passes `entry_z` the strategy sells
+/// the rich leg and buys the cheap one, then flattens both once it falls back
+/// inside `exit_z`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairsTrading {
+    pub a: String,
+    pub b: String,
+    pub lookback: usize,
+    pub entry_z: f64,
+    pub exit_z: f64,
+    /// Gross fraction of equity, split evenly between the legs
+    pub weight: f64,
+}
+
+impl PairsTrading {
+    pub fn new(a: &str, b: &str, lookback: usize) -> Self {
+        Self { a: a.to_owned(), b: b.to_owned(), lookback, entry_z: 2.0, exit_z: 0.5, weight: 1.0 }
+    }
+
+    pub fn with_thresholds(mut self, entry_z: f64, exit_z: f64) -> Self {
+        self.entry_z = entry_z;
+        self.exit_z = exit_z;
+        self
+    }
+
+    /// Z-score of the latest spread, once both legs have enough history
+    fn zscore(&self, ctx: &StrategyContext) -> Option<f64> {
+        let log = |symbol: &str| -> Vec<f64> {
+            let closes = ctx.closes(symbol);
+            closes[closes.len().saturating_sub(self.lookback)..].iter().map(|c| c.ln()).collect()
+        };
+        let (a, b) = (log(&self.a), log(&self.b));
+        let beta = regression_slope(&b, &a, self.lookback)?;
+        let spread: Vec<f64> = a.iter().zip(&b).map(|(a, b)| a - beta * b).collect();
+        let std = stddev(&spread, self.lookback).filter(|s| *s > 0.0)?;
+        Some((spread[spread.len() - 1] - sma(&spread, self.lookback)?) / std)
+    }
+}
+
+impl Strategy for PairsTrading {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let now = ctx.now();
+        if ![&self.a, &self.b].iter().all(|s| ctx.bars(s).last().is_some_and(|c| c.timestamp == now)) {
+            return;
+        }
+        let Some(z) = self.zscore(ctx) else {
+            return;
+        };
+
+        let held = ctx.position_qty(&self.a);
+        let side: i8 = if z > self.entry_z {
+            -1
+        } else if z < -self.entry_z {
+            1
+        } else if held != 0.0 && z.abs() < self.exit_z {
+            0
+        } else {
+            return;
+        };
+        let current = if held > 0.0 { 1 } else if held < 0.0 { -1 } else { 0 };
+        if side == current {
+            return;
+        }
+
+        let leg = f64::from(side) * self.weight / 2.0;
+        ctx.log(format!("Spread z-score {z:.2}, {} {} against {}", if side == 0 { "closing" } else { "trading" }, self.a, self.b));
+        let (a, b) = (self.a.clone(), self.b.clone());
+        ctx.target_weight(&a, leg);
+        ctx.target_weight(&b, -leg);
+    }
+
+    fn lookback(&self) -> usize {
+        self.lookback
+    }
+}
+
+impl Parameterized for PairsTrading {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("pairs", "Mean-revert the z-score of a hedged two-symbol spread")
+            .param(ParamSpec::new("a", "").describe("First leg"))
+            .param(ParamSpec::new("b", "").describe("Second leg, used as the hedge"))
+            .param(ParamSpec::new("lookback", 60_i64).range(5.0, 1000.0).describe("Bars used to fit the hedge ratio"))
+            .param(ParamSpec::new("entry_z", 2.0).range(0.0, 10.0).describe("Open when |z| exceeds this"))
+            .param(ParamSpec::new("exit_z", 0.5).range(0.0, 10.0).describe("Close when |z| falls below this"))
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 2.0).describe("Gross fraction of equity across both legs"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        let (a, b) = (params.string("a")?.trim(), params.string("b")?.trim());
+        if a.is_empty() || b.is_empty() || a == b {
+            return Err(ParamError::Invalid("a and b must be two different symbols".to_owned()));
+        }
+        let (entry_z, exit_z) = (params.float("entry_z")?, params.float("exit_z")?);
+        if exit_z >= entry_z {
+            return Err(ParamError::Invalid(format!("exit_z ({exit_z}) must be below entry_z ({entry_z})")));
+        }
+        Ok(Self {
+            a: a.to_owned(),
+            b: b.to_owned(),
+            lookback: params.usize("lookback")?,
+            entry_z,
+            exit_z,
+            weight: params.float("weight")?,
+        })
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("a", self.a.as_str())
+            .set("b", self.b.as_str())
+            .set("lookback", self.lookback)
+            .set("entry_z", self.entry_z)
+            .set("exit_z", self.exit_z)
+            .set("weight", self.weight)
+    }
+}
@@ -0,0 +1,384 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use crate::Strategy;
+
+/// Type of a strategy parameter
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ParamType {
+    Int,
+    Float,
+    Bool,
+    String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ParamValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+}
+
+impl ParamValue {
+    pub fn param_type(&self) -> ParamType {
+        match self {
+            Self::Bool(_) => ParamType::Bool,
+            Self::Int(_) => ParamType::Int,
+            Self::Float(_) => ParamType::Float,
+            Self::String(_) => ParamType::String,
+        }
+    }
+
+    /// Numeric value, used for range checks
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Self::Int(v) => Some(*v as f64),
+            Self::Float(v) => Some(*v),
+            Self::Bool(_) | Self::String(_) => None,
+        }
+    }
+}
+
+impl From<i64> for ParamValue {
+    fn from(v: i64) -> Self {
+        Self::Int(v)
+    }
+}
+
+impl From<usize> for ParamValue {
+    fn from(v: usize) -> Self {
+        Self::Int(i64::try_from(v).unwrap_or(i64::MAX))
+    }
+}
+
+impl From<f64> for ParamValue {
+    fn from(v: f64) -> Self {
+        Self::Float(v)
+    }
+}
+
+impl From<bool> for ParamValue {
+    fn from(v: bool) -> Self {
+        Self::Bool(v)
+    }
+}
+
+impl From<&str> for ParamValue {
+    fn from(v: &str) -> Self {
+        Self::String(v.to_owned())
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParamError {
+    #[error("Strategy parameters must be a JSON object")]
+    NotAnObject,
+    #[error("Unknown parameter `{0}`")]
+    Unknown(String),
+    #[error("Missing parameter `{0}`")]
+    Missing(String),
+    #[error("Parameter `{name}` must be of type {expected:?}")]
+    WrongType { name: String, expected: ParamType },
+    #[error("Parameter `{name}` must be one of {allowed:?}")]
+    NotAllowed { name: String, allowed: Vec<String> },
+    #[error("Parameter `{name}` = {value} is outside [{min}, {max}]")]
+    OutOfRange { name: String, value: f64, min: f64, max: f64 },
+    #[error("Invalid parameters: {0}")]
+    Invalid(String),
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RegistryError {
+    #[error("Unknown strategy `{0}`")]
+    UnknownStrategy(String),
+    #[error(transparent)]
+    Params(#[from] ParamError),
+}
+
+/// One entry of a strategy's parameter schema
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParamSpec {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub ty: ParamType,
+    pub default: ParamValue,
+    /// Inclusive bounds for numeric parameters
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    /// Allowed values for string parameters (empty = any)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub choices: Vec<String>,
+    #[serde(default)]
+    pub description: String,
+}
+
+impl ParamSpec {
+    pub fn new(name: &str, default: impl Into<ParamValue>) -> Self {
+        let default = default.into();
+        Self {
+            name: name.to_owned(),
+            ty: default.param_type(),
+            default,
+            min: None,
+            max: None,
+            choices: Vec::new(),
+            description: String::new(),
+        }
+    }
+
+    pub fn range(mut self, min: f64, max: f64) -> Self {
+        self.min = Some(min);
+        self.max = Some(max);
+        self
+    }
+
+    pub fn choices(mut self, choices: &[&str]) -> Self {
+        self.choices = choices.iter().map(|c| (*c).to_owned()).collect();
+        self
+    }
+
+    pub fn describe(mut self, description: &str) -> Self {
+        description.clone_into(&mut self.description);
+        self
+    }
+
+    /// Coerce a JSON value to this parameter's type and check its range
+    pub fn parse(&self, value: &Value) -> Result<ParamValue, ParamError> {
+        let wrong_type = || ParamError::WrongType { name: self.name.clone(), expected: self.ty };
+        let parsed = match self.ty {
+            ParamType::Int => ParamValue::Int(value.as_i64().ok_or_else(wrong_type)?),
+            ParamType::Float => ParamValue::Float(value.as_f64().ok_or_else(wrong_type)?),
+            ParamType::Bool => ParamValue::Bool(value.as_bool().ok_or_else(wrong_type)?),
+            ParamType::String => ParamValue::String(value.as_str().ok_or_else(wrong_type)?.to_owned()),
+        };
+
+        if let ParamValue::String(v) = &parsed
+            && !self.choices.is_empty()
+            && !self.choices.contains(v)
+        {
+            return Err(ParamError::NotAllowed { name: self.name.clone(), allowed: self.choices.clone() });
+        }
+        if let Some(v) = parsed.as_f64() {
+            let min = self.min.unwrap_or(f64::NEG_INFINITY);
+            let max = self.max.unwrap_or(f64::INFINITY);
+            if !v.is_finite() || v < min || v > max {
+                return Err(ParamError::OutOfRange { name: self.name.clone(), value: v, min, max });
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Name, description and parameters of a strategy
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StrategySchema {
+    pub name: String,
+    pub description: String,
+    pub params: Vec<ParamSpec>,
+}
+
+impl StrategySchema {
+    pub fn new(name: &str, description: &str) -> Self {
+        Self { name: name.to_owned(), description: description.to_owned(), params: Vec::new() }
+    }
+
+    pub fn param(mut self, spec: ParamSpec) -> Self {
+        self.params.push(spec);
+        self
+    }
+
+    /// Every parameter at its default
+    pub fn defaults(&self) -> Params {
+        Params(self.params.iter().map(|p| (p.name.clone(), p.default.clone())).collect())
+    }
+
+    /// Validate a JSON object against the schema, filling in defaults.
+    /// `null` means "all defaults".
+    pub fn validate(&self, json: &Value) -> Result<Params, ParamError> {
+        let empty = serde_json::Map::new();
+        let given = match json {
+            Value::Null => &empty,
+            Value::Object(map) => map,
+            _ => return Err(ParamError::NotAnObject),
+        };
+        if let Some(name) = given.keys().find(|k| !self.params.iter().any(|p| &p.name == *k)) {
+            return Err(ParamError::Unknown(name.clone()));
+        }
+
+        let mut params = self.defaults();
+        for spec in &self.params {
+            if let Some(value) = given.get(&spec.name) {
+                params.0.insert(spec.name.clone(), spec.parse(value)?);
+            }
+        }
+        Ok(params)
+    }
+}
+
+/// Validated parameter values, keyed by name
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Params(pub BTreeMap<String, ParamValue>);
+
+impl Params {
+    pub fn get(&self, name: &str) -> Result<&ParamValue, ParamError> {
+        self.0.get(name).ok_or_else(|| ParamError::Missing(name.to_owned()))
+    }
+
+    pub fn set(mut self, name: &str, value: impl Into<ParamValue>) -> Self {
+        self.0.insert(name.to_owned(), value.into());
+        self
+    }
+
+    pub fn int(&self, name: &str) -> Result<i64, ParamError> {
+        match self.get(name)? {
+            ParamValue::Int(v) => Ok(*v),
+            _ => Err(ParamError::WrongType { name: name.to_owned(), expected: ParamType::Int }),
+        }
+    }
+
+    /// Non-negative integer, e.g. a lookback period
+    pub fn usize(&self, name: &str) -> Result<usize, ParamError> {
+        let v = self.int(name)?;
+        usize::try_from(v).map_err(|_err| ParamError::OutOfRange {
+            name: name.to_owned(),
+            value: v as f64,
+            min: 0.0,
+            max: f64::INFINITY,
+        })
+    }
+
+    /// Ints are accepted too
+    pub fn float(&self, name: &str) -> Result<f64, ParamError> {
+        self.get(name)?
+            .as_f64()
+            .ok_or_else(|| ParamError::WrongType { name: name.to_owned(), expected: ParamType::Float })
+    }
+
+    pub fn bool(&self, name: &str) -> Result<bool, ParamError> {
+        match self.get(name)? {
+            ParamValue::Bool(v) => Ok(*v),
+            _ => Err(ParamError::WrongType { name: name.to_owned(), expected: ParamType::Bool }),
+        }
+    }
+
+    pub fn string(&self, name: &str) -> Result<&str, ParamError> {
+        match self.get(name)? {
+            ParamValue::String(v) => Ok(v),
+            _ => Err(ParamError::WrongType { name: name.to_owned(), expected: ParamType::String }),
+        }
+    }
+
+    /// Comma-separated list, e.g. `"SPY, EFA"`
+    pub fn list(&self, name: &str) -> Result<Vec<String>, ParamError> {
+        Ok(self.string(name)?
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_owned)
+            .collect())
+    }
+
+    pub fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap_or(Value::Null)
+    }
+}
+
+/// A strategy that can be described by, and built from, a parameter schema
+pub trait Parameterized: Sized {
+    fn schema() -> StrategySchema;
+
+    /// Build from validated parameters; may reject combinations the schema can't express
+    fn from_params(params: &Params) -> Result<Self, ParamError>;
+
+    /// Current parameters, round-trippable through [`Self::from_params`]
+    fn params(&self) -> Params;
+}
+
+/// Strategy name plus parameters, as stored or sent over the API
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrategySpec {
+    pub name: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+type Factory = fn(&Params) -> Result<Box<dyn Strategy + Send>, ParamError>;
+
+fn build<S: Parameterized + Strategy + Send + 'static>(params: &Params) -> Result<Box<dyn Strategy + Send>, ParamError> {
+    Ok(Box::new(S::from_params(params)?))
+}
+
+/// Strategies that can be listed and instantiated by name
+#[derive(Default)]
+pub struct StrategyRegistry {
+    entries: BTreeMap<String, (StrategySchema, Factory)>,
+}
+
+impl StrategyRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with every strategy shipped in this crate
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register::<crate::bollinger::BollingerReversion>();
+        registry.register::<crate::buy_and_hold::BuyAndHold>();
+        registry.register::<crate::donchian::DonchianBreakout>();
+        registry.register::<crate::dual_momentum::DualMomentum>();
+        registry.register::<crate::signal::Ensemble>();
+        registry.register::<crate::ma_cross::MaCross>();
+        registry.register::<crate::pairs::PairsTrading>();
+        registry.register::<crate::rsi::RsiReversion>();
+        registry.register::<crate::rules::RuleStrategy>();
+        #[cfg(feature = "scripting")]
+        registry.register::<crate::script::ScriptStrategy>();
+        registry.register::<crate::sma_cross::SmaCross>();
+        registry
+    }
+
+    /// Add a strategy, replacing any with the same name
+    pub fn register<S: Parameterized + Strategy + Send + 'static>(&mut self) {
+        let schema = S::schema();
+        self.entries.insert(schema.name.clone(), (schema, build::<S>));
+    }
+
+    /// Schemas in name order
+    pub fn schemas(&self) -> impl Iterator<Item = &StrategySchema> {
+        self.entries.values().map(|(schema, _)| schema)
+    }
+
+    pub fn schema(&self, name: &str) -> Option<&StrategySchema> {
+        self.entries.get(name).map(|(schema, _)| schema)
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.contains_key(name)
+    }
+
+    /// Validate parameters without building the strategy
+    pub fn validate(&self, name: &str, params: &Value) -> Result<Params, RegistryError> {
+        let (schema, factory) = self.entry(name)?;
+        let params = schema.validate(params)?;
+        factory(&params)?;
+        Ok(params)
+    }
+
+    pub fn create(&self, name: &str, params: &Value) -> Result<Box<dyn Strategy + Send>, RegistryError> {
+        let (schema, factory) = self.entry(name)?;
+        Ok(factory(&schema.validate(params)?)?)
+    }
+
+    pub fn create_from_spec(&self, spec: &StrategySpec) -> Result<Box<dyn Strategy + Send>, RegistryError> {
+        self.create(&spec.name, &spec.params)
+    }
+
+    fn entry(&self, name: &str) -> Result<&(StrategySchema, Factory), RegistryError> {
+        self.entries.get(name).ok_or_else(|| RegistryError::UnknownStrategy(name.to_owned()))
+    }
+}
@@ -0,0 +1,84 @@
+use dnn_core::market::Candle;
+use crate::indicators::RSI_WINDOW_PERIODS;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{hold_side, Strategy, StrategyContext};
+
+/// RSI level positions are closed at
+const MIDLINE: f64 = 50.0;
+
+/// Buy when RSI drops below `oversold`, close when it recovers past 50.
+/// With `allow_short`, sells above `overbought` the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RsiReversion {
+    pub period: usize,
+    pub oversold: f64,
+    pub overbought: f64,
+    pub allow_short: bool,
+    pub weight: f64,
+}
+
+impl RsiReversion {
+    pub fn new(period: usize, oversold: f64, overbought: f64) -> Self {
+        Self { period, oversold, overbought, allow_short: false, weight: 1.0 }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+}
+
+impl Strategy for RsiReversion {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        let Some(rsi) = ctx.rsi(&symbol, self.period) else {
+            return;
+        };
+
+        let held = ctx.position_qty(&symbol);
+        let side = if rsi < self.oversold {
+            1
+        } else if rsi > self.overbought && self.allow_short {
+            -1
+        } else if (held > 0.0 && rsi > MIDLINE) || (held < 0.0 && rsi < MIDLINE) {
+            0
+        } else {
+            return;
+        };
+        hold_side(ctx, &symbol, side, self.weight);
+    }
+
+    fn lookback(&self) -> usize {
+        RSI_WINDOW_PERIODS * self.period + 1
+    }
+}
+
+impl Parameterized for RsiReversion {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("rsi_reversion", "Buy oversold RSI, exit when it recovers past 50")
+            .param(ParamSpec::new("period", 14_i64).range(2.0, 200.0).describe("RSI period"))
+            .param(ParamSpec::new("oversold", 30.0).range(0.0, 50.0).describe("Buy below this RSI"))
+            .param(ParamSpec::new("overbought", 70.0).range(50.0, 100.0).describe("Short above this RSI"))
+            .param(ParamSpec::new("allow_short", false).describe("Also short overbought RSI"))
+            .param(ParamSpec::new("weight", 1.0).range(0.0, 1.0).describe("Fraction of equity per position"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Ok(Self {
+            period: params.usize("period")?,
+            oversold: params.float("oversold")?,
+            overbought: params.float("overbought")?,
+            allow_short: params.bool("allow_short")?,
+            weight: params.float("weight")?,
+        })
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("period", self.period)
+            .set("oversold", self.oversold)
+            .set("overbought", self.overbought)
+            .set("allow_short", self.allow_short)
+            .set("weight", self.weight)
+    }
+}
@@ -0,0 +1,430 @@
+//! Declarative strategies: entry/exit conditions, stops, targets and sizing,
+//! written as JSON or YAML.
+//!
+//! ```yaml
+//! name: RSI dip
+//! entry: RSI(14) < 30 AND close > SMA(200)
+//! exit: RSI(14) > 55
+//! stop_loss: { percent: 5 }
+//! take_profit: { atr: { period: 14, multiple: 3 } }
+//! sizing: { weight: 0.5 }
+//! ```
+//!
+//! See [`expr`] for the condition syntax.
+
+pub mod expr;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use dnn_core::market::Candle;
+use dnn_core::{ExecutionReport, OrderSide};
+use crate::indicators;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::sizing::Intent;
+use crate::{Strategy, StrategyContext};
+use expr::Condition;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    #[default]
+    Long,
+    Short,
+}
+
+/// Distance of a stop or target from the entry price
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Level {
+    /// Percent of the entry price
+    Percent(f64),
+    /// Absolute price distance
+    Points(f64),
+    /// Multiple of the ATR at entry
+    Atr { period: usize, multiple: f64 },
+}
+
+impl Level {
+    fn distance(self, entry: f64, bars: &[Candle]) -> Option<f64> {
+        match self {
+            Self::Percent(p) => Some(entry * p / 100.0),
+            Self::Points(p) => Some(p),
+            Self::Atr { period, multiple } => Some(indicators::atr(bars, period)? * multiple),
+        }
+    }
+
+    fn validate(self) -> Result<(), String> {
+        match self {
+            Self::Percent(p) if p <= 0.0 || p >= 100.0 || !p.is_finite() => Err("percent must be between 0 and 100".to_owned()),
+            Self::Points(p) if p <= 0.0 || !p.is_finite() => Err("points must be positive".to_owned()),
+            Self::Atr { period: 0, .. } => Err("ATR period must be at least 1".to_owned()),
+            Self::Atr { multiple, .. } if multiple <= 0.0 || !multiple.is_finite() => {
+                Err("ATR multiple must be positive".to_owned())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// How entries are sized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Sizing {
+    /// Leave it to the engine's position sizer, passing along the stop
+    #[default]
+    Sizer,
+    /// Fraction of equity
+    Weight(f64),
+    /// Fixed number of units
+    Quantity(f64),
+}
+
+/// A rule-based strategy as written by the user
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    #[serde(default)]
+    pub name: String,
+    /// Only trade this symbol (default: every symbol the strategy sees)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symbol: Option<String>,
+    #[serde(default)]
+    pub direction: Direction,
+    pub entry: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stop_loss: Option<Level>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub take_profit: Option<Level>,
+    #[serde(default)]
+    pub sizing: Sizing,
+}
+
+/// Line and column, both 1-based
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A problem with a rule spec, located precisely enough to highlight in an editor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleError {
+    /// Spec field the error is in, `None` for syntax errors in the document itself
+    pub field: Option<String>,
+    /// In the document when read by [`RuleStrategy::from_json`] or
+    /// [`RuleStrategy::from_yaml`], in the field's expression when the spec
+    /// was built directly
+    pub position: Option<Position>,
+    pub message: String,
+}
+
+impl RuleError {
+    fn field(field: &str, message: impl Into<String>) -> Self {
+        Self { field: Some(field.to_owned()), position: None, message: message.into() }
+    }
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(field) = &self.field {
+            write!(f, "{field}: ")?;
+        }
+        if let Some(p) = self.position {
+            write!(f, "line {}, column {}: ", p.line, p.column)?;
+        }
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+fn condition(field: &str, src: &str, errors: &mut Vec<RuleError>) -> Option<Condition> {
+    Condition::parse(src)
+        .map_err(|e| errors.push(RuleError {
+            field: Some(field.to_owned()),
+            position: Some(Position { line: 1, column: e.offset + 1 }),
+            message: e.message,
+        }))
+        .ok()
+}
+
+/// Moves the positions of expression errors from their field to `doc`.
+/// Expressions that aren't in the document verbatim (escaped JSON strings,
+/// folded YAML) are located at their field's key instead.
+fn in_document(doc: &str, spec: &RuleSpec, mut errors: Vec<RuleError>) -> Vec<RuleError> {
+    for error in &mut errors {
+        let expr = match error.field.as_deref() {
+            Some("entry") => Some(spec.entry.as_str()),
+            Some("exit") => spec.exit.as_deref(),
+            _ => None,
+        };
+        let (Some(field), Some(expr), Some(position)) = (&error.field, expr, error.position) else {
+            continue;
+        };
+        let Some(key) = find_key(doc, field) else { continue };
+        error.position = Some(match doc[key..].find(expr) {
+            Some(start) => {
+                let within = expr.char_indices().nth(position.column - 1).map_or(expr.len(), |(i, _)| i);
+                position_at(doc, key + start + within)
+            }
+            None => position_at(doc, key),
+        });
+    }
+    errors
+}
+
+/// Byte offset of a top-level key of a JSON or YAML document
+fn find_key(doc: &str, key: &str) -> Option<usize> {
+    let quoted = format!("\"{key}\"");
+    let plain = format!("{key}:");
+    let mut start = 0;
+    for line in doc.split_inclusive('\n') {
+        if let Some(at) = line.find(&quoted) {
+            return Some(start + at);
+        }
+        if line.starts_with(&plain) {
+            return Some(start);
+        }
+        start += line.len();
+    }
+    None
+}
+
+fn position_at(doc: &str, offset: usize) -> Position {
+    let before = &doc[..offset];
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position { line: before.matches('\n').count() + 1, column: before[line_start..].chars().count() + 1 }
+}
+
+/// Stop and target prices of an open position
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Exits {
+    stop: Option<f64>,
+    target: Option<f64>,
+}
+
+/// A [`RuleSpec`] compiled into a [`Strategy`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleStrategy {
+    spec: RuleSpec,
+    entry: Condition,
+    exit: Option<Condition>,
+    /// Of the open positions, by symbol
+    exits: HashMap<String, Exits>,
+    /// Symbols with entry orders out, whose exits are set when they fill
+    entering: HashSet<String>,
+}
+
+impl RuleStrategy {
+    /// Validate a spec, reporting every problem at once
+    pub fn new(spec: RuleSpec) -> Result<Self, Vec<RuleError>> {
+        let mut errors = Vec::new();
+        let entry = condition("entry", &spec.entry, &mut errors);
+        let exit = spec.exit.as_deref().and_then(|src| condition("exit", src, &mut errors));
+        for (field, level) in [("stop_loss", spec.stop_loss), ("take_profit", spec.take_profit)] {
+            if let Some(Err(message)) = level.map(Level::validate) {
+                errors.push(RuleError::field(field, message));
+            }
+        }
+        match spec.sizing {
+            Sizing::Weight(w) if w <= 0.0 || w > 1.0 || !w.is_finite() => {
+                errors.push(RuleError::field("sizing", "weight must be in (0, 1]"));
+            }
+            Sizing::Quantity(q) if q <= 0.0 || !q.is_finite() => {
+                errors.push(RuleError::field("sizing", "quantity must be positive"));
+            }
+            _ => {}
+        }
+        if spec.symbol.as_ref().is_some_and(|s| s.trim().is_empty()) {
+            errors.push(RuleError::field("symbol", "symbol must not be empty"));
+        }
+
+        match entry {
+            Some(entry) if errors.is_empty() => Ok(Self { spec, entry, exit, exits: HashMap::new(), entering: HashSet::new() }),
+            _ => Err(errors),
+        }
+    }
+
+    pub fn from_json(src: &str) -> Result<Self, Vec<RuleError>> {
+        let spec = serde_json::from_str(src).map_err(|e| {
+            vec![RuleError {
+                field: None,
+                position: Some(Position { line: e.line(), column: e.column() }),
+                message: e.to_string(),
+            }]
+        })?;
+        Self::new_in(src, &spec)
+    }
+
+    /// YAML, which also accepts JSON
+    pub fn from_yaml(src: &str) -> Result<Self, Vec<RuleError>> {
+        // Enums as `{ percent: 5 }` maps, like JSON, rather than YAML tags
+        let deserializer = serde_yaml::Deserializer::from_str(src);
+        let spec = serde_yaml::with::singleton_map_recursive::deserialize(deserializer).map_err(|e: serde_yaml::Error| {
+            vec![RuleError {
+                field: None,
+                position: e.location().map(|l| Position { line: l.line(), column: l.column() }),
+                message: e.to_string(),
+            }]
+        })?;
+        Self::new_in(src, &spec)
+    }
+
+    /// [`Self::new`] for a spec read from `doc`
+    fn new_in(doc: &str, spec: &RuleSpec) -> Result<Self, Vec<RuleError>> {
+        Self::new(spec.clone()).map_err(|errors| in_document(doc, spec, errors))
+    }
+
+    pub fn spec(&self) -> &RuleSpec {
+        &self.spec
+    }
+
+    fn sign(&self) -> f64 {
+        match self.spec.direction {
+            Direction::Long => 1.0,
+            Direction::Short => -1.0,
+        }
+    }
+
+    fn levels(&self, entry: f64, bars: &[Candle]) -> Exits {
+        let sign = self.sign();
+        Exits {
+            stop: self.spec.stop_loss.and_then(|l| l.distance(entry, bars)).map(|d| entry - sign * d),
+            target: self.spec.take_profit.and_then(|l| l.distance(entry, bars)).map(|d| entry + sign * d),
+        }
+    }
+
+    fn enter(&mut self, ctx: &mut StrategyContext, symbol: &str, close: f64, bars: &[Candle]) {
+        ctx.log(format!("{symbol} entry: {}", self.entry.source()));
+        self.entering.insert(symbol.to_owned());
+        let side = match self.spec.direction {
+            Direction::Long => OrderSide::Buy,
+            Direction::Short => OrderSide::Sell,
+        };
+        match self.spec.sizing {
+            Sizing::Sizer => {
+                let stop = self.levels(close, bars).stop;
+                ctx.intent(Intent::Enter { symbol: symbol.to_owned(), side, stop });
+            }
+            Sizing::Weight(w) => ctx.target_weight(symbol, self.sign() * w),
+            Sizing::Quantity(q) => {
+                ctx.submit(symbol, side, q, None);
+            }
+        }
+    }
+
+    /// Why the open position should be closed, if it should
+    fn exit_reason(&mut self, symbol: &str, avg_price: f64, close: f64, bars: &[Candle]) -> Option<String> {
+        let exits = if let Some(exits) = self.exits.get(symbol) {
+            *exits
+        } else {
+            // A position the strategy didn't see fill, e.g. one it was started with
+            let exits = self.levels(avg_price, bars);
+            self.exits.insert(symbol.to_owned(), exits);
+            exits
+        };
+        let sign = self.sign();
+        if exits.stop.is_some_and(|stop| sign * (close - stop) <= 0.0) {
+            return Some("stop loss".to_owned());
+        }
+        if exits.target.is_some_and(|target| sign * (close - target) >= 0.0) {
+            return Some("take profit".to_owned());
+        }
+        self.exit
+            .as_ref()
+            .filter(|exit| exit.holds(bars))
+            .map(|exit| format!("exit: {}", exit.source()))
+    }
+}
+
+impl Strategy for RuleStrategy {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        if self.spec.symbol.as_ref().is_some_and(|s| *s != symbol) {
+            return;
+        }
+        let bars = ctx.bars(&symbol).to_vec();
+
+        let position = ctx.position(&symbol).filter(|p| p.qty != 0.0).map(|p| (p.qty, p.avg_price));
+        match position {
+            None => {
+                self.exits.remove(&symbol);
+                if ctx.open_orders().iter().all(|o| o.symbol != symbol) {
+                    // Entries that were cancelled or rejected
+                    self.entering.remove(&symbol);
+                    if self.entry.holds(&bars) {
+                        self.enter(ctx, &symbol, bar.close, &bars);
+                    }
+                }
+            }
+            // Only manage positions in the rule's direction
+            Some((qty, avg_price)) if qty * self.sign() > 0.0 => {
+                if let Some(reason) = self.exit_reason(&symbol, avg_price, bar.close, &bars) {
+                    ctx.log(format!("{symbol} {reason}"));
+                    ctx.flatten(&symbol);
+                }
+            }
+            Some(_) => {}
+        }
+    }
+
+    /// Bars the conditions read, and the ATR of stops and targets
+    fn lookback(&self) -> usize {
+        let atr = |level: Option<Level>| match level {
+            Some(Level::Atr { period, .. }) => period + 1,
+            _ => 0,
+        };
+        self.entry
+            .history()
+            .max(self.exit.as_ref().map_or(0, Condition::history))
+            .max(atr(self.spec.stop_loss))
+            .max(atr(self.spec.take_profit))
+    }
+
+    /// Sets the stop and target of entries as they fill, from the fill price
+    /// and the ATR at that point
+    fn on_order_update(&mut self, ctx: &mut StrategyContext, report: &ExecutionReport) {
+        if !report.is_fill() {
+            return;
+        }
+        let sign = self.sign();
+        let filled: Vec<String> = self
+            .entering
+            .iter()
+            .filter(|symbol| ctx.position_qty(symbol) * sign > 0.0)
+            .cloned()
+            .collect();
+        for symbol in filled {
+            self.entering.remove(&symbol);
+            if let Some(avg_price) = ctx.position(&symbol).map(|p| p.avg_price) {
+                let exits = self.levels(avg_price, ctx.bars(&symbol));
+                self.exits.insert(symbol, exits);
+            }
+        }
+    }
+}
+
+impl Parameterized for RuleStrategy {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("rules", "Declarative entry/exit rules with stops, targets and sizing")
+            .param(ParamSpec::new("rules", "").describe("Rule spec as YAML or JSON"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Self::from_yaml(params.string("rules")?).map_err(|errors| {
+            ParamError::Invalid(errors.iter().map(ToString::to_string).collect::<Vec<_>>().join("; "))
+        })
+    }
+
+    fn params(&self) -> Params {
+        let mut yaml = Vec::new();
+        let mut serializer = serde_yaml::Serializer::new(&mut yaml);
+        // Enums as `{ percent: 5 }` maps, like JSON, rather than YAML tags
+        let _ = serde_yaml::with::singleton_map_recursive::serialize(&self.spec, &mut serializer);
+        let yaml = String::from_utf8(yaml).unwrap_or_default();
+        Params::default().set("rules", yaml.as_str())
+    }
+}
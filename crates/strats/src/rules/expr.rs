@@ -0,0 +1,644 @@
+//! Condition expressions such as `RSI(14) < 30 AND close > SMA(200)`.
+//!
+//! ```text
+//! or      := and ("OR" and)*
+//! and     := not ("AND" not)*
+//! not     := "NOT" not | compare
+//! compare := sum (("<" | "<=" | ">" | ">=" | "==" | "!=" | "CROSSES ABOVE" | "CROSSES BELOW") sum)?
+//! sum     := product (("+" | "-") product)*
+//! product := unary (("*" | "/") unary)*
+//! unary   := "-" unary | number | field | variable | indicator "(" period ("," field)? ")" | "(" or ")"
+//! ```
+//!
+//! Fields are `open`, `high`, `low`, `close` and `volume`. Indicators are `SMA`,
+//! `EMA`, `RSI`, `ATR`, `STDDEV`, `HIGHEST`, `LOWEST` and `ROC`. Variables are
+//! whatever names the caller passes to [`Condition::parse_with`], with values
+//! given at evaluation. Keywords and names are case-insensitive.
+
+use std::fmt;
+use dnn_core::market::Candle;
+use crate::indicators;
+
+/// Parse error at a character offset of the expression
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprError {
+    /// 0-based character offset
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ExprError {
+    fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self { offset, message: message.into() }
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "column {}: {}", self.offset + 1, self.message)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Open,
+    High,
+    Low,
+    Close,
+    Volume,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_lowercase().as_str() {
+            "open" => Self::Open,
+            "high" => Self::High,
+            "low" => Self::Low,
+            "close" => Self::Close,
+            "volume" => Self::Volume,
+            _ => return None,
+        })
+    }
+
+    fn of(self, bar: &Candle) -> f64 {
+        match self {
+            Self::Open => bar.open,
+            Self::High => bar.high,
+            Self::Low => bar.low,
+            Self::Close => bar.close,
+            Self::Volume => bar.volume,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indicator {
+    Sma,
+    Ema,
+    Rsi,
+    Atr,
+    Stddev,
+    Highest,
+    Lowest,
+    Roc,
+}
+
+impl Indicator {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name.to_ascii_uppercase().as_str() {
+            "SMA" => Self::Sma,
+            "EMA" => Self::Ema,
+            "RSI" => Self::Rsi,
+            "ATR" => Self::Atr,
+            "STDDEV" => Self::Stddev,
+            "HIGHEST" => Self::Highest,
+            "LOWEST" => Self::Lowest,
+            "ROC" => Self::Roc,
+            _ => return None,
+        })
+    }
+
+    fn default_field(self) -> Field {
+        match self {
+            Self::Highest => Field::High,
+            Self::Lowest => Field::Low,
+            _ => Field::Close,
+        }
+    }
+
+    /// Bars needed before the indicator has a value
+    fn lookback(self, period: usize) -> usize {
+        match self {
+            Self::Rsi | Self::Atr | Self::Roc => period + 1,
+            _ => period,
+        }
+    }
+
+    /// Bars read for a settled value, beyond the lookback for EMAs and RSIs
+    fn history(self, period: usize) -> usize {
+        match self {
+            Self::Ema => indicators::EMA_WINDOW_PERIODS * period,
+            Self::Rsi => indicators::RSI_WINDOW_PERIODS * period + 1,
+            _ => self.lookback(period),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Eq,
+    Ne,
+    CrossesAbove,
+    CrossesBelow,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Field(Field),
+    /// Index into the variables given at evaluation
+    Var(usize),
+    Indicator { kind: Indicator, period: usize, field: Field },
+    Neg(Box<Self>),
+    Arith { op: ArithOp, lhs: Box<Self>, rhs: Box<Self> },
+    Compare { op: CompareOp, lhs: Box<Self>, rhs: Box<Self> },
+    Not(Box<Self>),
+    And(Box<Self>, Box<Self>),
+    Or(Box<Self>, Box<Self>),
+}
+
+impl Expr {
+    /// Numeric value on the latest bar; `None` while indicators are warming up
+    pub fn value(&self, bars: &[Candle]) -> Option<f64> {
+        self.value_with(bars, &[])
+    }
+
+    /// [`Expr::value`] with values for the variables; `None` for missing ones
+    pub fn value_with(&self, bars: &[Candle], vars: &[Option<f64>]) -> Option<f64> {
+        match self {
+            Self::Number(v) => Some(*v),
+            Self::Field(field) => bars.last().map(|b| field.of(b)),
+            Self::Var(i) => vars.get(*i).copied().flatten(),
+            Self::Indicator { kind, period, field } => {
+                let values = || bars.iter().map(|b| field.of(b)).collect::<Vec<f64>>();
+                let window = || {
+                    let values = values();
+                    (values.len() >= *period && *period > 0).then(|| values[values.len() - period..].to_vec())
+                };
+                match kind {
+                    Indicator::Sma => indicators::sma(&values(), *period),
+                    Indicator::Ema => indicators::ema(&values(), *period),
+                    Indicator::Rsi => indicators::rsi(&values(), *period),
+                    Indicator::Atr => indicators::atr(bars, *period),
+                    Indicator::Stddev => indicators::stddev(&values(), *period),
+                    Indicator::Roc => indicators::rate_of_change(&values(), *period),
+                    Indicator::Highest => window()?.into_iter().reduce(f64::max),
+                    Indicator::Lowest => window()?.into_iter().reduce(f64::min),
+                }
+            }
+            Self::Neg(e) => Some(-e.value_with(bars, vars)?),
+            Self::Arith { op, lhs, rhs } => {
+                let (a, b) = (lhs.value_with(bars, vars)?, rhs.value_with(bars, vars)?);
+                match op {
+                    ArithOp::Add => Some(a + b),
+                    ArithOp::Sub => Some(a - b),
+                    ArithOp::Mul => Some(a * b),
+                    ArithOp::Div => (b != 0.0).then(|| a / b),
+                }
+            }
+            Self::Compare { .. } | Self::Not(_) | Self::And(..) | Self::Or(..) => None,
+        }
+    }
+
+    /// Truth on the latest bar; comparisons with missing values are false
+    pub fn holds(&self, bars: &[Candle]) -> bool {
+        self.holds_with(bars, &[])
+    }
+
+    /// [`Expr::holds`] with values for the variables. They stay the same
+    /// on the previous bar, so crossing one means the other side moved.
+    pub fn holds_with(&self, bars: &[Candle], vars: &[Option<f64>]) -> bool {
+        match self {
+            Self::Compare { op, lhs, rhs } => {
+                let (Some(a), Some(b)) = (lhs.value_with(bars, vars), rhs.value_with(bars, vars)) else {
+                    return false;
+                };
+                let previous = || {
+                    let prev = &bars[..bars.len().saturating_sub(1)];
+                    Some((lhs.value_with(prev, vars)?, rhs.value_with(prev, vars)?))
+                };
+                match op {
+                    CompareOp::Lt => a < b,
+                    CompareOp::Le => a <= b,
+                    CompareOp::Gt => a > b,
+                    CompareOp::Ge => a >= b,
+                    CompareOp::Eq => (a - b).abs() < f64::EPSILON,
+                    CompareOp::Ne => (a - b).abs() >= f64::EPSILON,
+                    CompareOp::CrossesAbove => a > b && previous().is_some_and(|(pa, pb)| pa <= pb),
+                    CompareOp::CrossesBelow => a < b && previous().is_some_and(|(pa, pb)| pa >= pb),
+                }
+            }
+            Self::Not(e) => !e.holds_with(bars, vars),
+            Self::And(a, b) => a.holds_with(bars, vars) && b.holds_with(bars, vars),
+            Self::Or(a, b) => a.holds_with(bars, vars) || b.holds_with(bars, vars),
+            _ => false,
+        }
+    }
+
+    /// Bars of history needed before every indicator has a value
+    pub fn lookback(&self) -> usize {
+        self.bars(Indicator::lookback)
+    }
+
+    /// Bars of history every indicator reads once settled
+    pub fn history(&self) -> usize {
+        self.bars(Indicator::history)
+    }
+
+    fn bars(&self, indicator: fn(Indicator, usize) -> usize) -> usize {
+        match self {
+            Self::Number(_) | Self::Field(_) | Self::Var(_) => 1,
+            Self::Indicator { kind, period, .. } => indicator(*kind, *period),
+            Self::Neg(e) | Self::Not(e) => e.bars(indicator),
+            Self::Compare { op: CompareOp::CrossesAbove | CompareOp::CrossesBelow, lhs, rhs } => {
+                lhs.bars(indicator).max(rhs.bars(indicator)) + 1
+            }
+            Self::Arith { lhs, rhs, .. } | Self::Compare { lhs, rhs, .. } | Self::And(lhs, rhs) | Self::Or(lhs, rhs) => {
+                lhs.bars(indicator).max(rhs.bars(indicator))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+    Comma,
+    End,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Number(n) => write!(f, "number {n}"),
+            Self::Ident(s) => write!(f, "`{s}`"),
+            Self::Op(op) => write!(f, "`{op}`"),
+            Self::LParen => f.write_str("`(`"),
+            Self::RParen => f.write_str("`)`"),
+            Self::Comma => f.write_str("`,`"),
+            Self::End => f.write_str("end of expression"),
+        }
+    }
+}
+
+fn tokenize(src: &str) -> Result<Vec<(Token, usize)>, ExprError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        let token = if c.is_ascii_digit() || c == '.' {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            Token::Number(text.parse().map_err(|_err| ExprError::new(start, format!("invalid number `{text}`")))?)
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            Token::Ident(chars[start..i].iter().collect())
+        } else {
+            let next = chars.get(i + 1).copied();
+            let (token, len) = match (c, next) {
+                ('<', Some('=')) => (Token::Op("<="), 2),
+                ('>', Some('=')) => (Token::Op(">="), 2),
+                ('=', Some('=')) => (Token::Op("=="), 2),
+                ('!', Some('=')) => (Token::Op("!="), 2),
+                ('<', _) => (Token::Op("<"), 1),
+                ('>', _) => (Token::Op(">"), 1),
+                ('+', _) => (Token::Op("+"), 1),
+                ('-', _) => (Token::Op("-"), 1),
+                ('*', _) => (Token::Op("*"), 1),
+                ('/', _) => (Token::Op("/"), 1),
+                ('(', _) => (Token::LParen, 1),
+                (')', _) => (Token::RParen, 1),
+                (',', _) => (Token::Comma, 1),
+                _ => return Err(ExprError::new(start, format!("unexpected character `{c}`"))),
+            };
+            i += len;
+            token
+        };
+        tokens.push((token, start));
+    }
+    tokens.push((Token::End, chars.len()));
+    Ok(tokens)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Number,
+    Bool,
+}
+
+/// Expression with its type and where it starts
+type Typed = (Expr, Type, usize);
+
+/// Deepest nesting of parentheses, `NOT`s and `-`s accepted. Parsing and
+/// evaluating recurse once per level, so this keeps hostile input from
+/// overflowing the stack.
+const MAX_DEPTH: usize = 64;
+/// Longest expression accepted, which also bounds chains of `AND`s or `+`s
+const MAX_TOKENS: usize = 1024;
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    vars: &'a [&'a str],
+    /// Current nesting, see [`MAX_DEPTH`]
+    depth: usize,
+}
+
+impl Parser<'_> {
+    /// The whole of `src` as an expression of type `ty`
+    fn parse(src: &str, vars: &[&str], ty: Type, what: &str) -> Result<Expr, ExprError> {
+        let tokens = tokenize(src)?;
+        if tokens.len() > MAX_TOKENS {
+            return Err(ExprError::new(tokens[MAX_TOKENS].1, format!("longer than {MAX_TOKENS} tokens")));
+        }
+        let mut parser = Parser { tokens, pos: 0, vars, depth: 0 };
+        let typed = parser.or()?;
+        let (token, offset) = parser.next();
+        if token != Token::End {
+            return Err(ExprError::new(offset, format!("unexpected {token}")));
+        }
+        Parser::require(ty, &typed, what)?;
+        Ok(typed.0)
+    }
+
+    fn peek(&self) -> &Token {
+        &self.tokens[self.pos].0
+    }
+
+    fn next(&mut self) -> (Token, usize) {
+        let token = self.tokens[self.pos].clone();
+        if self.pos + 1 < self.tokens.len() {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn keyword(&self, word: &str) -> bool {
+        matches!(self.peek(), Token::Ident(s) if s.eq_ignore_ascii_case(word))
+    }
+
+    fn expect(&mut self, expected: &Token) -> Result<(), ExprError> {
+        let (token, offset) = self.next();
+        if &token == expected {
+            Ok(())
+        } else {
+            Err(ExprError::new(offset, format!("expected {expected}, found {token}")))
+        }
+    }
+
+    /// Parse with `f` one level deeper
+    fn nested(&mut self, offset: usize, f: impl FnOnce(&mut Self) -> Result<Typed, ExprError>) -> Result<Typed, ExprError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(ExprError::new(offset, format!("nested more than {MAX_DEPTH} levels deep")));
+        }
+        self.depth += 1;
+        let typed = f(self);
+        self.depth -= 1;
+        typed
+    }
+
+    fn require(ty: Type, typed: &Typed, what: &str) -> Result<(), ExprError> {
+        if typed.1 == ty {
+            return Ok(());
+        }
+        let expected = match ty {
+            Type::Number => "a number",
+            Type::Bool => "a condition",
+        };
+        Err(ExprError::new(typed.2, format!("{what} expects {expected}")))
+    }
+
+    fn or(&mut self) -> Result<Typed, ExprError> {
+        let mut lhs = self.and()?;
+        while self.keyword("OR") {
+            self.next();
+            let rhs = self.and()?;
+            Self::require(Type::Bool, &lhs, "OR")?;
+            Self::require(Type::Bool, &rhs, "OR")?;
+            lhs = (Expr::Or(Box::new(lhs.0), Box::new(rhs.0)), Type::Bool, lhs.2);
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Typed, ExprError> {
+        let mut lhs = self.not()?;
+        while self.keyword("AND") {
+            self.next();
+            let rhs = self.not()?;
+            Self::require(Type::Bool, &lhs, "AND")?;
+            Self::require(Type::Bool, &rhs, "AND")?;
+            lhs = (Expr::And(Box::new(lhs.0), Box::new(rhs.0)), Type::Bool, lhs.2);
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Typed, ExprError> {
+        if self.keyword("NOT") {
+            let (_, offset) = self.next();
+            let inner = self.nested(offset, Self::not)?;
+            Self::require(Type::Bool, &inner, "NOT")?;
+            return Ok((Expr::Not(Box::new(inner.0)), Type::Bool, offset));
+        }
+        self.compare()
+    }
+
+    fn compare(&mut self) -> Result<Typed, ExprError> {
+        let lhs = self.sum()?;
+        let op = match self.peek() {
+            Token::Op("<") => CompareOp::Lt,
+            Token::Op("<=") => CompareOp::Le,
+            Token::Op(">") => CompareOp::Gt,
+            Token::Op(">=") => CompareOp::Ge,
+            Token::Op("==") => CompareOp::Eq,
+            Token::Op("!=") => CompareOp::Ne,
+            _ if self.keyword("CROSSES") => {
+                self.next();
+                let (direction, offset) = self.next();
+                match direction {
+                    Token::Ident(s) if s.eq_ignore_ascii_case("ABOVE") => CompareOp::CrossesAbove,
+                    Token::Ident(s) if s.eq_ignore_ascii_case("BELOW") => CompareOp::CrossesBelow,
+                    other => return Err(ExprError::new(offset, format!("expected ABOVE or BELOW, found {other}"))),
+                }
+            }
+            _ => return Ok(lhs),
+        };
+        if !matches!(op, CompareOp::CrossesAbove | CompareOp::CrossesBelow) {
+            self.next();
+        }
+        let rhs = self.sum()?;
+        Self::require(Type::Number, &lhs, "comparison")?;
+        Self::require(Type::Number, &rhs, "comparison")?;
+        Ok((Expr::Compare { op, lhs: Box::new(lhs.0), rhs: Box::new(rhs.0) }, Type::Bool, lhs.2))
+    }
+
+    fn sum(&mut self) -> Result<Typed, ExprError> {
+        let mut lhs = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Token::Op("+") => ArithOp::Add,
+                Token::Op("-") => ArithOp::Sub,
+                _ => return Ok(lhs),
+            };
+            self.next();
+            lhs = Self::arith(op, lhs, self.product()?)?;
+        }
+    }
+
+    fn product(&mut self) -> Result<Typed, ExprError> {
+        let mut lhs = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Token::Op("*") => ArithOp::Mul,
+                Token::Op("/") => ArithOp::Div,
+                _ => return Ok(lhs),
+            };
+            self.next();
+            lhs = Self::arith(op, lhs, self.unary()?)?;
+        }
+    }
+
+    fn arith(op: ArithOp, lhs: Typed, rhs: Typed) -> Result<Typed, ExprError> {
+        Self::require(Type::Number, &lhs, "arithmetic")?;
+        Self::require(Type::Number, &rhs, "arithmetic")?;
+        Ok((Expr::Arith { op, lhs: Box::new(lhs.0), rhs: Box::new(rhs.0) }, Type::Number, lhs.2))
+    }
+
+    fn unary(&mut self) -> Result<Typed, ExprError> {
+        let (token, offset) = self.next();
+        match token {
+            Token::Op("-") => {
+                let inner = self.nested(offset, Self::unary)?;
+                Self::require(Type::Number, &inner, "`-`")?;
+                Ok((Expr::Neg(Box::new(inner.0)), Type::Number, offset))
+            }
+            Token::Number(n) => Ok((Expr::Number(n), Type::Number, offset)),
+            Token::LParen => {
+                let inner = self.nested(offset, Self::or)?;
+                self.expect(&Token::RParen)?;
+                Ok((inner.0, inner.1, offset))
+            }
+            Token::Ident(name) => {
+                if let Some(field) = Field::parse(&name) {
+                    return Ok((Expr::Field(field), Type::Number, offset));
+                }
+                if let Some(i) = self.vars.iter().position(|v| v.eq_ignore_ascii_case(&name)) {
+                    return Ok((Expr::Var(i), Type::Number, offset));
+                }
+                let kind = Indicator::parse(&name)
+                    .ok_or_else(|| ExprError::new(offset, format!("unknown field or indicator `{name}`")))?;
+                self.indicator(kind, &name, offset)
+            }
+            other => Err(ExprError::new(offset, format!("expected a value, found {other}"))),
+        }
+    }
+
+    fn indicator(&mut self, kind: Indicator, name: &str, offset: usize) -> Result<Typed, ExprError> {
+        self.expect(&Token::LParen)?;
+        let (token, at) = self.next();
+        let period = match token {
+            Token::Number(n) if n >= 1.0 && n.fract() == 0.0 && n <= 10_000.0 => n as usize,
+            other => return Err(ExprError::new(at, format!("{name} needs a whole period of at least 1, found {other}"))),
+        };
+        let mut field = kind.default_field();
+        if *self.peek() == Token::Comma {
+            self.next();
+            let (token, at) = self.next();
+            field = match &token {
+                Token::Ident(s) if kind != Indicator::Atr => Field::parse(s),
+                _ => None,
+            }
+            .ok_or_else(|| ExprError::new(at, format!("expected a field for {name}, found {token}")))?;
+        }
+        self.expect(&Token::RParen)?;
+        Ok((Expr::Indicator { kind, period, field }, Type::Number, offset))
+    }
+}
+
+/// A parsed, type-checked condition
+#[derive(Debug, Clone, PartialEq)]
+pub struct Condition {
+    source: String,
+    expr: Expr,
+}
+
+impl Condition {
+    pub fn parse(src: &str) -> Result<Self, ExprError> {
+        Self::parse_with(src, &[])
+    }
+
+    /// Also accepts the names in `vars`, whose values are given in the same
+    /// order to [`Condition::holds_with`]
+    pub fn parse_with(src: &str, vars: &[&str]) -> Result<Self, ExprError> {
+        let expr = Parser::parse(src, vars, Type::Bool, "a rule")?;
+        Ok(Self { source: src.to_owned(), expr })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    pub fn holds(&self, bars: &[Candle]) -> bool {
+        self.expr.holds(bars)
+    }
+
+    pub fn holds_with(&self, bars: &[Candle], vars: &[Option<f64>]) -> bool {
+        self.expr.holds_with(bars, vars)
+    }
+
+    pub fn lookback(&self) -> usize {
+        self.expr.lookback()
+    }
+
+    pub fn history(&self) -> usize {
+        self.expr.history()
+    }
+}
+
+/// A parsed numeric expression, such as `close / SMA(50)`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula {
+    source: String,
+    expr: Expr,
+}
+
+impl Formula {
+    /// Like [`Condition::parse_with`], for a number instead of a condition
+    pub fn parse_with(src: &str, vars: &[&str]) -> Result<Self, ExprError> {
+        let expr = Parser::parse(src, vars, Type::Number, "a formula")?;
+        Ok(Self { source: src.to_owned(), expr })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn value_with(&self, bars: &[Candle], vars: &[Option<f64>]) -> Option<f64> {
+        self.expr.value_with(bars, vars)
+    }
+
+    pub fn lookback(&self) -> usize {
+        self.expr.lookback()
+    }
+}
@@ -0,0 +1,482 @@
+//! Strategies written in [Rhai](https://rhai.rs), enabled by the `scripting` feature.
+//!
+//! A script defines any of these functions; each is optional:
+//!
+//! ```text
+//! fn on_start(ctx) {}
+//! fn on_bar(ctx, bar) {}          // bar.time (ms), bar.open, bar.high, bar.low, bar.close, bar.volume
+//! fn on_timer(ctx, at) {}         // at: ms since the epoch
+//! fn on_order_update(ctx, report) {} // report.order_id, .status, .filled_qty, .fill_price, .leaves_qty, .reason
+//! fn on_end(ctx) {}
+//! ```
+//!
+//! `ctx` is the script's view of [`StrategyContext`]:
+//!
+//! - read: `ctx.symbol`, `ctx.now`, `ctx.cash`, `ctx.position(sym)`, `ctx.open_orders()`,
+//!   `ctx.closes(sym)`, `ctx.last_price(sym)`
+//! - indicators: `ctx.sma(sym, n)`, `ctx.ema(sym, n)`, `ctx.rsi(sym, n)`, `ctx.stddev(sym, n)`,
+//!   `ctx.atr(sym, n)`, `ctx.volatility(sym, n)` (`()` until there is enough history)
+//! - act: `ctx.buy(qty)`, `ctx.sell(qty)`, `ctx.submit(sym, "buy"|"sell", qty)`,
+//!   `ctx.limit(sym, side, qty, price)`, `ctx.cancel(id)`, `ctx.cancel_all()`,
+//!   `ctx.target_weight(sym, w)`, `ctx.flatten(sym)`, `ctx.schedule(ms)`, `ctx.log(msg)`
+//! - `ctx.state` is a map that survives between calls
+//!
+//! Scripts run sandboxed: no modules, no `eval`, and an operation budget, wall-clock
+//! budget and size limits per call, including on what `ctx.state` keeps ([`ScriptLimits`]). A script that errors or runs
+//! out of budget is disabled and the error is logged.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{Duration, Instant};
+use chrono::DateTime;
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Array, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope, AST};
+use thiserror::Error;
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::time::Timestamp;
+use dnn_core::{ExecutionReport, ExecutionStatus, Order, OrderSide};
+use crate::indicators;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{Strategy, StrategyContext};
+
+type ScriptResult<T> = Result<T, Box<EvalAltResult>>;
+
+/// Resources a script may use per hook call
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScriptLimits {
+    /// Rhai operations (a rough measure of CPU)
+    pub max_operations: u64,
+    pub max_duration: Duration,
+    pub max_call_depth: usize,
+    pub max_variables: usize,
+    /// Bytes per string
+    pub max_string_size: usize,
+    /// Elements per array or map
+    pub max_collection_size: usize,
+    /// Bars kept per symbol for indicators
+    pub max_history: usize,
+    /// Rough bytes `ctx.state` may hold between calls
+    pub max_state_size: usize,
+}
+
+impl Default for ScriptLimits {
+    fn default() -> Self {
+        Self {
+            max_operations: 1_000_000,
+            max_duration: Duration::from_millis(250),
+            max_call_depth: 32,
+            max_variables: 256,
+            max_string_size: 64 * 1024,
+            max_collection_size: 10_000,
+            max_history: 1_000,
+            max_state_size: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ScriptError {
+    #[error("Script failed to compile: {0}")]
+    Compile(String),
+    #[error("Script error in {hook}: {message}")]
+    Runtime { hook: String, message: String },
+}
+
+#[derive(Debug, Clone)]
+enum Action {
+    Submit { symbol: String, side: OrderSide, qty: f64, price: Option<f64> },
+    Cancel(u64),
+    CancelAll,
+    TargetWeight { symbol: String, weight: f64 },
+    Flatten(String),
+}
+
+/// What the script sees and asks for during one call
+#[derive(Debug, Default)]
+struct State {
+    now: Option<Timestamp>,
+    symbol: String,
+    portfolio: Option<Portfolio>,
+    open_orders: Vec<Order>,
+    history: HashMap<String, Vec<Candle>>,
+    next_order_id: u64,
+    actions: Vec<Action>,
+    logs: Vec<String>,
+    timers: Vec<Timestamp>,
+    state: Map,
+}
+
+/// The `ctx` handed to script functions
+#[derive(Debug, Clone, Default)]
+pub struct ScriptContext(Arc<Mutex<State>>);
+
+impl ScriptContext {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn closes(&self, symbol: &str) -> Vec<f64> {
+        self.lock().history.get(symbol).map_or_else(Vec::new, |bars| bars.iter().map(|c| c.close).collect())
+    }
+
+    fn push(&self, action: Action) {
+        self.lock().actions.push(action);
+    }
+
+    fn submit(&self, symbol: &str, side: OrderSide, qty: f64, price: Option<f64>) -> i64 {
+        let mut state = self.lock();
+        let id = state.next_order_id;
+        state.next_order_id += 1;
+        state.actions.push(Action::Submit { symbol: symbol.to_owned(), side, qty, price });
+        i64::try_from(id).unwrap_or(i64::MAX)
+    }
+}
+
+fn number(value: &Dynamic) -> ScriptResult<f64> {
+    value
+        .as_float()
+        .or_else(|_| value.as_int().map(|v| v as f64))
+        .map_err(|t| format!("expected a number, got {t}").into())
+}
+
+fn period(n: i64) -> usize {
+    usize::try_from(n).unwrap_or(0)
+}
+
+fn side(side: &str) -> ScriptResult<OrderSide> {
+    match side {
+        "buy" => Ok(OrderSide::Buy),
+        "sell" => Ok(OrderSide::Sell),
+        other => Err(format!("side must be \"buy\" or \"sell\", got {other:?}").into()),
+    }
+}
+
+/// Rough size of a value in bytes: strings count their length, containers
+/// their contents and everything else a word
+fn data_size(value: &Dynamic) -> usize {
+    if let Some(map) = value.read_lock::<Map>() {
+        return map.iter().map(|(k, v)| k.len() + data_size(v)).sum();
+    }
+    if let Some(array) = value.read_lock::<Array>() {
+        return array.iter().map(data_size).sum();
+    }
+    if let Ok(string) = value.as_immutable_string_ref() {
+        return string.len();
+    }
+    8
+}
+
+fn optional(value: Option<f64>) -> Dynamic {
+    value.map_or(Dynamic::UNIT, Dynamic::from_float)
+}
+
+fn candle_map(bar: &Candle) -> Map {
+    let mut map = Map::new();
+    map.insert("time".into(), bar.timestamp.timestamp_millis().into());
+    map.insert("open".into(), bar.open.into());
+    map.insert("high".into(), bar.high.into());
+    map.insert("low".into(), bar.low.into());
+    map.insert("close".into(), bar.close.into());
+    map.insert("volume".into(), bar.volume.into());
+    map
+}
+
+fn report_map(report: &ExecutionReport) -> Map {
+    let (status, reason) = match &report.status {
+        ExecutionStatus::New => ("new", None),
+        ExecutionStatus::PartiallyFilled => ("partially_filled", None),
+        ExecutionStatus::Filled => ("filled", None),
+        ExecutionStatus::Cancelled => ("cancelled", None),
+        ExecutionStatus::Rejected { reason } => ("rejected", Some(reason.clone())),
+    };
+    let mut map = Map::new();
+    map.insert("order_id".into(), i64::try_from(report.order_id).unwrap_or(i64::MAX).into());
+    map.insert("status".into(), status.into());
+    map.insert("reason".into(), reason.map_or(Dynamic::UNIT, Into::into));
+    map.insert("filled_qty".into(), report.filled_qty.into());
+    map.insert("fill_price".into(), report.fill_price.into());
+    map.insert("leaves_qty".into(), report.leaves_qty.into());
+    map.insert("time".into(), report.ts.timestamp_millis().into());
+    map
+}
+
+fn order_map(order: &Order) -> Map {
+    let mut map = Map::new();
+    map.insert("id".into(), i64::try_from(order.id).unwrap_or(i64::MAX).into());
+    map.insert("symbol".into(), order.symbol.clone().into());
+    map.insert("side".into(), if order.side == OrderSide::Buy { "buy" } else { "sell" }.into());
+    map.insert("qty".into(), order.qty.into());
+    map.insert("price".into(), optional(order.price));
+    map
+}
+
+/// The sandboxed engine with the `ctx` API registered
+fn build_engine(limits: &ScriptLimits, api: &ScriptContext, deadline: &Arc<Mutex<Option<Instant>>>) -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_modules(0)
+        .set_max_operations(limits.max_operations)
+        .set_max_call_levels(limits.max_call_depth)
+        .set_max_variables(limits.max_variables)
+        .set_max_string_size(limits.max_string_size)
+        .set_max_array_size(limits.max_collection_size)
+        .set_max_map_size(limits.max_collection_size)
+        .disable_symbol("eval");
+
+    let deadline = Arc::clone(deadline);
+    engine.on_progress(move |_| {
+        let expired = deadline
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .is_some_and(|d| Instant::now() > d);
+        expired.then(|| "time limit exceeded".into())
+    });
+    let (print, debug) = (api.clone(), api.clone());
+    engine.on_print(move |s| print.lock().logs.push(s.to_owned()));
+    engine.on_debug(move |s, _, _| debug.lock().logs.push(s.to_owned()));
+
+    engine
+        .register_type_with_name::<ScriptContext>("Context")
+        .register_get("symbol", |ctx: &mut ScriptContext| ctx.lock().symbol.clone())
+        .register_get("now", |ctx: &mut ScriptContext| ctx.lock().now.map_or(0, |t| t.timestamp_millis()))
+        .register_get("cash", |ctx: &mut ScriptContext| {
+            ctx.lock().portfolio.as_ref().map_or(0.0, |p| p.cash_in(&p.base_currency))
+        })
+        .register_get_set(
+            "state",
+            |ctx: &mut ScriptContext| ctx.lock().state.clone(),
+            |ctx: &mut ScriptContext, map: Map| ctx.lock().state = map,
+        )
+        .register_fn("position", |ctx: &mut ScriptContext, symbol: &str| {
+            ctx.lock().portfolio.as_ref().and_then(|p| p.positions.get(symbol)).map_or(0.0, |p| p.qty)
+        })
+        .register_fn("open_orders", |ctx: &mut ScriptContext| {
+            ctx.lock().open_orders.iter().map(|o| Dynamic::from_map(order_map(o))).collect::<Array>()
+        })
+        .register_fn("closes", |ctx: &mut ScriptContext, symbol: &str| {
+            ctx.closes(symbol).into_iter().map(Dynamic::from_float).collect::<Array>()
+        })
+        .register_fn("last_price", |ctx: &mut ScriptContext, symbol: &str| optional(ctx.closes(symbol).last().copied()))
+        .register_fn("sma", |ctx: &mut ScriptContext, symbol: &str, n: i64| {
+            optional(indicators::sma(&ctx.closes(symbol), period(n)))
+        })
+        .register_fn("ema", |ctx: &mut ScriptContext, symbol: &str, n: i64| {
+            optional(indicators::ema(&ctx.closes(symbol), period(n)))
+        })
+        .register_fn("rsi", |ctx: &mut ScriptContext, symbol: &str, n: i64| {
+            optional(indicators::rsi(&ctx.closes(symbol), period(n)))
+        })
+        .register_fn("stddev", |ctx: &mut ScriptContext, symbol: &str, n: i64| {
+            optional(indicators::stddev(&ctx.closes(symbol), period(n)))
+        })
+        .register_fn("volatility", |ctx: &mut ScriptContext, symbol: &str, n: i64| {
+            optional(indicators::volatility(&ctx.closes(symbol), period(n)))
+        })
+        .register_fn("atr", |ctx: &mut ScriptContext, symbol: &str, n: i64| {
+            let state = ctx.lock();
+            optional(state.history.get(symbol).and_then(|bars| indicators::atr(bars, period(n))))
+        })
+        .register_fn("buy", |ctx: &mut ScriptContext, qty: Dynamic| -> ScriptResult<i64> {
+            let symbol = ctx.lock().symbol.clone();
+            Ok(ctx.submit(&symbol, OrderSide::Buy, number(&qty)?, None))
+        })
+        .register_fn("sell", |ctx: &mut ScriptContext, qty: Dynamic| -> ScriptResult<i64> {
+            let symbol = ctx.lock().symbol.clone();
+            Ok(ctx.submit(&symbol, OrderSide::Sell, number(&qty)?, None))
+        })
+        .register_fn("submit", |ctx: &mut ScriptContext, symbol: &str, s: &str, qty: Dynamic| -> ScriptResult<i64> {
+            Ok(ctx.submit(symbol, side(s)?, number(&qty)?, None))
+        })
+        .register_fn("limit", |ctx: &mut ScriptContext, symbol: &str, s: &str, qty: Dynamic, price: Dynamic| -> ScriptResult<i64> {
+            Ok(ctx.submit(symbol, side(s)?, number(&qty)?, Some(number(&price)?)))
+        })
+        .register_fn("cancel", |ctx: &mut ScriptContext, id: i64| -> ScriptResult<()> {
+            let id = u64::try_from(id).map_err(|_err| format!("invalid order id {id}"))?;
+            ctx.push(Action::Cancel(id));
+            Ok(())
+        })
+        .register_fn("cancel_all", |ctx: &mut ScriptContext| ctx.push(Action::CancelAll))
+        .register_fn("target_weight", |ctx: &mut ScriptContext, symbol: &str, weight: Dynamic| -> ScriptResult<()> {
+            ctx.push(Action::TargetWeight { symbol: symbol.to_owned(), weight: number(&weight)? });
+            Ok(())
+        })
+        .register_fn("flatten", |ctx: &mut ScriptContext, symbol: &str| ctx.push(Action::Flatten(symbol.to_owned())))
+        .register_fn("schedule", |ctx: &mut ScriptContext, at: i64| -> ScriptResult<()> {
+            let at = DateTime::from_timestamp_millis(at).ok_or("timer out of range")?;
+            ctx.lock().timers.push(at);
+            Ok(())
+        })
+        .register_fn("log", |ctx: &mut ScriptContext, message: &str| ctx.lock().logs.push(message.to_owned()));
+    engine
+}
+
+/// A [`Strategy`] implemented by a Rhai script
+pub struct ScriptStrategy {
+    source: String,
+    limits: ScriptLimits,
+    engine: Engine,
+    ast: AST,
+    hooks: HashSet<String>,
+    api: ScriptContext,
+    deadline: Arc<Mutex<Option<Instant>>>,
+    error: Option<ScriptError>,
+}
+
+impl ScriptStrategy {
+    pub fn new(source: &str) -> Result<Self, ScriptError> {
+        Self::with_limits(source, ScriptLimits::default())
+    }
+
+    pub fn with_limits(source: &str, limits: ScriptLimits) -> Result<Self, ScriptError> {
+        let api = ScriptContext::default();
+        let deadline = Arc::new(Mutex::new(None));
+        let engine = build_engine(&limits, &api, &deadline);
+        let ast = engine.compile(source).map_err(|e| ScriptError::Compile(e.to_string()))?;
+        let hooks = ast.iter_functions().map(|f| f.name.to_owned()).collect();
+        Ok(Self {
+            source: source.to_owned(),
+            limits,
+            engine,
+            ast,
+            hooks,
+            api,
+            deadline,
+            error: None,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn limits(&self) -> &ScriptLimits {
+        &self.limits
+    }
+
+    /// The error that disabled the script, if any
+    pub fn error(&self) -> Option<&ScriptError> {
+        self.error.as_ref()
+    }
+
+    /// Call a script function if it is defined, then carry out what it asked for
+    fn call(&mut self, ctx: &mut StrategyContext, hook: &str, args: impl FuncArgs) {
+        if self.error.is_some() || !self.hooks.contains(hook) {
+            return;
+        }
+
+        {
+            let mut state = self.api.lock();
+            state.now = Some(ctx.now());
+            ctx.symbol().clone_into(&mut state.symbol);
+            state.portfolio = Some(ctx.portfolio().clone());
+            state.open_orders = ctx.open_orders().to_vec();
+            state.next_order_id = ctx.next_order_id();
+        }
+
+        *self.deadline.lock().unwrap_or_else(PoisonError::into_inner) = Some(Instant::now() + self.limits.max_duration);
+        let options = CallFnOptions::new().eval_ast(false);
+        let result = self.engine.call_fn_with_options::<Dynamic>(options, &mut Scope::new(), &self.ast, hook, args);
+        *self.deadline.lock().unwrap_or_else(PoisonError::into_inner) = None;
+
+        let mut state = self.api.lock();
+        for line in state.logs.drain(..) {
+            ctx.log(line);
+        }
+        let actions = std::mem::take(&mut state.actions);
+        let timers = std::mem::take(&mut state.timers);
+        let state_size = state.state.iter().map(|(k, v)| k.len() + data_size(v)).sum::<usize>();
+        drop(state);
+
+        let result = result.map_err(|e| e.to_string()).and_then(|_| {
+            if state_size > self.limits.max_state_size {
+                return Err(format!("ctx.state is larger than {} bytes", self.limits.max_state_size));
+            }
+            Ok(())
+        });
+        if let Err(message) = result {
+            let error = ScriptError::Runtime { hook: hook.to_owned(), message };
+            ctx.log(format!("{error}; script disabled"));
+            self.error = Some(error);
+            return;
+        }
+
+        for at in timers {
+            ctx.schedule(at);
+        }
+        for action in actions {
+            match action {
+                Action::Submit { symbol, side, qty, price } => {
+                    ctx.submit(&symbol, side, qty, price);
+                }
+                Action::Cancel(id) => ctx.cancel(id),
+                Action::CancelAll => ctx.cancel_all(),
+                Action::TargetWeight { symbol, weight } => ctx.target_weight(&symbol, weight),
+                Action::Flatten(symbol) => ctx.flatten(&symbol),
+            }
+        }
+    }
+}
+
+impl Strategy for ScriptStrategy {
+    fn on_start(&mut self, ctx: &mut StrategyContext) {
+        let api = self.api.clone();
+        self.call(ctx, "on_start", (api,));
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        {
+            let mut state = self.api.lock();
+            let history = state.history.entry(ctx.symbol().to_owned()).or_default();
+            history.push(bar.clone());
+            if history.len() > self.limits.max_history {
+                history.remove(0);
+            }
+        }
+        let api = self.api.clone();
+        self.call(ctx, "on_bar", (api, candle_map(bar)));
+    }
+
+    fn on_timer(&mut self, ctx: &mut StrategyContext, at: Timestamp) {
+        let api = self.api.clone();
+        self.call(ctx, "on_timer", (api, at.timestamp_millis()));
+    }
+
+    fn on_order_update(&mut self, ctx: &mut StrategyContext, report: &ExecutionReport) {
+        let api = self.api.clone();
+        self.call(ctx, "on_order_update", (api, report_map(report)));
+    }
+
+    fn on_end(&mut self, ctx: &mut StrategyContext) {
+        let api = self.api.clone();
+        self.call(ctx, "on_end", (api,));
+    }
+}
+
+impl Parameterized for ScriptStrategy {
+    fn schema() -> StrategySchema {
+        let defaults = ScriptLimits::default();
+        StrategySchema::new("script", "User strategy written in Rhai")
+            .param(ParamSpec::new("source", "").describe("Rhai source defining on_bar and other hooks"))
+            .param(
+                ParamSpec::new("max_operations", i64::try_from(defaults.max_operations).unwrap_or(i64::MAX))
+                    .range(1_000.0, 100_000_000.0)
+                    .describe("Operation budget per hook call"),
+            )
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        let limits = ScriptLimits {
+            max_operations: u64::try_from(params.int("max_operations")?).unwrap_or_default(),
+            ..ScriptLimits::default()
+        };
+        Self::with_limits(params.string("source")?, limits).map_err(|e| ParamError::Invalid(e.to_string()))
+    }
+
+    fn params(&self) -> Params {
+        Params::default()
+            .set("source", self.source.as_str())
+            .set("max_operations", i64::try_from(self.limits.max_operations).unwrap_or(i64::MAX))
+    }
+}
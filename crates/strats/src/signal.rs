@@ -0,0 +1,257 @@
+//! Signal layer: alphas score symbols, combinators merge scores, and a
+//! [`PortfolioConstruction`] turns them into target weights that
+//! [`SignalStrategy`] trades towards.
+//!
+//! Scores run from -1 (strong sell) to 1 (strong buy); 0 is neutral.
+
+pub mod alpha;
+pub mod construction;
+pub mod ensemble;
+
+use std::collections::BTreeMap;
+use dnn_core::market::Candle;
+use dnn_core::Signal;
+use crate::{Strategy, StrategyContext};
+
+pub use construction::{EqualWeight, PortfolioConstruction, ScoreWeighted, SignalWeight};
+pub use ensemble::Ensemble;
+
+/// Scores a symbol from market history.
+///
+/// `None` means no opinion yet, typically while an indicator warms up.
+pub trait Alpha: Send {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64>;
+}
+
+impl<F> Alpha for F
+where
+    F: FnMut(&StrategyContext, &str) -> Option<f64> + Send,
+{
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        self(ctx, symbol)
+    }
+}
+
+/// Buy above `threshold`, sell below `-threshold`, hold in between
+pub fn to_signal(score: f64, threshold: f64) -> Signal {
+    if score > threshold {
+        Signal::Buy
+    } else if score < -threshold {
+        Signal::Sell
+    } else {
+        Signal::Hold
+    }
+}
+
+/// Score of a discrete signal: 1, -1 or 0
+pub fn signal_score(signal: Signal) -> f64 {
+    match signal {
+        Signal::Buy => 1.0,
+        Signal::Sell => -1.0,
+        Signal::Hold => 0.0,
+    }
+}
+
+fn scores(members: &mut [Box<dyn Alpha>], ctx: &StrategyContext, symbol: &str) -> Vec<Option<f64>> {
+    members.iter_mut().map(|m| m.score(ctx, symbol)).collect()
+}
+
+/// Long only when every member is long, short only when every member is
+/// short. The score is the weakest member's, so the result is as strong as
+/// the least convinced alpha.
+pub struct All {
+    pub members: Vec<Box<dyn Alpha>>,
+}
+
+impl All {
+    pub fn new(members: Vec<Box<dyn Alpha>>) -> Self {
+        Self { members }
+    }
+}
+
+impl Alpha for All {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let scores = scores(&mut self.members, ctx, symbol).into_iter().collect::<Option<Vec<f64>>>()?;
+        if scores.is_empty() {
+            None
+        } else if scores.iter().all(|s| *s > 0.0) {
+            scores.into_iter().reduce(f64::min)
+        } else if scores.iter().all(|s| *s < 0.0) {
+            scores.into_iter().reduce(f64::max)
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+/// Long when any member is long and none is short (and vice versa), taking
+/// the strongest score. Conflicting members cancel out to 0.
+pub struct Any {
+    pub members: Vec<Box<dyn Alpha>>,
+}
+
+impl Any {
+    pub fn new(members: Vec<Box<dyn Alpha>>) -> Self {
+        Self { members }
+    }
+}
+
+impl Alpha for Any {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let scores: Vec<f64> = scores(&mut self.members, ctx, symbol).into_iter().flatten().collect();
+        if scores.is_empty() {
+            return None;
+        }
+        let long = scores.iter().copied().filter(|s| *s > 0.0).reduce(f64::max);
+        let short = scores.iter().copied().filter(|s| *s < 0.0).reduce(f64::min);
+        match (long, short) {
+            (Some(long), None) => Some(long),
+            (None, Some(short)) => Some(short),
+            _ => Some(0.0),
+        }
+    }
+}
+
+/// Majority vote over member signals. Scores the net fraction of members
+/// voting long, once the winning side reaches `quorum` of the members with
+/// an opinion; 0 otherwise.
+pub struct Vote {
+    pub members: Vec<Box<dyn Alpha>>,
+    /// Fraction of voters the winning side needs (0.5 = simple majority)
+    pub quorum: f64,
+    /// Scores within +/- this count as abstaining
+    pub threshold: f64,
+}
+
+impl Vote {
+    pub fn new(members: Vec<Box<dyn Alpha>>) -> Self {
+        Self { members, quorum: 0.5, threshold: 0.0 }
+    }
+
+    pub fn with_quorum(mut self, quorum: f64) -> Self {
+        self.quorum = quorum;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl Alpha for Vote {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let signals: Vec<Signal> = scores(&mut self.members, ctx, symbol)
+            .into_iter()
+            .flatten()
+            .map(|s| to_signal(s, self.threshold))
+            .collect();
+        if signals.is_empty() {
+            return None;
+        }
+        let voters = signals.len() as f64;
+        let buys = signals.iter().filter(|s| **s == Signal::Buy).count() as f64;
+        let sells = signals.iter().filter(|s| **s == Signal::Sell).count() as f64;
+        if buys.max(sells) / voters >= self.quorum && buys != sells {
+            Some((buys - sells) / voters)
+        } else {
+            Some(0.0)
+        }
+    }
+}
+
+/// Weighted average of member scores, over members with an opinion
+#[derive(Default)]
+pub struct WeightedAverage {
+    pub members: Vec<(f64, Box<dyn Alpha>)>,
+}
+
+impl WeightedAverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(mut self, weight: f64, alpha: impl Alpha + 'static) -> Self {
+        self.members.push((weight, Box::new(alpha)));
+        self
+    }
+}
+
+impl Alpha for WeightedAverage {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let (mut total, mut weights) = (0.0, 0.0);
+        for (weight, alpha) in &mut self.members {
+            if let Some(score) = alpha.score(ctx, symbol) {
+                total += *weight * score;
+                weights += weight.abs();
+            }
+        }
+        (weights > 0.0).then(|| total / weights)
+    }
+}
+
+/// Runs an alpha over a set of symbols and trades towards the target weights
+/// from a [`PortfolioConstruction`].
+///
+/// Rebalances once every symbol has a bar at the current time, and only
+/// trades a symbol when its weight is off target by more than `band`.
+pub struct SignalStrategy {
+    /// Symbols to score; empty trades the symbol of each bar
+    pub symbols: Vec<String>,
+    pub alpha: Box<dyn Alpha>,
+    pub construction: Box<dyn PortfolioConstruction>,
+    /// Tolerated drift from the target weight before rebalancing
+    pub band: f64,
+}
+
+impl SignalStrategy {
+    pub fn new(alpha: impl Alpha + 'static, construction: impl PortfolioConstruction + 'static) -> Self {
+        Self {
+            symbols: Vec::new(),
+            alpha: Box::new(alpha),
+            construction: Box::new(construction),
+            band: 0.05,
+        }
+    }
+
+    pub fn with_symbols(mut self, symbols: &[&str]) -> Self {
+        self.symbols = symbols.iter().map(|s| (*s).to_owned()).collect();
+        self
+    }
+
+    pub fn with_band(mut self, band: f64) -> Self {
+        self.band = band;
+        self
+    }
+}
+
+impl Strategy for SignalStrategy {
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let universe = if self.symbols.is_empty() {
+            vec![ctx.symbol().to_owned()]
+        } else {
+            self.symbols.clone()
+        };
+        let now = ctx.now();
+        if !universe.iter().all(|s| ctx.bars(s).last().is_some_and(|c| c.timestamp == now)) {
+            return;
+        }
+
+        let scores: BTreeMap<String, f64> = universe
+            .into_iter()
+            .filter_map(|s| Some((s.clone(), self.alpha.score(ctx, &s)?)))
+            .collect();
+        if scores.is_empty() {
+            return;
+        }
+
+        for (symbol, target) in self.construction.targets(&scores) {
+            let current = ctx.weight(&symbol);
+            let flatten = target == 0.0 && ctx.position_qty(&symbol) != 0.0;
+            if flatten || (target - current).abs() > self.band {
+                ctx.log(format!("{symbol} target weight {target:.3}"));
+                ctx.target_weight(&symbol, target);
+            }
+        }
+    }
+}
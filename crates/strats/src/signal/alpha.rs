@@ -0,0 +1,126 @@
+//! Built-in alphas, mirroring the reference strategies as scores
+
+use crate::indicators::{bollinger, rate_of_change};
+use crate::ma_cross::MovingAverage;
+use crate::signal::Alpha;
+use crate::StrategyContext;
+
+/// 1 while the fast average is above the slow one, -1 while below
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MaCrossAlpha {
+    pub fast: usize,
+    pub slow: usize,
+    pub average: MovingAverage,
+}
+
+impl MaCrossAlpha {
+    pub fn new(fast: usize, slow: usize) -> Self {
+        Self { fast, slow, average: MovingAverage::Sma }
+    }
+
+    pub fn with_average(mut self, average: MovingAverage) -> Self {
+        self.average = average;
+        self
+    }
+}
+
+impl Alpha for MaCrossAlpha {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let (fast, slow) = match self.average {
+            MovingAverage::Sma => (ctx.sma(symbol, self.fast)?, ctx.sma(symbol, self.slow)?),
+            MovingAverage::Ema => (ctx.ema(symbol, self.fast)?, ctx.ema(symbol, self.slow)?),
+        };
+        Some(if fast > slow {
+            1.0
+        } else if fast < slow {
+            -1.0
+        } else {
+            0.0
+        })
+    }
+}
+
+/// 1 when RSI is oversold, -1 when overbought, 0 in between
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RsiAlpha {
+    pub period: usize,
+    pub oversold: f64,
+    pub overbought: f64,
+}
+
+impl RsiAlpha {
+    pub fn new(period: usize) -> Self {
+        Self { period, oversold: 30.0, overbought: 70.0 }
+    }
+
+    pub fn with_levels(mut self, oversold: f64, overbought: f64) -> Self {
+        self.oversold = oversold;
+        self.overbought = overbought;
+        self
+    }
+}
+
+impl Alpha for RsiAlpha {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let rsi = ctx.rsi(symbol, self.period)?;
+        Some(if rsi < self.oversold {
+            1.0
+        } else if rsi > self.overbought {
+            -1.0
+        } else {
+            0.0
+        })
+    }
+}
+
+/// Return over `lookback` bars, divided by `scale` and clamped to [-1, 1]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MomentumAlpha {
+    pub lookback: usize,
+    /// Return that counts as full conviction
+    pub scale: f64,
+}
+
+impl MomentumAlpha {
+    pub fn new(lookback: usize) -> Self {
+        Self { lookback, scale: 0.1 }
+    }
+
+    pub fn with_scale(mut self, scale: f64) -> Self {
+        self.scale = scale;
+        self
+    }
+}
+
+impl Alpha for MomentumAlpha {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let ret = rate_of_change(&ctx.closes(symbol), self.lookback)?;
+        (self.scale > 0.0).then(|| (ret / self.scale).clamp(-1.0, 1.0))
+    }
+}
+
+/// Mean reversion within Bollinger Bands: -1 at the upper band, 1 at the
+/// lower band, scaled linearly in between and clamped outside
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BollingerAlpha {
+    pub period: usize,
+    pub k: f64,
+}
+
+impl BollingerAlpha {
+    pub fn new(period: usize, k: f64) -> Self {
+        Self { period, k }
+    }
+}
+
+impl Alpha for BollingerAlpha {
+    fn score(&mut self, ctx: &StrategyContext, symbol: &str) -> Option<f64> {
+        let close = ctx.last_price(symbol)?;
+        let bands = bollinger(&ctx.closes(symbol), self.period, self.k)?;
+        let half_width = bands.upper - bands.middle;
+        if half_width <= 0.0 {
+            return Some(0.0);
+        }
+        Some((-(close - bands.middle) / half_width).clamp(-1.0, 1.0))
+    }
+}
@@ -0,0 +1,130 @@
+//! Portfolio construction: from per-symbol scores to target weights
+
+use std::collections::BTreeMap;
+use dnn_core::Signal;
+use crate::signal::to_signal;
+
+/// Turns scores into target weights (fractions of equity, negative = short).
+///
+/// Symbols left out of the result keep whatever position they have.
+pub trait PortfolioConstruction: Send {
+    fn targets(&self, scores: &BTreeMap<String, f64>) -> Vec<(String, f64)>;
+}
+
+/// Each symbol on its own: `weight` long on a buy signal, `weight` short (or
+/// flat, without `allow_short`) on a sell, unchanged on hold
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SignalWeight {
+    pub weight: f64,
+    pub threshold: f64,
+    pub allow_short: bool,
+}
+
+impl SignalWeight {
+    pub fn new(weight: f64) -> Self {
+        Self { weight, threshold: 0.0, allow_short: false }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl PortfolioConstruction for SignalWeight {
+    fn targets(&self, scores: &BTreeMap<String, f64>) -> Vec<(String, f64)> {
+        scores
+            .iter()
+            .filter_map(|(symbol, score)| {
+                let weight = match to_signal(*score, self.threshold) {
+                    Signal::Buy => self.weight,
+                    Signal::Sell if self.allow_short => -self.weight,
+                    Signal::Sell => 0.0,
+                    Signal::Hold => return None,
+                };
+                Some((symbol.clone(), weight))
+            })
+            .collect()
+    }
+}
+
+/// Splits `gross` equally across every symbol with a buy (or, with
+/// `allow_short`, sell) signal; every other symbol goes flat
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EqualWeight {
+    pub gross: f64,
+    pub threshold: f64,
+    pub allow_short: bool,
+}
+
+impl EqualWeight {
+    pub fn new(gross: f64) -> Self {
+        Self { gross, threshold: 0.0, allow_short: false }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+
+    pub fn with_threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold;
+        self
+    }
+}
+
+impl PortfolioConstruction for EqualWeight {
+    fn targets(&self, scores: &BTreeMap<String, f64>) -> Vec<(String, f64)> {
+        let sides: Vec<(&String, f64)> = scores
+            .iter()
+            .map(|(symbol, score)| {
+                let side = match to_signal(*score, self.threshold) {
+                    Signal::Buy => 1.0,
+                    Signal::Sell if self.allow_short => -1.0,
+                    _ => 0.0,
+                };
+                (symbol, side)
+            })
+            .collect();
+        let active = sides.iter().filter(|(_, side)| *side != 0.0).count();
+        let each = if active == 0 { 0.0 } else { self.gross / active as f64 };
+        sides.into_iter().map(|(symbol, side)| (symbol.clone(), side * each)).collect()
+    }
+}
+
+/// Weights proportional to conviction: `gross * score / n` over the `n`
+/// scored symbols, so a full-strength score on every symbol invests `gross`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScoreWeighted {
+    pub gross: f64,
+    pub allow_short: bool,
+}
+
+impl ScoreWeighted {
+    pub fn new(gross: f64) -> Self {
+        Self { gross, allow_short: false }
+    }
+
+    pub fn with_short(mut self) -> Self {
+        self.allow_short = true;
+        self
+    }
+}
+
+impl PortfolioConstruction for ScoreWeighted {
+    fn targets(&self, scores: &BTreeMap<String, f64>) -> Vec<(String, f64)> {
+        let n = scores.len().max(1) as f64;
+        scores
+            .iter()
+            .map(|(symbol, score)| {
+                let score = if self.allow_short { score.clamp(-1.0, 1.0) } else { score.clamp(0.0, 1.0) };
+                (symbol.clone(), self.gross * score / n)
+            })
+            .collect()
+    }
+}
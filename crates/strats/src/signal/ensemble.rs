@@ -0,0 +1,280 @@
+//! Ensembles described as data, so they can be built through the registry
+
+use serde::{Deserialize, Serialize};
+use dnn_core::market::Candle;
+use dnn_core::time::Timestamp;
+use crate::indicators::{EMA_WINDOW_PERIODS, RSI_WINDOW_PERIODS};
+use crate::ma_cross::MovingAverage;
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::signal::alpha::{BollingerAlpha, MaCrossAlpha, MomentumAlpha, RsiAlpha};
+use crate::signal::{
+    Alpha, All, Any, EqualWeight, PortfolioConstruction, ScoreWeighted, SignalStrategy, SignalWeight, Vote,
+    WeightedAverage,
+};
+use crate::{ExecutionReport, Strategy, StrategyContext};
+
+fn one() -> f64 {
+    1.0
+}
+
+fn half() -> f64 {
+    0.5
+}
+
+fn oversold() -> f64 {
+    30.0
+}
+
+fn overbought() -> f64 {
+    70.0
+}
+
+fn momentum_scale() -> f64 {
+    0.1
+}
+
+fn default_band() -> f64 {
+    0.05
+}
+
+/// An alpha or a combination of alphas
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum AlphaSpec {
+    MaCross {
+        fast: usize,
+        slow: usize,
+        #[serde(default)]
+        average: MovingAverage,
+    },
+    Rsi {
+        period: usize,
+        #[serde(default = "oversold")]
+        oversold: f64,
+        #[serde(default = "overbought")]
+        overbought: f64,
+    },
+    Momentum {
+        lookback: usize,
+        #[serde(default = "momentum_scale")]
+        scale: f64,
+    },
+    Bollinger { period: usize, k: f64 },
+    All { members: Vec<Self> },
+    Any { members: Vec<Self> },
+    Vote {
+        members: Vec<Self>,
+        #[serde(default = "half")]
+        quorum: f64,
+        #[serde(default)]
+        threshold: f64,
+    },
+    Weighted { members: Vec<WeightedMember> },
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WeightedMember {
+    #[serde(default = "one")]
+    pub weight: f64,
+    pub alpha: AlphaSpec,
+}
+
+impl AlphaSpec {
+    pub fn build(&self) -> Result<Box<dyn Alpha>, ParamError> {
+        let invalid = |message: &str| Err(ParamError::Invalid(message.to_owned()));
+        let many = |members: &[Self]| -> Result<Vec<Box<dyn Alpha>>, ParamError> {
+            if members.is_empty() {
+                return Err(ParamError::Invalid("a combinator needs at least one member".to_owned()));
+            }
+            members.iter().map(Self::build).collect()
+        };
+
+        Ok(match self {
+            Self::MaCross { fast, slow, average } => {
+                if *fast == 0 || fast >= slow {
+                    return invalid("ma_cross needs 0 < fast < slow");
+                }
+                Box::new(MaCrossAlpha::new(*fast, *slow).with_average(*average))
+            }
+            Self::Rsi { period, oversold, overbought } => {
+                if *period == 0 || oversold >= overbought {
+                    return invalid("rsi needs a period and oversold < overbought");
+                }
+                Box::new(RsiAlpha::new(*period).with_levels(*oversold, *overbought))
+            }
+            Self::Momentum { lookback, scale } => {
+                if *lookback == 0 || *scale <= 0.0 {
+                    return invalid("momentum needs a lookback and a positive scale");
+                }
+                Box::new(MomentumAlpha::new(*lookback).with_scale(*scale))
+            }
+            Self::Bollinger { period, k } => {
+                if *period < 2 || *k <= 0.0 {
+                    return invalid("bollinger needs a period of at least 2 and a positive k");
+                }
+                Box::new(BollingerAlpha::new(*period, *k))
+            }
+            Self::All { members } => Box::new(All::new(many(members)?)),
+            Self::Any { members } => Box::new(Any::new(many(members)?)),
+            Self::Vote { members, quorum, threshold } => {
+                if !(0.0..=1.0).contains(quorum) {
+                    return invalid("vote quorum must be between 0 and 1");
+                }
+                Box::new(Vote::new(many(members)?).with_quorum(*quorum).with_threshold(*threshold))
+            }
+            Self::Weighted { members } => {
+                if members.is_empty() {
+                    return invalid("a combinator needs at least one member");
+                }
+                let members = members.iter().map(|m| Ok((m.weight, m.alpha.build()?))).collect::<Result<_, ParamError>>()?;
+                Box::new(WeightedAverage { members })
+            }
+        })
+    }
+
+    /// Bars of history every alpha reads once settled
+    pub fn lookback(&self) -> usize {
+        let most = |members: &mut dyn Iterator<Item = &Self>| members.map(Self::lookback).max().unwrap_or(0);
+        match self {
+            Self::MaCross { slow, average: MovingAverage::Sma, .. } => *slow,
+            Self::MaCross { slow, average: MovingAverage::Ema, .. } => EMA_WINDOW_PERIODS * slow,
+            Self::Rsi { period, .. } => RSI_WINDOW_PERIODS * period + 1,
+            Self::Momentum { lookback, .. } => lookback + 1,
+            Self::Bollinger { period, .. } => *period,
+            Self::All { members } | Self::Any { members } | Self::Vote { members, .. } => most(&mut members.iter()),
+            Self::Weighted { members } => most(&mut members.iter().map(|m| &m.alpha)),
+        }
+    }
+}
+
+/// How scores become target weights
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ConstructionSpec {
+    Signal {
+        #[serde(default = "one")]
+        weight: f64,
+        #[serde(default)]
+        threshold: f64,
+        #[serde(default)]
+        allow_short: bool,
+    },
+    EqualWeight {
+        #[serde(default = "one")]
+        gross: f64,
+        #[serde(default)]
+        threshold: f64,
+        #[serde(default)]
+        allow_short: bool,
+    },
+    ScoreWeighted {
+        #[serde(default = "one")]
+        gross: f64,
+        #[serde(default)]
+        allow_short: bool,
+    },
+}
+
+impl Default for ConstructionSpec {
+    fn default() -> Self {
+        Self::Signal { weight: 1.0, threshold: 0.0, allow_short: false }
+    }
+}
+
+impl ConstructionSpec {
+    pub fn build(&self) -> Box<dyn PortfolioConstruction> {
+        match *self {
+            Self::Signal { weight, threshold, allow_short } => Box::new(SignalWeight { weight, threshold, allow_short }),
+            Self::EqualWeight { gross, threshold, allow_short } => Box::new(EqualWeight { gross, threshold, allow_short }),
+            Self::ScoreWeighted { gross, allow_short } => Box::new(ScoreWeighted { gross, allow_short }),
+        }
+    }
+}
+
+/// A complete signal strategy: symbols, alpha, construction and rebalance band
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EnsembleSpec {
+    /// Symbols to score together; empty trades each bar's symbol
+    #[serde(default)]
+    pub symbols: Vec<String>,
+    pub alpha: AlphaSpec,
+    #[serde(default)]
+    pub construction: ConstructionSpec,
+    #[serde(default = "default_band")]
+    pub band: f64,
+}
+
+/// A [`SignalStrategy`] built from an [`EnsembleSpec`], registered as "ensemble"
+pub struct Ensemble {
+    spec: EnsembleSpec,
+    inner: SignalStrategy,
+}
+
+impl Ensemble {
+    pub fn new(spec: EnsembleSpec) -> Result<Self, ParamError> {
+        if !(0.0..1.0).contains(&spec.band) {
+            return Err(ParamError::Invalid("band must be in [0, 1)".to_owned()));
+        }
+        let inner = SignalStrategy {
+            symbols: spec.symbols.clone(),
+            alpha: spec.alpha.build()?,
+            construction: spec.construction.build(),
+            band: spec.band,
+        };
+        Ok(Self { spec, inner })
+    }
+
+    /// Parse a spec from YAML (or JSON, which is valid YAML)
+    pub fn from_yaml(src: &str) -> Result<Self, ParamError> {
+        let spec = serde_yaml::from_str(src).map_err(|e| ParamError::Invalid(format!("ensemble spec: {e}")))?;
+        Self::new(spec)
+    }
+
+    pub fn spec(&self) -> &EnsembleSpec {
+        &self.spec
+    }
+}
+
+impl Strategy for Ensemble {
+    fn on_start(&mut self, ctx: &mut StrategyContext) {
+        self.inner.on_start(ctx);
+    }
+
+    fn on_bar(&mut self, ctx: &mut StrategyContext, bar: &Candle) {
+        self.inner.on_bar(ctx, bar);
+    }
+
+    fn on_timer(&mut self, ctx: &mut StrategyContext, at: Timestamp) {
+        self.inner.on_timer(ctx, at);
+    }
+
+    fn on_order_update(&mut self, ctx: &mut StrategyContext, report: &ExecutionReport) {
+        self.inner.on_order_update(ctx, report);
+    }
+
+    fn on_end(&mut self, ctx: &mut StrategyContext) {
+        self.inner.on_end(ctx);
+    }
+
+    fn lookback(&self) -> usize {
+        self.spec.alpha.lookback()
+    }
+}
+
+impl Parameterized for Ensemble {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("ensemble", "Combine alpha signals and trade towards target weights")
+            .param(ParamSpec::new("spec", "").describe("Ensemble spec as YAML or JSON"))
+    }
+
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        Self::from_yaml(params.string("spec")?)
+    }
+
+    fn params(&self) -> Params {
+        let json = serde_json::to_string(&self.spec).unwrap_or_default();
+        Params::default().set("spec", json.as_str())
+    }
+}
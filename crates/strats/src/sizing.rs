@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use dnn_core::error::FxError;
+use dnn_core::fx::FxRates;
+use dnn_core::instrument::InstrumentMaster;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::{Order, OrderSide};
+
+/// What a strategy wants to do, without a share count.
+/// The sizing layer turns intents into [`Order`]s.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Intent {
+    /// Open or add to a position; quantity comes from the [`PositionSizer`]
+    Enter {
+        symbol: String,
+        side: OrderSide,
+        /// Protective stop price, used by stop-distance based sizers
+        stop: Option<f64>,
+    },
+    /// Flatten any position in the symbol
+    Exit { symbol: String },
+    /// Hold this fraction of equity in the symbol (negative = short)
+    TargetWeight { symbol: String, weight: f64 },
+}
+
+impl Intent {
+    pub fn symbol(&self) -> &str {
+        match self {
+            Self::Enter { symbol, .. } | Self::Exit { symbol } | Self::TargetWeight { symbol, .. } => symbol,
+        }
+    }
+}
+
+/// Market inputs a sizer may need for one symbol
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SymbolMetrics {
+    pub price: f64,
+    /// Average true range, in price units
+    pub atr: Option<f64>,
+    /// Standard deviation of returns
+    pub volatility: Option<f64>,
+}
+
+/// Everything a sizer sees when sizing an entry
+#[derive(Debug, Clone, Copy)]
+pub struct SizingContext {
+    /// Current portfolio equity in the base currency
+    pub equity: f64,
+    pub metrics: SymbolMetrics,
+    pub stop: Option<f64>,
+    /// Base currency value of a one point price move on one unit: the
+    /// contract multiplier times the FX rate
+    pub point_value: f64,
+}
+
+/// Turns an entry into an absolute quantity (always >= 0)
+pub trait PositionSizer: Send + Sync {
+    fn size(&self, ctx: &SizingContext) -> f64;
+}
+
+/// Invest a fixed fraction of equity per entry
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FixedFractional {
+    pub fraction: f64,
+}
+
+impl PositionSizer for FixedFractional {
+    fn size(&self, ctx: &SizingContext) -> f64 {
+        if ctx.metrics.price <= 0.0 || ctx.point_value <= 0.0 {
+            return 0.0;
+        }
+        ctx.equity * self.fraction / (ctx.metrics.price * ctx.point_value)
+    }
+}
+
+/// Risk `risk_fraction` of equity per `atr_multiple` ATRs of adverse movement
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct VolatilityTarget {
+    pub risk_fraction: f64,
+    pub atr_multiple: f64,
+}
+
+impl PositionSizer for VolatilityTarget {
+    fn size(&self, ctx: &SizingContext) -> f64 {
+        match ctx.metrics.atr {
+            Some(atr) if atr > 0.0 && self.atr_multiple > 0.0 && ctx.point_value > 0.0 => {
+                ctx.equity * self.risk_fraction / (atr * self.atr_multiple * ctx.point_value)
+            }
+            _ => 0.0,
+        }
+    }
+}
+
+/// Kelly criterion, scaled down by `fraction` (0.5 = half Kelly) and capped at `max_fraction`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Kelly {
+    pub win_rate: f64,
+    /// Average win divided by average loss
+    pub payoff_ratio: f64,
+    pub fraction: f64,
+    pub max_fraction: f64,
+}
+
+impl Kelly {
+    /// Optimal fraction of equity to allocate, before scaling
+    pub fn kelly_fraction(&self) -> f64 {
+        if self.payoff_ratio <= 0.0 {
+            return 0.0;
+        }
+        (self.win_rate - (1.0 - self.win_rate) / self.payoff_ratio).max(0.0)
+    }
+}
+
+impl PositionSizer for Kelly {
+    fn size(&self, ctx: &SizingContext) -> f64 {
+        if ctx.metrics.price <= 0.0 || ctx.point_value <= 0.0 {
+            return 0.0;
+        }
+        let f = (self.kelly_fraction() * self.fraction).min(self.max_fraction);
+        ctx.equity * f / (ctx.metrics.price * ctx.point_value)
+    }
+}
+
+/// Lose at most `risk_fraction` of equity if the stop is hit
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct FixedRisk {
+    pub risk_fraction: f64,
+}
+
+impl PositionSizer for FixedRisk {
+    fn size(&self, ctx: &SizingContext) -> f64 {
+        let Some(stop) = ctx.stop else {
+            return 0.0;
+        };
+        let distance = (ctx.metrics.price - stop).abs() * ctx.point_value;
+        if distance <= 0.0 {
+            return 0.0;
+        }
+        ctx.equity * self.risk_fraction / distance
+    }
+}
+
+/// How to split capital across a basket of symbols
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BasketWeighting {
+    EqualWeight,
+    /// Weight inversely to volatility so each symbol contributes similar risk
+    RiskParity,
+}
+
+/// Portfolio weights for a basket, summing to `gross` (symbols without volatility get none under risk parity)
+pub fn basket_weights(
+    metrics: &HashMap<String, SymbolMetrics>,
+    weighting: BasketWeighting,
+    gross: f64,
+) -> HashMap<String, f64> {
+    let raw: HashMap<&String, f64> = match weighting {
+        BasketWeighting::EqualWeight => metrics.keys().map(|s| (s, 1.0)).collect(),
+        BasketWeighting::RiskParity => metrics
+            .iter()
+            .filter_map(|(s, m)| m.volatility.filter(|v| *v > 0.0).map(|v| (s, 1.0 / v)))
+            .collect(),
+    };
+
+    let total: f64 = raw.values().sum();
+    if total <= 0.0 {
+        return HashMap::new();
+    }
+    raw.into_iter()
+        .map(|(s, w)| (s.clone(), gross * w / total))
+        .collect()
+}
+
+/// Sits between strategy output and order submission, turning [`Intent`]s into [`Order`]s
+/// using current portfolio equity.
+pub struct SizingLayer {
+    sizer: Box<dyn PositionSizer>,
+    instruments: InstrumentMaster,
+}
+
+impl SizingLayer {
+    pub fn new(sizer: Box<dyn PositionSizer>) -> Self {
+        Self {
+            sizer,
+            instruments: InstrumentMaster::new(),
+        }
+    }
+
+    /// Round quantities to instrument lot sizes and value them with
+    /// instrument multipliers and currencies
+    pub fn with_instruments(mut self, instruments: InstrumentMaster) -> Self {
+        self.instruments = instruments;
+        self
+    }
+
+    /// Base currency value of a one point move on one unit of `symbol`. Symbols
+    /// without reference data take the held position's, or trade in the base
+    /// currency with a multiplier of 1.
+    fn point_value(&self, symbol: &str, portfolio: &Portfolio, fx: &FxRates) -> Result<f64, FxError> {
+        let (currency, multiplier) = match (self.instruments.get(symbol), portfolio.positions.get(symbol)) {
+            (Some(inst), _) => (inst.currency.as_str(), inst.multiplier),
+            (None, Some(pos)) => (pos.currency.as_str(), pos.multiplier),
+            (None, None) => (portfolio.base_currency.as_str(), 1.0),
+        };
+        Ok(multiplier * fx.rate(currency, &portfolio.base_currency)?)
+    }
+
+    /// One order per symbol, netting the intents on it in order: an exit
+    /// followed by a target weight trades straight to the target. Equity is
+    /// valued at the last price of every symbol in `prices`. Orders are
+    /// numbered from `next_id`, the counter strategies number theirs from.
+    pub fn orders(
+        &self,
+        intents: &[Intent],
+        portfolio: &Portfolio,
+        prices: &HashMap<String, f64>,
+        metrics: &HashMap<String, SymbolMetrics>,
+        fx: &FxRates,
+        next_id: &mut u64,
+    ) -> anyhow::Result<Vec<Order>> {
+        let equity = portfolio.total_value(prices, fx)?;
+
+        // Position each symbol is headed to after the intents so far, in
+        // the order symbols first appear
+        let mut targets: Vec<(&str, f64)> = Vec::new();
+        for intent in intents {
+            let Some(m) = metrics.get(intent.symbol()).copied() else {
+                continue;
+            };
+            let index = if let Some(index) = targets.iter().position(|(s, _)| *s == intent.symbol()) {
+                index
+            } else {
+                let held = portfolio.positions.get(intent.symbol()).map_or(0.0, |p| p.qty);
+                targets.push((intent.symbol(), held));
+                targets.len() - 1
+            };
+            let current = targets[index].1;
+            let point_value = self.point_value(intent.symbol(), portfolio, fx)?;
+
+            // Signed quantity to trade
+            let delta = match intent {
+                Intent::Enter { side, stop, .. } => {
+                    let qty = self.sizer.size(&SizingContext { equity, metrics: m, stop: *stop, point_value });
+                    match side {
+                        OrderSide::Buy => qty,
+                        OrderSide::Sell => -qty,
+                    }
+                }
+                Intent::Exit { .. } => -current,
+                Intent::TargetWeight { weight, .. } if m.price > 0.0 && point_value > 0.0 => {
+                    equity * weight / (m.price * point_value) - current
+                }
+                Intent::TargetWeight { .. } => 0.0,
+            };
+            targets[index].1 = current + delta;
+        }
+
+        let mut orders = Vec::new();
+        for (symbol, target) in targets {
+            let delta = target - portfolio.positions.get(symbol).map_or(0.0, |p| p.qty);
+            let qty = match self.instruments.get(symbol) {
+                Some(inst) => inst.round_qty(delta.abs()),
+                None => delta.abs(),
+            };
+            if qty <= 0.0 || !qty.is_finite() {
+                continue;
+            }
+
+            orders.push(Order {
+                id: *next_id,
+                symbol: symbol.to_owned(),
+                qty,
+                price: None,
+                side: if delta > 0.0 { OrderSide::Buy } else { OrderSide::Sell },
+            });
+            *next_id += 1;
+        }
+        Ok(orders)
+    }
+}
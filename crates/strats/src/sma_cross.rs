@@ -1,37 +1,61 @@
-use crate::{ExecutionReport, Strategy};
+use crate::registry::{ParamError, ParamSpec, Parameterized, Params, StrategySchema};
+use crate::{Strategy, StrategyContext};
+use crate::sizing::Intent;
 use dnn_core::market::Candle;
-use dnn_core::{Order, OrderSide};
+use dnn_core::OrderSide;
 
+/// Long while the short moving average is above the long one, flat otherwise.
+/// Entries are sized by the engine's sizing layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct SmaCross {
     pub short: usize,
     pub long: usize,
-    prices: Vec<f64>,
+}
+
+impl SmaCross {
+    pub fn new(short: usize, long: usize) -> Self {
+        Self { short, long }
+    }
 }
 
 impl Strategy for SmaCross {
-    fn on_market_event(&mut self, bar: &Candle) -> Vec<Order> {
-        self.prices.push(bar.close);
-        if self.prices.len() < self.long {
-            return vec![];
+    fn on_bar(&mut self, ctx: &mut StrategyContext, _bar: &Candle) {
+        let symbol = ctx.symbol().to_owned();
+        let (Some(short_avg), Some(long_avg)) = (ctx.sma(&symbol, self.short), ctx.sma(&symbol, self.long)) else {
+            return;
+        };
+
+        let held = ctx.position_qty(&symbol);
+        if short_avg > long_avg && held <= 0.0 {
+            ctx.log(format!("{symbol} SMA{} crossed above SMA{}", self.short, self.long));
+            ctx.intent(Intent::Enter { symbol, side: OrderSide::Buy, stop: None });
+        } else if short_avg < long_avg && held > 0.0 {
+            ctx.log(format!("{symbol} SMA{} crossed below SMA{}", self.short, self.long));
+            ctx.flatten(&symbol);
         }
+    }
+
+    fn lookback(&self) -> usize {
+        self.long
+    }
+}
+
+impl Parameterized for SmaCross {
+    fn schema() -> StrategySchema {
+        StrategySchema::new("sma_cross", "Long while the short SMA is above the long SMA")
+            .param(ParamSpec::new("short", 10_i64).range(1.0, 500.0).describe("Short SMA period"))
+            .param(ParamSpec::new("long", 30_i64).range(2.0, 1000.0).describe("Long SMA period"))
+    }
 
-        let short_avg = self.prices[self.prices.len()-self.short..].iter().copied().sum::<f64>() / self.short as f64;
-        let long_avg  = self.prices[self.prices.len()-self.long..].iter().copied().sum::<f64>() / self.long as f64;
-
-        if short_avg > long_avg {
-            vec![Order {
-                id: bar.timestamp.timestamp() as u64,
-                symbol: "AAPL".into(),
-                qty: 10.0,
-                price: None,
-                side: OrderSide::Buy,
-            }]
-        } else {
-            vec![]
+    fn from_params(params: &Params) -> Result<Self, ParamError> {
+        let (short, long) = (params.usize("short")?, params.usize("long")?);
+        if short >= long {
+            return Err(ParamError::Invalid(format!("short ({short}) must be below long ({long})")));
         }
+        Ok(Self::new(short, long))
     }
 
-    fn on_fill(&mut self, report: &ExecutionReport) {
-        println!("Order filled at {} for qty {}", report.fill_price, report.filled_qty);
+    fn params(&self) -> Params {
+        Params::default().set("short", self.short).set("long", self.long)
     }
 }
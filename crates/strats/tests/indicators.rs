@@ -0,0 +1,35 @@
+use strats::donchian::DonchianBreakout;
+use strats::indicators::{ema, rsi, EMA_WINDOW_PERIODS, RSI_WINDOW_PERIODS};
+use strats::ma_cross::{MaCross, MovingAverage};
+use strats::rsi::RsiReversion;
+use strats::Strategy;
+
+#[test]
+fn test_ema_ignores_history_past_its_window() {
+    let values: Vec<f64> = (0..200).map(|i| 100.0 + (f64::from(i) / 7.0).sin() * 10.0).collect();
+    let window = EMA_WINDOW_PERIODS * 10;
+    let full = ema(&values, 10).unwrap();
+    assert_eq!(full, ema(&values[values.len() - window..], 10).unwrap());
+    assert_ne!(full, ema(&values[values.len() - window + 1..], 10).unwrap());
+    // Short histories still seed from their start
+    assert_eq!(ema(&values[..10], 10), Some(values[..10].iter().sum::<f64>() / 10.0));
+    assert_eq!(ema(&values[..9], 10), None);
+}
+
+#[test]
+fn test_rsi_ignores_history_past_its_window() {
+    let values: Vec<f64> = (0..300).map(|i| 100.0 + (f64::from(i) / 5.0).sin() * 10.0).collect();
+    let window = RSI_WINDOW_PERIODS * 14 + 1;
+    let full = rsi(&values, 14).unwrap();
+    assert_eq!(full, rsi(&values[values.len() - window..], 14).unwrap());
+    assert_ne!(full, rsi(&values[values.len() - window + 1..], 14).unwrap());
+    assert_eq!(rsi(&values[..14], 14), None);
+}
+
+#[test]
+fn test_strategies_ask_for_their_history() {
+    assert_eq!(MaCross::new(50, 600, MovingAverage::Sma).lookback(), 600);
+    assert_eq!(MaCross::new(5, 20, MovingAverage::Ema).lookback(), EMA_WINDOW_PERIODS * 20);
+    assert_eq!(DonchianBreakout::new(20, 400).lookback(), 401);
+    assert_eq!(RsiReversion::new(14, 30.0, 70.0).lookback(), RSI_WINDOW_PERIODS * 14 + 1);
+}
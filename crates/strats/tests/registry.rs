@@ -0,0 +1,70 @@
+use serde_json::json;
+use strats::registry::{ParamError, ParamType, Parameterized, RegistryError, StrategySpec};
+use strats::sma_cross::SmaCross;
+use strats::StrategyRegistry;
+
+#[test]
+fn test_builtin_schemas() {
+    let registry = StrategyRegistry::builtin();
+    let names: Vec<&str> = registry
+        .schemas()
+        .map(|s| s.name.as_str())
+        .filter(|name| *name != "script")
+        .collect();
+    assert_eq!(names, [
+        "bollinger_reversion",
+        "buy_and_hold",
+        "donchian_breakout",
+        "dual_momentum",
+        "ensemble",
+        "ma_cross",
+        "pairs",
+        "rsi_reversion",
+        "rules",
+        "sma_cross",
+    ]);
+
+    let schema = registry.schema("sma_cross").unwrap();
+    assert_eq!(schema.params[0].name, "short");
+    assert_eq!(schema.params[0].ty, ParamType::Int);
+
+    // Schemas serialise for the API
+    let value = serde_json::to_value(schema).unwrap();
+    assert_eq!(value["params"][1], json!({
+        "name": "long", "type": "int", "default": 30, "min": 2.0, "max": 1000.0, "description": "Long SMA period",
+    }));
+}
+
+#[test]
+fn test_validate_params() {
+    let registry = StrategyRegistry::builtin();
+
+    let params = registry.validate("sma_cross", &json!({ "short": 5 })).unwrap();
+    assert_eq!(params.usize("short"), Ok(5));
+    assert_eq!(params.usize("long"), Ok(30));
+    assert_eq!(registry.validate("buy_and_hold", &json!(null)).unwrap().float("weight"), Ok(1.0));
+
+    let err = |name: &str, params| registry.validate(name, &params).unwrap_err();
+    assert_eq!(err("nope", json!({})), RegistryError::UnknownStrategy("nope".into()));
+    assert_eq!(err("sma_cross", json!([1])), ParamError::NotAnObject.into());
+    assert_eq!(err("sma_cross", json!({ "fast": 1 })), ParamError::Unknown("fast".into()).into());
+    assert_eq!(
+        err("sma_cross", json!({ "short": 2.5 })),
+        ParamError::WrongType { name: "short".into(), expected: ParamType::Int }.into()
+    );
+    assert!(matches!(err("sma_cross", json!({ "short": 0 })), RegistryError::Params(ParamError::OutOfRange { .. })));
+    assert!(matches!(err("sma_cross", json!({ "short": 40 })), RegistryError::Params(ParamError::Invalid(_))));
+    // Ints are fine where floats are expected
+    assert!(registry.validate("buy_and_hold", &json!({ "weight": 1 })).is_ok());
+}
+
+#[test]
+fn test_create_from_json() {
+    let registry = StrategyRegistry::builtin();
+    let spec: StrategySpec = serde_json::from_str(r#"{ "name": "sma_cross", "params": { "short": 3, "long": 7 } }"#).unwrap();
+    assert!(registry.create_from_spec(&spec).is_ok());
+
+    let strategy = SmaCross::from_params(&registry.validate(&spec.name, &spec.params).unwrap()).unwrap();
+    assert_eq!(strategy, SmaCross::new(3, 7));
+    assert_eq!(strategy.params().to_json(), json!({ "long": 7, "short": 3 }));
+}
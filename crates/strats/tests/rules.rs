@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+use chrono::{Duration, TimeZone, Utc};
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::{ExecutionReport, ExecutionStatus, OrderSide};
+use strats::context::Command;
+use strats::rules::expr::{CompareOp, Condition, Expr, ExprError, Formula};
+use strats::rules::{Level, Position, RuleError, RuleSpec, RuleStrategy, Sizing};
+use strats::sizing::Intent;
+use strats::{Strategy, StrategyContext};
+
+fn bars(closes: &[f64]) -> Vec<Candle> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    closes
+        .iter()
+        .enumerate()
+        .map(|(i, c)| Candle::new(start + Duration::days(i as i64), *c, c + 1.0, c - 1.0, *c, 1_000.0).unwrap())
+        .collect()
+}
+
+fn parse_err(src: &str) -> ExprError {
+    Condition::parse(src).unwrap_err()
+}
+
+#[test]
+fn test_parse_conditions() {
+    let c = Condition::parse("RSI(14) < 30 AND close > SMA(200)").unwrap();
+    assert!(matches!(c.expr(), Expr::And(..)));
+    assert_eq!(c.lookback(), 200);
+    // So does RSI, past its first value
+    assert_eq!(Condition::parse("RSI(30) < 30").unwrap().history(), 241);
+
+    // AND binds tighter than OR, arithmetic tighter than comparison
+    let c = Condition::parse("close > 1 OR close < 2 and not volume * 2 >= 10").unwrap();
+    assert!(matches!(c.expr(), Expr::Or(_, rhs) if matches!(**rhs, Expr::And(..))));
+
+    let c = Condition::parse("ema(5) crosses above ema(20, open)").unwrap();
+    assert!(matches!(c.expr(), Expr::Compare { op: CompareOp::CrossesAbove, .. }));
+    assert_eq!(c.lookback(), 21);
+    // EMAs read further back to settle
+    assert_eq!(c.history(), 81);
+}
+
+#[test]
+fn test_parse_errors_have_positions() {
+    assert_eq!(parse_err("close > FOO(3)"), ExprError { offset: 8, message: "unknown field or indicator `FOO`".into() });
+    assert_eq!(parse_err("close >").offset, 7);
+    assert_eq!(parse_err("SMA(0) > 1").message, "SMA needs a whole period of at least 1, found number 0");
+    assert_eq!(parse_err("close + 1").message, "a rule expects a condition");
+    assert_eq!(parse_err("close > 1 AND 5").offset, 14);
+    assert_eq!(parse_err("(close > 1) + 2").offset, 0);
+    assert_eq!(parse_err("close > 1)").message, "unexpected `)`");
+    assert_eq!(parse_err("close # 1").to_string(), "column 7: unexpected character `#`");
+    assert_eq!(parse_err("close crosses over 1").message, "expected ABOVE or BELOW, found `over`");
+}
+
+#[test]
+fn test_deep_nesting_is_rejected() {
+    let nested = |depth: usize| format!("{}close > 1{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(Condition::parse(&nested(60)).is_ok());
+    assert_eq!(parse_err(&nested(65)).message, "nested more than 64 levels deep");
+    assert_eq!(parse_err(&nested(100_000)).message, "longer than 1024 tokens");
+    assert_eq!(parse_err(&format!("{}close > 1", "NOT ".repeat(100))).offset, 256);
+    assert!(parse_err(&format!("{}1 > 0", "-".repeat(100))).message.starts_with("nested more than"));
+    assert_eq!(parse_err(&"close + ".repeat(600)).message, "longer than 1024 tokens");
+}
+
+#[test]
+fn test_evaluate_conditions() {
+    let rising = bars(&[10.0, 11.0, 12.0, 13.0]);
+    let holds = |src: &str, data: &[Candle]| Condition::parse(src).unwrap().holds(data);
+
+    assert!(holds("close > SMA(3)", &rising));
+    assert!(holds("HIGHEST(4) == 14 AND LOWEST(2) == 11", &rising));
+    assert!(holds("(close - open) / 2 == 0", &rising));
+    // Not enough history yet: false, not an error
+    assert!(!holds("close > SMA(10)", &rising));
+    assert!(holds("NOT close > SMA(10)", &rising));
+
+    let cross = bars(&[10.0, 10.0, 10.0, 14.0]);
+    assert!(holds("close crosses above SMA(3)", &cross));
+    assert!(!holds("close crosses above SMA(3)", &cross[..3]));
+    assert!(holds("RSI(2) > 70", &cross));
+}
+
+#[test]
+fn test_variables_and_formulas() {
+    let vars = ["change_pct", "market_cap"];
+    let rising = bars(&[10.0, 11.0, 12.0, 13.0]);
+    let c = Condition::parse_with("CHANGE_PCT > 5 AND close > SMA(3)", &vars).unwrap();
+    assert!(c.holds_with(&rising, &[Some(8.3), None]));
+    assert!(!c.holds_with(&rising, &[Some(1.0), None]));
+    // Missing values make comparisons false, like indicators warming up
+    assert!(!Condition::parse_with("market_cap > 0", &vars).unwrap().holds_with(&rising, &[Some(8.3), None]));
+    assert_eq!(parse_err("change_pct > 5").message, "unknown field or indicator `change_pct`");
+
+    let f = Formula::parse_with("close / SMA(2) * market_cap", &vars).unwrap();
+    assert_eq!(f.value_with(&rising, &[None, Some(25.0)]), Some(26.0));
+    assert_eq!(f.value_with(&rising, &[None, None]), None);
+    assert_eq!(Formula::parse_with("close > 1", &vars).unwrap_err().message, "a formula expects a number");
+}
+
+#[test]
+fn test_validate_spec() {
+    let errors = RuleStrategy::from_yaml(
+        "entry: RSI(14) < 30 AND\nexit: close > SMA(x)\nstop_loss: { percent: 150 }\nsizing: { weight: 2 }\n",
+    )
+    .unwrap_err();
+    let fields: Vec<&str> = errors.iter().filter_map(|e| e.field.as_deref()).collect();
+    assert_eq!(fields, ["entry", "exit", "stop_loss", "sizing"]);
+    // Expression errors are located in the document
+    assert_eq!(errors[0].position, Some(Position { line: 1, column: 24 }));
+    assert_eq!(errors[1].position, Some(Position { line: 2, column: 19 }));
+    let errors = RuleStrategy::from_json("{\n  \"entry\": \"close > SMA(x)\"\n}").unwrap_err();
+    assert_eq!(errors[0].position, Some(Position { line: 2, column: 25 }));
+    // and relative to the expression when the spec is built directly
+    let spec = RuleSpec {
+        entry: "close > SMA(x)".to_owned(),
+        ..RuleStrategy::from_yaml("entry: close > 1").unwrap().spec().clone()
+    };
+    let errors = RuleStrategy::new(spec).unwrap_err();
+    assert_eq!(errors[0].position, Some(Position { line: 1, column: 13 }));
+
+    // Syntax errors in the document carry its line and column
+    let errors = RuleStrategy::from_json("{\n  \"entry\": \"close > 1\",\n  \"stop\": 3\n}").unwrap_err();
+    assert_eq!(errors[0].field, None);
+    assert_eq!(errors[0].position.map(|p| p.line), Some(3));
+    let errors = RuleStrategy::from_yaml("entry: [").unwrap_err();
+    assert!(matches!(errors[0], RuleError { field: None, position: Some(_), .. }));
+
+    let strategy = RuleStrategy::from_json(
+        r#"{ "entry": "close > 1", "stop_loss": { "atr": { "period": 14, "multiple": 2 } }, "sizing": { "quantity": 5 } }"#,
+    )
+    .unwrap();
+    assert_eq!(strategy.spec().stop_loss, Some(Level::Atr { period: 14, multiple: 2.0 }));
+    assert_eq!(strategy.spec().sizing, Sizing::Quantity(5.0));
+    // The engine keeps enough history for the ATR stop
+    assert_eq!(strategy.lookback(), 15);
+}
+
+#[test]
+fn test_rule_strategy_entries_and_exits() {
+    let mut strategy = RuleStrategy::from_yaml(
+        "entry: close crosses above SMA(3)\nstop_loss: { percent: 10 }\ntake_profit: { points: 5 }\n",
+    )
+    .unwrap();
+    let data = bars(&[10.0, 10.0, 10.0, 14.0]);
+    let history = HashMap::from([("X".to_owned(), data.clone())]);
+    let mut portfolio = Portfolio::new(1_000.0);
+    let mut next_id = 1;
+
+    let mut ctx = StrategyContext::new(data[3].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &data[3]);
+    let (commands, logs, _) = ctx.into_parts();
+    // Sized by the engine, with the stop 10% under the close
+    assert!(matches!(
+        &commands[..],
+        [Command::Intent(Intent::Enter { side: OrderSide::Buy, stop: Some(stop), .. })] if (stop - 12.6).abs() < 1e-9
+    ));
+    assert_eq!(logs[0].message, "X entry: close crosses above SMA(3)");
+
+    // Long at 14: take profit at 19
+    portfolio.apply_fill("X", OrderSide::Buy, 10.0, 14.0);
+    let up = bars(&[10.0, 10.0, 10.0, 14.0, 19.5]);
+    let history = HashMap::from([("X".to_owned(), up.clone())]);
+    let mut ctx = StrategyContext::new(up[4].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &up[4]);
+    let (commands, logs, _) = ctx.into_parts();
+    assert!(matches!(&commands[..], [Command::Intent(Intent::Exit { .. })]));
+    assert_eq!(logs[0].message, "X take profit");
+}
+
+#[test]
+fn test_rule_exits_are_set_per_symbol_at_fill() {
+    let mut strategy = RuleStrategy::from_yaml(
+        "entry: close > 0\nstop_loss: { atr: { period: 2, multiple: 1 } }\nsizing: { quantity: 1 }\n",
+    )
+    .unwrap();
+    let calm = bars(&[10.0, 10.0, 10.0]);
+    let history = HashMap::from([("X".to_owned(), calm.clone()), ("Y".to_owned(), calm.clone())]);
+    let mut portfolio = Portfolio::new(1_000.0);
+    let mut next_id = 1;
+    let ts = calm[2].timestamp;
+
+    let mut ctx = StrategyContext::new(ts, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &calm[2]);
+    assert!(matches!(&ctx.into_parts().0[..], [Command::Submit(_)]));
+
+    // Filled at 10 with an ATR of 2: stop at 8
+    portfolio.apply_fill("X", OrderSide::Buy, 1.0, 10.0);
+    let report = ExecutionReport {
+        order_id: 1,
+        status: ExecutionStatus::Filled,
+        filled_qty: 1.0,
+        fill_price: 10.0,
+        leaves_qty: 0.0,
+        ts,
+    };
+    let mut ctx = StrategyContext::new(ts, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_order_update(&mut ctx, &report);
+
+    // A flat symbol doesn't reset the stop of another
+    let mut ctx = StrategyContext::new(ts, "Y", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &calm[2]);
+
+    // The ATR widens to 2.75, but the stop stays where it was set at the fill
+    let wide = bars(&[10.0, 10.0, 10.0, 7.5]);
+    let history = HashMap::from([("X".to_owned(), wide.clone())]);
+    let mut ctx = StrategyContext::new(wide[3].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &wide[3]);
+    let (commands, logs, _) = ctx.into_parts();
+    assert!(matches!(&commands[..], [Command::Intent(Intent::Exit { .. })]));
+    assert_eq!(logs[0].message, "X stop loss");
+}
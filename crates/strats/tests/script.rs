@@ -0,0 +1,127 @@
+#![cfg(feature = "scripting")]
+
+use std::collections::HashMap;
+use chrono::{Duration, TimeZone, Utc};
+use serde_json::json;
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::time::Timestamp;
+use dnn_core::OrderSide;
+use strats::context::{Command, LogLine};
+use strats::script::{ScriptError, ScriptLimits, ScriptStrategy};
+use strats::sizing::Intent;
+use strats::{Strategy, StrategyContext, StrategyRegistry};
+
+fn ts(day: i64) -> Timestamp {
+    Utc.with_ymd_and_hms(2024, 3, 1, 0, 0, 0).unwrap() + Duration::days(day)
+}
+
+/// Feed one bar of `AAPL` to the strategy the way the engine would
+fn bar(strategy: &mut ScriptStrategy, day: i64, close: f64, next_id: &mut u64) -> (Vec<Command>, Vec<LogLine>) {
+    let portfolio = Portfolio::new(10_000.0);
+    let candle = Candle::new(ts(day), close, close, close, close, 100.0).unwrap();
+    let history = HashMap::new();
+    let mut ctx = StrategyContext::new(ts(day), "AAPL", &portfolio, &[], &history, next_id);
+    strategy.on_bar(&mut ctx, &candle);
+    let (commands, logs, _) = ctx.into_parts();
+    (commands, logs)
+}
+
+const SCRIPT: &str = r#"
+fn on_bar(ctx, bar) {
+    let state = ctx.state;
+    state.bars = (state.bars ?? 0) + 1;
+    ctx.state = state;
+
+    let avg = ctx.sma(ctx.symbol, 2);
+    if avg == () { return; }
+    print(`bar ${state.bars} close ${bar.close} sma ${avg}`);
+
+    if bar.close > avg && ctx.position(ctx.symbol) == 0.0 {
+        let id = ctx.limit(ctx.symbol, "buy", 5, bar.close - 1.0);
+        ctx.log(`order ${id}`);
+        ctx.target_weight("MSFT", 0.5);
+    }
+}
+"#;
+
+#[test]
+fn test_script_hooks_and_api() {
+    let mut strategy = ScriptStrategy::new(SCRIPT).unwrap();
+    let mut next_id = 7;
+
+    let (commands, logs) = bar(&mut strategy, 0, 100.0, &mut next_id);
+    assert!(commands.is_empty() && logs.is_empty());
+
+    let (commands, logs) = bar(&mut strategy, 1, 104.0, &mut next_id);
+    let messages: Vec<&str> = logs.iter().map(|l| l.message.as_str()).collect();
+    assert_eq!(messages, ["bar 2 close 104.0 sma 102.0", "order 7"]);
+    assert!(matches!(
+        &commands[0],
+        Command::Submit(o) if o.id == 7 && o.side == OrderSide::Buy && o.qty == 5.0 && o.price == Some(103.0)
+    ));
+    assert!(matches!(&commands[1], Command::Intent(Intent::TargetWeight { weight, .. }) if *weight == 0.5));
+    assert_eq!(next_id, 8);
+    assert!(strategy.error().is_none());
+}
+
+#[test]
+fn test_script_limits() {
+    let limits = ScriptLimits { max_operations: 10_000, ..ScriptLimits::default() };
+    let mut spin = ScriptStrategy::with_limits("fn on_bar(ctx, bar) { loop { ctx.buy(1); } }", limits).unwrap();
+    let (commands, logs) = bar(&mut spin, 0, 100.0, &mut 1);
+    // Nothing from a failed call is acted on, and the script stays disabled
+    assert!(commands.is_empty());
+    assert!(logs[0].message.contains("script disabled"));
+    assert!(matches!(spin.error(), Some(ScriptError::Runtime { hook, .. }) if hook == "on_bar"));
+    assert!(bar(&mut spin, 1, 100.0, &mut 1).1.is_empty());
+
+    let limits = ScriptLimits { max_collection_size: 100, ..ScriptLimits::default() };
+    let mut hog = ScriptStrategy::with_limits("fn on_bar(ctx, bar) { let a = []; a.pad(1000, 0); }", limits).unwrap();
+    bar(&mut hog, 0, 100.0, &mut 1);
+    assert!(hog.error().is_some());
+    // State grows a little every call, within the collection limits
+    let limits = ScriptLimits { max_state_size: 1_000, ..ScriptLimits::default() };
+    let grow = r#"
+fn on_bar(ctx, bar) {
+    let value = "x";
+    value.pad(300, "x");
+    let state = ctx.state;
+    state[`k${bar.time}`] = value;
+    ctx.state = state;
+}
+"#;
+    let mut grow = ScriptStrategy::with_limits(grow, limits).unwrap();
+    for day in 0..3 {
+        bar(&mut grow, day, 100.0, &mut 1);
+    }
+    assert!(grow.error().is_none());
+    let logs = bar(&mut grow, 3, 100.0, &mut 1).1;
+    assert!(logs[0].message.contains("ctx.state is larger than 1000 bytes"), "{}", logs[0].message);
+
+    let mut cancel = ScriptStrategy::new("fn on_bar(ctx, bar) { ctx.cancel(-1); }").unwrap();
+    let (commands, _) = bar(&mut cancel, 0, 100.0, &mut 1);
+    assert!(commands.is_empty());
+    assert!(matches!(cancel.error(), Some(ScriptError::Runtime { message, .. }) if message.contains("invalid order id -1")));
+}
+
+#[test]
+fn test_script_sandbox_and_compile_errors() {
+    let Err(ScriptError::Compile(message)) = ScriptStrategy::new("fn on_bar(ctx, bar) {\n  let x = ;\n}") else {
+        panic!("expected a compile error");
+    };
+    assert!(message.contains("line 2"), "{message}");
+
+    assert!(ScriptStrategy::new(r#"fn on_bar(ctx, bar) { eval("1") }"#).is_err());
+
+    let mut import = ScriptStrategy::new(r#"fn on_bar(ctx, bar) { import "os" as os; }"#).unwrap();
+    bar(&mut import, 0, 100.0, &mut 1);
+    assert!(import.error().is_some());
+}
+
+#[test]
+fn test_script_from_registry() {
+    let registry = StrategyRegistry::builtin();
+    assert!(registry.create("script", &json!({ "source": SCRIPT })).is_ok());
+    assert!(registry.validate("script", &json!({ "source": "fn on_bar(" })).is_err());
+}
@@ -0,0 +1,190 @@
+use std::collections::{BTreeMap, HashMap};
+use chrono::{Duration, TimeZone, Utc};
+use dnn_core::fx::FxRates;
+use dnn_core::market::Candle;
+use dnn_core::portfolio::Portfolio;
+use dnn_core::{OrderSide, Signal};
+use strats::context::Command;
+use strats::indicators::{EMA_WINDOW_PERIODS, RSI_WINDOW_PERIODS};
+use strats::registry::ParamError;
+use strats::signal::alpha::{BollingerAlpha, MaCrossAlpha, MomentumAlpha};
+use strats::signal::ensemble::{AlphaSpec, ConstructionSpec};
+use strats::signal::{
+    signal_score, to_signal, All, Alpha, Any, Ensemble, EqualWeight, PortfolioConstruction, ScoreWeighted,
+    SignalStrategy, SignalWeight, Vote, WeightedAverage,
+};
+use strats::sizing::Intent;
+use strats::{Strategy, StrategyContext};
+
+fn bars(closes: &[f64]) -> Vec<Candle> {
+    let start = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+    closes
+        .iter()
+        .enumerate()
+        .map(|(i, c)| Candle::new(start + Duration::days(i as i64), *c, c + 1.0, c - 1.0, *c, 1_000.0).unwrap())
+        .collect()
+}
+
+fn fixed(score: Option<f64>) -> Box<dyn Alpha> {
+    Box::new(move |_: &StrategyContext, _: &str| score)
+}
+
+/// Score `alpha` against a flat portfolio and `data` as the history of "X"
+fn score(alpha: &mut dyn Alpha, data: &[Candle]) -> Option<f64> {
+    let history = HashMap::from([("X".to_owned(), data.to_vec())]);
+    let portfolio = Portfolio::new(1_000.0);
+    let mut next_id = 1;
+    let ctx = StrategyContext::new(data[data.len() - 1].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    alpha.score(&ctx, "X")
+}
+
+fn targets(construction: &dyn PortfolioConstruction, scores: &[(&str, f64)]) -> Vec<(String, f64)> {
+    let scores: BTreeMap<String, f64> = scores.iter().map(|(s, v)| ((*s).to_owned(), *v)).collect();
+    construction.targets(&scores)
+}
+
+#[test]
+fn test_signals_and_alphas() {
+    assert_eq!(to_signal(0.4, 0.25), Signal::Buy);
+    assert_eq!(to_signal(-0.2, 0.25), Signal::Hold);
+    assert_eq!(to_signal(-0.3, 0.25), Signal::Sell);
+    assert_eq!(signal_score(Signal::Sell), -1.0);
+
+    let rising = bars(&[10.0, 11.0, 12.0, 13.0, 14.0, 15.0]);
+    assert_eq!(score(&mut MaCrossAlpha::new(2, 4), &rising), Some(1.0));
+    assert_eq!(score(&mut MaCrossAlpha::new(2, 10), &rising), None);
+    // 50% over five bars, past the 10% full-conviction scale
+    assert_eq!(score(&mut MomentumAlpha::new(5), &rising), Some(1.0));
+    assert_eq!(score(&mut MomentumAlpha::new(5).with_scale(1.0), &rising), Some(0.5));
+    // Close at the top of the bands reads as overbought
+    let bollinger = score(&mut BollingerAlpha::new(6, 1.0), &rising).unwrap();
+    assert!((-1.0..-0.5).contains(&bollinger));
+}
+
+#[test]
+fn test_combinators() {
+    let data = bars(&[10.0]);
+    let mut all = All::new(vec![fixed(Some(1.0)), fixed(Some(0.5))]);
+    assert_eq!(score(&mut all, &data), Some(0.5));
+    let mut all = All::new(vec![fixed(Some(1.0)), fixed(Some(-0.5))]);
+    assert_eq!(score(&mut all, &data), Some(0.0));
+    // AND waits for every member
+    let mut all = All::new(vec![fixed(Some(1.0)), fixed(None)]);
+    assert_eq!(score(&mut all, &data), None);
+
+    let mut any = Any::new(vec![fixed(Some(0.0)), fixed(Some(0.5)), fixed(None)]);
+    assert_eq!(score(&mut any, &data), Some(0.5));
+    let mut any = Any::new(vec![fixed(Some(1.0)), fixed(Some(-1.0))]);
+    assert_eq!(score(&mut any, &data), Some(0.0));
+
+    let mut vote = Vote::new(vec![fixed(Some(1.0)), fixed(Some(0.3)), fixed(Some(-1.0)), fixed(None)]);
+    assert_eq!(score(&mut vote, &data), Some(1.0 / 3.0));
+    let mut vote = Vote::new(vec![fixed(Some(1.0)), fixed(Some(0.3)), fixed(Some(-1.0))])
+        .with_quorum(0.75)
+        .with_threshold(0.5);
+    assert_eq!(score(&mut vote, &data), Some(0.0));
+
+    let mut weighted = WeightedAverage::new()
+        .add(3.0, |_: &StrategyContext, _: &str| Some(1.0))
+        .add(1.0, |_: &StrategyContext, _: &str| Some(-1.0))
+        .add(5.0, |_: &StrategyContext, _: &str| None);
+    assert_eq!(score(&mut weighted, &data), Some(0.5));
+}
+
+#[test]
+fn test_portfolio_construction() {
+    let scores = [("A", 1.0), ("B", -0.5), ("C", 0.1)];
+
+    let signal = SignalWeight::new(0.5).with_threshold(0.2);
+    assert_eq!(targets(&signal, &scores), [("A".to_owned(), 0.5), ("B".to_owned(), 0.0)]);
+    assert_eq!(targets(&signal.with_short(), &scores)[1], ("B".to_owned(), -0.5));
+
+    let equal = EqualWeight::new(1.0).with_short();
+    assert_eq!(targets(&equal, &scores), [
+        ("A".to_owned(), 1.0 / 3.0),
+        ("B".to_owned(), -1.0 / 3.0),
+        ("C".to_owned(), 1.0 / 3.0),
+    ]);
+    let equal = EqualWeight::new(1.0).with_threshold(0.2);
+    assert_eq!(targets(&equal, &scores), [("A".to_owned(), 1.0), ("B".to_owned(), 0.0), ("C".to_owned(), 0.0)]);
+
+    let weighted = ScoreWeighted::new(0.9);
+    let weights: Vec<f64> = targets(&weighted, &scores).into_iter().map(|(_, w)| w).collect();
+    assert!(weights.iter().zip([0.3, 0.0, 0.03]).all(|(w, expected)| (w - expected).abs() < 1e-12));
+}
+
+#[test]
+fn test_signal_strategy_rebalances_towards_targets() {
+    let data = bars(&[10.0, 11.0, 12.0, 13.0]);
+    let history = HashMap::from([("X".to_owned(), data.clone())]);
+    let mut strategy = SignalStrategy::new(MaCrossAlpha::new(2, 3), SignalWeight::new(0.5));
+
+    let mut portfolio = Portfolio::new(1_000.0);
+    let mut next_id = 1;
+    let mut ctx = StrategyContext::new(data[3].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &data[3]);
+    let (commands, logs, _) = ctx.into_parts();
+    assert!(matches!(&commands[..], [Command::Intent(Intent::TargetWeight { weight, .. })] if *weight == 0.5));
+    assert_eq!(logs[0].message, "X target weight 0.500");
+
+    // Within the band of the target: nothing to do
+    portfolio.apply_fill("X", OrderSide::Buy, 37.0, 13.0);
+    let mut ctx = StrategyContext::new(data[3].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &data[3]);
+    assert!(ctx.into_parts().0.is_empty());
+
+    // Waits for every symbol in the universe to have a bar
+    let mut strategy = SignalStrategy::new(MaCrossAlpha::new(2, 3), SignalWeight::new(0.5)).with_symbols(&["X", "Y"]);
+    let mut ctx = StrategyContext::new(data[3].timestamp, "X", &portfolio, &[], &history, &mut next_id);
+    strategy.on_bar(&mut ctx, &data[3]);
+    assert!(ctx.into_parts().0.is_empty());
+}
+
+#[test]
+fn test_weights_value_holdings_in_base_currency() {
+    let data = bars(&[10.0]);
+    let history = HashMap::from([("SAP".to_owned(), data.clone())]);
+    let mut portfolio = Portfolio::new(1_000.0);
+    portfolio.deposit("EUR", 500.0);
+    portfolio.apply_fill_in("SAP", "EUR", OrderSide::Buy, 10.0, 10.0);
+    let mut fx = FxRates::new();
+    fx.set("EUR", "USD", 1.2);
+
+    let mut next_id = 1;
+    let ctx = StrategyContext::new(data[0].timestamp, "SAP", &portfolio, &[], &history, &mut next_id).with_fx(&fx);
+    // 1000 USD + 400 EUR cash + 100 EUR of SAP
+    assert!((ctx.equity() - 1_600.0).abs() < 1e-9);
+    assert!((ctx.weight("SAP") - 120.0 / 1_600.0).abs() < 1e-12);
+
+    // Without the rates only the base-currency cash can be valued
+    let ctx = StrategyContext::new(data[0].timestamp, "SAP", &portfolio, &[], &history, &mut next_id);
+    assert!((ctx.equity() - 1_000.0).abs() < 1e-9);
+    assert_eq!(ctx.weight("SAP"), 0.0);
+}
+
+#[test]
+fn test_ensemble_spec() {
+    let ensemble = Ensemble::from_yaml(
+        "alpha:\n  type: vote\n  members:\n    - { type: ma_cross, fast: 5, slow: 20, average: ema }\n    - { type: rsi, period: 14 }\n    - { type: momentum, lookback: 20 }\nconstruction: { type: equal_weight, allow_short: true }\n",
+    )
+    .unwrap();
+    let spec = ensemble.spec();
+    assert!(matches!(&spec.alpha, AlphaSpec::Vote { members, quorum, .. } if members.len() == 3 && *quorum == 0.5));
+    assert_eq!(spec.construction, ConstructionSpec::EqualWeight { gross: 1.0, threshold: 0.0, allow_short: true });
+    assert_eq!(spec.band, 0.05);
+    // The engine keeps as much history as the longest member reads
+    assert_eq!(ensemble.lookback(), (EMA_WINDOW_PERIODS * 20).max(RSI_WINDOW_PERIODS * 14 + 1));
+    let long = Ensemble::from_yaml(
+        "alpha:\n  type: weighted\n  members:\n    - alpha: { type: momentum, lookback: 252 }\n    - alpha: { type: any, members: [{ type: ma_cross, fast: 50, slow: 300 }] }\n",
+    )
+    .unwrap();
+    assert_eq!(long.lookback(), 300);
+
+    let invalid = |src: &str| match Ensemble::from_yaml(src) {
+        Err(ParamError::Invalid(message)) => message,
+        _ => panic!("{src} should be invalid"),
+    };
+    assert_eq!(invalid("alpha: { type: all, members: [] }"), "a combinator needs at least one member");
+    assert_eq!(invalid("alpha: { type: ma_cross, fast: 20, slow: 5 }"), "ma_cross needs 0 < fast < slow");
+    assert!(invalid("alpha: { type: rsi, period: 14, lenght: 3 }").starts_with("ensemble spec: "));
+}
@@ -0,0 +1,127 @@
+use std::collections::HashMap;
+use dnn_core::fx::FxRates;
+use dnn_core::instrument::{AssetClass, Instrument, InstrumentMaster};
+use dnn_core::portfolio::Portfolio;
+use dnn_core::OrderSide;
+use strats::sizing::{
+    basket_weights, BasketWeighting, FixedFractional, FixedRisk, Intent, Kelly, PositionSizer,
+    SizingContext, SizingLayer, SymbolMetrics, VolatilityTarget,
+};
+
+fn ctx(price: f64, atr: Option<f64>, stop: Option<f64>) -> SizingContext {
+    SizingContext {
+        equity: 100_000.0,
+        metrics: SymbolMetrics { price, atr, volatility: None },
+        stop,
+        point_value: 1.0,
+    }
+}
+
+#[test]
+fn test_single_symbol_sizers() {
+    let fixed = FixedFractional { fraction: 0.1 };
+    assert_eq!(fixed.size(&ctx(50.0, None, None)), 200.0);
+
+    let vol = VolatilityTarget { risk_fraction: 0.01, atr_multiple: 2.0 };
+    assert_eq!(vol.size(&ctx(50.0, Some(2.5), None)), 200.0);
+    assert_eq!(vol.size(&ctx(50.0, None, None)), 0.0);
+
+    let risk = FixedRisk { risk_fraction: 0.02 };
+    assert_eq!(risk.size(&ctx(100.0, None, Some(95.0))), 400.0);
+    assert_eq!(risk.size(&ctx(100.0, None, None)), 0.0);
+
+    // f* = 0.6 - 0.4 / 2 = 0.4, half Kelly = 0.2
+    let kelly = Kelly { win_rate: 0.6, payoff_ratio: 2.0, fraction: 0.5, max_fraction: 0.25 };
+    assert!((kelly.kelly_fraction() - 0.4).abs() < 1e-12);
+    assert!((kelly.size(&ctx(100.0, None, None)) - 200.0).abs() < 1e-9);
+
+    // A point worth 50 takes a fiftieth of the units
+    let contract = SizingContext { point_value: 50.0, ..ctx(100.0, Some(2.0), Some(95.0)) };
+    assert_eq!(FixedFractional { fraction: 0.1 }.size(&contract), 2.0);
+    assert_eq!(vol.size(&contract), 5.0);
+    assert_eq!(risk.size(&contract), 8.0);
+}
+
+#[test]
+fn test_basket_weights() {
+    let metrics = HashMap::from([
+        ("A".to_owned(), SymbolMetrics { price: 10.0, atr: None, volatility: Some(0.01) }),
+        ("B".to_owned(), SymbolMetrics { price: 20.0, atr: None, volatility: Some(0.03) }),
+    ]);
+
+    let equal = basket_weights(&metrics, BasketWeighting::EqualWeight, 1.0);
+    assert!((equal["A"] - 0.5).abs() < 1e-12);
+
+    let parity = basket_weights(&metrics, BasketWeighting::RiskParity, 1.0);
+    assert!((parity["A"] - 0.75).abs() < 1e-12);
+    assert!((parity["B"] - 0.25).abs() < 1e-12);
+}
+
+#[test]
+fn test_sizing_layer_turns_intents_into_orders() {
+    let mut portfolio = Portfolio::new(100_000.0);
+    portfolio.apply_fill("B", OrderSide::Buy, 100.0, 20.0);
+
+    let metrics = HashMap::from([
+        ("A".to_owned(), SymbolMetrics { price: 10.0, atr: None, volatility: None }),
+        ("B".to_owned(), SymbolMetrics { price: 20.0, atr: None, volatility: None }),
+    ]);
+    let intents = vec![
+        Intent::Enter { symbol: "A".into(), side: OrderSide::Buy, stop: None },
+        Intent::Exit { symbol: "B".into() },
+        Intent::TargetWeight { symbol: "B".into(), weight: 0.1 },
+        Intent::Enter { symbol: "UNKNOWN".into(), side: OrderSide::Buy, stop: None },
+    ];
+
+    let layer = SizingLayer::new(Box::new(FixedFractional { fraction: 0.05 }));
+    let mut next_id = 7;
+    let prices = HashMap::from([("A".to_owned(), 10.0), ("B".to_owned(), 20.0)]);
+    let orders = layer.orders(&intents, &portfolio, &prices, &metrics, &FxRates::new(), &mut next_id).unwrap();
+
+    assert_eq!(orders.len(), 2);
+    assert_eq!((orders[0].side, orders[0].qty), (OrderSide::Buy, 500.0));
+    // Exit then 10% of 100k at 20: one order from the 100 held to 500 shares
+    assert_eq!(orders[1].symbol, "B");
+    assert_eq!((orders[1].side, orders[1].qty), (OrderSide::Buy, 400.0));
+    // Numbered from the shared counter
+    assert_eq!((orders[0].id, orders[1].id, next_id), (7, 8, 9));
+
+    // Target weights of the same bar replace each other
+    let intents = vec![
+        Intent::TargetWeight { symbol: "A".into(), weight: 0.1 },
+        Intent::TargetWeight { symbol: "A".into(), weight: 0.2 },
+        Intent::Exit { symbol: "B".into() },
+    ];
+    let orders = layer.orders(&intents, &portfolio, &prices, &metrics, &FxRates::new(), &mut next_id).unwrap();
+    assert_eq!(orders.len(), 2);
+    assert_eq!((orders[0].side, orders[0].qty), (OrderSide::Buy, 2_000.0));
+    assert_eq!((orders[1].side, orders[1].qty), (OrderSide::Sell, 100.0));
+}
+
+#[test]
+fn test_sizing_values_contracts_in_the_base_currency() {
+    let mut portfolio = Portfolio::new(100_000.0);
+    portfolio.apply_fill("B", OrderSide::Buy, 100.0, 20.0);
+    let mut fx = FxRates::new();
+    fx.set("EUR", "USD", 1.25);
+    let mut future = Instrument::new("FUT", AssetClass::Future, "EUREX", "EUR");
+    future.multiplier = 10.0;
+    let mut instruments = InstrumentMaster::new();
+    instruments.insert(future);
+
+    // Only FUT is sized, but B's last price still counts: equity is
+    // 98,000 cash and 100 B at 30
+    let prices = HashMap::from([("FUT".to_owned(), 101.0), ("B".to_owned(), 30.0)]);
+    let metrics = HashMap::from([("FUT".to_owned(), SymbolMetrics { price: 101.0, atr: None, volatility: None })]);
+    let layer = SizingLayer::new(Box::new(FixedFractional { fraction: 0.05 })).with_instruments(instruments);
+    let mut next_id = 1;
+
+    // 50,500 USD at 101 EUR * 10 * 1.25 a contract
+    let intents = vec![Intent::TargetWeight { symbol: "FUT".into(), weight: 0.5 }];
+    let orders = layer.orders(&intents, &portfolio, &prices, &metrics, &fx, &mut next_id).unwrap();
+    assert_eq!((orders[0].side, orders[0].qty), (OrderSide::Buy, 40.0));
+
+    let intents = vec![Intent::Enter { symbol: "FUT".into(), side: OrderSide::Sell, stop: None }];
+    let orders = layer.orders(&intents, &portfolio, &prices, &metrics, &fx, &mut next_id).unwrap();
+    assert_eq!((orders[0].side, orders[0].qty), (OrderSide::Sell, 4.0));
+}